@@ -0,0 +1,203 @@
+use crate::BloomFilter;
+use std::hash::{BuildHasher, Hash};
+
+/// A Bloom filter that "forgets" old items, made of two generations of [`BloomFilter`]s.
+///
+/// Items are inserted into the active generation, while membership checks consult both the active
+/// and the warming (previous) generation. Once the active generation has received `capacity`
+/// new items, the warming generation is cleared and the two generations swap roles.
+///
+/// This means an item is guaranteed to be contained for at least `capacity` insertions of other
+/// items after it was inserted, and at most `2 * capacity`.
+/// This is the common pattern for "seen recently" caches.
+///
+/// # Examples
+/// ```
+/// use fastbloom::{AgingBloomFilter, BloomFilter};
+///
+/// let filter = BloomFilter::with_false_pos(0.001).expected_items(1000);
+/// let mut aging = AgingBloomFilter::new(filter, 1000);
+/// aging.insert("42");
+/// assert!(aging.contains("42"));
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AgingBloomFilter<const BLOCK_SIZE_BITS: usize = 512, S = crate::DefaultHasher> {
+    active: BloomFilter<BLOCK_SIZE_BITS, S>,
+    warming: BloomFilter<BLOCK_SIZE_BITS, S>,
+    /// The number of new items inserted into the active generation
+    num_active: usize,
+    /// The number of new items the active generation holds before generations are swapped
+    capacity: usize,
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher + Clone> AgingBloomFilter<BLOCK_SIZE_BITS, S> {
+    /// Creates a new `AgingBloomFilter` using `filter` as the template for both generations.
+    /// Each generation receives at most `capacity` new items before the generations are swapped.
+    ///
+    /// `filter` is cleared before use.
+    ///
+    /// # Panics
+    /// Panics if `capacity` is 0.
+    pub fn new(mut filter: BloomFilter<BLOCK_SIZE_BITS, S>, capacity: usize) -> Self {
+        assert!(capacity > 0);
+        filter.clear();
+        Self {
+            warming: filter.clone(),
+            active: filter,
+            num_active: 0,
+            capacity,
+        }
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> AgingBloomFilter<BLOCK_SIZE_BITS, S> {
+    /// Inserts an element into the active generation, swapping generations first if the active generation is full.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item may have been previously in either generation (indicating a potential false positive),
+    /// `false` otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::{AgingBloomFilter, BloomFilter};
+    ///
+    /// let mut aging = AgingBloomFilter::new(BloomFilter::with_num_bits(1024).hashes(4), 1);
+    /// aging.insert(&1);
+    /// aging.insert(&2);
+    /// assert!(aging.contains(&1));
+    /// aging.insert(&3);
+    /// assert!(aging.contains(&2));
+    /// assert!(aging.contains(&3));
+    /// ```
+    #[inline]
+    pub fn insert(&mut self, val: &(impl Hash + ?Sized)) -> bool {
+        if self.num_active >= self.capacity {
+            self.rotate();
+        }
+        let in_warming = self.warming.contains(val);
+        let in_active = self.active.insert(val);
+        if !in_active {
+            self.num_active += 1;
+        }
+        in_active || in_warming
+    }
+
+    /// Checks if an element is possibly in either generation.
+    #[inline]
+    pub fn contains(&self, val: &(impl Hash + ?Sized)) -> bool {
+        self.active.contains(val) || self.warming.contains(val)
+    }
+
+    /// Clears the warming generation and swaps it with the active generation.
+    ///
+    /// This is done automatically by [`AgingBloomFilter::insert`] once the active generation is full.
+    pub fn rotate(&mut self) {
+        std::mem::swap(&mut self.active, &mut self.warming);
+        self.active.clear();
+        self.num_active = 0;
+    }
+
+    /// Returns the number of new items inserted into the active generation since the last rotation.
+    #[inline]
+    pub fn num_active(&self) -> usize {
+        self.num_active
+    }
+
+    /// Returns the number of new items each generation receives before generations are swapped.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns a reference to the generation new items are inserted into.
+    #[inline]
+    pub fn active(&self) -> &BloomFilter<BLOCK_SIZE_BITS, S> {
+        &self.active
+    }
+
+    /// Returns a reference to the previous generation, which is cleared on the next rotation.
+    #[inline]
+    pub fn warming(&self) -> &BloomFilter<BLOCK_SIZE_BITS, S> {
+        &self.warming
+    }
+
+    /// Returns the number of bytes this `AgingBloomFilter` uses, including the bits of both generations.
+    /// See [`BloomFilter::memory_usage`].
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>() + self.active.heap_bytes() + self.warming.heap_bytes()
+    }
+
+    /// Clear both generations, removing all items.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.active.clear();
+        self.warming.clear();
+        self.num_active = 0;
+    }
+}
+
+impl<T, const BLOCK_SIZE_BITS: usize, S: BuildHasher> Extend<T>
+    for AgingBloomFilter<BLOCK_SIZE_BITS, S>
+where
+    T: Hash,
+{
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.insert(&val);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aging(capacity: usize) -> AgingBloomFilter {
+        let filter = BloomFilter::with_false_pos(0.0001)
+            .seed(&42)
+            .expected_items(capacity);
+        AgingBloomFilter::new(filter, capacity)
+    }
+
+    #[test]
+    fn recent_items_contained() {
+        let capacity = 1000;
+        let mut filter = aging(capacity);
+        for x in 0..10 * capacity {
+            filter.insert(&x);
+            let oldest_guaranteed = (x + 1).saturating_sub(capacity);
+            assert!((oldest_guaranteed..=x).all(|y| filter.contains(&y)));
+        }
+    }
+
+    #[test]
+    fn old_items_expire() {
+        let capacity = 1000;
+        let mut filter = aging(capacity);
+        filter.extend(0..capacity);
+        filter.extend(capacity..3 * capacity);
+        let still_contained = (0..capacity).filter(|x| filter.contains(x)).count();
+        assert!(still_contained < capacity / 100);
+    }
+
+    #[test]
+    fn duplicates_do_not_count() {
+        let mut filter = aging(10);
+        for _ in 0..100 {
+            filter.insert(&1);
+        }
+        assert_eq!(filter.num_active(), 1);
+    }
+
+    #[test]
+    fn nothing_after_clear() {
+        let mut filter = aging(100);
+        filter.extend(0..150);
+        filter.clear();
+        assert!(!(0..150).any(|x| filter.contains(&x)));
+        assert_eq!(filter.num_active(), 0);
+    }
+}
@@ -0,0 +1,167 @@
+use crate::{
+    BitStorage, BitStorageMut, BloomFilter, BuildHasher, BuilderWithBits, DefaultHasher,
+    IndexStrategy,
+};
+use allocator_api2::alloc::{Allocator, Global};
+use allocator_api2::vec::Vec;
+
+/// A [`BloomFilter`] whose bits are allocated by `A`, e.g. an arena, a shared memory segment,
+/// or a NUMA-pinned pool managed by the application.
+///
+/// `A` is an [`allocator_api2`] `Allocator`, which on nightly, with `allocator-api2`'s `nightly` feature,
+/// is the standard library's `Allocator`.
+///
+/// Constructed via [`BloomFilter::with_num_bits_in`] or [`BuilderWithBits::in_allocator`].
+pub type AllocBloomFilter<const BLOCK_SIZE_BITS: usize = 512, S = DefaultHasher, A = Global> =
+    BloomFilter<BLOCK_SIZE_BITS, S, Vec<u64, A>>;
+
+impl<A: Allocator> BitStorage for Vec<u64, A> {
+    #[inline]
+    fn as_slice(&self) -> &[u64] {
+        self
+    }
+
+    #[inline]
+    fn heap_bytes(&self) -> usize {
+        self.capacity() * std::mem::size_of::<u64>()
+    }
+
+    fn pad_to(mut self, block_len: usize) -> Self {
+        let r = self.len() % block_len;
+        if r != 0 {
+            self.resize(self.len() + block_len - r, 0);
+        }
+        self.shrink_to_fit();
+        self
+    }
+}
+
+impl<A: Allocator> BitStorageMut for Vec<u64, A> {
+    #[inline]
+    fn as_mut_slice(&mut self) -> &mut [u64] {
+        self
+    }
+}
+
+impl BloomFilter {
+    /// Creates a new instance of [`BuilderWithBits`] to construct a `BloomFilter` with `num_bits` number of bits,
+    /// allocated by `alloc`.
+    ///
+    /// # Panics
+    /// Panics if the number of bits, `num_bits`, is 0.
+    ///
+    /// # Examples
+    /// ```
+    /// use allocator_api2::alloc::Global;
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let mut bloom = BloomFilter::with_num_bits_in(1024, Global).hashes(4);
+    /// bloom.insert(&1);
+    /// assert!(bloom.contains(&1));
+    /// ```
+    pub fn with_num_bits_in<A: Allocator>(
+        num_bits: usize,
+        alloc: A,
+    ) -> BuilderWithBits<512, DefaultHasher, Vec<u64, A>> {
+        assert!(num_bits > 0);
+        let len = num_bits.div_ceil(64);
+        let mut data = Vec::with_capacity_in(len, alloc);
+        data.resize(len, 0);
+        BuilderWithBits {
+            data,
+            hasher: Default::default(),
+            two_choice: false,
+            index_strategy: IndexStrategy::DoubleHashing,
+            max_rounds: 32,
+            prefix: None,
+        }
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> BuilderWithBits<BLOCK_SIZE_BITS, S> {
+    /// Copies the bits into a `Vec` allocated by `alloc`, so that the later constructed
+    /// [`AllocBloomFilter`] lives in the allocator's memory.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use allocator_api2::alloc::Global;
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let orig = BloomFilter::with_false_pos(0.001).seed(&1).items([1, 2]);
+    /// let bloom = BloomFilter::from_vec(orig.as_slice().to_vec())
+    ///     .seed(&1)
+    ///     .in_allocator(Global)
+    ///     .hashes(orig.num_hashes());
+    /// assert!(bloom.contains(&1));
+    /// ```
+    pub fn in_allocator<A: Allocator>(
+        self,
+        alloc: A,
+    ) -> BuilderWithBits<BLOCK_SIZE_BITS, S, Vec<u64, A>> {
+        let mut data = Vec::with_capacity_in(self.data.len(), alloc);
+        data.extend_from_slice(&self.data);
+        BuilderWithBits {
+            data,
+            hasher: self.hasher,
+            two_choice: self.two_choice,
+            index_strategy: self.index_strategy,
+            max_rounds: self.max_rounds,
+            prefix: self.prefix,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use allocator_api2::alloc::{AllocError, Layout};
+    use std::ptr::NonNull;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Counts the bytes it has allocated and not yet deallocated.
+    #[derive(Clone, Copy)]
+    struct Counting<'a>(&'a AtomicUsize);
+
+    unsafe impl Allocator for Counting<'_> {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            self.0.fetch_add(layout.size(), Ordering::Relaxed);
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            self.0.fetch_sub(layout.size(), Ordering::Relaxed);
+            Global.deallocate(ptr, layout)
+        }
+    }
+
+    #[test]
+    fn allocates_in_allocator() {
+        let allocated = AtomicUsize::new(0);
+        let orig = BloomFilter::with_num_bits(1000).seed(&1).items(0..100);
+        let mut bloom = BloomFilter::with_num_bits_in(1000, Counting(&allocated))
+            .seed(&1)
+            .hashes(orig.num_hashes());
+        assert_eq!(allocated.load(Ordering::Relaxed), 1024 / 8);
+        assert_eq!(bloom.heap_bytes(), 1024 / 8);
+        bloom.extend(0..100);
+        assert_eq!(bloom.as_slice(), orig.as_slice());
+
+        let copied = BloomFilter::from_vec(orig.as_slice().to_vec())
+            .seed(&1)
+            .in_allocator(Counting(&allocated))
+            .hashes(orig.num_hashes());
+        assert_eq!(allocated.load(Ordering::Relaxed), 2 * 1024 / 8);
+        assert_eq!(copied, bloom);
+        drop((bloom, copied));
+        assert_eq!(allocated.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn pads_to_block_size() {
+        let mut bits = Vec::new_in(Global);
+        bits.extend_from_slice(&[1; 9]);
+        let padded = crate::bit_vector::BlockedBitVec::<256, _>::from_storage(bits);
+        assert_eq!(padded.as_slice(), [&[1; 9][..], &[0; 3]].concat());
+    }
+}
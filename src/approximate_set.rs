@@ -0,0 +1,127 @@
+use crate::{BloomFilter, DynBloomFilter};
+use std::hash::Hash;
+
+/// A set with approximate membership: items that were inserted are always contained,
+/// and items that were not may be contained, with some false positive rate.
+///
+/// Implemented by [`BloomFilter`] and [`DynBloomFilter`], so that applications and benchmarks can be generic
+/// over the filter structure, e.g. to compare the filters of this crate on the same workload.
+///
+/// # Examples
+/// ```
+/// use fastbloom::{ApproximateSet, BloomFilter};
+///
+/// fn seen<F: ApproximateSet>(filter: &mut F, ids: &[u64]) -> usize {
+///     ids.iter().filter(|id| filter.insert(*id)).count()
+/// }
+///
+/// let mut bloom = BloomFilter::with_false_pos(0.001).expected_items(100);
+/// assert_eq!(seen(&mut bloom, &[1, 2, 1]), 1);
+/// assert!(ApproximateSet::estimated_fp_rate(&bloom) < 0.001);
+/// ```
+pub trait ApproximateSet {
+    /// Inserts an element into the set.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item may have been previously in the set (indicating a potential false positive),
+    /// `false` otherwise.
+    fn insert(&mut self, val: &(impl Hash + ?Sized)) -> bool;
+
+    /// Checks if an element is possibly in the set.
+    fn contains(&self, val: &(impl Hash + ?Sized)) -> bool;
+
+    /// Returns the estimated probability that an item not in the set is contained, given its current contents.
+    fn estimated_fp_rate(&self) -> f64;
+
+    /// Returns the set as bytes, including the parameters needed to read it with [`from_bytes`](Self::from_bytes).
+    fn to_bytes(&self) -> Vec<u8>;
+
+    /// Reads a set written by [`to_bytes`](Self::to_bytes).
+    ///
+    /// Returns `None` if `bytes` is not a set of this type.
+    fn from_bytes(bytes: &[u8]) -> Option<Self>
+    where
+        Self: Sized;
+}
+
+/// Serialized with [`to_bytes_with_params`](BloomFilter::to_bytes_with_params).
+impl<const BLOCK_SIZE_BITS: usize> ApproximateSet for BloomFilter<BLOCK_SIZE_BITS> {
+    #[inline]
+    fn insert(&mut self, val: &(impl Hash + ?Sized)) -> bool {
+        BloomFilter::insert(self, val)
+    }
+
+    #[inline]
+    fn contains(&self, val: &(impl Hash + ?Sized)) -> bool {
+        BloomFilter::contains(self, val)
+    }
+
+    fn estimated_fp_rate(&self) -> f64 {
+        self.estimate_fp_rate()
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_with_params()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        Self::from_bytes_with_params(bytes)
+    }
+}
+
+/// Serialized with [`to_bytes_with_params`](DynBloomFilter::to_bytes_with_params).
+impl ApproximateSet for DynBloomFilter {
+    #[inline]
+    fn insert(&mut self, val: &(impl Hash + ?Sized)) -> bool {
+        DynBloomFilter::insert(self, val)
+    }
+
+    #[inline]
+    fn contains(&self, val: &(impl Hash + ?Sized)) -> bool {
+        DynBloomFilter::contains(self, val)
+    }
+
+    fn estimated_fp_rate(&self) -> f64 {
+        self.estimate_fp_rate()
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_with_params()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        Self::from_bytes_with_params(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks the trait's guarantees for the empty `set`.
+    fn check_set<F: ApproximateSet + PartialEq + std::fmt::Debug>(mut set: F) {
+        assert_eq!(set.estimated_fp_rate(), 0.0);
+        for x in 0..1000 {
+            set.insert(&x);
+        }
+        assert!((0..1000).all(|x| set.contains(&x)));
+        let fp = set.estimated_fp_rate();
+        assert!(fp > 0.0 && fp < 0.02, "{fp}");
+
+        let bytes = set.to_bytes();
+        assert_eq!(F::from_bytes(&bytes), Some(set));
+        assert_eq!(F::from_bytes(&bytes[..bytes.len() - 1]), None);
+    }
+
+    #[test]
+    fn implementations() {
+        check_set(BloomFilter::with_false_pos(0.01).expected_items(1000));
+        check_set(
+            BloomFilter::with_false_pos(0.01)
+                .block_size_128()
+                .expected_items(1000),
+        );
+        check_set(DynBloomFilter::new_with_false_pos(256, 0.01, 1000));
+    }
+}
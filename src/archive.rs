@@ -0,0 +1,110 @@
+use crate::{ArchivedBloomFilter, AtomicCounters, BloomFilter, CowBloomFilter};
+use rkyv::api::high::HighDeserializer;
+use rkyv::rancor::Panic;
+use rkyv::{Archive, Deserialize};
+use std::hash::{BuildHasher, Hash};
+
+impl<const BLOCK_SIZE_BITS: usize, S> ArchivedBloomFilter<BLOCK_SIZE_BITS, S>
+where
+    S: BuildHasher + Archive,
+    S::Archived: Deserialize<S, HighDeserializer<Panic>>,
+{
+    /// Returns a read-only [`BloomFilter`] reading the bits of this archived `BloomFilter` in place.
+    ///
+    /// The bits are borrowed from the archive, unless archived `u64`s are laid out differently than native ones,
+    /// e.g. on big-endian targets, where they are copied. Only the hasher is deserialized.
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::{ArchivedBloomFilter, BloomFilter};
+    /// use rkyv::rancor::Error;
+    ///
+    /// let bloom = BloomFilter::with_false_pos(0.001).seed(&42).items([1, 2]);
+    /// let bytes = rkyv::to_bytes::<Error>(&bloom).unwrap(); // e.g. memory-mapped from a file
+    ///
+    /// let archived = rkyv::access::<ArchivedBloomFilter, Error>(&bytes).unwrap();
+    /// let view = archived.as_filter();
+    /// assert!(view.contains(&1));
+    /// assert!(view.contains(&2));
+    /// ```
+    pub fn as_filter(&self) -> CowBloomFilter<'_, BLOCK_SIZE_BITS, S> {
+        BloomFilter {
+            bits: self.bits.to_bit_vec(),
+            target_hashes: self.target_hashes.to_native(),
+            num_rounds: self.num_rounds.as_ref().map(|x| x.to_native()),
+            num_hashes: self.num_hashes.to_native(),
+            two_choice: self.two_choice,
+            index_strategy: rkyv::deserialize::<_, Panic>(&self.index_strategy)
+                .unwrap_or_else(|e| match e {}),
+            prefix: rkyv::deserialize::<_, Panic>(&self.prefix).unwrap_or_else(|e| match e {}),
+            hasher: rkyv::deserialize::<S, Panic>(&self.hasher).unwrap_or_else(|e| match e {}),
+            counters: AtomicCounters::new(),
+        }
+    }
+
+    /// Checks if an element is possibly in this archived Bloom filter, without deserializing it.
+    ///
+    /// To check many elements, use [`as_filter`](Self::as_filter) once instead.
+    #[inline]
+    pub fn contains(&self, val: &(impl Hash + ?Sized)) -> bool {
+        self.as_filter().contains(val)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Prefix;
+    use rkyv::rancor::Error;
+
+    #[test]
+    fn archived_contains() {
+        let mut bloom = BloomFilter::with_false_pos(0.001)
+            .seed(&7)
+            .expected_items(1000);
+        bloom.extend(0..1000);
+        let bytes = rkyv::to_bytes::<Error>(&bloom).unwrap();
+        let archived = rkyv::access::<ArchivedBloomFilter, Error>(&bytes).unwrap();
+        assert!((0..1000).all(|x| archived.contains(&x)));
+
+        let view = archived.as_filter();
+        assert_eq!(view.as_slice(), bloom.as_slice());
+        if cfg!(target_endian = "little") {
+            let bits = view.as_slice().as_ptr().cast::<u8>();
+            assert!(bytes.as_ptr_range().contains(&bits));
+        }
+        let false_pos = (1000..11_000).filter(|x| view.contains(x)).count();
+        assert_eq!(
+            false_pos,
+            (1000..11_000).filter(|x| bloom.contains(x)).count()
+        );
+
+        let de = rkyv::deserialize::<BloomFilter, Error>(archived).unwrap();
+        assert_eq!(de, bloom);
+        assert!((0..1000).all(|x| de.contains(&x)));
+    }
+
+    #[test]
+    fn archived_options() {
+        let mut bloom = BloomFilter::with_num_bits(1024)
+            .block_size_128()
+            .two_choice()
+            .prefix(Prefix::Delimiter(b'/'))
+            .hashes(5);
+        bloom.insert_with_prefix(b"users/alice");
+        let bytes = rkyv::to_bytes::<Error>(&bloom).unwrap();
+        let archived = rkyv::access::<ArchivedBloomFilter<128>, Error>(&bytes).unwrap();
+        let view = archived.as_filter();
+        assert!(view.contains(b"users/alice".as_slice()));
+        assert!(view.contains(b"users/".as_slice()));
+        assert_eq!(view.as_slice(), bloom.as_slice());
+    }
+
+    #[test]
+    fn rejects_partial_block() {
+        let bloom = BloomFilter::with_num_bits(3 * 64).block_size_64().hashes(3);
+        let bytes = rkyv::to_bytes::<Error>(&bloom).unwrap();
+        assert!(rkyv::access::<ArchivedBloomFilter<64>, Error>(&bytes).is_ok());
+        assert!(rkyv::access::<ArchivedBloomFilter<512>, Error>(&bytes).is_err());
+    }
+}
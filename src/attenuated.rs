@@ -0,0 +1,204 @@
+use crate::BloomFilter;
+use std::hash::{BuildHasher, Hash};
+
+/// An attenuated Bloom filter, an array of [`BloomFilter`] levels where level `i` holds the items `i` hops away.
+///
+/// Attenuated Bloom filters are used as routing tables in peer-to-peer overlays: a node keeps one per link,
+/// and routes a query along the link whose filter contains the item at the lowest level.
+/// A node learns its neighbors' content by [`merge_shifted`](Self::merge_shifted)ing their filters into its own,
+/// so items known to a neighbor at level `i` are known one hop further away, at level `i + 1`.
+/// Levels further away combine the content of more nodes, so they are less precise.
+///
+/// # Examples
+/// ```
+/// use fastbloom::{AttenuatedBloomFilter, BloomFilter};
+///
+/// let filter = BloomFilter::with_false_pos(0.001).expected_items(1000);
+/// let mut neighbor = AttenuatedBloomFilter::new(filter.clone(), 3);
+/// neighbor.insert(0, "42");
+///
+/// let mut link = AttenuatedBloomFilter::new(filter, 3);
+/// link.insert(0, "🦀");
+/// link.merge_shifted(&neighbor);
+/// assert_eq!(link.contains("🦀"), Some(0));
+/// assert_eq!(link.contains("42"), Some(1));
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AttenuatedBloomFilter<const BLOCK_SIZE_BITS: usize = 512, S = crate::DefaultHasher> {
+    levels: Vec<BloomFilter<BLOCK_SIZE_BITS, S>>,
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher + Clone>
+    AttenuatedBloomFilter<BLOCK_SIZE_BITS, S>
+{
+    /// Creates a new `AttenuatedBloomFilter` with `depth` levels, using `filter` as the template for each level.
+    ///
+    /// `filter` is cleared before use.
+    ///
+    /// # Panics
+    /// Panics if `depth` is 0.
+    pub fn new(mut filter: BloomFilter<BLOCK_SIZE_BITS, S>, depth: usize) -> Self {
+        assert!(depth > 0);
+        filter.clear();
+        Self {
+            levels: vec![filter; depth],
+        }
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> AttenuatedBloomFilter<BLOCK_SIZE_BITS, S> {
+    #[inline]
+    fn assert_compatible(&self, other: &Self) {
+        assert_eq!(self.depth(), other.depth());
+        assert_eq!(self.levels[0].num_bits(), other.levels[0].num_bits());
+        assert_eq!(self.levels[0].num_hashes(), other.levels[0].num_hashes());
+    }
+
+    /// Inserts an element into level `level`, i.e. as `level` hops away.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item may have been previously in the level (indicating a potential false positive),
+    /// `false` otherwise.
+    ///
+    /// # Panics
+    /// Panics if `level` is not less than [`depth`](Self::depth).
+    #[inline]
+    pub fn insert(&mut self, level: usize, val: &(impl Hash + ?Sized)) -> bool {
+        self.levels[level].insert(val)
+    }
+
+    /// Returns the lowest level that possibly contains the element, i.e. the fewest hops away it may be,
+    /// or `None` if no level contains it.
+    #[inline]
+    pub fn contains(&self, val: &(impl Hash + ?Sized)) -> Option<usize> {
+        self.levels.iter().position(|level| level.contains(val))
+    }
+
+    /// Adds all items of `other` to this filter at the same levels.
+    ///
+    /// Both filters must have been created from the same template, i.e. with the same size, number of hashes, and hasher.
+    ///
+    /// # Panics
+    /// Panics if `other` has a different depth, number of bits, or number of hashes.
+    pub fn merge(&mut self, other: &Self) {
+        self.assert_compatible(other);
+        for (level, other) in self.levels.iter_mut().zip(other.levels.iter()) {
+            level.union(other);
+        }
+    }
+
+    /// Adds all items of `other` to this filter one level further away, i.e. one hop further.
+    /// Items in the last level of `other` are dropped.
+    ///
+    /// Both filters must have been created from the same template, i.e. with the same size, number of hashes, and hasher.
+    ///
+    /// # Panics
+    /// Panics if `other` has a different depth, number of bits, or number of hashes.
+    pub fn merge_shifted(&mut self, other: &Self) {
+        self.assert_compatible(other);
+        for (level, other) in self.levels.iter_mut().skip(1).zip(other.levels.iter()) {
+            level.union(other);
+        }
+    }
+
+    /// Returns the number of levels.
+    #[inline]
+    pub fn depth(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Returns a reference to the filter for level `level`.
+    ///
+    /// # Panics
+    /// Panics if `level` is not less than [`depth`](Self::depth).
+    #[inline]
+    pub fn level(&self, level: usize) -> &BloomFilter<BLOCK_SIZE_BITS, S> {
+        &self.levels[level]
+    }
+
+    /// Returns the number of bytes this `AttenuatedBloomFilter` uses, including the bits of all levels.
+    /// See [`BloomFilter::memory_usage`].
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.levels.capacity() * std::mem::size_of::<BloomFilter<BLOCK_SIZE_BITS, S>>()
+            + self
+                .levels
+                .iter()
+                .map(|level| level.heap_bytes())
+                .sum::<usize>()
+    }
+
+    /// Clear all levels, removing all items.
+    #[inline]
+    pub fn clear(&mut self) {
+        for level in self.levels.iter_mut() {
+            level.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attenuated(depth: usize) -> AttenuatedBloomFilter {
+        let filter = BloomFilter::with_false_pos(0.0001)
+            .seed(&42)
+            .expected_items(1000);
+        AttenuatedBloomFilter::new(filter, depth)
+    }
+
+    #[test]
+    fn routes_along_chain() {
+        // Node `i` holds items `100 * i..100 * (i + 1)`, and nodes form a chain 0 - 1 - 2 - 3
+        let depth = 3;
+        let mut nodes: Vec<_> = (0..4usize)
+            .map(|i| {
+                let mut node = attenuated(depth);
+                for x in 100 * i..100 * (i + 1) {
+                    node.insert(0, &x);
+                }
+                node
+            })
+            .collect();
+        for i in (0..3).rev() {
+            let next = nodes[i + 1].clone();
+            nodes[i].merge_shifted(&next);
+        }
+        for i in 0..4 {
+            for x in 100 * i..100 * (i + 1) {
+                let expected = if i < depth { Some(i) } else { None };
+                assert_eq!(nodes[0].contains(&x), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn merge_keeps_levels() {
+        let mut a = attenuated(2);
+        let mut b = attenuated(2);
+        a.insert(0, &1);
+        b.insert(1, &2);
+        a.merge(&b);
+        assert_eq!(a.contains(&1), Some(0));
+        assert_eq!(a.contains(&2), Some(1));
+    }
+
+    #[test]
+    #[should_panic]
+    fn merge_different_depths() {
+        attenuated(2).merge(&attenuated(3));
+    }
+
+    #[test]
+    fn nothing_after_clear() {
+        let mut filter = attenuated(3);
+        for x in 0..100 {
+            filter.insert(x % 3, &x);
+        }
+        filter.clear();
+        assert!(!(0..100).any(|x| filter.contains(&x).is_some()));
+    }
+}
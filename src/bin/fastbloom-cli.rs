@@ -0,0 +1,395 @@
+//! Inspects, converts, and merges serialized Bloom filters.
+//!
+//! Requires the `cli` feature: `cargo install fastbloom --features cli`.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use fastbloom::{DynBloomFilter, ParquetBloomFilter};
+use std::process::ExitCode;
+use std::str::FromStr;
+use std::{env, fs};
+
+const USAGE: &str = "\
+Usage:
+  fastbloom-cli stats <FILE> [--format <FORMAT>]
+  fastbloom-cli convert <INPUT> <OUTPUT> --from <FORMAT> --to <FORMAT>
+  fastbloom-cli merge <OUTPUT> <INPUT>... [--format <FORMAT>]
+
+Formats:
+  fastbloom  bytes of `DynBloomFilter::to_bytes_with_params` (default)
+  base64     text of `BloomFilter::to_base64`
+  hex        text of `BloomFilter::to_hex`
+  parquet    Parquet split block Bloom filter header and bitset
+  bitset     Parquet split block Bloom filter bitset, without the header
+
+Filters convert only within the fastbloom formats or within the Parquet formats,
+since the two hash items differently.";
+
+/// A file format of a filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Fastbloom,
+    Base64,
+    Hex,
+    Parquet,
+    Bitset,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "fastbloom" => Ok(Self::Fastbloom),
+            "base64" => Ok(Self::Base64),
+            "hex" => Ok(Self::Hex),
+            "parquet" => Ok(Self::Parquet),
+            "bitset" => Ok(Self::Bitset),
+            _ => Err(format!("unknown format `{s}`")),
+        }
+    }
+}
+
+/// A filter read from a file.
+#[derive(Debug)]
+enum Filter {
+    Fastbloom(DynBloomFilter),
+    Parquet(ParquetBloomFilter),
+}
+
+impl Filter {
+    /// Reads a filter from the contents of a file in `format`.
+    fn decode(bytes: &[u8], format: Format) -> Result<Self, String> {
+        let text = || {
+            std::str::from_utf8(bytes)
+                .map(str::trim)
+                .map_err(|_| "filter is not text".to_string())
+        };
+        let filter = match format {
+            Format::Fastbloom => DynBloomFilter::from_bytes_with_params(bytes).map(Self::Fastbloom),
+            Format::Base64 => URL_SAFE_NO_PAD
+                .decode(text()?)
+                .ok()
+                .and_then(|bytes| DynBloomFilter::from_bytes_with_params(&bytes))
+                .map(Self::Fastbloom),
+            Format::Hex => decode_hex(text()?)
+                .and_then(|bytes| DynBloomFilter::from_bytes_with_params(&bytes))
+                .map(Self::Fastbloom),
+            Format::Parquet => ParquetBloomFilter::from_bytes(bytes).map(Self::Parquet),
+            Format::Bitset => ParquetBloomFilter::from_bitset(bytes).map(Self::Parquet),
+        };
+        filter.ok_or_else(|| format!("not a valid {format:?} filter"))
+    }
+
+    /// Returns the contents of a file with this filter in `format`.
+    fn encode(&self, format: Format) -> Result<Vec<u8>, String> {
+        match (self, format) {
+            (Self::Fastbloom(filter), Format::Fastbloom) => Ok(filter.to_bytes_with_params()),
+            (Self::Fastbloom(filter), Format::Base64) => {
+                Ok(URL_SAFE_NO_PAD.encode(filter.to_bytes_with_params()).into_bytes())
+            }
+            (Self::Fastbloom(filter), Format::Hex) => Ok(filter
+                .to_bytes_with_params()
+                .iter()
+                .flat_map(|byte| format!("{byte:02x}").into_bytes())
+                .collect()),
+            (Self::Parquet(filter), Format::Parquet) => Ok(filter.to_bytes()),
+            (Self::Parquet(filter), Format::Bitset) => Ok(filter.to_bitset()),
+            _ => Err(format!(
+                "cannot convert between fastbloom and Parquet filters to {format:?}: they hash items differently"
+            )),
+        }
+    }
+
+    /// Returns a description of the size, fill, and accuracy of this filter.
+    fn stats(&self) -> String {
+        // an item sets `num_hashes / groups_per_block` bits in each group of `group_bits` bits of one block
+        let (kind, num_bits, num_hashes, group_bits, groups_per_block, words) = match self {
+            Self::Fastbloom(filter) => (
+                "fastbloom",
+                filter.num_bits(),
+                filter.num_hashes(),
+                filter.block_size_bits(),
+                1,
+                filter.as_slice().to_vec(),
+            ),
+            Self::Parquet(filter) => (
+                "Parquet split block",
+                filter.num_bits(),
+                filter.num_hashes(),
+                32,
+                8,
+                filter
+                    .to_bitset()
+                    .chunks_exact(4)
+                    .map(|word| u32::from_le_bytes(word.try_into().unwrap()) as u64)
+                    .collect(),
+            ),
+        };
+        let group_fills: Vec<f64> = words
+            .chunks_exact(group_bits.div_ceil(64))
+            .map(|group| {
+                let ones: u32 = group.iter().map(|x| x.count_ones()).sum();
+                ones as f64 / group_bits as f64
+            })
+            .collect();
+        let hashes_per_group = (num_hashes / groups_per_block) as i32;
+        let num_blocks = group_fills.len() / groups_per_block as usize;
+        let est_fp = group_fills
+            .chunks_exact(groups_per_block as usize)
+            .map(|block| {
+                block
+                    .iter()
+                    .map(|f| f.powi(hashes_per_group))
+                    .product::<f64>()
+            })
+            .sum::<f64>()
+            / num_blocks as f64;
+        let num_set: usize = words.iter().map(|x| x.count_ones() as usize).sum();
+        let fill = num_set as f64 / num_bits as f64;
+        let est_items = -(num_bits as f64 / num_hashes as f64) * (1.0 - fill).ln();
+        format!(
+            "kind: {kind}\n\
+             bits: {num_bits}\n\
+             blocks: {num_blocks}\n\
+             hashes: {num_hashes}\n\
+             bits set: {num_set} ({:.2}% full)\n\
+             estimated items: {est_items:.0}\n\
+             estimated false positive rate: {est_fp:.3e}\n",
+            100.0 * fill,
+        )
+    }
+
+    /// Merges `other` into this filter, so it contains the items of both.
+    fn merge(&mut self, other: &Self) -> Result<(), String> {
+        match (self, other) {
+            (Self::Fastbloom(filter), Self::Fastbloom(other)) => {
+                let mut bytes = filter.to_bytes_with_params();
+                let other = other.to_bytes_with_params();
+                let header_len = bytes.len() - filter.num_bits() / 8;
+                if bytes.len() != other.len() || bytes[..header_len] != other[..header_len] {
+                    return Err(
+                        "filters have different sizes, block sizes, hashes, or hasher keys".into(),
+                    );
+                }
+                for (byte, other) in bytes[header_len..].iter_mut().zip(&other[header_len..]) {
+                    *byte |= other;
+                }
+                *filter = DynBloomFilter::from_bytes_with_params(&bytes).unwrap();
+            }
+            (Self::Parquet(filter), Self::Parquet(other)) => {
+                let mut bitset = filter.to_bitset();
+                let other = other.to_bitset();
+                if bitset.len() != other.len() {
+                    return Err("filters have different sizes".into());
+                }
+                for (byte, other) in bitset.iter_mut().zip(&other) {
+                    *byte |= other;
+                }
+                *filter = ParquetBloomFilter::from_bitset(&bitset).unwrap();
+            }
+            _ => return Err("cannot merge fastbloom and Parquet filters".into()),
+        }
+        Ok(())
+    }
+}
+
+/// Decodes pairs of hex digits, or returns `None` if `s` is not hex.
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    let pairs = s.as_bytes().chunks_exact(2);
+    if !pairs.remainder().is_empty() || !s.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digit = |c: u8| (c as char).to_digit(16).unwrap() as u8;
+    Some(
+        pairs
+            .map(|pair| digit(pair[0]) << 4 | digit(pair[1]))
+            .collect(),
+    )
+}
+
+/// Removes `--name <value>` from `args`, returning the value, or `default` if it was not given.
+fn take_format(
+    args: &mut Vec<String>,
+    name: &str,
+    default: Option<Format>,
+) -> Result<Format, String> {
+    match args.iter().position(|arg| arg == name) {
+        Some(i) => {
+            let value = args
+                .get(i + 1)
+                .ok_or(format!("missing value of {name}"))?
+                .parse()?;
+            args.drain(i..i + 2);
+            Ok(value)
+        }
+        None => default.ok_or(format!("missing {name}")),
+    }
+}
+
+fn read(path: &str, format: Format) -> Result<Filter, String> {
+    let bytes = fs::read(path).map_err(|e| format!("{path}: {e}"))?;
+    Filter::decode(&bytes, format).map_err(|e| format!("{path}: {e}"))
+}
+
+fn write(path: &str, filter: &Filter, format: Format) -> Result<(), String> {
+    fs::write(path, filter.encode(format)?).map_err(|e| format!("{path}: {e}"))
+}
+
+/// Runs the command in `args`, returning its output.
+fn run(mut args: Vec<String>) -> Result<String, String> {
+    let command = if args.is_empty() {
+        String::new()
+    } else {
+        args.remove(0)
+    };
+    match command.as_str() {
+        "stats" => {
+            let format = take_format(&mut args, "--format", Some(Format::Fastbloom))?;
+            let [path] = args.as_slice() else {
+                return Err(USAGE.into());
+            };
+            Ok(read(path, format)?.stats())
+        }
+        "convert" => {
+            let from = take_format(&mut args, "--from", None)?;
+            let to = take_format(&mut args, "--to", None)?;
+            let [input, output] = args.as_slice() else {
+                return Err(USAGE.into());
+            };
+            write(output, &read(input, from)?, to)?;
+            Ok(String::new())
+        }
+        "merge" => {
+            let format = take_format(&mut args, "--format", Some(Format::Fastbloom))?;
+            let [output, first, rest @ ..] = args.as_slice() else {
+                return Err(USAGE.into());
+            };
+            let mut filter = read(first, format)?;
+            for path in rest {
+                filter
+                    .merge(&read(path, format)?)
+                    .map_err(|e| format!("{path}: {e}"))?;
+            }
+            write(output, &filter, format)?;
+            Ok(String::new())
+        }
+        "help" | "--help" | "-h" => Ok(format!("{USAGE}\n")),
+        _ => Err(USAGE.into()),
+    }
+}
+
+fn main() -> ExitCode {
+    match run(env::args().skip(1).collect()) {
+        Ok(output) => {
+            print!("{output}");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fastbloom::BloomFilter;
+
+    fn temp_path(name: &str) -> String {
+        let path = env::temp_dir().join(format!("fastbloom-cli-{}-{name}", std::process::id()));
+        path.to_str().unwrap().to_string()
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn stats_of_filter() {
+        let mut filter = DynBloomFilter::new_with_num_bits(256, 1024, 4);
+        filter.extend(0..10);
+        let path = temp_path("stats");
+        fs::write(&path, filter.to_bytes_with_params()).unwrap();
+        let stats = run(args(&["stats", &path])).unwrap();
+        assert!(stats.contains("bits: 1024\n"));
+        assert!(stats.contains("blocks: 4\n"));
+        assert!(stats.contains("hashes: 4\n"));
+        assert!(stats.contains("estimated items: 10\n"));
+
+        let mut parquet = ParquetBloomFilter::new_with_num_bytes(64);
+        parquet.insert("a");
+        fs::write(&path, parquet.to_bytes()).unwrap();
+        let stats = run(args(&["stats", &path, "--format", "parquet"])).unwrap();
+        assert!(stats.contains("bits set: 8 (1.56% full)\n"));
+        assert!(stats.contains("estimated false positive rate: 4.547e-13\n"));
+        assert!(run(args(&["stats", &path])).is_err());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn convert_formats() {
+        let bloom = BloomFilter::with_num_bits(512)
+            .block_size_128()
+            .items(["alice"]);
+        let (input, output) = (temp_path("convert-in"), temp_path("convert-out"));
+        fs::write(&input, bloom.to_base64()).unwrap();
+        run(args(&[
+            "convert", &input, &output, "--from", "base64", "--to", "hex",
+        ]))
+        .unwrap();
+        assert_eq!(fs::read_to_string(&output).unwrap(), bloom.to_hex());
+
+        let mut parquet = ParquetBloomFilter::new_with_num_bytes(32);
+        parquet.insert("alice");
+        fs::write(&input, parquet.to_bytes()).unwrap();
+        run(args(&[
+            "convert", &input, &output, "--from", "parquet", "--to", "bitset",
+        ]))
+        .unwrap();
+        assert_eq!(fs::read(&output).unwrap(), parquet.to_bitset());
+        let err = run(args(&[
+            "convert", &input, &output, "--from", "parquet", "--to", "hex",
+        ]));
+        assert!(err.unwrap_err().contains("hash items differently"));
+        fs::remove_file(input).unwrap();
+        fs::remove_file(output).unwrap();
+    }
+
+    #[test]
+    fn merge_files() {
+        let mut a = BloomFilter::with_num_bits(1024).seed(&1).hashes(3);
+        let mut b = a.clone();
+        a.insert("alice");
+        b.insert("bob");
+        let paths = ["merge-a", "merge-b", "merge-out"].map(temp_path);
+        fs::write(&paths[0], a.to_hex()).unwrap();
+        fs::write(&paths[1], b.to_hex()).unwrap();
+        run(args(&[
+            "merge", &paths[2], &paths[0], &paths[1], "--format", "hex",
+        ]))
+        .unwrap();
+        let merged: BloomFilter =
+            BloomFilter::from_hex(&fs::read_to_string(&paths[2]).unwrap()).unwrap();
+        assert!(merged.contains("alice") && merged.contains("bob"));
+
+        let other = BloomFilter::with_num_bits(1024).seed(&2).hashes(3);
+        fs::write(&paths[1], other.to_hex()).unwrap();
+        assert!(run(args(&[
+            "merge", &paths[2], &paths[0], &paths[1], "--format", "hex"
+        ]))
+        .is_err());
+        for path in paths {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn usage_errors() {
+        assert!(run(args(&[])).is_err());
+        assert!(run(args(&["stats"])).is_err());
+        assert!(run(args(&["convert", "a", "b", "--from", "raw", "--to", "hex"])).is_err());
+        assert!(run(args(&["help"])).unwrap().starts_with("Usage:"));
+    }
+}
@@ -0,0 +1,180 @@
+//! Conversions between the bits of filters and `bitvec`'s [`BitSlice`] and [`BitVec`].
+//!
+//! The `i`th bit of a filter is bit `i % 64`, from the least significant, of its `u64` `i / 64`,
+//! which is the `i`th bit of a `BitSlice<u64, Lsb0>` of its `u64`s, so the views share the filter's bits.
+
+use crate::{BitStorage, BitStorageMut, BlockedBitVec, BloomFilter, FastbloomError};
+use bitvec::prelude::{BitSlice, BitVec, Lsb0};
+use std::hash::BuildHasher;
+
+impl<const BLOCK_SIZE_BITS: usize, T: BitStorage> BlockedBitVec<BLOCK_SIZE_BITS, T> {
+    /// Returns the bits as a [`BitSlice`], without copying them.
+    #[inline]
+    pub fn as_bitslice(&self) -> &BitSlice<u64, Lsb0> {
+        BitSlice::from_slice(self.as_slice())
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, T: BitStorageMut> BlockedBitVec<BLOCK_SIZE_BITS, T> {
+    /// Returns the bits as a mutable [`BitSlice`], without copying them.
+    #[inline]
+    pub fn as_mut_bitslice(&mut self) -> &mut BitSlice<u64, Lsb0> {
+        BitSlice::from_slice_mut(self.as_mut_slice())
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher, T: BitStorage>
+    BloomFilter<BLOCK_SIZE_BITS, S, T>
+{
+    /// Returns the bits of this `BloomFilter` as a [`BitSlice`], without copying them,
+    /// e.g. to diff two filters or render their bits with `bitvec`'s tooling.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let a = BloomFilter::with_num_bits(1024).seed(&1).items([1, 2]);
+    /// let b = BloomFilter::with_num_bits(1024).seed(&1).items([1]);
+    /// let diff = a.as_bitslice().to_bitvec() ^ b.as_bitslice();
+    /// assert!(diff.any());
+    /// assert!(diff.iter_ones().all(|i| a.test_bit(i) && !b.test_bit(i)));
+    /// ```
+    #[inline]
+    pub fn as_bitslice(&self) -> &BitSlice<u64, Lsb0> {
+        self.bits.as_bitslice()
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher, T: BitStorageMut>
+    BloomFilter<BLOCK_SIZE_BITS, S, T>
+{
+    /// Returns the bits of this `BloomFilter` as a mutable [`BitSlice`], without copying them.
+    /// See [`as_mut_slice`](Self::as_mut_slice).
+    #[inline]
+    pub fn as_mut_bitslice(&mut self) -> &mut BitSlice<u64, Lsb0> {
+        self.bits.as_mut_bitslice()
+    }
+}
+
+/// Copies the bits.
+impl<const BLOCK_SIZE_BITS: usize, T: BitStorage> From<&BlockedBitVec<BLOCK_SIZE_BITS, T>>
+    for BitVec<u64, Lsb0>
+{
+    fn from(bits: &BlockedBitVec<BLOCK_SIZE_BITS, T>) -> Self {
+        bits.as_bitslice().to_bitvec()
+    }
+}
+
+/// Moves the bits, without copying them.
+impl<const BLOCK_SIZE_BITS: usize> From<BlockedBitVec<BLOCK_SIZE_BITS>> for BitVec<u64, Lsb0> {
+    fn from(bits: BlockedBitVec<BLOCK_SIZE_BITS>) -> Self {
+        BitVec::from_vec(bits.into_inner())
+    }
+}
+
+/// Copies the bits.
+///
+/// # Errors
+/// Returns [`FastbloomError::NotWholeBlockBits`] if the number of bits is not a multiple of the block size.
+impl<const BLOCK_SIZE_BITS: usize> TryFrom<&BitSlice<u64, Lsb0>>
+    for BlockedBitVec<BLOCK_SIZE_BITS>
+{
+    type Error = FastbloomError;
+
+    fn try_from(bits: &BitSlice<u64, Lsb0>) -> Result<Self, Self::Error> {
+        check_whole_blocks::<BLOCK_SIZE_BITS>(bits.len())?;
+        let mut words = vec![0; bits.len() / 64];
+        BitSlice::from_slice_mut(&mut words).copy_from_bitslice(bits);
+        Ok(Self::from_storage(words))
+    }
+}
+
+/// Moves the bits, without copying them unless they start partway into a `u64`.
+///
+/// # Errors
+/// Returns [`FastbloomError::NotWholeBlockBits`] if the number of bits is not a multiple of the block size.
+impl<const BLOCK_SIZE_BITS: usize> TryFrom<BitVec<u64, Lsb0>> for BlockedBitVec<BLOCK_SIZE_BITS> {
+    type Error = FastbloomError;
+
+    fn try_from(mut bits: BitVec<u64, Lsb0>) -> Result<Self, Self::Error> {
+        check_whole_blocks::<BLOCK_SIZE_BITS>(bits.len())?;
+        bits.force_align();
+        Ok(Self::from_storage(bits.into_vec()))
+    }
+}
+
+fn check_whole_blocks<const BLOCK_SIZE_BITS: usize>(num_bits: usize) -> Result<(), FastbloomError> {
+    if num_bits.is_multiple_of(BLOCK_SIZE_BITS) {
+        Ok(())
+    } else {
+        Err(FastbloomError::NotWholeBlockBits {
+            num_bits,
+            block_size_bits: BLOCK_SIZE_BITS,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitvec::bitvec;
+
+    #[test]
+    fn bit_indexes_agree() {
+        let mut bloom = BloomFilter::with_num_bits(1024)
+            .block_size_256()
+            .seed(&1)
+            .hashes(5);
+        bloom.extend(0..20);
+        let ones: Vec<usize> = bloom.as_bitslice().iter_ones().collect();
+        assert_eq!(
+            ones,
+            (0..1024).filter(|&i| bloom.test_bit(i)).collect::<Vec<_>>()
+        );
+
+        let mut copy = bloom.clone();
+        copy.clear();
+        for &i in &ones {
+            copy.as_mut_bitslice().set(i, true);
+        }
+        assert_eq!(copy, bloom);
+    }
+
+    #[test]
+    fn round_trips() {
+        let mut bloom = BloomFilter::with_num_bits(1024)
+            .block_size_128()
+            .seed(&1)
+            .hashes(5);
+        bloom.extend(0..20);
+        let bits = BlockedBitVec::<128>::from(bloom.as_slice().to_vec());
+
+        let copied = BitVec::from(&bits);
+        assert_eq!(copied.as_bitslice(), bloom.as_bitslice());
+        assert_eq!(
+            BlockedBitVec::<128>::try_from(copied.as_bitslice()).unwrap(),
+            bits
+        );
+        let moved = BitVec::from(bits.clone());
+        assert_eq!(BlockedBitVec::<128>::try_from(moved).unwrap(), bits);
+
+        let unaligned = &copied[3..3 + 128];
+        let converted = BlockedBitVec::<64>::try_from(unaligned).unwrap();
+        assert_eq!(converted.as_bitslice(), unaligned);
+        let converted = BlockedBitVec::<128>::try_from(unaligned.to_bitvec()).unwrap();
+        assert_eq!(converted.as_bitslice(), unaligned);
+    }
+
+    #[test]
+    fn rejects_partial_blocks() {
+        assert_eq!(
+            BlockedBitVec::<128>::try_from(bitvec![u64, Lsb0; 1; 192]).unwrap_err(),
+            FastbloomError::NotWholeBlockBits {
+                num_bits: 192,
+                block_size_bits: 128
+            }
+        );
+        assert!(BlockedBitVec::<64>::try_from(bitvec![u64, Lsb0; 0; 100].as_bitslice()).is_err());
+    }
+}
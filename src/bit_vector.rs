@@ -1,4 +1,7 @@
+use crate::{simd, FastbloomError};
+use std::borrow::Cow;
 use std::ops::Range;
+use std::sync::Arc;
 
 /// The number of bits in the bit mask that is used to index a u64's bits.
 ///
@@ -14,45 +17,287 @@ const BIT_MASK: u64 = (1 << BIT_MASK_LEN) - 1;
 /// The bit size of blocks therefore can be 64, 128, 256, etc.
 /// Only `BlockedBitVec`'s with block sizes following this rule can be constructed.
 ///
-/// Loading a block, such as with `get_block`, is cache efficient.
-/// Membership checks can be done locally inside a block.
+/// Loading a block, such as with [`get_block`](Self::get_block), is cache efficient.
+/// Membership checks can be done locally inside a block, with [`check_for_block`](Self::check_for_block)
+/// and [`set_for_block`](Self::set_for_block).
 ///
 /// Indexing a block is also efficient, since it can be done with bit operators because
 /// the size of a block is a power of 2.
-#[derive(Clone, Debug, Eq, PartialEq)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub struct BlockedBitVec<const BLOCK_SIZE_BITS: usize> {
-    bits: Vec<u64>,
+///
+/// The bits are kept in any [`BitStorage`], by default a `Vec<u64>`.
+///
+/// With the `serde` feature, the bits are serialized as their `u64`s. In human-readable formats, e.g. JSON,
+/// they're serialized as the gaps between the set bits instead if that's smaller, which is chosen automatically.
+///
+/// This is the bit vector of [`BloomFilter`](crate::BloomFilter), exported for other block-based structures to use.
+///
+/// # Examples
+/// ```
+/// use fastbloom::BlockedBitVec;
+///
+/// let mut bits = BlockedBitVec::<256>::from(vec![0; 10]);
+/// assert_eq!(bits.num_blocks(), 3);
+/// assert_eq!(bits.as_slice().len(), 12);
+///
+/// let block = bits.get_block_mut(2);
+/// assert!(!BlockedBitVec::<256>::set_for_block(block, 200));
+/// assert!(BlockedBitVec::<256>::check_for_block(bits.get_block(2), 200));
+/// assert!(bits.test_bit(2 * 256 + 200));
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    rkyv(bytecheck(verify))
+)]
+pub struct BlockedBitVec<const BLOCK_SIZE_BITS: usize, T = Vec<u64>> {
+    bits: T,
 }
 
-impl<const BLOCK_SIZE_BITS: usize> BlockedBitVec<BLOCK_SIZE_BITS> {
-    /// Block size in u64s
-    const BLOCK_SIZE: usize = BLOCK_SIZE_BITS / 64;
-    /// Used to shift u64 index
-    const LOG2_BLOCK_SIZE: u32 = u32::ilog2(Self::BLOCK_SIZE as u32);
+/// A contiguous `u64` slice that a [`BloomFilter`](crate::BloomFilter) reads its bits from.
+///
+/// Implemented for `Vec<u64>`, `Box<[u64]>`, `Arc<[u64]>`, `Cow<[u64]>`, `&[u64]`, and `&mut [u64]`.
+/// Implement it for other memory, e.g. an mmap region, to back a filter with it.
+pub trait BitStorage {
+    /// Returns the bits as a `u64` slice.
+    fn as_slice(&self) -> &[u64];
 
+    /// Returns the number of bytes this storage has allocated on the heap.
+    ///
+    /// By default this is 0, i.e. the storage is assumed to borrow its bits.
     #[inline]
-    const fn block_range(index: usize) -> Range<usize> {
-        let block_index = index * Self::BLOCK_SIZE;
-        block_index..(block_index + Self::BLOCK_SIZE)
+    fn heap_bytes(&self) -> usize {
+        0
     }
 
-    /// The number of blocks in the `BlockedBitVector`
+    /// Pads the bits with `0u64`s so their length is a multiple of `block_len`, if the storage can grow.
+    ///
+    /// By default the storage is returned as is, so it must already fit the block size.
     #[inline]
-    pub fn num_blocks(&self) -> usize {
-        self.bits.len() >> Self::LOG2_BLOCK_SIZE
+    fn pad_to(self, block_len: usize) -> Self
+    where
+        Self: Sized,
+    {
+        let _ = block_len;
+        self
     }
+}
 
-    /// Returns a reference to the raw data for the `i`th block in the `BlockedBitVec`
+/// A [`BitStorage`] that can also be written to, as needed to insert items.
+pub trait BitStorageMut: BitStorage {
+    /// Returns the bits as a mutable `u64` slice.
+    fn as_mut_slice(&mut self) -> &mut [u64];
+}
+
+impl BitStorage for Vec<u64> {
     #[inline]
-    pub fn get_block(&self, i: usize) -> &[u64] {
-        &self.bits[Self::block_range(i)]
+    fn as_slice(&self) -> &[u64] {
+        self
     }
 
-    /// Returns a mutable reference to the raw data for the `i`th block in the `BlockedBitVec`
     #[inline]
-    pub fn get_block_mut(&mut self, index: usize) -> &mut [u64] {
-        &mut self.bits[Self::block_range(index)]
+    fn heap_bytes(&self) -> usize {
+        self.capacity() * std::mem::size_of::<u64>()
+    }
+
+    fn pad_to(mut self, block_len: usize) -> Self {
+        let r = self.len() % block_len;
+        if r != 0 {
+            self.extend(vec![0; block_len - r]);
+        }
+        self.shrink_to_fit();
+        self
+    }
+}
+
+impl BitStorageMut for Vec<u64> {
+    #[inline]
+    fn as_mut_slice(&mut self) -> &mut [u64] {
+        self
+    }
+}
+
+impl BitStorage for Box<[u64]> {
+    #[inline]
+    fn as_slice(&self) -> &[u64] {
+        self
+    }
+
+    #[inline]
+    fn heap_bytes(&self) -> usize {
+        std::mem::size_of_val::<[u64]>(self)
+    }
+}
+
+impl BitStorageMut for Box<[u64]> {
+    #[inline]
+    fn as_mut_slice(&mut self) -> &mut [u64] {
+        self
+    }
+}
+
+impl BitStorage for Arc<[u64]> {
+    #[inline]
+    fn as_slice(&self) -> &[u64] {
+        self
+    }
+
+    /// The bits and the reference counts, though they are shared with all clones.
+    #[inline]
+    fn heap_bytes(&self) -> usize {
+        std::mem::size_of_val::<[u64]>(self) + 2 * std::mem::size_of::<usize>()
+    }
+}
+
+impl BitStorage for Cow<'_, [u64]> {
+    #[inline]
+    fn as_slice(&self) -> &[u64] {
+        self
+    }
+
+    #[inline]
+    fn heap_bytes(&self) -> usize {
+        match self {
+            Cow::Borrowed(_) => 0,
+            Cow::Owned(bits) => bits.heap_bytes(),
+        }
+    }
+
+    fn pad_to(self, block_len: usize) -> Self {
+        match self.len() % block_len {
+            0 => self,
+            _ => Cow::Owned(self.into_owned().pad_to(block_len)),
+        }
+    }
+}
+
+/// Borrowed bits are copied on the first write, e.g. the first insert.
+impl BitStorageMut for Cow<'_, [u64]> {
+    #[inline]
+    fn as_mut_slice(&mut self) -> &mut [u64] {
+        self.to_mut()
+    }
+}
+
+impl BitStorage for &[u64] {
+    #[inline]
+    fn as_slice(&self) -> &[u64] {
+        self
+    }
+}
+
+impl BitStorage for &mut [u64] {
+    #[inline]
+    fn as_slice(&self) -> &[u64] {
+        self
+    }
+}
+
+impl BitStorageMut for &mut [u64] {
+    #[inline]
+    fn as_mut_slice(&mut self) -> &mut [u64] {
+        self
+    }
+}
+
+/// The number of `u64`s in a cache line.
+const CACHE_LINE_U64S: usize = 8;
+
+/// A 64 byte cache line of bits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C, align(64))]
+struct CacheLine([u64; CACHE_LINE_U64S]);
+
+/// A [`BitStorage`] whose bits start at a 64 byte boundary, i.e. the start of a cache line.
+///
+/// Since blocks are at most 64 bytes and their sizes divide 64, every block is then within one cache line,
+/// so checking an item loads one cache line per block, and vector loads of a block are aligned.
+/// A `Vec<u64>` is only guaranteed to be 8 byte aligned, so a 512 bit block of it may straddle two cache lines.
+///
+/// The bits are allocated in whole cache lines, so up to 56 bytes more than the bits may be allocated.
+///
+/// # Examples
+/// ```
+/// use fastbloom::{AlignedBits, BitStorage};
+///
+/// let bits = AlignedBits::from(vec![1, 2, 3]);
+/// assert_eq!(bits.as_slice(), &[1, 2, 3]);
+/// assert_eq!(bits.as_slice().as_ptr() as usize % 64, 0);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AlignedBits {
+    lines: Box<[CacheLine]>,
+    /// The number of `u64`s of bits. Any `u64`s after them in the last line are 0.
+    len: usize,
+}
+
+impl AlignedBits {
+    /// Returns `len` `u64`s of unset bits.
+    pub fn zeroed(len: usize) -> Self {
+        Self {
+            lines: vec![CacheLine::default(); len.div_ceil(CACHE_LINE_U64S)].into_boxed_slice(),
+            len,
+        }
+    }
+}
+
+impl From<&[u64]> for AlignedBits {
+    fn from(bits: &[u64]) -> Self {
+        let mut aligned = Self::zeroed(bits.len());
+        aligned.as_mut_slice().copy_from_slice(bits);
+        aligned
+    }
+}
+
+impl From<Vec<u64>> for AlignedBits {
+    fn from(bits: Vec<u64>) -> Self {
+        Self::from(bits.as_slice())
+    }
+}
+
+impl From<AlignedBits> for Vec<u64> {
+    fn from(bits: AlignedBits) -> Self {
+        bits.as_slice().to_vec()
+    }
+}
+
+impl BitStorage for AlignedBits {
+    #[inline]
+    fn as_slice(&self) -> &[u64] {
+        // SAFETY: the lines are `len` or more initialized `u64`s, laid out contiguously as `[u64; 8]`s.
+        unsafe { std::slice::from_raw_parts(self.lines.as_ptr().cast::<u64>(), self.len) }
+    }
+
+    #[inline]
+    fn heap_bytes(&self) -> usize {
+        std::mem::size_of_val::<[CacheLine]>(&self.lines)
+    }
+
+    /// Block lengths divide the length of a cache line, so the padding always fits in the last line.
+    fn pad_to(mut self, block_len: usize) -> Self {
+        self.len = self.len.next_multiple_of(block_len);
+        self
+    }
+}
+
+impl BitStorageMut for AlignedBits {
+    #[inline]
+    fn as_mut_slice(&mut self) -> &mut [u64] {
+        // SAFETY: as for `as_slice`, and the lines are borrowed mutably.
+        unsafe { std::slice::from_raw_parts_mut(self.lines.as_mut_ptr().cast::<u64>(), self.len) }
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, T> BlockedBitVec<BLOCK_SIZE_BITS, T> {
+    /// Block size in u64s
+    const BLOCK_SIZE: usize = BLOCK_SIZE_BITS / 64;
+    /// Used to shift u64 index
+    const LOG2_BLOCK_SIZE: u32 = u32::ilog2(Self::BLOCK_SIZE as u32);
+
+    #[inline]
+    const fn block_range(index: usize) -> Range<usize> {
+        let block_index = index * Self::BLOCK_SIZE;
+        block_index..(block_index + Self::BLOCK_SIZE)
     }
 
     /// Returns a bit "coordinate" (u64 and bit index pair) from a index in a block, `bit_index`.
@@ -65,7 +310,10 @@ impl<const BLOCK_SIZE_BITS: usize> BlockedBitVec<BLOCK_SIZE_BITS> {
         (index, bit)
     }
 
-    /// Sets the `bit_index`th bit in the block to 1.
+    /// Sets the `bit_index`th bit in the block to 1, returning `true` if it was already 1.
+    ///
+    /// `block` is a block of this block size, e.g. from [`get_block_mut`](Self::get_block_mut),
+    /// and `bit_index` is less than `BLOCK_SIZE_BITS`.
     #[inline]
     pub fn set_for_block(block: &mut [u64], bit_index: usize) -> bool {
         let (index, bit) = Self::coordinate(bit_index);
@@ -75,34 +323,553 @@ impl<const BLOCK_SIZE_BITS: usize> BlockedBitVec<BLOCK_SIZE_BITS> {
     }
 
     /// Returns true if the `bit_index`th in the block is 1.
+    ///
+    /// `block` is a block of this block size, e.g. from [`get_block`](Self::get_block),
+    /// and `bit_index` is less than `BLOCK_SIZE_BITS`.
     #[inline]
     pub fn check_for_block(block: &[u64], bit_index: usize) -> bool {
         let (index, bit) = Self::coordinate(bit_index);
         block[index] & bit > 0
     }
+}
+
+impl<const BLOCK_SIZE_BITS: usize> BlockedBitVec<BLOCK_SIZE_BITS, &'static [u64]> {
+    /// Creates a `BlockedBitVec` from `bits` in const contexts.
+    ///
+    /// # Panics
+    /// Panics, at compile time in const contexts, if `bits` is empty or not a multiple of the block size.
+    pub(crate) const fn from_static(bits: &'static [u64]) -> Self {
+        assert!(
+            !bits.is_empty() && bits.len().is_multiple_of(Self::BLOCK_SIZE),
+            "the bits must be a positive number of whole blocks"
+        );
+        Self { bits }
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, T: BitStorage> BlockedBitVec<BLOCK_SIZE_BITS, T> {
+    /// Creates a `BlockedBitVec` from `bits`, padded to fit the block size if the storage can grow.
+    ///
+    /// # Panics
+    /// Panics if the length of `bits` is not a multiple of the block size and the storage cannot grow.
+    pub fn from_storage(bits: T) -> Self {
+        Self::try_from_storage(bits).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Creates a `BlockedBitVec` from `bits`, padded to fit the block size if the storage can grow.
+    ///
+    /// Returns an error if the length of `bits` is not a multiple of the block size and the storage cannot grow.
+    pub fn try_from_storage(bits: T) -> Result<Self, FastbloomError> {
+        let bits = bits.pad_to(Self::BLOCK_SIZE);
+        let num_u64s = bits.as_slice().len();
+        if num_u64s % Self::BLOCK_SIZE != 0 {
+            return Err(FastbloomError::NotWholeBlocks {
+                num_u64s,
+                block_size_bits: BLOCK_SIZE_BITS,
+            });
+        }
+        Ok(Self { bits })
+    }
+
+    /// Converts the storage of the bits with `f`, which must keep their contents.
+    #[inline]
+    pub fn map_storage<U: BitStorage>(
+        self,
+        f: impl FnOnce(T) -> U,
+    ) -> BlockedBitVec<BLOCK_SIZE_BITS, U> {
+        BlockedBitVec::from_storage(f(self.bits))
+    }
+
+    /// Returns the storage of the bits.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.bits
+    }
+
+    /// The number of blocks in the `BlockedBitVector`
+    #[inline]
+    pub fn num_blocks(&self) -> usize {
+        self.bits.as_slice().len() >> Self::LOG2_BLOCK_SIZE
+    }
+
+    /// Returns a reference to the raw data for the `i`th block in the `BlockedBitVec`
+    ///
+    /// # Panics
+    /// Panics if `i` is not less than [`num_blocks`](Self::num_blocks).
+    #[inline]
+    pub fn get_block(&self, i: usize) -> &[u64] {
+        &self.bits.as_slice()[Self::block_range(i)]
+    }
+
+    /// Returns true if the `bit_index`th bit of the `BlockedBitVec` is 1: bit `bit_index % 64`,
+    /// from the least significant, of `u64` `bit_index / 64`.
+    ///
+    /// # Panics
+    /// Panics if `bit_index` is not less than the number of bits.
+    #[inline]
+    pub fn test_bit(&self, bit_index: usize) -> bool {
+        let (index, bit) = Self::coordinate(bit_index);
+        self.bits.as_slice()[index] & bit > 0
+    }
 
+    /// Returns all the `u64`s of the `BlockedBitVec`, block after block.
     #[inline]
     pub fn as_slice(&self) -> &[u64] {
-        &self.bits
+        self.bits.as_slice()
+    }
+
+    /// Returns the number of bytes the bits take on the heap. See [`BitStorage::heap_bytes`].
+    #[inline]
+    pub fn heap_bytes(&self) -> usize {
+        self.bits.heap_bytes()
     }
+}
 
+impl<const BLOCK_SIZE_BITS: usize, T: BitStorageMut> BlockedBitVec<BLOCK_SIZE_BITS, T> {
+    /// Returns a mutable reference to the raw data for the `i`th block in the `BlockedBitVec`
+    ///
+    /// # Panics
+    /// Panics if `index` is not less than [`num_blocks`](Self::num_blocks).
+    #[inline]
+    pub fn get_block_mut(&mut self, index: usize) -> &mut [u64] {
+        &mut self.bits.as_mut_slice()[Self::block_range(index)]
+    }
+
+    /// Sets the `bit_index`th bit of the `BlockedBitVec`, numbered as for [`test_bit`](Self::test_bit), to 1,
+    /// returning `true` if it was already 1.
+    ///
+    /// # Panics
+    /// Panics if `bit_index` is not less than the number of bits.
+    #[inline]
+    pub fn set_bit(&mut self, bit_index: usize) -> bool {
+        let (index, bit) = Self::coordinate(bit_index);
+        let word = &mut self.bits.as_mut_slice()[index];
+        let previously_set = *word & bit > 0;
+        *word |= bit;
+        previously_set
+    }
+
+    /// Returns all the `u64`s of the `BlockedBitVec`, to modify.
+    #[inline]
+    pub fn as_mut_slice(&mut self) -> &mut [u64] {
+        self.bits.as_mut_slice()
+    }
+
+    /// Sets all the bits to 0.
     #[inline]
     pub fn clear(&mut self) {
-        for i in 0..self.bits.len() {
-            self.bits[i] = 0;
+        for x in self.bits.as_mut_slice() {
+            *x = 0;
         }
     }
+
+    /// Sets the bits of `other` in this `BlockedBitVec`, 256 bits at a time.
+    #[inline]
+    pub fn union<U: BitStorage>(&mut self, other: &BlockedBitVec<BLOCK_SIZE_BITS, U>) {
+        simd::union(self.bits.as_mut_slice(), other.as_slice());
+    }
+
+    /// Clears the bits of this `BlockedBitVec` that are not set in `other`, 256 bits at a time.
+    #[inline]
+    pub fn intersect<U: BitStorage>(&mut self, other: &BlockedBitVec<BLOCK_SIZE_BITS, U>) {
+        simd::intersect(self.bits.as_mut_slice(), other.as_slice());
+    }
 }
 
+impl<const BLOCK_SIZE_BITS: usize, T: BitStorage> PartialEq for BlockedBitVec<BLOCK_SIZE_BITS, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+impl<const BLOCK_SIZE_BITS: usize, T: BitStorage> Eq for BlockedBitVec<BLOCK_SIZE_BITS, T> {}
+
 impl<const BLOCK_SIZE_BITS: usize> From<Vec<u64>> for BlockedBitVec<BLOCK_SIZE_BITS> {
-    fn from(mut bits: Vec<u64>) -> Self {
-        let num_u64s_per_block = BLOCK_SIZE_BITS / 64;
-        let r = bits.len() % num_u64s_per_block;
-        if r != 0 {
-            bits.extend(vec![0; num_u64s_per_block - r]);
+    fn from(bits: Vec<u64>) -> Self {
+        Self::from_storage(bits)
+    }
+}
+
+/// The bits of an archived `BlockedBitVec` are not a whole, non-zero number of blocks.
+#[cfg(feature = "rkyv")]
+#[derive(Debug)]
+struct InvalidArchivedBits;
+
+#[cfg(feature = "rkyv")]
+impl std::fmt::Display for InvalidArchivedBits {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("the number of u64s must be a non-zero multiple of the block size")
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl std::error::Error for InvalidArchivedBits {}
+
+// SAFETY: `verify` only succeeds if the bits are whole blocks, as `BlockedBitVec::from_storage` asserts.
+#[cfg(feature = "rkyv")]
+unsafe impl<const BLOCK_SIZE_BITS: usize, C> rkyv::bytecheck::Verify<C>
+    for ArchivedBlockedBitVec<BLOCK_SIZE_BITS, Vec<u64>>
+where
+    C: rkyv::rancor::Fallible + ?Sized,
+    C::Error: rkyv::rancor::Source,
+{
+    fn verify(&self, _: &mut C) -> Result<(), C::Error> {
+        let blocks = self.bits.chunks_exact(BLOCK_SIZE_BITS / 64);
+        if self.bits.is_empty() || !blocks.remainder().is_empty() {
+            rkyv::rancor::fail!(InvalidArchivedBits);
         }
-        bits.shrink_to_fit();
-        Self { bits }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl<const BLOCK_SIZE_BITS: usize> ArchivedBlockedBitVec<BLOCK_SIZE_BITS, Vec<u64>> {
+    /// Returns the archived bits, borrowed if archived `u64`s are laid out as native ones, e.g. on
+    /// little-endian targets by default, and copied otherwise.
+    pub(crate) fn to_bit_vec(&self) -> BlockedBitVec<BLOCK_SIZE_BITS, Cow<'_, [u64]>> {
+        type ArchivedU64 = rkyv::Archived<u64>;
+        const PROBE: u64 = 0x0102_0304_0506_0708;
+        let archived = self.bits.as_slice();
+        let native = std::mem::align_of::<ArchivedU64>() == std::mem::align_of::<u64>()
+            // SAFETY: every archived `u64` is 8 bytes, which are all initialized.
+            && unsafe { std::mem::transmute::<ArchivedU64, u64>(ArchivedU64::from_native(PROBE)) }
+                == PROBE;
+        let bits = if native {
+            // SAFETY: archived `u64`s have the size, alignment, and byte order of `u64`s.
+            Cow::Borrowed(unsafe {
+                std::slice::from_raw_parts(archived.as_ptr().cast::<u64>(), archived.len())
+            })
+        } else {
+            Cow::Owned(archived.iter().map(|x| x.to_native()).collect())
+        };
+        BlockedBitVec::from_storage(bits)
+    }
+}
+
+/// Serialized as a borsh `Vec<u64>`: the number of `u64`s as a little-endian `u32`, followed by the little-endian `u64`s.
+#[cfg(feature = "borsh")]
+impl<const BLOCK_SIZE_BITS: usize, T: BitStorage> borsh::BorshSerialize
+    for BlockedBitVec<BLOCK_SIZE_BITS, T>
+{
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.as_slice().serialize(writer)
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl<const BLOCK_SIZE_BITS: usize, T: From<Vec<u64>>> borsh::BorshDeserialize
+    for BlockedBitVec<BLOCK_SIZE_BITS, T>
+{
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let bits = Vec::<u64>::deserialize_reader(reader)?;
+        if bits.is_empty() || !bits.chunks_exact(Self::BLOCK_SIZE).remainder().is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "The number of u64s must be a non-zero multiple of the block size.",
+            ));
+        }
+        Ok(Self {
+            bits: T::from(bits),
+        })
+    }
+}
+
+/// The serialized form of a `BlockedBitVec` in human-readable formats: its [`Words`], or no words and its sparse encoding.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename = "BlockedBitVec")]
+struct SerdeBits<T> {
+    bits: T,
+    #[serde(default)]
+    sparse: Option<SparseBits>,
+}
+
+/// The serialized form of a `BlockedBitVec` in binary formats, which can't leave out a field,
+/// so it has no sparse encoding: its `u64`s, as serialized before there was one, or its [`Words`] with
+/// [`FixedWidth`](crate::FixedWidth).
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename = "BlockedBitVec")]
+struct DenseBits<T> {
+    bits: T,
+}
+
+/// Serializes `bytes` as a base64 string in human-readable formats, e.g. JSON,
+/// otherwise as a byte string, which stays compact in formats with variable-length integers, e.g. postcard.
+#[cfg(feature = "serde")]
+fn serialize_byte_str<S: serde::Serializer>(
+    bytes: &[u8],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    use base64::Engine;
+
+    if serializer.is_human_readable() {
+        serializer.serialize_str(&base64::engine::general_purpose::STANDARD.encode(bytes))
+    } else {
+        serializer.serialize_bytes(bytes)
+    }
+}
+
+/// Dense bits to serialize, as a byte string of little-endian `u64`s.
+#[cfg(feature = "serde")]
+struct Words<'a>(&'a [u64]);
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Words<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let bytes: Vec<u8> = self.0.iter().flat_map(|x| x.to_le_bytes()).collect();
+        serialize_byte_str(&bytes, serializer)
+    }
+}
+
+/// A `u8` or `u64` that is serialized in little-endian byte strings.
+#[cfg(feature = "serde")]
+trait LeWord: Sized + for<'de> serde::Deserialize<'de> {
+    const SIZE: usize;
+    fn from_le(bytes: &[u8]) -> Self;
+}
+
+#[cfg(feature = "serde")]
+impl LeWord for u8 {
+    const SIZE: usize = 1;
+    fn from_le(bytes: &[u8]) -> Self {
+        bytes[0]
+    }
+}
+
+#[cfg(feature = "serde")]
+impl LeWord for u64 {
+    const SIZE: usize = 8;
+    fn from_le(bytes: &[u8]) -> Self {
+        u64::from_le_bytes(bytes.try_into().unwrap())
+    }
+}
+
+/// Deserialized words, from a byte string from [`serialize_byte_str`], or a sequence as serialized before it.
+#[cfg(feature = "serde")]
+struct LeVec<W>(Vec<W>);
+
+#[cfg(feature = "serde")]
+impl<'de, W: LeWord> serde::Deserialize<'de> for LeVec<W> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct LeVecVisitor<W>(std::marker::PhantomData<W>);
+
+        impl<'de, W: LeWord> serde::de::Visitor<'de> for LeVecVisitor<W> {
+            type Value = LeVec<W>;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "a base64 or byte string of {}-byte words", W::SIZE)
+            }
+
+            fn visit_str<E: serde::de::Error>(self, s: &str) -> Result<LeVec<W>, E> {
+                use base64::Engine;
+
+                let bytes = base64::engine::general_purpose::STANDARD
+                    .decode(s)
+                    .map_err(E::custom)?;
+                self.visit_bytes(&bytes)
+            }
+
+            fn visit_bytes<E: serde::de::Error>(self, bytes: &[u8]) -> Result<LeVec<W>, E> {
+                let words = bytes.chunks_exact(W::SIZE);
+                if !words.remainder().is_empty() {
+                    return Err(E::invalid_length(bytes.len(), &self));
+                }
+                Ok(LeVec(words.map(W::from_le).collect()))
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                self,
+                mut seq: A,
+            ) -> Result<LeVec<W>, A::Error> {
+                let mut words = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+                while let Some(word) = seq.next_element()? {
+                    words.push(word);
+                }
+                Ok(LeVec(words))
+            }
+        }
+
+        let visitor = LeVecVisitor(std::marker::PhantomData);
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(visitor)
+        } else {
+            deserializer.deserialize_bytes(visitor)
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+fn deserialize_le_vec<'de, D: serde::Deserializer<'de>, W: LeWord>(
+    deserializer: D,
+) -> Result<Vec<W>, D::Error> {
+    serde::Deserialize::deserialize(deserializer).map(|LeVec(words)| words)
+}
+
+/// The set bits of a lightly filled bit vector, delta-coded.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SparseBits {
+    /// The number of `u64`s.
+    len: usize,
+    /// For each set bit, the number of unset bits since the previous set bit, as a LEB128 varint.
+    #[serde(
+        serialize_with = "serialize_byte_str",
+        deserialize_with = "deserialize_le_vec"
+    )]
+    gaps: Vec<u8>,
+}
+
+#[cfg(feature = "serde")]
+impl SparseBits {
+    /// Encodes `bits`, or returns `None` if the encoding is not smaller than the `u64`s.
+    fn encode(bits: &[u64]) -> Option<Self> {
+        let num_ones: usize = bits.iter().map(|x| x.count_ones() as usize).sum();
+        // Every gap takes at least one byte.
+        if num_ones >= 8 * bits.len() {
+            return None;
+        }
+        let mut gaps = Vec::with_capacity(num_ones);
+        let mut next = 0;
+        for (i, &x) in bits.iter().enumerate() {
+            let mut x = x;
+            while x != 0 {
+                let index = (i as u64) * 64 + u64::from(x.trailing_zeros());
+                let mut gap = index - next;
+                while gap >= 0x80 {
+                    gaps.push(gap as u8 | 0x80);
+                    gap >>= 7;
+                }
+                gaps.push(gap as u8);
+                next = index + 1;
+                x &= x - 1;
+            }
+        }
+        (gaps.len() < 8 * bits.len()).then_some(Self {
+            len: bits.len(),
+            gaps,
+        })
+    }
+
+    fn decode(&self) -> Result<Vec<u64>, &'static str> {
+        let mut bits = Vec::new();
+        bits.try_reserve_exact(self.len)
+            .map_err(|_| "too many bits")?;
+        bits.resize(self.len, 0);
+        let num_bits = self.len as u64 * 64;
+        let mut next = 0u64;
+        let mut gaps = self.gaps.iter();
+        while gaps.len() > 0 {
+            let mut gap = 0u64;
+            for shift in (0..64).step_by(7) {
+                let byte = gaps.next().ok_or("truncated gap")?;
+                gap |= u64::from(byte & 0x7f) << shift;
+                if byte & 0x80 == 0 {
+                    break;
+                }
+            }
+            let index = next
+                .checked_add(gap)
+                .filter(|&index| index < num_bits)
+                .ok_or("set bit out of range")?;
+            bits[(index / 64) as usize] |= 1 << (index % 64);
+            next = index + 1;
+        }
+        Ok(bits)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<const BLOCK_SIZE_BITS: usize, T: BitStorage> serde::Serialize
+    for BlockedBitVec<BLOCK_SIZE_BITS, T>
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let bits = self.as_slice();
+        if !serializer.is_human_readable() {
+            return DenseBits { bits }.serialize(serializer);
+        }
+        let serde_bits = match SparseBits::encode(bits) {
+            Some(sparse) => SerdeBits {
+                bits: Words(&[]),
+                sparse: Some(sparse),
+            },
+            None => SerdeBits {
+                bits: Words(bits),
+                sparse: None,
+            },
+        };
+        serde_bits.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, const BLOCK_SIZE_BITS: usize, T: From<Vec<u64>>> serde::Deserialize<'de>
+    for BlockedBitVec<BLOCK_SIZE_BITS, T>
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let bits = if deserializer.is_human_readable() {
+            let SerdeBits { bits, sparse } = SerdeBits::<LeVec<u64>>::deserialize(deserializer)?;
+            match sparse {
+                Some(sparse) => sparse.decode().map_err(D::Error::custom)?,
+                None => bits.0,
+            }
+        } else {
+            DenseBits::<Vec<u64>>::deserialize(deserializer)?.bits
+        };
+        Self::from_serde_bits(bits).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<const BLOCK_SIZE_BITS: usize, T> BlockedBitVec<BLOCK_SIZE_BITS, T> {
+    fn from_serde_bits(bits: Vec<u64>) -> Result<Self, &'static str>
+    where
+        T: From<Vec<u64>>,
+    {
+        if bits.is_empty() || !bits.chunks_exact(Self::BLOCK_SIZE).remainder().is_empty() {
+            return Err("The number of u64s must be a non-zero multiple of the block size.");
+        }
+        Ok(Self {
+            bits: T::from(bits),
+        })
+    }
+
+    /// Serializes the bits as a byte string of little-endian `u64`s in binary formats, for [`FixedWidth`](crate::FixedWidth).
+    pub(crate) fn serialize_fixed_width<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        T: BitStorage,
+    {
+        if serializer.is_human_readable() {
+            return serde::Serialize::serialize(self, serializer);
+        }
+        serde::Serialize::serialize(
+            &DenseBits {
+                bits: Words(self.as_slice()),
+            },
+            serializer,
+        )
+    }
+
+    /// Deserializes bits serialized with [`serialize_fixed_width`](Self::serialize_fixed_width).
+    pub(crate) fn deserialize_fixed_width<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error>
+    where
+        T: From<Vec<u64>>,
+    {
+        use serde::de::Error;
+
+        if deserializer.is_human_readable() {
+            return serde::Deserialize::deserialize(deserializer);
+        }
+        let bits = <DenseBits<LeVec<u64>> as serde::Deserialize>::deserialize(deserializer)?
+            .bits
+            .0;
+        Self::from_serde_bits(bits).map_err(D::Error::custom)
     }
 }
 
@@ -128,6 +895,43 @@ mod tests {
         }
     }
 
+    #[test]
+    fn aligned_bits() {
+        for len in [0usize, 1, 7, 8, 9, 100] {
+            let bits: Vec<u64> = (1..=len as u64).collect();
+            let aligned = AlignedBits::from(bits.clone());
+            assert_eq!(aligned.as_slice(), bits);
+            assert_eq!(aligned.as_slice().as_ptr() as usize % 64, 0);
+            assert_eq!(aligned.heap_bytes(), 64 * len.div_ceil(8));
+            assert_eq!(Vec::from(aligned), bits);
+        }
+        let b = BlockedBitVec::<256, AlignedBits>::from_storage(AlignedBits::from(vec![1; 9]));
+        assert_eq!(b.num_blocks(), 3);
+        assert_eq!(b.as_slice()[8..], [1, 0, 0, 0]);
+        let b = BlockedBitVec::<512, AlignedBits>::from_storage(AlignedBits::from(vec![1; 9]));
+        assert_eq!(b.as_slice()[8..], [1, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn bit_indexes_match_blocks() {
+        fn bit_indexes_match_blocks_<const N: usize>() {
+            let mut bits = BlockedBitVec::<N>::from(vec![0; 32]);
+            for i in (0..32 * 64).step_by(7) {
+                assert!(!bits.set_bit(i));
+                assert!(bits.set_bit(i));
+                assert!(BlockedBitVec::<N>::check_for_block(
+                    bits.get_block(i / N),
+                    i % N
+                ));
+            }
+            assert!((0..32 * 64).all(|i| bits.test_bit(i) == i.is_multiple_of(7)));
+        }
+        bit_indexes_match_blocks_::<64>();
+        bit_indexes_match_blocks_::<128>();
+        bit_indexes_match_blocks_::<256>();
+        bit_indexes_match_blocks_::<512>();
+    }
+
     #[test]
     fn test_only_random_inserts_are_contained() {
         let mut vec = BlockedBitVec::<64>::from(vec![0; 80]);
@@ -149,4 +953,118 @@ mod tests {
             assert!(BlockedBitVec::<64>::check_for_block(block_mut, bit_index));
         }
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_sparse_when_smaller() {
+        let mut bits = vec![0u64; 64];
+        bits[0] = 0b1011;
+        bits[3] = 1 << 63;
+        bits[63] = 1 << 5;
+        let vec = BlockedBitVec::<512>::from(bits);
+        let json = serde_json::to_value(&vec).unwrap();
+        assert_eq!(json["bits"], "");
+        // gaps of 0, 0, 1, 251, and 3781 unset bits: [0, 0, 1, 0xfb, 0x01, 0xc5, 0x1d]
+        assert_eq!(
+            json["sparse"],
+            serde_json::json!({"len": 64, "gaps": "AAAB+wHFHQ=="})
+        );
+        let back: BlockedBitVec<512> = serde_json::from_value(json).unwrap();
+        assert_eq!(back, vec);
+
+        // serialized before base64 encoding
+        let back: BlockedBitVec<512> = serde_json::from_value(serde_json::json!({
+            "bits": [],
+            "sparse": {"len": 64, "gaps": [0, 0, 1, 0xfb, 0x01, 0xc5, 0x1d]}
+        }))
+        .unwrap();
+        assert_eq!(back, vec);
+
+        let empty = BlockedBitVec::<512>::from(vec![0u64; 8]);
+        let back: BlockedBitVec<512> =
+            serde_json::from_value(serde_json::to_value(&empty).unwrap()).unwrap();
+        assert_eq!(back, empty);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_dense_when_smaller() {
+        let vec = BlockedBitVec::<128>::from(vec![0x5555_5555_5555_5555u64; 4]);
+        let json = serde_json::to_value(&vec).unwrap();
+        assert_eq!(json["sparse"], serde_json::Value::Null);
+        // little-endian `u64`s, base64 encoded
+        assert_eq!(json["bits"], "VVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVVU=");
+        let back: BlockedBitVec<128> = serde_json::from_value(json).unwrap();
+        assert_eq!(back, vec);
+
+        let back: BlockedBitVec<128> =
+            serde_json::from_str(r#"{"bits":"AQAAAAAAAAACAAAAAAAAAA=="}"#).unwrap();
+        assert_eq!(back.as_slice(), [1, 2]);
+        // serialized before sparse and base64 encoding
+        let back: BlockedBitVec<128> = serde_json::from_str(r#"{"bits":[1,2]}"#).unwrap();
+        assert_eq!(back.as_slice(), [1, 2]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_rejects_malformed() {
+        for json in [
+            r#"{"bits":[1]}"#,
+            r#"{"bits":[]}"#,
+            r#"{"bits":""}"#,
+            r#"{"bits":[],"sparse":{"len":0,"gaps":""}}"#,
+            r#"{"bits":[],"sparse":{"len":1,"gaps":[]}}"#,
+            r#"{"bits":[],"sparse":{"len":2,"gaps":[128]}}"#,
+            r#"{"bits":[],"sparse":{"len":2,"gaps":[128,1]}}"#,
+            r#"{"bits":"AQ=="}"#,
+            r#"{"bits":"not base64!"}"#,
+        ] {
+            assert!(
+                serde_json::from_str::<BlockedBitVec<128>>(json).is_err(),
+                "{json}"
+            );
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_binary_as_u64s() {
+        // as derived before there were other encodings
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct Derived {
+            bits: Vec<u64>,
+        }
+
+        let vec = BlockedBitVec::<512>::from(vec![u64::MAX; 8]);
+        let bytes = postcard::to_allocvec(&vec).unwrap();
+        let derived = Derived {
+            bits: vec.as_slice().to_vec(),
+        };
+        assert_eq!(bytes, postcard::to_allocvec(&derived).unwrap());
+        let back: BlockedBitVec<512> = postcard::from_bytes(&bytes).unwrap();
+        assert_eq!(back, vec);
+
+        let one_word = postcard::to_allocvec(&Derived { bits: vec![1] }).unwrap();
+        assert!(postcard::from_bytes::<BlockedBitVec<512>>(&one_word).is_err());
+        assert!(postcard::from_bytes::<BlockedBitVec<512>>(&[0]).is_err());
+    }
+
+    #[cfg(feature = "borsh")]
+    #[test]
+    fn borsh_round_trip() {
+        let vec = BlockedBitVec::<128>::from(vec![1, 0x0102_0304_0506_0708]);
+        let bytes = borsh::to_vec(&vec).unwrap();
+        assert_eq!(bytes[..4], [2, 0, 0, 0]);
+        assert_eq!(bytes[4..12], 1u64.to_le_bytes());
+        assert_eq!(bytes[12..], 0x0102_0304_0506_0708u64.to_le_bytes());
+        assert_eq!(
+            borsh::from_slice::<BlockedBitVec<128>>(&bytes).unwrap(),
+            vec
+        );
+
+        let one_word = borsh::to_vec(&[1u64][..]).unwrap();
+        assert!(borsh::from_slice::<BlockedBitVec<128>>(&one_word).is_err());
+        let empty = borsh::to_vec(&[0u64; 0][..]).unwrap();
+        assert!(borsh::from_slice::<BlockedBitVec<128>>(&empty).is_err());
+    }
 }
@@ -0,0 +1,141 @@
+use crate::{BitStorage, BloomFilter};
+use std::hash::BuildHasher;
+
+/// The distribution of the number of bits set per block of a [`BloomFilter`].
+///
+/// Constructed via [`BloomFilter::block_stats`]. Items are spread over the blocks by their hashes,
+/// so blocks fill about evenly, with a spread that shrinks relative to the mean as items are inserted.
+/// A wide or lopsided distribution points to a problem: a `max` near the block size means the filter is
+/// under-sized and its fullest blocks have high false positive rates, and a `histogram` with a second peak,
+/// or blocks far from the mean, means items are not hashed uniformly, e.g. by a poor hasher or skewed keys.
+///
+/// # Examples
+/// ```
+/// use fastbloom::BloomFilter;
+///
+/// let bloom = BloomFilter::with_false_pos(0.01).block_size_64().items(0..10_000);
+/// let stats = bloom.block_stats();
+/// assert_eq!(stats.histogram().len(), 65);
+/// assert_eq!(stats.histogram().iter().sum::<u64>(), bloom.num_blocks() as u64);
+/// assert!(stats.min() as f64 <= stats.mean() && stats.mean() <= stats.max() as f64);
+/// assert!(stats.max() < 64);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockStats {
+    min: u32,
+    max: u32,
+    mean: f64,
+    /// The number of blocks with each number of bits set, from 0 to the block size.
+    histogram: Vec<u64>,
+}
+
+impl BlockStats {
+    /// Returns the fewest bits set in any block.
+    #[inline]
+    pub fn min(&self) -> u32 {
+        self.min
+    }
+
+    /// Returns the most bits set in any block.
+    #[inline]
+    pub fn max(&self) -> u32 {
+        self.max
+    }
+
+    /// Returns the mean number of bits set per block.
+    #[inline]
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    /// Returns the number of blocks with each number of bits set: the `i`th count is of the blocks with `i` bits set,
+    /// for `i` from 0 to the block size.
+    #[inline]
+    pub fn histogram(&self) -> &[u64] {
+        &self.histogram
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher, T: BitStorage>
+    BloomFilter<BLOCK_SIZE_BITS, S, T>
+{
+    /// Returns the number of bits set in each block, in order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let mut bloom = BloomFilter::with_num_bits(1024).hashes(4);
+    /// bloom.insert(&1);
+    /// assert_eq!(bloom.block_popcounts().collect::<Vec<_>>().len(), 2);
+    /// assert_eq!(bloom.block_popcounts().sum::<u32>(), 4);
+    /// ```
+    pub fn block_popcounts(&self) -> impl Iterator<Item = u32> + '_ {
+        self.as_slice()
+            .chunks_exact(BLOCK_SIZE_BITS / 64)
+            .map(|block| block.iter().map(|x| x.count_ones()).sum())
+    }
+
+    /// Returns the distribution of the number of bits set per block, to check how evenly the filter is filled.
+    /// See [`BlockStats`].
+    pub fn block_stats(&self) -> BlockStats {
+        let mut histogram = vec![0; BLOCK_SIZE_BITS + 1];
+        let (mut min, mut max, mut total) = (u32::MAX, 0, 0u64);
+        for ones in self.block_popcounts() {
+            histogram[ones as usize] += 1;
+            min = min.min(ones);
+            max = max.max(ones);
+            total += ones as u64;
+        }
+        BlockStats {
+            min,
+            max,
+            mean: total as f64 / self.num_blocks() as f64,
+            histogram,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_stats() {
+        let empty = BloomFilter::with_num_bits(4096).block_size_128().hashes(4);
+        let stats = empty.block_stats();
+        assert_eq!((stats.min(), stats.max(), stats.mean()), (0, 0, 0.0));
+        assert_eq!(stats.histogram()[0], 32);
+        assert_eq!(stats.histogram().len(), 129);
+
+        let mut bloom = empty.clone();
+        bloom.as_mut_slice()[0] = u64::MAX;
+        bloom.as_mut_slice()[3] = 0b111;
+        let stats = bloom.block_stats();
+        assert_eq!((stats.min(), stats.max()), (0, 64));
+        assert_eq!(stats.mean(), 67.0 / 32.0);
+        assert_eq!(stats.histogram()[64], 1);
+        assert_eq!(stats.histogram()[3], 1);
+        assert_eq!(stats.histogram()[0], 30);
+        assert_eq!(
+            bloom.block_popcounts().take(3).collect::<Vec<_>>(),
+            [64, 3, 0]
+        );
+    }
+
+    #[test]
+    fn under_sized() {
+        let empty = BloomFilter::with_num_bits(1 << 16)
+            .block_size_64()
+            .seed(&1)
+            .hashes(4);
+        let (mut sized, mut under_sized) = (empty.clone(), empty);
+        sized.extend(0..2000);
+        under_sized.extend(0..100_000);
+        let (sized, under_sized) = (sized.block_stats(), under_sized.block_stats());
+        assert!(sized.max() < 32 && sized.mean() < 10.0);
+        assert_eq!(under_sized.max(), 64);
+        assert!(under_sized.mean() > 60.0 && under_sized.histogram()[0] == 0);
+    }
+}
@@ -1,4 +1,15 @@
-use crate::{BloomFilter, BuildHasher, DefaultHasher};
+use crate::bit_vector::BlockedBitVec;
+#[cfg(feature = "blake3")]
+use crate::KeyedBlake3;
+#[cfg(feature = "wyhash")]
+use crate::WyHash;
+#[cfg(feature = "xxh3")]
+use crate::Xxh3;
+use crate::{
+    AlignedBits, AtomicCounters, BitStorage, BitStorageMut, BloomFilter, BuildHasher,
+    DefaultHasher, DynBloomFilter, FastbloomError, FixedHashesBloomFilter, IndexStrategy, Prefix,
+    RegisterBlockedBloomFilter, SipHash24, U32BloomFilter,
+};
 use std::hash::Hash;
 
 use crate::sparse_hash;
@@ -15,21 +26,28 @@ use crate::sparse_hash;
 /// let builder = BloomFilter::from_vec(vec![0; 8]);
 /// ```
 #[derive(Debug, Clone)]
-pub struct BuilderWithBits<const BLOCK_SIZE_BITS: usize = 512, S = DefaultHasher> {
-    pub(crate) data: Vec<u64>,
+pub struct BuilderWithBits<const BLOCK_SIZE_BITS: usize = 512, S = DefaultHasher, T = Vec<u64>> {
+    pub(crate) data: T,
     pub(crate) hasher: S,
+    pub(crate) two_choice: bool,
+    pub(crate) index_strategy: IndexStrategy,
+    pub(crate) max_rounds: u64,
+    pub(crate) prefix: Option<Prefix>,
 }
 
-impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> PartialEq
-    for BuilderWithBits<BLOCK_SIZE_BITS, S>
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher, T: BitStorage> PartialEq
+    for BuilderWithBits<BLOCK_SIZE_BITS, S, T>
 {
     fn eq(&self, other: &Self) -> bool {
-        self.data == other.data
+        self.data.as_slice() == other.data.as_slice()
     }
 }
-impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> Eq for BuilderWithBits<BLOCK_SIZE_BITS, S> {}
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher, T: BitStorage> Eq
+    for BuilderWithBits<BLOCK_SIZE_BITS, S, T>
+{
+}
 
-impl<const BLOCK_SIZE_BITS: usize> BuilderWithBits<BLOCK_SIZE_BITS> {
+impl<const BLOCK_SIZE_BITS: usize, T> BuilderWithBits<BLOCK_SIZE_BITS, DefaultHasher, T> {
     /// Sets the seed for this builder. The later constructed [`BloomFilter`]
     /// will use this seed when hashing items.
     ///
@@ -46,7 +64,9 @@ impl<const BLOCK_SIZE_BITS: usize> BuilderWithBits<BLOCK_SIZE_BITS> {
     }
 }
 
-impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> BuilderWithBits<BLOCK_SIZE_BITS, S> {
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher, T: BitStorage>
+    BuilderWithBits<BLOCK_SIZE_BITS, S, T>
+{
     /// Sets the hasher for this builder. The later constructed [`BloomFilter`] will use
     /// this hasher when inserting and checking items.
     ///
@@ -58,13 +78,181 @@ impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> BuilderWithBits<BLOCK_SIZE_BI
     ///
     /// let bloom = BloomFilter::with_num_bits(1024).hasher(RandomState::default()).hashes(4);
     /// ```
-    pub fn hasher<H: BuildHasher>(self, hasher: H) -> BuilderWithBits<BLOCK_SIZE_BITS, H> {
-        BuilderWithBits::<BLOCK_SIZE_BITS, H> {
+    pub fn hasher<H: BuildHasher>(self, hasher: H) -> BuilderWithBits<BLOCK_SIZE_BITS, H, T> {
+        BuilderWithBits::<BLOCK_SIZE_BITS, H, T> {
             data: self.data,
             hasher,
+            two_choice: self.two_choice,
+            index_strategy: self.index_strategy,
+            max_rounds: self.max_rounds,
+            prefix: self.prefix,
         }
     }
 
+    /// Sets the hasher for this builder to SipHash-2-4 keyed with `seed`, for filters fed by untrusted input.
+    /// See [`SipHash24`]; use `.hasher(SipHash24::default())` for a random key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_num_bits(1024).siphash24(&1).hashes(4);
+    /// ```
+    pub fn siphash24(self, seed: &u128) -> BuilderWithBits<BLOCK_SIZE_BITS, SipHash24, T> {
+        self.hasher(SipHash24::with_key(&seed.to_be_bytes()))
+    }
+
+    /// Sets the hasher for this builder to [`Xxh3`] with `seed`.
+    ///
+    /// Requires the `xxh3` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_num_bits(1024).xxh3(&1).hashes(4);
+    /// ```
+    #[cfg(feature = "xxh3")]
+    pub fn xxh3(self, seed: &u64) -> BuilderWithBits<BLOCK_SIZE_BITS, Xxh3, T> {
+        self.hasher(Xxh3::with_seed(*seed))
+    }
+
+    /// Sets the hasher for this builder to [`WyHash`] with `seed`.
+    ///
+    /// Requires the `wyhash` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_num_bits(1024).wyhash(&1).hashes(4);
+    /// ```
+    #[cfg(feature = "wyhash")]
+    pub fn wyhash(self, seed: &u64) -> BuilderWithBits<BLOCK_SIZE_BITS, WyHash, T> {
+        self.hasher(WyHash::with_seed(*seed))
+    }
+
+    /// Sets the hasher for this builder to [`KeyedBlake3`] with the secret `key`.
+    ///
+    /// Requires the `blake3` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_num_bits(1024).keyed_blake3(&[7; 32]).hashes(4);
+    /// ```
+    #[cfg(feature = "blake3")]
+    pub fn keyed_blake3(self, key: &[u8; 32]) -> BuilderWithBits<BLOCK_SIZE_BITS, KeyedBlake3, T> {
+        self.hasher(KeyedBlake3::new(key))
+    }
+
+    /// Enables "power of two choices" insertion for the later constructed [`BloomFilter`].
+    ///
+    /// Each item has two candidate blocks, and all of its bits are set in the one with fewer bits set.
+    /// Membership checks check both candidates. This evens out the number of bits set per block,
+    /// but since an item not in the filter can match either candidate, the false positive rate is
+    /// typically higher than without two choices, and membership checks are slower.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_num_bits(1024).block_size_64().two_choice().hashes(4);
+    /// ```
+    pub fn two_choice(mut self) -> Self {
+        self.two_choice = true;
+        self
+    }
+
+    /// Sets how the bit indexes of each item's single bit hashes are derived for the later constructed [`BloomFilter`].
+    /// The default is [`IndexStrategy::DoubleHashing`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::{BloomFilter, IndexStrategy};
+    ///
+    /// let bloom = BloomFilter::with_num_bits(1024)
+    ///     .index_strategy(IndexStrategy::EnhancedDoubleHashing)
+    ///     .hashes(4);
+    /// assert_eq!(bloom.index_strategy(), IndexStrategy::EnhancedDoubleHashing);
+    /// ```
+    pub fn index_strategy(mut self, strategy: IndexStrategy) -> Self {
+        self.index_strategy = strategy;
+        self
+    }
+
+    /// Caps the sparse hash of each item at `max_rounds` bits per `u64` for the later constructed [`BloomFilter`].
+    /// See [`BloomFilter::num_rounds`].
+    ///
+    /// Each item's hashes are split between single bit hashes and a sparse hash to minimize the work per item.
+    /// Sparse hashes with more bits per `u64` take less work per bit but set bits less independently, so a lower cap
+    /// sets more of the bits as single bits, for slower but more predictable hashing. Caps below the smallest sparse
+    /// hash for the block size, 8 bits for 512 bit blocks and 16 bits for others, disable it, as
+    /// [`no_sparse_hash`](Self::no_sparse_hash) does. As for [`BuilderWithBits::hashes_with_rounds`], serialized
+    /// formats that only store [`num_hashes`](BloomFilter::num_hashes) choose the split themselves, so filters built
+    /// with a cap must be reconstructed with the same cap.
+    ///
+    /// # Panics
+    /// Panics if `max_rounds` is greater than 32.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_num_bits(1024).block_size_64().hashes(40);
+    /// assert_eq!(bloom.num_rounds(), Some(30));
+    /// let bloom = BloomFilter::with_num_bits(1024).block_size_64().max_rounds(16).hashes(40);
+    /// assert_eq!(bloom.num_rounds(), Some(16));
+    /// ```
+    pub fn max_rounds(mut self, max_rounds: u64) -> Self {
+        assert!(max_rounds <= 32, "max_rounds must be at most 32");
+        self.max_rounds = max_rounds;
+        self
+    }
+
+    /// Disables the sparse hash for the later constructed [`BloomFilter`], so that each item sets exactly
+    /// [`num_hashes`](BloomFilter::num_hashes) single bits, as in a textbook Bloom filter with that many hashes,
+    /// and as `HASHING.md` describes with `r` undefined. Equivalent to [`max_rounds(0)`](Self::max_rounds).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_num_bits(1024).no_sparse_hash().hashes(40);
+    /// assert_eq!(bloom.num_hashes(), 40);
+    /// assert_eq!(bloom.num_rounds(), None);
+    /// ```
+    pub fn no_sparse_hash(self) -> Self {
+        self.max_rounds(0)
+    }
+
+    /// Enables prefix mode for the later constructed [`BloomFilter`].
+    ///
+    /// Keys inserted with [`BloomFilter::insert_with_prefix`] also have their `prefix` inserted,
+    /// so [`BloomFilter::contains_prefix`] can check if any key with a given prefix is possibly in the filter.
+    /// Each key may add two items to the filter, which should be accounted for when sizing it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::{BloomFilter, Prefix};
+    ///
+    /// let bloom = BloomFilter::with_num_bits(1024).prefix(Prefix::Len(4)).hashes(4);
+    /// ```
+    pub fn prefix(mut self, prefix: Prefix) -> Self {
+        self.prefix = Some(prefix);
+        self
+    }
+
     /// "Consumes" this builder, using the provided `num_hashes` to return an
     /// empty [`BloomFilter`].
     ///
@@ -74,10 +262,75 @@ impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> BuilderWithBits<BLOCK_SIZE_BI
     ///
     /// let bloom = BloomFilter::with_num_bits(1024).hashes(4);
     /// ```
-    pub fn hashes(self, num_hashes: u32) -> BloomFilter<BLOCK_SIZE_BITS, S> {
+    pub fn hashes(self, num_hashes: u32) -> BloomFilter<BLOCK_SIZE_BITS, S, T> {
         self.hashes_f(num_hashes as f64)
     }
 
+    /// "Consumes" this builder, returning an empty [`BloomFilter`] with an explicit split of its hashes per item:
+    /// `num_hashes` single bit hashes, and, if `num_rounds` is `Some(r)`, a sparse hash setting about `r` bits
+    /// in each `u64` of the item's block.
+    ///
+    /// [`hashes`](Self::hashes) and [`expected_items`](Self::expected_items) choose this split for a total number
+    /// of hashes to minimize the work per item. Sparse hashes with more bits per `u64` take less work per bit
+    /// but set bits less independently, so accuracy and speed depend on the split; this is for tuning it by benchmarks.
+    /// The [`num_hashes`](BloomFilter::num_hashes) of the constructed filter is the approximate total number of bits
+    /// per item. Since other constructors, and serialized formats that only store `num_hashes`, choose the split
+    /// themselves, filters built this way must be reconstructed with `hashes_with_rounds` and the same arguments,
+    /// unless they are read from a format that stores the split, e.g. [`BloomFilter::to_bytes_with_params`].
+    ///
+    /// # Panics
+    /// Panics if `num_rounds` is not in `8..=32` for 512 bit blocks, or in `16..=32` for other block sizes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_num_bits(1024).hashes_with_rounds(2, Some(12));
+    /// assert_eq!(bloom.num_rounds(), Some(12));
+    /// assert!(bloom.num_hashes() > 100); // about 13 bits in each of 8 `u64`s, and 2 more
+    /// ```
+    pub fn hashes_with_rounds(
+        self,
+        num_hashes: u32,
+        num_rounds: Option<u64>,
+    ) -> BloomFilter<BLOCK_SIZE_BITS, S, T> {
+        let mut target_hashes = num_hashes as f64;
+        if let Some(num_rounds) = num_rounds {
+            let min_rounds = sparse_hash::min_target_bits(BLOCK_SIZE_BITS);
+            assert!(
+                sparse_hash::is_valid_rounds(num_rounds, BLOCK_SIZE_BITS),
+                "num_rounds must be in {min_rounds}..=32 for {BLOCK_SIZE_BITS} bit blocks"
+            );
+            let u64s_per_block = (BLOCK_SIZE_BITS / 64) as f64;
+            target_hashes += sparse_hash::hashes_for_bits(num_rounds) * u64s_per_block;
+        }
+        BloomFilter {
+            bits: BlockedBitVec::from_storage(self.data),
+            target_hashes: target_hashes.round() as u64,
+            num_hashes: num_hashes as u64,
+            num_rounds,
+            two_choice: self.two_choice,
+            index_strategy: self.index_strategy,
+            prefix: self.prefix,
+            hasher: self.hasher,
+            counters: AtomicCounters::new(),
+        }
+    }
+
+    /// Like [`hashes_with_rounds`](Self::hashes_with_rounds), but with the total number of hashes too,
+    /// as stored by the formats that carry the parameters of a filter.
+    pub(crate) fn hashes_with_split(
+        self,
+        target_hashes: u32,
+        num_hashes: u32,
+        num_rounds: Option<u64>,
+    ) -> BloomFilter<BLOCK_SIZE_BITS, S, T> {
+        let mut filter = self.hashes_with_rounds(num_hashes, num_rounds);
+        filter.target_hashes = target_hashes as u64;
+        filter
+    }
+
     /// To generate ~`total_num_hashes` we'll use a combination of traditional index derived from hashes and "sparse hashes".
     /// sparse hashes's are per u64 in the block, and for that u64 represent some indexes already set.
     /// "rounds" are the amount of work/iterations we need to do to get a sparse hash.
@@ -92,18 +345,77 @@ impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> BuilderWithBits<BLOCK_SIZE_BI
     /// Note:
     /// - the min number of rounds is 1, generating around ~32 bits, which is the max entropy in the u64.
     /// - the max number of rounds is ~4. That produces a sparse hash of ~4 bits set (1/2^4), at which point we may as well calculate 4 bit indexes normally.
-    fn hashes_f(self, total_num_hashes: f64) -> BloomFilter<BLOCK_SIZE_BITS, S> {
+    fn hashes_f(self, total_num_hashes: f64) -> BloomFilter<BLOCK_SIZE_BITS, S, T> {
+        self.try_hashes_f(total_num_hashes)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Like [`hashes_f`](Self::hashes_f), but returns an error if the bits are not a whole number of blocks.
+    fn try_hashes_f(
+        self,
+        total_num_hashes: f64,
+    ) -> Result<BloomFilter<BLOCK_SIZE_BITS, S, T>, FastbloomError> {
         let total_num_hashes = total_num_hashes.floor();
         let (num_hashes, num_rounds) =
-            sparse_hash::optimize_hashing(total_num_hashes, BLOCK_SIZE_BITS);
+            sparse_hash::optimize_hashing(total_num_hashes, BLOCK_SIZE_BITS, self.max_rounds);
 
-        BloomFilter {
-            bits: self.data.into(),
+        Ok(BloomFilter {
+            bits: BlockedBitVec::try_from_storage(self.data)?,
             target_hashes: total_num_hashes as u64,
             num_hashes,
             num_rounds,
+            two_choice: self.two_choice,
+            index_strategy: self.index_strategy,
+            prefix: self.prefix,
             hasher: self.hasher,
+            counters: AtomicCounters::new(),
+        })
+    }
+
+    /// Like [`hashes`](Self::hashes), but returns an error instead of panicking, and rejects 0 hashes,
+    /// with which every item would be reported as in the filter.
+    ///
+    /// # Errors
+    /// Returns [`FastbloomError::InvalidHashes`] if `num_hashes` is 0, and [`FastbloomError::NotWholeBlocks`]
+    /// if the bits cannot be padded and are not a whole number of blocks.
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::{BloomFilter, FastbloomError};
+    ///
+    /// let bits = [0u64; 4];
+    /// let err = BloomFilter::from_slice(&bits).try_hashes(4).unwrap_err();
+    /// assert!(matches!(err, FastbloomError::NotWholeBlocks { num_u64s: 4, block_size_bits: 512 }));
+    /// let bloom = BloomFilter::from_slice(&bits).block_size_256().try_hashes(4).unwrap();
+    /// ```
+    pub fn try_hashes(
+        self,
+        num_hashes: u32,
+    ) -> Result<BloomFilter<BLOCK_SIZE_BITS, S, T>, FastbloomError> {
+        if num_hashes == 0 {
+            return Err(FastbloomError::InvalidHashes(num_hashes));
         }
+        self.try_hashes_f(num_hashes as f64)
+    }
+
+    /// Like [`expected_items`](Self::expected_items), but returns an error instead of panicking.
+    ///
+    /// # Errors
+    /// Returns [`FastbloomError::NotWholeBlocks`] if the bits cannot be padded and are not a whole number of blocks.
+    pub fn try_expected_items(
+        self,
+        expected_num_items: usize,
+    ) -> Result<BloomFilter<BLOCK_SIZE_BITS, S, T>, FastbloomError> {
+        let num_hashes = self.optimal_hashes_for(expected_num_items);
+        self.try_hashes_f(num_hashes)
+    }
+
+    /// Returns the optimal number of hashes for `expected_num_items` in these bits.
+    fn optimal_hashes_for(&self, expected_num_items: usize) -> f64 {
+        let u64s_per_block = (BLOCK_SIZE_BITS / 64) as f64;
+        let num_blocks = (self.data.as_slice().len() as f64 / u64s_per_block).ceil();
+        let items_per_block = expected_num_items as f64 / num_blocks;
+        BloomFilter::<BLOCK_SIZE_BITS>::optimal_hashes_f(items_per_block)
     }
 
     /// "Consumes" this builder, using the provided `expected_num_items` to return an
@@ -118,14 +430,15 @@ impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> BuilderWithBits<BLOCK_SIZE_BI
     ///
     /// let bloom = BloomFilter::with_num_bits(1024).expected_items(500);
     /// ```
-    pub fn expected_items(self, expected_num_items: usize) -> BloomFilter<BLOCK_SIZE_BITS, S> {
-        let u64s_per_block = (BLOCK_SIZE_BITS / 64) as f64;
-        let num_blocks = (self.data.len() as f64 / u64s_per_block).ceil();
-        let items_per_block = expected_num_items as f64 / num_blocks;
-        let num_hashes = BloomFilter::<BLOCK_SIZE_BITS>::optimal_hashes_f(items_per_block);
+    pub fn expected_items(self, expected_num_items: usize) -> BloomFilter<BLOCK_SIZE_BITS, S, T> {
+        let num_hashes = self.optimal_hashes_for(expected_num_items);
         self.hashes_f(num_hashes)
     }
+}
 
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher, T: BitStorageMut>
+    BuilderWithBits<BLOCK_SIZE_BITS, S, T>
+{
     /// "Consumes" this builder and constructs a [`BloomFilter`] containing
     /// all values in `items`. Like [`BuilderWithBits::expected_items`], the number of hashes per item
     /// is optimized based on `items.len()` to maximize Bloom filter accuracy
@@ -141,7 +454,7 @@ impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> BuilderWithBits<BLOCK_SIZE_BI
     pub fn items<I: IntoIterator<IntoIter = impl ExactSizeIterator<Item = impl Hash>>>(
         self,
         items: I,
-    ) -> BloomFilter<BLOCK_SIZE_BITS, S> {
+    ) -> BloomFilter<BLOCK_SIZE_BITS, S, T> {
         let into_iter = items.into_iter();
         let mut filter = self.expected_items(into_iter.len());
         filter.extend(into_iter);
@@ -149,6 +462,144 @@ impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> BuilderWithBits<BLOCK_SIZE_BI
     }
 }
 
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher + Clone, T: BitStorageMut + Clone>
+    BuilderWithBits<BLOCK_SIZE_BITS, S, T>
+{
+    /// Returns an empty [`BloomFilter`] with `num_hashes` hashes per item, like [`hashes`](Self::hashes),
+    /// without consuming this builder.
+    ///
+    /// A configured builder can be kept as a template to construct many identical filters,
+    /// e.g. one per partition, which can later be merged or compared.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let template = BloomFilter::with_num_bits(1024).block_size_256().seed(&1);
+    /// let partitions: Vec<_> = (0..4).map(|_| template.build(4)).collect();
+    /// assert!(partitions.iter().all(|filter| *filter == partitions[0]));
+    /// ```
+    pub fn build(&self, num_hashes: u32) -> BloomFilter<BLOCK_SIZE_BITS, S, T> {
+        self.clone().hashes(num_hashes)
+    }
+
+    /// Returns a [`BloomFilter`] containing all values in `items`, like [`items`](Self::items),
+    /// without consuming this builder.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let template = BloomFilter::with_num_bits(1024).seed(&1);
+    /// let a = template.build_from([1, 2]);
+    /// let b = template.build_from([3, 4]);
+    /// assert!(a.contains(&1) && b.contains(&3));
+    /// ```
+    pub fn build_from<I: IntoIterator<IntoIter = impl ExactSizeIterator<Item = impl Hash>>>(
+        &self,
+        items: I,
+    ) -> BloomFilter<BLOCK_SIZE_BITS, S, T> {
+        self.clone().items(items)
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> BuilderWithBits<BLOCK_SIZE_BITS, S> {
+    /// Copies the bits into [`AlignedBits`], which start at a cache line boundary,
+    /// so that each block of the later constructed [`AlignedBloomFilter`](crate::AlignedBloomFilter) is within one cache line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let mut bloom = BloomFilter::with_num_bits(1024).cache_aligned().hashes(4);
+    /// bloom.insert(&1);
+    /// assert!(bloom.contains(&1));
+    /// assert_eq!(bloom.as_slice().as_ptr() as usize % 64, 0);
+    /// ```
+    pub fn cache_aligned(self) -> BuilderWithBits<BLOCK_SIZE_BITS, S, AlignedBits> {
+        BuilderWithBits {
+            data: AlignedBits::from(self.data),
+            hasher: self.hasher,
+            two_choice: self.two_choice,
+            index_strategy: self.index_strategy,
+            max_rounds: self.max_rounds,
+            prefix: self.prefix,
+        }
+    }
+
+    /// "Consumes" this builder, returning an empty [`RegisterBlockedBloomFilter`] with the same bits and hasher.
+    /// Each item sets one bit in each `u64` of its block, so the number of hashes is `BLOCK_SIZE_BITS / 64`.
+    ///
+    /// Two choice insertion is not supported by [`RegisterBlockedBloomFilter`] and is ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_num_bits(1024).block_size_256().register_blocked();
+    /// assert_eq!(bloom.num_hashes(), 4);
+    /// ```
+    pub fn register_blocked(self) -> RegisterBlockedBloomFilter<BLOCK_SIZE_BITS, S> {
+        RegisterBlockedBloomFilter {
+            bits: self.data.into(),
+            hasher: self.hasher,
+        }
+    }
+
+    /// "Consumes" this builder, returning an empty [`FixedHashesBloomFilter`] with the same bits and hasher,
+    /// and `NUM_HASHES` hashes per item.
+    ///
+    /// Two choice insertion and prefix mode are not supported by [`FixedHashesBloomFilter`] and are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_num_bits(1024).fixed_hashes::<4>();
+    /// assert_eq!(bloom.num_hashes(), 4);
+    /// ```
+    pub fn fixed_hashes<const NUM_HASHES: usize>(
+        self,
+    ) -> FixedHashesBloomFilter<BLOCK_SIZE_BITS, NUM_HASHES, S> {
+        FixedHashesBloomFilter {
+            bits: self.data.into(),
+            hasher: self.hasher,
+        }
+    }
+
+    /// "Consumes" this builder, returning an empty [`U32BloomFilter`] with the same bits, as `u32` words, and hasher,
+    /// and `num_hashes` hashes per item.
+    ///
+    /// Two choice insertion and prefix mode are not supported by [`U32BloomFilter`] and are ignored.
+    ///
+    /// # Panics
+    /// Panics if `num_hashes` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_num_bits(1024).u32_words(4);
+    /// assert_eq!(bloom.num_hashes(), 4);
+    /// assert_eq!(bloom.as_slice().len(), 32);
+    /// ```
+    pub fn u32_words(self, num_hashes: u32) -> U32BloomFilter<BLOCK_SIZE_BITS, S> {
+        assert!(num_hashes > 0);
+        let words = self
+            .data
+            .iter()
+            .flat_map(|&x| [x as u32, (x >> 32) as u32])
+            .collect();
+        U32BloomFilter::new(words, num_hashes, self.hasher)
+    }
+}
+
 fn optimal_size(items_count: f64, fp_p: f64) -> usize {
     let log2 = f64::ln(2.0f64);
     let log2_2 = log2 * log2;
@@ -171,6 +622,10 @@ fn optimal_size(items_count: f64, fp_p: f64) -> usize {
 pub struct BuilderWithFalsePositiveRate<const BLOCK_SIZE_BITS: usize = 512, S = DefaultHasher> {
     pub(crate) desired_fp_rate: f64,
     pub(crate) hasher: S,
+    pub(crate) two_choice: bool,
+    pub(crate) index_strategy: IndexStrategy,
+    pub(crate) max_rounds: u64,
+    pub(crate) prefix: Option<Prefix>,
 }
 
 impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> PartialEq
@@ -223,9 +678,180 @@ impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher>
         BuilderWithFalsePositiveRate::<BLOCK_SIZE_BITS, H> {
             desired_fp_rate: self.desired_fp_rate,
             hasher,
+            two_choice: self.two_choice,
+            index_strategy: self.index_strategy,
+            max_rounds: self.max_rounds,
+            prefix: self.prefix,
         }
     }
 
+    /// Sets the hasher for this builder to SipHash-2-4 keyed with `seed`, for filters fed by untrusted input.
+    /// See [`SipHash24`]; use `.hasher(SipHash24::default())` for a random key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_false_pos(0.001).siphash24(&1).expected_items(100);
+    /// ```
+    pub fn siphash24(
+        self,
+        seed: &u128,
+    ) -> BuilderWithFalsePositiveRate<BLOCK_SIZE_BITS, SipHash24> {
+        self.hasher(SipHash24::with_key(&seed.to_be_bytes()))
+    }
+
+    /// Sets the hasher for this builder to [`Xxh3`] with `seed`.
+    ///
+    /// Requires the `xxh3` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_false_pos(0.001).xxh3(&1).expected_items(100);
+    /// ```
+    #[cfg(feature = "xxh3")]
+    pub fn xxh3(self, seed: &u64) -> BuilderWithFalsePositiveRate<BLOCK_SIZE_BITS, Xxh3> {
+        self.hasher(Xxh3::with_seed(*seed))
+    }
+
+    /// Sets the hasher for this builder to [`WyHash`] with `seed`.
+    ///
+    /// Requires the `wyhash` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_false_pos(0.001).wyhash(&1).expected_items(100);
+    /// ```
+    #[cfg(feature = "wyhash")]
+    pub fn wyhash(self, seed: &u64) -> BuilderWithFalsePositiveRate<BLOCK_SIZE_BITS, WyHash> {
+        self.hasher(WyHash::with_seed(*seed))
+    }
+
+    /// Sets the hasher for this builder to [`KeyedBlake3`] with the secret `key`.
+    ///
+    /// Requires the `blake3` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_false_pos(0.001).keyed_blake3(&[7; 32]).expected_items(100);
+    /// ```
+    #[cfg(feature = "blake3")]
+    pub fn keyed_blake3(
+        self,
+        key: &[u8; 32],
+    ) -> BuilderWithFalsePositiveRate<BLOCK_SIZE_BITS, KeyedBlake3> {
+        self.hasher(KeyedBlake3::new(key))
+    }
+
+    /// Enables "power of two choices" insertion for the later constructed [`BloomFilter`].
+    ///
+    /// Each item has two candidate blocks, and all of its bits are set in the one with fewer bits set.
+    /// Membership checks check both candidates. This evens out the number of bits set per block,
+    /// but since an item not in the filter can match either candidate, the false positive rate is
+    /// typically higher than without two choices, and membership checks are slower.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_false_pos(0.001).block_size_64().two_choice().expected_items(100);
+    /// ```
+    pub fn two_choice(mut self) -> Self {
+        self.two_choice = true;
+        self
+    }
+
+    /// Sets how the bit indexes of each item's single bit hashes are derived for the later constructed [`BloomFilter`].
+    /// The default is [`IndexStrategy::DoubleHashing`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::{BloomFilter, IndexStrategy};
+    ///
+    /// let bloom = BloomFilter::with_false_pos(0.001)
+    ///     .index_strategy(IndexStrategy::EnhancedDoubleHashing)
+    ///     .expected_items(100);
+    /// assert_eq!(bloom.index_strategy(), IndexStrategy::EnhancedDoubleHashing);
+    /// ```
+    pub fn index_strategy(mut self, strategy: IndexStrategy) -> Self {
+        self.index_strategy = strategy;
+        self
+    }
+
+    /// Caps the sparse hash of each item at `max_rounds` bits per `u64` for the later constructed [`BloomFilter`].
+    /// See [`BloomFilter::num_rounds`].
+    ///
+    /// Each item's hashes are split between single bit hashes and a sparse hash to minimize the work per item.
+    /// Sparse hashes with more bits per `u64` take less work per bit but set bits less independently, so a lower cap
+    /// sets more of the bits as single bits, for slower but more predictable hashing. Caps below the smallest sparse
+    /// hash for the block size, 8 bits for 512 bit blocks and 16 bits for others, disable it, as
+    /// [`no_sparse_hash`](Self::no_sparse_hash) does. As for [`BuilderWithBits::hashes_with_rounds`], serialized
+    /// formats that only store [`num_hashes`](BloomFilter::num_hashes) choose the split themselves, so filters built
+    /// with a cap must be reconstructed with the same cap.
+    ///
+    /// # Panics
+    /// Panics if `max_rounds` is greater than 32.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_false_pos(0.001).max_rounds(16).expected_items(100);
+    /// assert!(bloom.num_rounds().is_none_or(|r| r <= 16));
+    /// ```
+    pub fn max_rounds(mut self, max_rounds: u64) -> Self {
+        assert!(max_rounds <= 32, "max_rounds must be at most 32");
+        self.max_rounds = max_rounds;
+        self
+    }
+
+    /// Disables the sparse hash for the later constructed [`BloomFilter`], so that each item sets exactly
+    /// [`num_hashes`](BloomFilter::num_hashes) single bits, as in a textbook Bloom filter with that many hashes,
+    /// and as `HASHING.md` describes with `r` undefined. Equivalent to [`max_rounds(0)`](Self::max_rounds).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_false_pos(0.001).no_sparse_hash().expected_items(100);
+    /// assert_eq!(bloom.num_rounds(), None);
+    /// ```
+    pub fn no_sparse_hash(self) -> Self {
+        self.max_rounds(0)
+    }
+
+    /// Enables prefix mode for the later constructed [`BloomFilter`].
+    ///
+    /// Keys inserted with [`BloomFilter::insert_with_prefix`] also have their `prefix` inserted,
+    /// so [`BloomFilter::contains_prefix`] can check if any key with a given prefix is possibly in the filter.
+    /// Each key may add two items to the filter, which should be accounted for when sizing it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::{BloomFilter, Prefix};
+    ///
+    /// let bloom = BloomFilter::with_false_pos(0.001).prefix(Prefix::Len(4)).expected_items(100);
+    /// ```
+    pub fn prefix(mut self, prefix: Prefix) -> Self {
+        self.prefix = Some(prefix);
+        self
+    }
+
     /// "Consumes" this builder, using the provided `expected_num_items` to return an
     /// empty [`BloomFilter`]. The number of hashes and underlying memory is optimized based on `expected_num_items`
     /// to meet the desired false positive rate.
@@ -240,9 +866,56 @@ impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher>
     /// ```
     pub fn expected_items(self, expected_num_items: usize) -> BloomFilter<BLOCK_SIZE_BITS, S> {
         let num_bits = optimal_size(expected_num_items as f64, self.desired_fp_rate);
-        BloomFilter::new_builder::<BLOCK_SIZE_BITS>(num_bits)
-            .hasher(self.hasher)
-            .expected_items(expected_num_items)
+        let builder = BloomFilter::new_builder::<BLOCK_SIZE_BITS>(num_bits).hasher(self.hasher);
+        BuilderWithBits {
+            two_choice: self.two_choice,
+            index_strategy: self.index_strategy,
+            max_rounds: self.max_rounds,
+            prefix: self.prefix,
+            ..builder
+        }
+        .expected_items(expected_num_items)
+    }
+
+    /// "Consumes" this builder, returning an empty [`DynBloomFilter`] like [`expected_items`](Self::expected_items),
+    /// with the block size selected from the false positive rate instead of `BLOCK_SIZE_BITS`.
+    ///
+    /// Smaller blocks are faster, but less accurate at low false positive rates, so the smallest block size that meets
+    /// the rate is selected: 64 bits for rates of at least 1%, 128 bits down to 0.1%, 256 bits down to 0.001%,
+    /// and 512 bits below that.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let mut loose = BloomFilter::with_false_pos(0.05).auto_block_size(1000);
+    /// assert_eq!(loose.block_size_bits(), 64);
+    /// let tight = BloomFilter::with_false_pos(1e-6).auto_block_size(1000);
+    /// assert_eq!(tight.block_size_bits(), 512);
+    ///
+    /// loose.insert("42");
+    /// assert!(loose.contains("42"));
+    /// ```
+    pub fn auto_block_size(self, expected_num_items: usize) -> DynBloomFilter<S> {
+        match self.desired_fp_rate {
+            fp if fp >= 0.01 => self
+                .block_size_64()
+                .expected_items(expected_num_items)
+                .into(),
+            fp if fp >= 0.001 => self
+                .block_size_128()
+                .expected_items(expected_num_items)
+                .into(),
+            fp if fp >= 0.00001 => self
+                .block_size_256()
+                .expected_items(expected_num_items)
+                .into(),
+            _ => self
+                .block_size_512()
+                .expected_items(expected_num_items)
+                .into(),
+        }
     }
 
     /// "Consumes" this builder and constructs a [`BloomFilter`] containing
@@ -265,6 +938,98 @@ impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher>
         filter.extend(into_iter);
         filter
     }
+
+    /// "Consumes" this builder, using the provided `expected_num_items` to return an
+    /// empty [`RegisterBlockedBloomFilter`]. The underlying memory is optimized based on `expected_num_items`
+    /// to meet the desired false positive rate with `BLOCK_SIZE_BITS / 64` hashes per item.
+    ///
+    /// Two choice insertion is not supported by [`RegisterBlockedBloomFilter`] and is ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_false_pos(0.001).register_blocked(500);
+    /// ```
+    pub fn register_blocked(
+        self,
+        expected_num_items: usize,
+    ) -> RegisterBlockedBloomFilter<BLOCK_SIZE_BITS, S> {
+        let num_hashes = (BLOCK_SIZE_BITS / 64) as f64;
+        // Solve `fp = (1 - e^(-n * k / m))^k` for `m`, with `k` fixed
+        let fill = self.desired_fp_rate.powf(1.0 / num_hashes);
+        let num_bits = -(expected_num_items as f64) * num_hashes / f64::ln(1.0 - fill);
+        let num_bits = std::cmp::max(num_bits.ceil() as usize, BLOCK_SIZE_BITS);
+        BloomFilter::new_builder::<BLOCK_SIZE_BITS>(num_bits)
+            .hasher(self.hasher)
+            .register_blocked()
+    }
+
+    /// "Consumes" this builder, using the provided `expected_num_items` to return an
+    /// empty [`U32BloomFilter`]. The underlying memory and number of hashes are optimized based on `expected_num_items`
+    /// to meet the desired false positive rate.
+    ///
+    /// Two choice insertion and prefix mode are not supported by [`U32BloomFilter`] and are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_false_pos(0.001).u32_words(500);
+    /// ```
+    pub fn u32_words(self, expected_num_items: usize) -> U32BloomFilter<BLOCK_SIZE_BITS, S> {
+        let num_bits = optimal_size(expected_num_items as f64, self.desired_fp_rate);
+        let builder = BloomFilter::new_builder::<BLOCK_SIZE_BITS>(num_bits).hasher(self.hasher);
+        let num_hashes = builder.optimal_hashes_for(expected_num_items).floor() as u32;
+        builder.u32_words(num_hashes)
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher + Clone>
+    BuilderWithFalsePositiveRate<BLOCK_SIZE_BITS, S>
+{
+    /// Returns an empty [`BloomFilter`] sized for `expected_num_items`, like [`expected_items`](Self::expected_items),
+    /// without consuming this builder.
+    ///
+    /// A configured builder can be kept as a template to construct many identical filters,
+    /// e.g. one per partition, which can later be merged or compared.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let template = BloomFilter::with_false_pos(0.001).seed(&1);
+    /// let mut partitions: Vec<_> = (0..4).map(|_| template.build(1000)).collect();
+    /// partitions[0].insert(&42);
+    /// assert!(partitions[0].contains(&42));
+    /// assert_eq!(partitions[1], partitions[2]);
+    /// ```
+    pub fn build(&self, expected_num_items: usize) -> BloomFilter<BLOCK_SIZE_BITS, S> {
+        self.clone().expected_items(expected_num_items)
+    }
+
+    /// Returns a [`BloomFilter`] containing all values in `items`, like [`items`](Self::items),
+    /// without consuming this builder.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let template = BloomFilter::with_false_pos(0.001).seed(&1);
+    /// let a = template.build_from([1, 2]);
+    /// let b = template.build_from([3, 4]);
+    /// assert!(a.contains(&1) && b.contains(&3));
+    /// ```
+    pub fn build_from<I: IntoIterator<IntoIter = impl ExactSizeIterator<Item = impl Hash>>>(
+        &self,
+        items: I,
+    ) -> BloomFilter<BLOCK_SIZE_BITS, S> {
+        self.clone().items(items)
+    }
 }
 
 macro_rules! impl_builder_block_size {
@@ -282,11 +1047,15 @@ macro_rules! impl_builder_block_size {
                     BuilderWithFalsePositiveRate::<$size, S> {
                         desired_fp_rate: self.desired_fp_rate,
                         hasher: self.hasher,
+                        two_choice: self.two_choice,
+                        index_strategy: self.index_strategy,
+                        max_rounds: self.max_rounds,
+prefix: self.prefix,
                     }
                 }
             }
 
-            impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> BuilderWithBits<BLOCK_SIZE_BITS, S> {
+            impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher, T: BitStorage> BuilderWithBits<BLOCK_SIZE_BITS, S, T> {
                 #[doc = concat!("Set the block size of the Bloom filter to ", stringify!($size), " bits.")]
                 #[doc = concat!("The underlying bit vector size will be rounded up to be a multiple of the block size.")]
                 #[doc = "# Example"]
@@ -294,10 +1063,14 @@ macro_rules! impl_builder_block_size {
                 #[doc = "use fastbloom::BloomFilter;"]
                 #[doc = concat!("let builder = BloomFilter::with_num_bits(1000).block_size_", stringify!($size), "();")]
                 #[doc = "```"]
-                pub fn $fn_name(self) -> BuilderWithBits<$size, S> {
-                    BuilderWithBits::<$size, S> {
+                pub fn $fn_name(self) -> BuilderWithBits<$size, S, T> {
+                    BuilderWithBits::<$size, S, T> {
                         data: self.data,
                         hasher: self.hasher,
+                        two_choice: self.two_choice,
+                        index_strategy: self.index_strategy,
+                        max_rounds: self.max_rounds,
+prefix: self.prefix,
                     }
                 }
             }
@@ -316,6 +1089,105 @@ impl_builder_block_size!(
 mod for_accuracy_tests {
     use crate::BloomFilter;
 
+    #[test]
+    fn reusable_builders() {
+        let template = BloomFilter::with_num_bits(4096).seed(&3).two_choice();
+        let a = template.build_from(0..100);
+        assert_eq!(a, template.clone().items(0..100));
+        assert_eq!(template.build(7), template.clone().hashes(7));
+
+        let template = BloomFilter::with_false_pos(0.01).block_size_128().seed(&3);
+        assert_eq!(template.build(100), template.clone().expected_items(100));
+        let b = template.build_from(0..100);
+        assert_eq!(b, template.clone().items(0..100));
+        assert!((0..100).all(|x| b.contains(&x)));
+    }
+
+    #[test]
+    fn hashes_with_rounds() {
+        let bloom = BloomFilter::with_num_bits(1 << 14).hashes(20);
+        let (num_hashes, num_rounds) = (bloom.num_hashes, bloom.num_rounds);
+        let same =
+            BloomFilter::with_num_bits(1 << 14).hashes_with_rounds(num_hashes as u32, num_rounds);
+        assert_eq!(same.num_hashes(), bloom.num_hashes());
+
+        for rounds in [None, Some(8), Some(20), Some(32)] {
+            let mut bloom = BloomFilter::with_num_bits(1 << 20)
+                .seed(&1)
+                .hashes_with_rounds(3, rounds);
+            bloom.extend(0..100);
+            assert!((0..100).all(|x| bloom.contains(&x)));
+            let fp = (100..10_100).filter(|x| bloom.contains(x)).count();
+            assert!(fp < 10, "{rounds:?}: {fp}");
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn hashes_with_invalid_rounds() {
+        BloomFilter::with_num_bits(1024)
+            .block_size_64()
+            .hashes_with_rounds(3, Some(8));
+    }
+
+    #[test]
+    fn max_rounds() {
+        for max_rounds in [0, 16, 24, 32] {
+            let mut bloom = BloomFilter::with_num_bits(1 << 16)
+                .block_size_64()
+                .seed(&1)
+                .max_rounds(max_rounds)
+                .hashes(40);
+            assert_eq!(bloom.num_hashes(), 40);
+            assert!(bloom.num_rounds().is_none_or(|r| r <= max_rounds));
+            bloom.extend(0..100);
+            assert!((0..100).all(|x| bloom.contains(&x)));
+        }
+
+        let textbook = BloomFilter::with_num_bits(1 << 16)
+            .seed(&1)
+            .no_sparse_hash()
+            .hashes(200);
+        let same = BloomFilter::with_num_bits(1 << 16)
+            .seed(&1)
+            .hashes_with_rounds(200, None);
+        assert_eq!(textbook.num_rounds(), None);
+        assert_eq!(textbook.num_hashes, 200);
+        assert_eq!(textbook, same);
+
+        let bloom = BloomFilter::with_false_pos(1e-9)
+            .no_sparse_hash()
+            .expected_items(100);
+        assert_eq!(bloom.num_rounds(), None);
+        assert_eq!(bloom.num_hashes, bloom.num_hashes() as u64);
+    }
+
+    #[test]
+    #[should_panic]
+    fn max_rounds_too_high() {
+        let _ = BloomFilter::with_num_bits(1024).max_rounds(33);
+    }
+
+    #[test]
+    fn auto_block_size() {
+        for (fp, block_size_bits) in [
+            (0.5, 64),
+            (0.01, 64),
+            (0.005, 128),
+            (0.0001, 256),
+            (1e-7, 512),
+        ] {
+            let mut filter = BloomFilter::with_false_pos(fp)
+                .seed(&1)
+                .auto_block_size(1000);
+            assert_eq!(filter.block_size_bits(), block_size_bits);
+            for x in 0..1000 {
+                filter.insert(&x);
+            }
+            assert!((0..1000).all(|x| filter.contains(&x)));
+        }
+    }
+
     #[test]
     fn data_size() {
         let size_bits = 512 * 1000;
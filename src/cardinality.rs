@@ -0,0 +1,201 @@
+use crate::{BitStorage, BloomFilter};
+use std::hash::{BuildHasher, Hash};
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher, T: BitStorage>
+    BloomFilter<BLOCK_SIZE_BITS, S, T>
+{
+    /// Returns the estimated number of items with `ones` of this filter's bits set,
+    /// `-(m / k) * ln(1 - ones / m)` for `m` bits and `k` hashes.
+    pub(crate) fn estimate_len_with_ones(&self, ones: u64) -> f64 {
+        let num_bits = self.num_bits() as f64;
+        -(num_bits / self.num_hashes() as f64) * (1.0 - ones as f64 / num_bits).ln()
+    }
+
+    /// Returns the estimated number of distinct items in this Bloom filter, from the fraction of its bits that are set.
+    ///
+    /// The estimate is accurate while the filter is not much fuller than for its target false positive rate,
+    /// and is infinite if every bit is set.
+    ///
+    /// See <https://doi.org/10.1021/ci600526a>.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_false_pos(0.01).items(0..1000);
+    /// assert!((bloom.estimate_len() - 1000.0).abs() < 50.0);
+    /// ```
+    pub fn estimate_len(&self) -> f64 {
+        let ones = self.as_slice().iter().map(|x| x.count_ones() as u64).sum();
+        self.estimate_len_with_ones(ones)
+    }
+
+    /// Returns the estimated false positive rate of this Bloom filter, from the fraction of the bits of each block that are set.
+    ///
+    /// An item not in the filter is a false positive if all of its bits are set, so this is the mean over blocks
+    /// of the block's fraction of set bits to the power of the number of hashes. Since crowded blocks
+    /// are weighted by their false positive rate, the estimate accounts for the uneven fill of blocked filters,
+    /// and is an upper bound for items whose bits are in several blocks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_false_pos(0.01).items(0..1000);
+    /// assert!(bloom.estimate_fp_rate() < 0.02);
+    /// ```
+    pub fn estimate_fp_rate(&self) -> f64 {
+        let num_hashes = self.num_hashes() as i32;
+        let total: f64 = self
+            .block_popcounts()
+            .map(|ones| (ones as f64 / BLOCK_SIZE_BITS as f64).powi(num_hashes))
+            .sum();
+        total / self.num_blocks() as f64
+    }
+
+    /// Returns the measured false positive rate of this Bloom filter: the fraction of `negative_sample`
+    /// that it contains, or 0 if the sample is empty.
+    ///
+    /// Every item of `negative_sample` must not have been inserted, e.g. items held out from the filter's items,
+    /// so that each one it contains is a false positive. Unlike [`estimate_fp_rate`](Self::estimate_fp_rate),
+    /// the measurement includes the effect of the hasher and the distribution of real keys,
+    /// with a relative error of about `1 / sqrt(n * fp)` for `n` sample items.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_false_pos(0.01).items(0..1000);
+    /// let fp = bloom.measure_fp_rate(1000..101_000);
+    /// assert!(fp > 0.0 && fp < 0.02);
+    /// ```
+    pub fn measure_fp_rate(&self, negative_sample: impl IntoIterator<Item = impl Hash>) -> f64 {
+        let (mut total, mut false_positives) = (0u64, 0u64);
+        for val in negative_sample {
+            total += 1;
+            false_positives += self.contains(&val) as u64;
+        }
+        if total == 0 {
+            0.0
+        } else {
+            false_positives as f64 / total as f64
+        }
+    }
+
+    /// Returns the estimated number of distinct items in either this filter or `other`,
+    /// without constructing their union.
+    ///
+    /// This is [`estimate_len`](Self::estimate_len) of the bitwise OR of the filters' bits.
+    ///
+    /// # Panics
+    /// Panics if the filters have different numbers of bits or hashes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let a = BloomFilter::with_num_bits(1 << 16).seed(&1).hashes(7);
+    /// let (mut a, mut b) = (a.clone(), a);
+    /// a.extend(0..1000);
+    /// b.extend(500..2000);
+    /// assert!((a.estimate_union_len(&b) - 2000.0).abs() < 100.0);
+    /// ```
+    pub fn estimate_union_len<U: BitStorage>(
+        &self,
+        other: &BloomFilter<BLOCK_SIZE_BITS, S, U>,
+    ) -> f64 {
+        assert_eq!(self.num_bits(), other.num_bits());
+        assert_eq!(self.num_hashes(), other.num_hashes());
+        let ones = self
+            .as_slice()
+            .iter()
+            .zip(other.as_slice())
+            .map(|(x, y)| (x | y).count_ones() as u64)
+            .sum();
+        self.estimate_len_with_ones(ones)
+    }
+
+    /// Returns the estimated number of distinct items in both this filter and `other`,
+    /// without constructing their intersection.
+    ///
+    /// By inclusion–exclusion, this is the sum of the filters' [`estimate_len`](Self::estimate_len)s
+    /// less their [`estimate_union_len`](Self::estimate_union_len), and at least 0.
+    /// Since the error of each estimate remains, the estimate is relatively less accurate for small intersections.
+    ///
+    /// # Panics
+    /// Panics if the filters have different numbers of bits or hashes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let a = BloomFilter::with_num_bits(1 << 16).seed(&1).hashes(7);
+    /// let (mut a, mut b) = (a.clone(), a);
+    /// a.extend(0..1000);
+    /// b.extend(500..2000);
+    /// assert!((a.estimate_intersection_len(&b) - 500.0).abs() < 100.0);
+    /// ```
+    pub fn estimate_intersection_len<U: BitStorage>(
+        &self,
+        other: &BloomFilter<BLOCK_SIZE_BITS, S, U>,
+    ) -> f64 {
+        let estimate = self.estimate_len() + other.estimate_len() - self.estimate_union_len(other);
+        estimate.max(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimates_set_sizes() {
+        for num_bits in [1 << 14, 1 << 18] {
+            let empty = BloomFilter::with_num_bits(num_bits).seed(&2).hashes(5);
+            assert_eq!(empty.estimate_len(), 0.0);
+            let mut a = empty.clone();
+            let mut b = empty.clone();
+            a.extend(0..1000);
+            b.extend(800..1500);
+            assert!((a.estimate_len() - 1000.0).abs() < 50.0);
+            assert!((a.estimate_union_len(&b) - 1500.0).abs() < 75.0);
+            assert!((a.estimate_intersection_len(&b) - 200.0).abs() < 75.0);
+            assert_eq!(a.estimate_intersection_len(&empty), 0.0);
+        }
+        let full = BloomFilter::from_vec(vec![u64::MAX; 8]).hashes(3);
+        assert!(full.estimate_len().is_infinite());
+    }
+
+    #[test]
+    fn estimates_fp_rate() {
+        for fp in [0.1, 0.01, 0.001] {
+            let bloom = BloomFilter::with_false_pos(fp).seed(&3).items(0..10_000);
+            let actual = bloom.measure_fp_rate(10_000..1_010_000);
+            let estimate = bloom.estimate_fp_rate();
+            assert!(
+                estimate > 0.5 * actual && estimate < 2.0 * actual,
+                "{fp} {actual} {estimate}"
+            );
+        }
+        let empty = BloomFilter::with_num_bits(1024).hashes(3);
+        assert_eq!(empty.estimate_fp_rate(), 0.0);
+        let full = BloomFilter::from_vec(vec![u64::MAX; 8]).hashes(3);
+        assert_eq!(full.estimate_fp_rate(), 1.0);
+        assert_eq!(full.measure_fp_rate(["a", "b"]), 1.0);
+        assert_eq!(empty.measure_fp_rate(0..100), 0.0);
+        assert_eq!(empty.measure_fp_rate(Vec::<u64>::new()), 0.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn union_size_mismatch() {
+        let a = BloomFilter::with_num_bits(1024).hashes(3);
+        let b = BloomFilter::with_num_bits(2048).hashes(3);
+        a.estimate_union_len(&b);
+    }
+}
@@ -0,0 +1,169 @@
+use crate::{BloomFilter, BuilderWithFalsePositiveRate};
+use std::hash::{BuildHasher, Hash};
+
+/// The false positive rate of every layer after the first, as in CRLite.
+const LAYER_FP_RATE: f64 = 0.5;
+
+/// Building stops after this many layers, which only happens if an item is both a positive and a negative.
+const MAX_LAYERS: usize = 256;
+
+/// A cascade of [`BloomFilter`] layers with no false positives within a known universe of items.
+///
+/// The first layer contains the positive items. Each following layer contains the false positives of the previous layer
+/// from the other set: layer 1 contains the negatives that are false positives in layer 0, layer 2 contains the positives
+/// that are false positives in layer 1, and so on, until a layer has no false positives.
+/// An item is a positive if the first layer not containing it is odd, or if all layers contain it and there are an odd number of layers.
+///
+/// [`contains`](Self::contains) is exact for all items the cascade was built from. Items outside of that universe
+/// are false positives at about the rate of the first layer.
+///
+/// See <https://ieeexplore.ieee.org/document/7958597>.
+///
+/// # Examples
+/// ```
+/// use fastbloom::{BloomFilter, FilterCascade};
+///
+/// let revoked: Vec<u64> = (0..100).collect();
+/// let valid: Vec<u64> = (100..10_000).collect();
+/// let cascade = FilterCascade::new(BloomFilter::with_false_pos(0.01), &revoked, &valid);
+/// assert!(revoked.iter().all(|x| cascade.contains(x)));
+/// assert!(!valid.iter().any(|x| cascade.contains(x)));
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FilterCascade<const BLOCK_SIZE_BITS: usize = 512, S = crate::DefaultHasher> {
+    layers: Vec<BloomFilter<BLOCK_SIZE_BITS, S>>,
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher + Clone> FilterCascade<BLOCK_SIZE_BITS, S> {
+    /// Creates a new `FilterCascade` containing exactly the `positives` out of the universe of `positives` and `negatives`.
+    ///
+    /// The first layer is built from `builder`. Each following layer uses the same hasher and block size
+    /// with a false positive rate of 0.5.
+    ///
+    /// # Panics
+    /// Panics if an item is in both `positives` and `negatives`.
+    pub fn new<T: Hash>(
+        builder: BuilderWithFalsePositiveRate<BLOCK_SIZE_BITS, S>,
+        positives: &[T],
+        negatives: &[T],
+    ) -> Self {
+        let mut builder = builder;
+        let mut layers = Vec::new();
+        let mut include: Vec<&T> = positives.iter().collect();
+        let mut exclude: Vec<&T> = negatives.iter().collect();
+        loop {
+            assert!(
+                layers.len() < MAX_LAYERS,
+                "positives and negatives must be disjoint"
+            );
+            let depth = layers.len();
+            let mut layer = builder.clone().expected_items(include.len());
+            for val in include.iter() {
+                layer.insert(&(depth, val));
+            }
+            let false_positives: Vec<&T> = exclude
+                .into_iter()
+                .filter(|val| layer.contains(&(depth, val)))
+                .collect();
+            layers.push(layer);
+            if false_positives.is_empty() {
+                break;
+            }
+            exclude = include;
+            include = false_positives;
+            builder.desired_fp_rate = LAYER_FP_RATE;
+        }
+        Self { layers }
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> FilterCascade<BLOCK_SIZE_BITS, S> {
+    /// Checks if an element is a positive.
+    ///
+    /// # Returns
+    ///
+    /// The exact answer for items in the universe the cascade was built from.
+    /// For other items, `true` if the item is possibly a positive, `false` otherwise.
+    pub fn contains(&self, val: &(impl Hash + ?Sized)) -> bool {
+        for (depth, layer) in self.layers.iter().enumerate() {
+            if !layer.contains(&(depth, val)) {
+                return depth % 2 == 1;
+            }
+        }
+        self.layers.len() % 2 == 1
+    }
+
+    /// Returns the number of layers in the cascade.
+    #[inline]
+    pub fn num_layers(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Returns the total number of in-memory bits supporting all layers.
+    pub fn num_bits(&self) -> usize {
+        self.layers.iter().map(|layer| layer.num_bits()).sum()
+    }
+
+    /// Returns the number of bytes this `FilterCascade` uses, including the bits of all layers.
+    /// See [`BloomFilter::memory_usage`].
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.layers.capacity() * std::mem::size_of::<BloomFilter<BLOCK_SIZE_BITS, S>>()
+            + self
+                .layers
+                .iter()
+                .map(|layer| layer.heap_bytes())
+                .sum::<usize>()
+    }
+
+    /// Returns a reference to the layers of the cascade, from first to last.
+    #[inline]
+    pub fn layers(&self) -> &[BloomFilter<BLOCK_SIZE_BITS, S>] {
+        &self.layers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_within_universe() {
+        fn exact_within_universe_<const N: usize>() {
+            let positives: Vec<u64> = (0..1000).collect();
+            let negatives: Vec<u64> = (1000..100_000).collect();
+            let cascade = FilterCascade::new(
+                BloomFilter::new_with_false_pos::<N>(0.01).seed(&42),
+                &positives,
+                &negatives,
+            );
+            assert!(cascade.num_layers() > 1);
+            assert!(positives.iter().all(|x| cascade.contains(x)));
+            assert!(!negatives.iter().any(|x| cascade.contains(x)));
+        }
+        exact_within_universe_::<64>();
+        exact_within_universe_::<128>();
+        exact_within_universe_::<256>();
+        exact_within_universe_::<512>();
+    }
+
+    #[test]
+    fn empty_sets() {
+        let builder = BloomFilter::with_false_pos(0.01).seed(&42);
+        let empty: [u64; 0] = [];
+        let cascade = FilterCascade::new(builder.clone(), &empty, &[1, 2, 3]);
+        assert_eq!(cascade.num_layers(), 1);
+        assert!(!(1u64..=3).any(|x| cascade.contains(&x)));
+        let cascade = FilterCascade::new(builder, &[1, 2, 3], &empty);
+        assert_eq!(cascade.num_layers(), 1);
+        assert!((1u64..=3).all(|x| cascade.contains(&x)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn overlapping_sets() {
+        let builder = BloomFilter::with_false_pos(0.01).seed(&42);
+        FilterCascade::new(builder, &[1, 2, 3], &[3, 4, 5]);
+    }
+}
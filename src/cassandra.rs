@@ -0,0 +1,227 @@
+use crate::murmur3::cassandra_murmur3_x64_128;
+
+/// The most bits per item Cassandra's `BloomCalculations` sizes a filter with.
+const MAX_BITS_PER_ITEM: u32 = 20;
+/// The bits Cassandra's `FilterFactory` adds to every filter.
+const BITSET_EXCESS: usize = 20;
+
+/// Returns the false positive rate of a filter with `bits_per_item` bits and `num_hashes` hashes per item.
+fn false_pos(bits_per_item: u32, num_hashes: u32) -> f64 {
+    let k = f64::from(num_hashes);
+    (1.0 - (-k / f64::from(bits_per_item)).exp()).powi(num_hashes as i32)
+}
+
+/// Returns the number of hashes minimizing the false positive rate of a filter with `bits_per_item` bits per item.
+fn optimal_hashes(bits_per_item: u32) -> u32 {
+    (1..=bits_per_item)
+        .min_by(|&a, &b| false_pos(bits_per_item, a).total_cmp(&false_pos(bits_per_item, b)))
+        .unwrap()
+}
+
+/// A Bloom filter in the format of the `Filter.db` component of Apache Cassandra and ScyllaDB SSTables.
+///
+/// Items are partition keys, as their serialized bytes, which are hashed with Cassandra's variant of MurmurHash3,
+/// the same hash as the tokens of `Murmur3Partitioner`.
+/// This is the format of SSTables from Cassandra 3.0 (`ma`) on; the older format, which orders the two halves of the hash
+/// the other way around, is not supported.
+///
+/// # Examples
+/// ```
+/// use fastbloom::CassandraBloomFilter;
+///
+/// // bloom_filter_fp_chance = 0.01
+/// let mut filter = CassandraBloomFilter::new_with_false_pos(0.01, 1000);
+/// filter.insert(&1i32.to_be_bytes()); // an int partition key
+/// filter.insert("alice"); // a text partition key
+///
+/// // the contents of Filter.db
+/// let bytes = filter.to_bytes();
+/// let filter = CassandraBloomFilter::from_bytes(&bytes).unwrap();
+/// assert!(filter.contains(&1i32.to_be_bytes()));
+/// assert!(filter.contains("alice"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CassandraBloomFilter {
+    bits: Vec<u64>,
+    num_hashes: u32,
+}
+
+impl CassandraBloomFilter {
+    /// Creates an empty `CassandraBloomFilter` sized for `expected_num_items` to meet the false positive rate `fp`,
+    /// the same way as Cassandra's `FilterFactory.getFilter(numElements, maxFalsePosProbability)`.
+    ///
+    /// # Panics
+    /// Panics if `fp` is not less than 1, or is less than the lowest rate Cassandra supports, about 6.71e-5.
+    pub fn new_with_false_pos(fp: f64, expected_num_items: usize) -> Self {
+        assert!(fp < 1.0, "invalid false positive rate: {fp}");
+        let bits_per_item = (2..=MAX_BITS_PER_ITEM)
+            .find(|&c| false_pos(c, optimal_hashes(c)) <= fp)
+            .unwrap_or_else(|| panic!("unsupported false positive rate: {fp}"));
+        // Use fewer hashes if the rate is still met.
+        let mut num_hashes = optimal_hashes(bits_per_item);
+        while num_hashes > 1 && false_pos(bits_per_item, num_hashes - 1) <= fp {
+            num_hashes -= 1;
+        }
+        let num_bits = expected_num_items * bits_per_item as usize + BITSET_EXCESS;
+        Self {
+            bits: vec![0; num_bits.div_ceil(64)],
+            num_hashes,
+        }
+    }
+
+    /// Creates a `CassandraBloomFilter` from the contents of a `Filter.db` file.
+    /// Any bytes after the filter are ignored.
+    ///
+    /// Returns `None` if the bytes are truncated or there are no bits.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let (num_hashes, rest) = bytes.split_first_chunk::<4>()?;
+        let num_hashes = u32::try_from(i32::from_be_bytes(*num_hashes)).ok()?;
+        let (len, rest) = rest.split_first_chunk::<4>()?;
+        let len = usize::try_from(i32::from_be_bytes(*len)).ok()?;
+        if len == 0 {
+            return None;
+        }
+        let bits = rest
+            .get(..len.checked_mul(8)?)?
+            .chunks_exact(8)
+            .map(|word| u64::from_be_bytes(word.try_into().unwrap()))
+            .collect();
+        Some(Self { bits, num_hashes })
+    }
+
+    /// Returns the contents of the `Filter.db` file of this `CassandraBloomFilter`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(8 + 8 * self.bits.len());
+        bytes.extend_from_slice(&(self.num_hashes as i32).to_be_bytes());
+        bytes.extend_from_slice(&(self.bits.len() as i32).to_be_bytes());
+        for word in &self.bits {
+            bytes.extend_from_slice(&word.to_be_bytes());
+        }
+        bytes
+    }
+
+    /// Returns the indexes of the bits of a partition key, as Cassandra's `BloomFilter.getHashBuckets`.
+    #[inline]
+    fn bit_indexes(&self, key: &[u8]) -> impl Iterator<Item = usize> {
+        let [h1, h2] = cassandra_murmur3_x64_128(key, 0);
+        let num_bits = self.num_bits() as i64;
+        (0..self.num_hashes as i64).map(move |i| {
+            let base = (h2 as i64).wrapping_add(i.wrapping_mul(h1 as i64));
+            (base % num_bits).unsigned_abs() as usize
+        })
+    }
+
+    /// Inserts the serialized bytes of a partition key into the Bloom filter.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item may have been previously in the Bloom filter (indicating a potential false positive),
+    /// `false` otherwise.
+    #[inline]
+    pub fn insert(&mut self, key: &(impl AsRef<[u8]> + ?Sized)) -> bool {
+        let mut previously_contained = true;
+        for index in self.bit_indexes(key.as_ref()) {
+            let bit = 1 << (index % 64);
+            previously_contained &= self.bits[index / 64] & bit != 0;
+            self.bits[index / 64] |= bit;
+        }
+        previously_contained
+    }
+
+    /// Checks if the serialized bytes of a partition key are possibly in the Bloom filter.
+    #[inline]
+    pub fn contains(&self, key: &(impl AsRef<[u8]> + ?Sized)) -> bool {
+        self.bit_indexes(key.as_ref())
+            .all(|index| self.bits[index / 64] & (1 << (index % 64)) != 0)
+    }
+
+    /// Returns the number of hashes per item.
+    #[inline]
+    pub fn num_hashes(&self) -> u32 {
+        self.num_hashes
+    }
+
+    /// Returns the total number of in-memory bits supporting the Bloom filter.
+    pub fn num_bits(&self) -> usize {
+        self.bits.len() * 64
+    }
+
+    /// Returns a `u64` slice of this `CassandraBloomFilter`’s contents.
+    #[inline]
+    pub fn as_slice(&self) -> &[u64] {
+        &self.bits
+    }
+
+    /// Clear all of the bits in the Bloom filter, removing all items.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.bits.fill(0);
+    }
+}
+
+impl<T: AsRef<[u8]>> Extend<T> for CassandraBloomFilter {
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for key in iter {
+            self.insert(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sized_like_cassandra() {
+        // (bloom_filter_fp_chance, hashes, bits per item)
+        for (fp, num_hashes, bits_per_item) in [
+            (0.5, 1, 2),
+            (0.1, 3, 5),
+            (0.01, 5, 10),
+            (0.001, 8, 15),
+            (6.75e-5, 14, 20),
+        ] {
+            let filter = CassandraBloomFilter::new_with_false_pos(fp, 6400);
+            assert_eq!(filter.num_hashes(), num_hashes, "{fp}");
+            assert_eq!(
+                filter.num_bits(),
+                (6400 * bits_per_item + BITSET_EXCESS).div_ceil(64) * 64,
+                "{fp}"
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_tiny_false_pos() {
+        CassandraBloomFilter::new_with_false_pos(1e-5, 100);
+    }
+
+    #[test]
+    fn inserts_always_contained() {
+        let mut filter = CassandraBloomFilter::new_with_false_pos(0.01, 1000);
+        filter.extend((0..1000i32).map(i32::to_be_bytes));
+        assert!((0..1000i32).all(|key| filter.contains(&key.to_be_bytes())));
+        let false_pos = (1000..11_000i32)
+            .filter(|key| filter.contains(&key.to_be_bytes()))
+            .count();
+        assert!(false_pos < 200);
+        filter.clear();
+        assert!(!(0..1000i32).any(|key| filter.contains(&key.to_be_bytes())));
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let mut filter = CassandraBloomFilter::new_with_false_pos(0.01, 100);
+        filter.extend(["a", "bb", "\u{80}"]);
+        let bytes = filter.to_bytes();
+        assert_eq!(bytes[..8], [0, 0, 0, 5, 0, 0, 0, 16]);
+        assert_eq!(bytes.len(), 8 + 8 * 16);
+        assert_eq!(CassandraBloomFilter::from_bytes(&bytes), Some(filter));
+        assert!(CassandraBloomFilter::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+        assert!(CassandraBloomFilter::from_bytes(&[0, 0, 0, 5, 0, 0, 0, 0]).is_none());
+        assert!(CassandraBloomFilter::from_bytes(&[0, 0, 0, 5, 0xff, 0xff, 0xff, 0xff]).is_none());
+    }
+}
@@ -0,0 +1,105 @@
+use crate::{BitStorage, BloomFilter, BuilderWithBits};
+use std::hash::BuildHasher;
+use std::io;
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher, T: BitStorage>
+    BloomFilter<BLOCK_SIZE_BITS, S, T>
+{
+    /// Returns the bytes of this `BloomFilter`, as [`as_bytes`](Self::as_bytes), compressed with zstd at compression `level`.
+    ///
+    /// `level` is a zstd compression level, from 1 (fastest) to 22 (smallest); 0 uses zstd's default level.
+    /// Lightly filled filters compress well, while the bits of filters filled to their target false positive rate
+    /// are close to random and barely compress.
+    ///
+    /// Like [`as_slice`](Self::as_slice), only the bits are included; the number of hashes, block size, and hasher
+    /// are needed to load the filter with [`from_compressed_bytes`](BloomFilter::from_compressed_bytes).
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_num_bits(1 << 16).seed(&42).items([1, 2]);
+    /// let bytes = bloom.to_compressed_bytes(3);
+    /// assert!(bytes.len() < bloom.num_bits() / 8);
+    /// ```
+    pub fn to_compressed_bytes(&self, level: i32) -> Vec<u8> {
+        zstd::bulk::compress(&self.as_bytes(), level).expect("compressing to memory does not fail")
+    }
+}
+
+impl BloomFilter {
+    /// Creates a new instance of [`BuilderWithBits`] to construct a `BloomFilter` from bits compressed with
+    /// [`to_compressed_bytes`](BloomFilter::to_compressed_bytes).
+    ///
+    /// As with [`from_vec`](Self::from_vec), the bits will be padded with `0u64`s to fit the block size.
+    ///
+    /// # Errors
+    /// Returns an error if `bytes` are not zstd compressed, or do not decompress to a whole, non-zero number of `u64`s.
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let orig = BloomFilter::with_false_pos(0.001).seed(&42).items([1, 2]);
+    /// let bytes = orig.to_compressed_bytes(3);
+    /// let new = BloomFilter::from_compressed_bytes(&bytes)
+    ///     .unwrap()
+    ///     .seed(&42)
+    ///     .hashes(orig.num_hashes());
+    ///
+    /// assert!(new.contains(&1));
+    /// assert!(new.contains(&2));
+    /// ```
+    pub fn from_compressed_bytes(bytes: &[u8]) -> io::Result<BuilderWithBits<512>> {
+        let bytes = zstd::stream::decode_all(bytes)?;
+        BloomFilter::from_bytes(&bytes).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "decompressed bits are not a whole, non-zero number of u64s",
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compressed_round_trip() {
+        for level in [0, 1, 3, 19] {
+            let orig = BloomFilter::with_num_bits(1 << 16).seed(&7).items(0..100);
+            let bytes = orig.to_compressed_bytes(level);
+            assert!(bytes.len() < orig.num_bits() / 8);
+            let new = BloomFilter::from_compressed_bytes(&bytes)
+                .unwrap()
+                .seed(&7)
+                .hashes(orig.num_hashes());
+            assert_eq!(new.as_slice(), orig.as_slice());
+            assert!((0..100).all(|x| new.contains(&x)));
+        }
+    }
+
+    #[test]
+    fn compressed_other_block_size() {
+        let orig = BloomFilter::with_num_bits(1024)
+            .block_size_64()
+            .seed(&7)
+            .items(0..10);
+        let new = BloomFilter::from_compressed_bytes(&orig.to_compressed_bytes(1))
+            .unwrap()
+            .block_size_64()
+            .seed(&7)
+            .hashes(orig.num_hashes());
+        assert_eq!(new, orig);
+    }
+
+    #[test]
+    fn rejects_malformed() {
+        assert!(BloomFilter::from_compressed_bytes(b"not zstd").is_err());
+        let partial_word = zstd::bulk::compress(&[0; 12], 1).unwrap();
+        assert!(BloomFilter::from_compressed_bytes(&partial_word).is_err());
+        let empty = zstd::bulk::compress(&[], 1).unwrap();
+        assert!(BloomFilter::from_compressed_bytes(&empty).is_err());
+    }
+}
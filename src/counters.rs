@@ -0,0 +1,177 @@
+#[cfg(feature = "counters")]
+use crate::{BitStorage, BloomFilter};
+#[cfg(feature = "counters")]
+use std::hash::BuildHasher;
+#[cfg(feature = "counters")]
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The counts of operations on a [`BloomFilter`] since it was created or its counters were reset.
+///
+/// Constructed via [`BloomFilter::counters`]. Inserts count every call to an `insert` method, including
+/// repeated items, and queries count every call to a `contains` method. The ratio of positives to queries
+/// is the rate at which queries hit the filter, false positives included.
+///
+/// # Examples
+/// ```
+/// use fastbloom::BloomFilter;
+///
+/// let mut bloom = BloomFilter::with_num_bits(1024).hashes(4);
+/// bloom.insert(&1);
+/// bloom.contains(&1);
+/// bloom.contains(&2);
+/// let counters = bloom.counters();
+/// assert_eq!(counters.inserts(), 1);
+/// assert_eq!(counters.queries(), 2);
+/// assert!(counters.positives() >= 1);
+/// ```
+#[cfg(feature = "counters")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Counters {
+    inserts: u64,
+    queries: u64,
+    positives: u64,
+}
+
+#[cfg(feature = "counters")]
+impl Counters {
+    /// Returns the number of items inserted.
+    #[inline]
+    pub fn inserts(&self) -> u64 {
+        self.inserts
+    }
+
+    /// Returns the number of items checked.
+    #[inline]
+    pub fn queries(&self) -> u64 {
+        self.queries
+    }
+
+    /// Returns the number of items checked that are possibly in the filter.
+    #[inline]
+    pub fn positives(&self) -> u64 {
+        self.positives
+    }
+}
+
+/// The counters of a filter, updated with relaxed atomics so that queries through `&self` can count.
+///
+/// Without the `counters` feature, this is empty and counting does nothing, so it costs nothing.
+#[cfg(feature = "counters")]
+#[derive(Debug, Default)]
+pub(crate) struct AtomicCounters {
+    inserts: AtomicU64,
+    queries: AtomicU64,
+    positives: AtomicU64,
+}
+
+#[cfg(feature = "counters")]
+impl AtomicCounters {
+    pub(crate) const fn new() -> Self {
+        Self {
+            inserts: AtomicU64::new(0),
+            queries: AtomicU64::new(0),
+            positives: AtomicU64::new(0),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn record_insert(&self) {
+        self.inserts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn record_query(&self, positive: bool) {
+        self.queries.fetch_add(1, Ordering::Relaxed);
+        if positive {
+            self.positives.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn load(&self) -> Counters {
+        Counters {
+            inserts: self.inserts.load(Ordering::Relaxed),
+            queries: self.queries.load(Ordering::Relaxed),
+            positives: self.positives.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(feature = "counters")]
+impl Clone for AtomicCounters {
+    fn clone(&self) -> Self {
+        let counters = self.load();
+        Self {
+            inserts: AtomicU64::new(counters.inserts),
+            queries: AtomicU64::new(counters.queries),
+            positives: AtomicU64::new(counters.positives),
+        }
+    }
+}
+
+#[cfg(not(feature = "counters"))]
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AtomicCounters;
+
+#[cfg(not(feature = "counters"))]
+impl AtomicCounters {
+    pub(crate) const fn new() -> Self {
+        Self
+    }
+
+    #[inline(always)]
+    pub(crate) fn record_insert(&self) {}
+
+    #[inline(always)]
+    pub(crate) fn record_query(&self, _positive: bool) {}
+}
+
+#[cfg(feature = "counters")]
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher, T: BitStorage>
+    BloomFilter<BLOCK_SIZE_BITS, S, T>
+{
+    /// Returns the counts of inserts, queries, and positive results of this filter. See [`Counters`].
+    ///
+    /// The counts are not serialized, and start from 0 for deserialized filters. Clones start from the counts
+    /// of the filter they are cloned from.
+    pub fn counters(&self) -> Counters {
+        self.counters.load()
+    }
+
+    /// Resets the counts of inserts, queries, and positive results to 0, e.g. at the start of each reporting interval.
+    pub fn reset_counters(&self) {
+        self.counters.inserts.store(0, Ordering::Relaxed);
+        self.counters.queries.store(0, Ordering::Relaxed);
+        self.counters.positives.store(0, Ordering::Relaxed);
+    }
+}
+
+#[cfg(all(test, feature = "counters"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_operations() {
+        let mut bloom = BloomFilter::with_num_bits(4096)
+            .seed(&1)
+            .two_choice()
+            .hashes(4);
+        bloom.extend(0..10);
+        bloom.insert_bytes(b"a");
+        bloom.insert_hash(7);
+        let key = bloom.prepare(&10);
+        bloom.insert_prepared(&key);
+        assert_eq!(bloom.counters().inserts(), 13);
+
+        assert!(bloom.contains_all(0..10));
+        assert!(bloom.contains_bytes(b"a") && bloom.contains_hash(7));
+        assert!(bloom.contains_prepared(&key));
+        let negatives = (100..1100).filter(|x| !bloom.contains(x)).count() as u64;
+        let counters = bloom.counters();
+        assert_eq!(counters.queries(), 1013);
+        assert_eq!(counters.positives(), 1013 - negatives);
+        assert_eq!(bloom.clone().counters(), counters);
+
+        bloom.reset_counters();
+        assert_eq!(bloom.counters(), Counters::default());
+    }
+}
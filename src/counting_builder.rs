@@ -0,0 +1,145 @@
+use crate::{get_orginal_hashes, BloomFilter, BuilderWithFalsePositiveRate, DefaultHasher};
+use std::hash::{BuildHasher, Hash};
+
+/// The number of hash bits selecting a register. 2^12 registers estimate with about 1.6% standard error.
+const PRECISION: u32 = 12;
+const NUM_REGISTERS: usize = 1 << PRECISION;
+
+/// A Bloom filter builder that counts the distinct items in a first pass, then sizes the filter for them.
+///
+/// Useful when items are streamed, e.g. from a file, and their number is not known in advance.
+/// Distinct items are counted with a HyperLogLog sketch of 4 KiB, using the filter's hasher,
+/// so the count is an estimate with about 1.6% standard error, and duplicate items are not counted twice.
+///
+/// Constructed via [`BuilderWithFalsePositiveRate::counting`].
+///
+/// # Examples
+/// ```
+/// use fastbloom::BloomFilter;
+///
+/// let items = (0..1000).chain(0..1000); // e.g. lines of a file, with duplicates
+/// let filter = BloomFilter::with_false_pos(0.001).counting().items(items);
+/// assert!((0..1000).all(|x| filter.contains(&x)));
+/// ```
+///
+/// Or count and insert in separate passes:
+/// ```
+/// use fastbloom::BloomFilter;
+///
+/// let mut builder = BloomFilter::with_false_pos(0.001).counting();
+/// for x in 0..1000 {
+///     builder.count(&x);
+/// }
+/// let mut filter = builder.build();
+/// filter.extend(0..1000);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CountingBuilder<const BLOCK_SIZE_BITS: usize = 512, S = DefaultHasher> {
+    builder: BuilderWithFalsePositiveRate<BLOCK_SIZE_BITS, S>,
+    /// The HyperLogLog registers, each the maximum rank of the hashes selecting it
+    registers: Box<[u8]>,
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher>
+    BuilderWithFalsePositiveRate<BLOCK_SIZE_BITS, S>
+{
+    /// Returns a [`CountingBuilder`] that sizes the later constructed [`BloomFilter`] by counting its items first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let builder = BloomFilter::with_false_pos(0.001).seed(&1).counting();
+    /// ```
+    pub fn counting(self) -> CountingBuilder<BLOCK_SIZE_BITS, S> {
+        CountingBuilder {
+            builder: self,
+            registers: vec![0; NUM_REGISTERS].into_boxed_slice(),
+        }
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> CountingBuilder<BLOCK_SIZE_BITS, S> {
+    /// Counts an item, if it was not counted before.
+    #[inline]
+    pub fn count(&mut self, val: &(impl Hash + ?Sized)) {
+        let [hash, _] = get_orginal_hashes(&self.builder.hasher, val);
+        let index = (hash >> (64 - PRECISION)) as usize;
+        let rank = ((hash << PRECISION) | (1 << (PRECISION - 1))).leading_zeros() as u8 + 1;
+        self.registers[index] = self.registers[index].max(rank);
+    }
+
+    /// Returns the estimated number of distinct items counted.
+    pub fn estimate(&self) -> usize {
+        let m = NUM_REGISTERS as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|&r| 1.0 / (1u64 << r) as f64)
+            .sum();
+        let estimate = alpha * m * m / sum;
+        let zeros = self.registers.iter().filter(|&&r| r == 0).count();
+        if estimate <= 2.5 * m && zeros > 0 {
+            // Linear counting is more accurate for few items
+            (m * f64::ln(m / zeros as f64)).round() as usize
+        } else {
+            estimate.round() as usize
+        }
+    }
+
+    /// "Consumes" this builder, returning an empty [`BloomFilter`] sized for the [`estimate`](Self::estimate)d number of items
+    /// to meet the desired false positive rate.
+    pub fn build(self) -> BloomFilter<BLOCK_SIZE_BITS, S> {
+        let expected_num_items = self.estimate();
+        self.builder.expected_items(expected_num_items)
+    }
+
+    /// "Consumes" this builder and constructs a [`BloomFilter`] containing all values in `items`,
+    /// iterating `items` twice: once to count them, and once to insert them.
+    pub fn items<I>(mut self, items: I) -> BloomFilter<BLOCK_SIZE_BITS, S>
+    where
+        I: IntoIterator + Clone,
+        I::Item: Hash,
+    {
+        for val in items.clone() {
+            self.count(&val);
+        }
+        let mut filter = self.build();
+        filter.extend(items);
+        filter
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimate_is_accurate() {
+        for num_items in [0, 10, 1000, 10_000, 100_000] {
+            let mut builder = BloomFilter::with_false_pos(0.01).seed(&42).counting();
+            for x in 0..num_items {
+                builder.count(&x);
+                builder.count(&x);
+            }
+            let estimate = builder.estimate() as f64;
+            let error = (estimate - num_items as f64).abs() / (num_items as f64).max(1.0);
+            assert!(error < 0.05, "{num_items} {estimate}");
+        }
+    }
+
+    #[test]
+    fn sized_like_expected_items() {
+        let num_items = 10_000;
+        let filter = BloomFilter::with_false_pos(0.01)
+            .seed(&42)
+            .counting()
+            .items(0..num_items);
+        let expected = BloomFilter::with_false_pos(0.01).expected_items(num_items);
+        let ratio = filter.num_bits() as f64 / expected.num_bits() as f64;
+        assert!((0.95..1.05).contains(&ratio), "{ratio}");
+        assert!((0..num_items).all(|x| filter.contains(&x)));
+    }
+}
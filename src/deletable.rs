@@ -0,0 +1,269 @@
+use crate::BloomFilter;
+use std::hash::{BuildHasher, Hash};
+
+/// A deletable Bloom filter (DlBF), a [`BloomFilter`] that supports removing items without counters.
+///
+/// The underlying bit vector is split into regions, and a small bitmap, with one bit per region, records whether a
+/// collision (an insert setting an already set bit) ever happened in that region. Bits in collision-free regions were
+/// set by exactly one item, so they can be safely reset when that item is removed.
+/// An item can be removed if at least one of its bits is in a collision-free region.
+/// As the filter fills up, more regions collide and fewer items are removable.
+///
+/// More regions make more items removable, at the cost of one bit of memory per region.
+/// Sparse hashes set many bits per `u64`, so filters with few hashes per item and spare capacity
+/// (see [`BuilderWithBits::hashes`](crate::BuilderWithBits::hashes)) keep far more items removable.
+///
+/// See <https://arxiv.org/abs/1005.0352>.
+///
+/// # Examples
+/// ```
+/// use fastbloom::{BloomFilter, DeletableBloomFilter};
+///
+/// let filter = BloomFilter::with_false_pos(0.001).expected_items(1000);
+/// let mut deletable = DeletableBloomFilter::new(filter, 1024);
+/// deletable.insert("42");
+/// assert!(deletable.contains("42"));
+/// assert!(deletable.remove("42"));
+/// assert!(!deletable.contains("42"));
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeletableBloomFilter<const BLOCK_SIZE_BITS: usize = 512, S = crate::DefaultHasher> {
+    filter: BloomFilter<BLOCK_SIZE_BITS, S>,
+    /// One bit per region, set if a collision happened in the region
+    collisions: Vec<u64>,
+    /// The number of `u64`s of the filter per region
+    region_len: usize,
+    num_regions: usize,
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> DeletableBloomFilter<BLOCK_SIZE_BITS, S> {
+    /// Creates a new `DeletableBloomFilter` from `filter`, tracking collisions in up to `num_regions` regions.
+    /// Regions are whole `u64`s of the filter, so there are at most as many regions as `u64`s in `filter`.
+    ///
+    /// `filter` is cleared before use.
+    ///
+    /// # Panics
+    /// Panics if `num_regions` is 0.
+    pub fn new(mut filter: BloomFilter<BLOCK_SIZE_BITS, S>, num_regions: usize) -> Self {
+        assert!(num_regions > 0);
+        filter.clear();
+        let num_u64s = filter.as_slice().len();
+        let region_len = num_u64s.div_ceil(std::cmp::min(num_regions, num_u64s));
+        let num_regions = num_u64s.div_ceil(region_len);
+        Self {
+            filter,
+            collisions: vec![0; num_regions.div_ceil(64)],
+            region_len,
+            num_regions,
+        }
+    }
+
+    #[inline]
+    fn region(&self, u64_index: usize) -> usize {
+        u64_index / self.region_len
+    }
+
+    #[inline]
+    fn is_collided(&self, region: usize) -> bool {
+        self.collisions[region >> 6] & (1 << (region & 63)) > 0
+    }
+
+    #[inline]
+    fn set_collided(&mut self, region: usize) {
+        self.collisions[region >> 6] |= 1 << (region & 63);
+    }
+
+    /// Inserts an element into the filter, marking the regions of any bits that were already set as collided.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item may have been previously in the filter (indicating a potential false positive),
+    /// `false` otherwise.
+    pub fn insert(&mut self, val: &(impl Hash + ?Sized)) -> bool {
+        let masks = self.filter.masks(val);
+        let mut previously_contained = true;
+        for &(i, mask) in masks.iter() {
+            let existing = self.filter.as_slice()[i] & mask;
+            previously_contained &= existing == mask;
+            if existing > 0 {
+                self.set_collided(self.region(i));
+            }
+        }
+        let bits = self.filter.as_mut_slice();
+        for (i, mask) in masks {
+            bits[i] |= mask;
+        }
+        previously_contained
+    }
+
+    /// Checks if an element is possibly in the filter.
+    #[inline]
+    pub fn contains(&self, val: &(impl Hash + ?Sized)) -> bool {
+        self.filter.contains(val)
+    }
+
+    /// Returns `true` if the element is possibly in the filter and can be removed,
+    /// i.e. at least one of its bits is in a collision-free region.
+    pub fn is_removable(&self, val: &(impl Hash + ?Sized)) -> bool {
+        self.contains(val)
+            && self
+                .filter
+                .masks(val)
+                .iter()
+                .any(|&(i, _)| !self.is_collided(self.region(i)))
+    }
+
+    /// Removes an element from the filter by resetting its bits in collision-free regions.
+    ///
+    /// Only items that were inserted should be removed: removing a false positive may reset bits
+    /// of another item, causing a false negative.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item was removed, `false` if it was not in the filter or all of its bits are in collided regions.
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::{BloomFilter, DeletableBloomFilter};
+    ///
+    /// let mut deletable = DeletableBloomFilter::new(BloomFilter::with_num_bits(1024).hashes(4), 16);
+    /// deletable.insert(&1);
+    /// assert!(deletable.remove(&1));
+    /// assert!(!deletable.remove(&1));
+    /// ```
+    pub fn remove(&mut self, val: &(impl Hash + ?Sized)) -> bool {
+        if !self.is_removable(val) {
+            return false;
+        }
+        let masks = self.filter.masks(val);
+        for (i, mask) in masks {
+            if !self.is_collided(self.region(i)) {
+                self.filter.as_mut_slice()[i] &= !mask;
+            }
+        }
+        true
+    }
+
+    /// Returns the number of regions collisions are tracked for.
+    #[inline]
+    pub fn num_regions(&self) -> usize {
+        self.num_regions
+    }
+
+    /// Returns the number of regions a collision has happened in.
+    /// Items with all their bits in these regions can't be removed.
+    pub fn num_collided_regions(&self) -> usize {
+        self.collisions
+            .iter()
+            .map(|x| x.count_ones() as usize)
+            .sum()
+    }
+
+    /// Returns a reference to the underlying Bloom filter.
+    #[inline]
+    pub fn filter(&self) -> &BloomFilter<BLOCK_SIZE_BITS, S> {
+        &self.filter
+    }
+
+    /// Returns the number of bytes this `DeletableBloomFilter` uses, including the bits of the filter and of the collision regions.
+    /// See [`BloomFilter::memory_usage`].
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.filter.heap_bytes()
+            + self.collisions.capacity() * std::mem::size_of::<u64>()
+    }
+
+    /// Clear the filter and all collisions, removing all items.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.filter.clear();
+        for x in self.collisions.iter_mut() {
+            *x = 0;
+        }
+    }
+}
+
+impl<T, const BLOCK_SIZE_BITS: usize, S: BuildHasher> Extend<T>
+    for DeletableBloomFilter<BLOCK_SIZE_BITS, S>
+where
+    T: Hash,
+{
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.insert(&val);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deletable<const N: usize>(num_items: usize) -> DeletableBloomFilter<N> {
+        let filter = BloomFilter::new_builder::<N>(64 * num_items)
+            .seed(&42)
+            .hashes(4);
+        let num_regions = filter.as_slice().len();
+        DeletableBloomFilter::new(filter, num_regions)
+    }
+
+    #[test]
+    fn masks_match_insert() {
+        fn masks_match_insert_<const N: usize>() {
+            let mut filter = BloomFilter::new_with_false_pos::<N>(0.001)
+                .seed(&42)
+                .expected_items(1000);
+            let mut deletable = DeletableBloomFilter::new(filter.clone(), 64);
+            for x in 0..1000 {
+                assert_eq!(filter.insert(&x), deletable.insert(&x));
+            }
+            assert_eq!(&filter, deletable.filter());
+        }
+        masks_match_insert_::<64>();
+        masks_match_insert_::<128>();
+        masks_match_insert_::<256>();
+        masks_match_insert_::<512>();
+    }
+
+    #[test]
+    fn no_false_negatives_after_remove() {
+        fn no_false_negatives_after_remove_<const N: usize>() {
+            let num_items = 1000;
+            let mut filter = deletable::<N>(num_items);
+            filter.extend(0..num_items);
+            let removed: Vec<_> = (0..num_items / 2).filter(|x| filter.remove(x)).collect();
+            assert!(removed.len() > num_items / 4);
+            assert!(!removed.iter().any(|x| filter.contains(x)));
+            assert!((num_items / 2..num_items).all(|x| filter.contains(&x)));
+        }
+        no_false_negatives_after_remove_::<64>();
+        no_false_negatives_after_remove_::<128>();
+        no_false_negatives_after_remove_::<256>();
+        no_false_negatives_after_remove_::<512>();
+    }
+
+    #[test]
+    fn nothing_after_clear() {
+        let mut filter = deletable::<512>(100);
+        filter.extend(0..100);
+        filter.clear();
+        assert!(!(0..100).any(|x| filter.contains(&x)));
+        assert_eq!(filter.num_collided_regions(), 0);
+    }
+
+    #[test]
+    fn regions_cover_filter() {
+        let filter = BloomFilter::with_num_bits(64 * 100).hashes(4);
+        assert_eq!(
+            DeletableBloomFilter::new(filter.clone(), 1).num_regions(),
+            1
+        );
+        assert_eq!(
+            DeletableBloomFilter::new(filter.clone(), 7).num_regions(),
+            7
+        );
+        assert_eq!(DeletableBloomFilter::new(filter, 1000).num_regions(), 104);
+    }
+}
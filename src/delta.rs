@@ -0,0 +1,235 @@
+#[cfg(feature = "serde")]
+use crate::FastbloomError;
+use crate::{BitStorage, BitStorageMut, BloomFilter};
+use std::hash::BuildHasher;
+
+/// The changed `u64`s of the bits of a [`BloomFilter`] between two versions, to sync a replica
+/// without sending the whole filter.
+///
+/// Constructed via [`BloomFilter::diff`] and applied with [`BloomFilter::apply_delta`]. Each changed `u64`
+/// is stored as its index and the XOR of its old and new bits, so a delta can be applied to the old version
+/// to get the new one, or to the new version to get the old one. Filters that are only inserted into
+/// between versions change few `u64`s, so the delta is much smaller than the filter.
+///
+/// # Examples
+/// ```
+/// use fastbloom::BloomFilter;
+///
+/// let mut bloom = BloomFilter::with_num_bits(1 << 20).seed(&1).hashes(4);
+/// let mut replica = bloom.clone();
+/// let old = bloom.clone();
+/// bloom.extend(0..10);
+///
+/// let delta = bloom.diff(&old);
+/// assert!(delta.len() <= 40);
+/// replica.apply_delta(&delta);
+/// assert_eq!(replica, bloom);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "DeltaFields"))]
+pub struct Delta {
+    /// The number of `u64`s of the filter.
+    num_u64s: usize,
+    /// The indexes, in ascending order, and XOR-ed old and new bits of the changed `u64`s.
+    words: Vec<(usize, u64)>,
+}
+
+/// The fields of a [`Delta`], deserialized before they are checked.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(rename = "Delta")]
+struct DeltaFields {
+    num_u64s: usize,
+    words: Vec<(usize, u64)>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<DeltaFields> for Delta {
+    type Error = FastbloomError;
+
+    /// Checks the indexes as [`Delta::from_bytes`] does.
+    fn try_from(fields: DeltaFields) -> Result<Self, FastbloomError> {
+        Self::checked(fields.num_u64s, fields.words).ok_or(FastbloomError::InvalidParameters)
+    }
+}
+
+impl Delta {
+    /// Returns the delta, or `None` if the indexes are not ascending and less than `num_u64s`,
+    /// so that [`BloomFilter::apply_delta`] can't index out of bounds.
+    fn checked(num_u64s: usize, words: Vec<(usize, u64)>) -> Option<Self> {
+        let ascending = words.windows(2).all(|w| w[0].0 < w[1].0);
+        let in_bounds = words.last().is_none_or(|&(index, _)| index < num_u64s);
+        (ascending && in_bounds).then_some(Self { num_u64s, words })
+    }
+
+    /// Returns the number of changed `u64`s.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Returns `true` if no bits changed.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Returns the indexes and XOR-ed old and new bits of the changed `u64`s, in ascending order of index.
+    #[inline]
+    pub fn words(&self) -> &[(usize, u64)] {
+        &self.words
+    }
+
+    /// Returns the delta as bytes: the number of `u64`s of the filter, followed by the index and
+    /// XOR-ed bits of each changed `u64`, all as little-endian `u64`s.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(8 + 16 * self.words.len());
+        bytes.extend_from_slice(&(self.num_u64s as u64).to_le_bytes());
+        for &(index, xor) in &self.words {
+            bytes.extend_from_slice(&(index as u64).to_le_bytes());
+            bytes.extend_from_slice(&xor.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Reads a delta written by [`to_bytes`](Self::to_bytes).
+    ///
+    /// Returns `None` if `bytes` is not in the layout of [`to_bytes`](Self::to_bytes), or the indexes are not
+    /// ascending and less than the number of `u64`s.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let (len, rest) = bytes.split_first_chunk::<8>()?;
+        if !rest.len().is_multiple_of(16) {
+            return None;
+        }
+        let num_u64s = usize::try_from(u64::from_le_bytes(*len)).ok()?;
+        let words = rest
+            .chunks_exact(16)
+            .map(|pair| {
+                let (index, xor) = pair.split_at(8);
+                let index = usize::try_from(u64::from_le_bytes(index.try_into().unwrap())).ok()?;
+                Some((index, u64::from_le_bytes(xor.try_into().unwrap())))
+            })
+            .collect::<Option<_>>()?;
+        Self::checked(num_u64s, words)
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher, T: BitStorage>
+    BloomFilter<BLOCK_SIZE_BITS, S, T>
+{
+    /// Returns the [`Delta`] from `old` to this filter: the `u64`s of bits that differ between them.
+    ///
+    /// # Panics
+    /// Panics if the filters have different numbers of bits.
+    pub fn diff<U: BitStorage>(&self, old: &BloomFilter<BLOCK_SIZE_BITS, S, U>) -> Delta {
+        assert_eq!(self.num_bits(), old.num_bits());
+        let words = self
+            .as_slice()
+            .iter()
+            .zip(old.as_slice())
+            .enumerate()
+            .filter(|(_, (new, old))| new != old)
+            .map(|(i, (new, old))| (i, new ^ old))
+            .collect();
+        Delta {
+            num_u64s: self.as_slice().len(),
+            words,
+        }
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher, T: BitStorageMut>
+    BloomFilter<BLOCK_SIZE_BITS, S, T>
+{
+    /// Applies `delta`, XOR-ing its changed `u64`s into the bits, so that a replica of the old version of
+    /// a filter becomes the version `delta` was taken [`diff`](Self::diff) from.
+    ///
+    /// # Panics
+    /// Panics if the filter and `delta` have different numbers of bits.
+    pub fn apply_delta(&mut self, delta: &Delta) {
+        assert_eq!(self.as_slice().len(), delta.num_u64s);
+        let bits = self.bits.as_mut_slice();
+        for &(index, xor) in &delta.words {
+            bits[index] ^= xor;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_diff() {
+        let mut bloom = BloomFilter::with_num_bits(1 << 16).seed(&1).hashes(6);
+        bloom.extend(0..1000);
+        let old = bloom.clone();
+        let mut replica = bloom.clone();
+        assert!(bloom.diff(&old).is_empty());
+
+        bloom.extend(1000..1100);
+        let delta = bloom.diff(&old);
+        assert!(!delta.is_empty() && delta.len() <= 600);
+        assert!(delta
+            .words()
+            .iter()
+            .all(|&(i, xor)| xor != 0 && bloom.as_slice()[i] ^ old.as_slice()[i] == xor));
+        replica.apply_delta(&delta);
+        assert_eq!(replica, bloom);
+
+        // Applying it again reverts to the old version
+        replica.apply_delta(&delta);
+        assert_eq!(replica, old);
+    }
+
+    #[test]
+    fn bytes_roundtrip() {
+        let old = BloomFilter::with_num_bits(4096).seed(&1).hashes(4);
+        let mut bloom = old.clone();
+        bloom.extend(0..20);
+        let delta = bloom.diff(&old);
+        let bytes = delta.to_bytes();
+        assert_eq!(bytes.len(), 8 + 16 * delta.len());
+        assert_eq!(Delta::from_bytes(&bytes), Some(delta));
+
+        assert_eq!(Delta::from_bytes(&bytes[..bytes.len() - 1]), None);
+        assert_eq!(Delta::from_bytes(&[]), None);
+        let mut out_of_bounds = 64u64.to_le_bytes().to_vec();
+        out_of_bounds.extend_from_slice(&64u64.to_le_bytes());
+        out_of_bounds.extend_from_slice(&1u64.to_le_bytes());
+        assert_eq!(Delta::from_bytes(&out_of_bounds), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_checks_indexes() {
+        let old = BloomFilter::with_num_bits(4096).seed(&1).hashes(4);
+        let mut bloom = old.clone();
+        bloom.extend(0..20);
+        let delta = bloom.diff(&old);
+        let json = serde_json::to_value(&delta).unwrap();
+        assert_eq!(serde_json::from_value::<Delta>(json).unwrap(), delta);
+
+        // an index out of bounds, and indexes not in ascending order
+        for words in [
+            serde_json::json!([[64, 1]]),
+            serde_json::json!([[1, 1], [0, 1]]),
+        ] {
+            let json = serde_json::json!({"num_u64s": 64, "words": words});
+            assert!(serde_json::from_value::<Delta>(json).is_err());
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn apply_to_different_size() {
+        let old = BloomFilter::with_num_bits(4096).hashes(4);
+        let mut bloom = old.clone();
+        bloom.insert(&1);
+        let delta = bloom.diff(&old);
+        BloomFilter::with_num_bits(8192)
+            .hashes(4)
+            .apply_delta(&delta);
+    }
+}
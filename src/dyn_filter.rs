@@ -0,0 +1,257 @@
+use crate::{BlockStats, BloomFilter, DefaultHasher, FilterStats};
+use std::hash::{BuildHasher, Hash};
+
+/// A [`BloomFilter`] whose block size is selected at runtime, e.g. from a config file,
+/// instead of by the `BLOCK_SIZE_BITS` const generic.
+///
+/// Each variant wraps a [`BloomFilter`] of one block size, and all methods forward to it.
+/// The match on the variant adds a small cost to each call; prefer [`BloomFilter`] when the block size is known at compile time.
+///
+/// # Examples
+/// ```
+/// use fastbloom::DynBloomFilter;
+///
+/// let block_size_bits = 256; // e.g. read from a config file
+/// let mut filter = DynBloomFilter::new_with_false_pos(block_size_bits, 0.01, 1000);
+/// filter.insert("42");
+/// assert!(filter.contains("42"));
+/// assert_eq!(filter.block_size_bits(), 256);
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DynBloomFilter<S = DefaultHasher> {
+    /// A [`BloomFilter`] with 64 bit blocks.
+    Block64(BloomFilter<64, S>),
+    /// A [`BloomFilter`] with 128 bit blocks.
+    Block128(BloomFilter<128, S>),
+    /// A [`BloomFilter`] with 256 bit blocks.
+    Block256(BloomFilter<256, S>),
+    /// A [`BloomFilter`] with 512 bit blocks.
+    Block512(BloomFilter<512, S>),
+}
+
+/// Calls `$body` with `$filter` bound to the wrapped [`BloomFilter`] of `$self`.
+macro_rules! forward {
+    ($self:expr, $filter:ident => $body:expr) => {
+        match $self {
+            DynBloomFilter::Block64($filter) => $body,
+            DynBloomFilter::Block128($filter) => $body,
+            DynBloomFilter::Block256($filter) => $body,
+            DynBloomFilter::Block512($filter) => $body,
+        }
+    };
+}
+
+impl DynBloomFilter {
+    /// Creates an empty `DynBloomFilter` with blocks of `block_size_bits` bits, sized for `expected_num_items`
+    /// to meet the false positive rate `fp`, like [`BuilderWithFalsePositiveRate::expected_items`](crate::BuilderWithFalsePositiveRate::expected_items).
+    ///
+    /// # Panics
+    /// Panics if `block_size_bits` is not 64, 128, 256, or 512, or if `fp` is 0.
+    pub fn new_with_false_pos(block_size_bits: usize, fp: f64, expected_num_items: usize) -> Self {
+        match block_size_bits {
+            64 => BloomFilter::new_with_false_pos::<64>(fp)
+                .expected_items(expected_num_items)
+                .into(),
+            128 => BloomFilter::new_with_false_pos::<128>(fp)
+                .expected_items(expected_num_items)
+                .into(),
+            256 => BloomFilter::new_with_false_pos::<256>(fp)
+                .expected_items(expected_num_items)
+                .into(),
+            512 => BloomFilter::new_with_false_pos::<512>(fp)
+                .expected_items(expected_num_items)
+                .into(),
+            _ => panic!("unsupported block size: {block_size_bits}"),
+        }
+    }
+
+    /// Creates an empty `DynBloomFilter` with blocks of `block_size_bits` bits, at least `num_bits` bits,
+    /// and `num_hashes` hashes per item, like [`BuilderWithBits::hashes`](crate::BuilderWithBits::hashes).
+    ///
+    /// # Panics
+    /// Panics if `block_size_bits` is not 64, 128, 256, or 512, or if `num_bits` is 0.
+    pub fn new_with_num_bits(block_size_bits: usize, num_bits: usize, num_hashes: u32) -> Self {
+        match block_size_bits {
+            64 => BloomFilter::new_builder::<64>(num_bits)
+                .hashes(num_hashes)
+                .into(),
+            128 => BloomFilter::new_builder::<128>(num_bits)
+                .hashes(num_hashes)
+                .into(),
+            256 => BloomFilter::new_builder::<256>(num_bits)
+                .hashes(num_hashes)
+                .into(),
+            512 => BloomFilter::new_builder::<512>(num_bits)
+                .hashes(num_hashes)
+                .into(),
+            _ => panic!("unsupported block size: {block_size_bits}"),
+        }
+    }
+}
+
+impl<S: BuildHasher> DynBloomFilter<S> {
+    /// Inserts an element into the Bloom filter.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item may have been previously in the Bloom filter (indicating a potential false positive),
+    /// `false` otherwise.
+    #[inline]
+    pub fn insert(&mut self, val: &(impl Hash + ?Sized)) -> bool {
+        forward!(self, filter => filter.insert(val))
+    }
+
+    /// Checks if an element is possibly in the Bloom filter.
+    #[inline]
+    pub fn contains(&self, val: &(impl Hash + ?Sized)) -> bool {
+        forward!(self, filter => filter.contains(val))
+    }
+
+    /// Returns the number of bits per block.
+    #[inline]
+    pub fn block_size_bits(&self) -> usize {
+        match self {
+            DynBloomFilter::Block64(_) => 64,
+            DynBloomFilter::Block128(_) => 128,
+            DynBloomFilter::Block256(_) => 256,
+            DynBloomFilter::Block512(_) => 512,
+        }
+    }
+
+    /// Returns the number of hashes per item.
+    #[inline]
+    pub fn num_hashes(&self) -> u32 {
+        forward!(self, filter => filter.num_hashes())
+    }
+
+    /// Returns the total number of in-memory bits supporting the Bloom filter.
+    pub fn num_bits(&self) -> usize {
+        forward!(self, filter => filter.num_bits())
+    }
+
+    /// Returns the total number of in-memory blocks supporting the Bloom filter.
+    /// Each block is [`block_size_bits`](Self::block_size_bits) bits.
+    pub fn num_blocks(&self) -> usize {
+        forward!(self, filter => filter.num_blocks())
+    }
+
+    /// Returns the estimated false positive rate of the Bloom filter. See [`BloomFilter::estimate_fp_rate`].
+    pub fn estimate_fp_rate(&self) -> f64 {
+        forward!(self, filter => filter.estimate_fp_rate())
+    }
+
+    /// Returns the fraction of `negative_sample`, which must not have been inserted, that the Bloom filter contains.
+    /// See [`BloomFilter::measure_fp_rate`].
+    pub fn measure_fp_rate(&self, negative_sample: impl IntoIterator<Item = impl Hash>) -> f64 {
+        forward!(self, filter => filter.measure_fp_rate(negative_sample))
+    }
+
+    /// Returns the distribution of the number of bits set per block. See [`BloomFilter::block_stats`].
+    pub fn block_stats(&self) -> BlockStats {
+        forward!(self, filter => filter.block_stats())
+    }
+
+    /// Returns a snapshot of the size, parameters, and fill of the Bloom filter. See [`BloomFilter::stats`].
+    pub fn stats(&self) -> FilterStats {
+        forward!(self, filter => filter.stats())
+    }
+
+    /// Returns the number of bytes this `DynBloomFilter` uses, including its bits. See [`BloomFilter::memory_usage`].
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>() + forward!(self, filter => filter.heap_bytes())
+    }
+
+    /// Returns a `u64` slice of this `DynBloomFilter`’s contents.
+    #[inline]
+    pub fn as_slice(&self) -> &[u64] {
+        forward!(self, filter => filter.as_slice())
+    }
+
+    /// Clear all of the bits in the Bloom filter, removing all items.
+    #[inline]
+    pub fn clear(&mut self) {
+        forward!(self, filter => filter.clear())
+    }
+}
+
+macro_rules! impl_from_block_size {
+    ($($size:literal = $variant:ident),* $(,)*) => (
+        $(
+            impl<S> From<BloomFilter<$size, S>> for DynBloomFilter<S> {
+                fn from(filter: BloomFilter<$size, S>) -> Self {
+                    DynBloomFilter::$variant(filter)
+                }
+            }
+        )*
+    )
+}
+
+impl_from_block_size!(64 = Block64, 128 = Block128, 256 = Block256, 512 = Block512);
+
+impl<T, S: BuildHasher> Extend<T> for DynBloomFilter<S>
+where
+    T: Hash,
+{
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        forward!(self, filter => filter.extend(iter))
+    }
+}
+
+impl<S: BuildHasher> PartialEq for DynBloomFilter<S> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (DynBloomFilter::Block64(a), DynBloomFilter::Block64(b)) => a == b,
+            (DynBloomFilter::Block128(a), DynBloomFilter::Block128(b)) => a == b,
+            (DynBloomFilter::Block256(a), DynBloomFilter::Block256(b)) => a == b,
+            (DynBloomFilter::Block512(a), DynBloomFilter::Block512(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+impl<S: BuildHasher> Eq for DynBloomFilter<S> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forwards_to_block_size() {
+        for block_size_bits in [64, 128, 256, 512] {
+            let mut filter = DynBloomFilter::new_with_false_pos(block_size_bits, 0.01, 1000);
+            assert_eq!(filter.block_size_bits(), block_size_bits);
+            assert_eq!(filter.num_bits(), filter.num_blocks() * block_size_bits);
+            filter.extend(0..1000);
+            assert!((0..1000).all(|x| filter.contains(&x)));
+            filter.clear();
+            assert!(filter.as_slice().iter().all(|&x| x == 0));
+
+            let filter = DynBloomFilter::new_with_num_bits(block_size_bits, 4096, 5);
+            assert_eq!(filter.num_hashes(), 5);
+            assert_eq!(filter.num_bits(), 4096);
+        }
+    }
+
+    #[test]
+    fn from_bloom_filter() {
+        let mut filter = BloomFilter::with_num_bits(1024)
+            .block_size_128()
+            .seed(&1)
+            .hashes(4);
+        filter.insert("42");
+        let dyn_filter: DynBloomFilter = filter.clone().into();
+        assert_eq!(dyn_filter, DynBloomFilter::Block128(filter));
+        assert!(dyn_filter.contains("42"));
+        assert_ne!(
+            DynBloomFilter::new_with_num_bits(64, 1024, 4),
+            DynBloomFilter::new_with_num_bits(128, 1024, 4)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn unsupported_block_size() {
+        DynBloomFilter::new_with_false_pos(32, 0.01, 1000);
+    }
+}
@@ -0,0 +1,73 @@
+use crate::BuilderWithFalsePositiveRate;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+impl<const BLOCK_SIZE_BITS: usize> BuilderWithFalsePositiveRate<BLOCK_SIZE_BITS> {
+    /// "Consumes" this builder, constructing a `BloomFilter` of the lines of the text file `items`
+    /// and writing it, with its parameters, to the file `out`, to be embedded with [`include_bloom!`](crate::include_bloom).
+    ///
+    /// This is a helper for build scripts, enabled by the `build` feature, so that a word list in the source tree
+    /// is compiled into a [`StaticBloomFilter`](crate::StaticBloomFilter) without a separate pipeline to produce
+    /// the filter. Each line, without its line ending, is inserted as a `&str`. Nothing is printed, so the build script
+    /// should print `cargo:rerun-if-changed` for `items` to rebuild the filter when the word list changes.
+    ///
+    /// # Errors
+    /// Returns an error if `items` cannot be read as UTF-8 text, or `out` cannot be written.
+    ///
+    /// # Examples
+    /// In `build.rs`, with `fastbloom` as a build dependency with the `build` feature:
+    /// ```no_run
+    /// use fastbloom::BloomFilter;
+    /// use std::{env, path::Path};
+    ///
+    /// println!("cargo:rerun-if-changed=words.txt");
+    /// let out = Path::new(&env::var("OUT_DIR").unwrap()).join("words.bf");
+    /// BloomFilter::with_false_pos(0.01)
+    ///     .embed_lines("words.txt", out)
+    ///     .unwrap();
+    /// ```
+    /// Then in the crate:
+    /// ```ignore
+    /// use fastbloom::{include_bloom, StaticBloomFilter};
+    ///
+    /// static WORDS: StaticBloomFilter = include_bloom!(concat!(env!("OUT_DIR"), "/words.bf"));
+    /// ```
+    pub fn embed_lines(self, items: impl AsRef<Path>, out: impl AsRef<Path>) -> io::Result<()> {
+        let text = fs::read_to_string(items)?;
+        let lines: Vec<&str> = text.lines().collect();
+        fs::write(out, self.items(lines).to_bytes_with_params())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BloomFilter;
+    use std::{env, fs};
+
+    #[test]
+    fn embeds_lines() {
+        let dir = env::temp_dir().join(format!("fastbloom-embed-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("words.txt"), "apple\nbanana\r\ncherry\n").unwrap();
+
+        BloomFilter::with_false_pos(0.001)
+            .block_size_256()
+            .seed(&1)
+            .embed_lines(dir.join("words.txt"), dir.join("words.bf"))
+            .unwrap();
+        let bytes = fs::read(dir.join("words.bf")).unwrap();
+        let bloom = BloomFilter::<256>::from_bytes_with_params(&bytes).unwrap();
+        let expected = BloomFilter::with_false_pos(0.001)
+            .block_size_256()
+            .seed(&1)
+            .items(["apple", "banana", "cherry"]);
+        assert_eq!(bloom, expected);
+        assert!(bloom.contains("banana"));
+
+        assert!(BloomFilter::with_false_pos(0.01)
+            .embed_lines(dir.join("missing.txt"), dir.join("missing.bf"))
+            .is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,166 @@
+use std::fmt;
+
+/// An error constructing, combining, or inserting into [`BloomFilter`](crate::BloomFilter)s, returned by the `try_`
+/// variants of its constructors, builders, and [`union`](crate::BloomFilter::union) instead of panicking.
+///
+/// # Examples
+/// ```
+/// use fastbloom::{BloomFilter, FastbloomError};
+///
+/// let err = BloomFilter::try_with_false_pos(0.0).unwrap_err();
+/// assert_eq!(err, FastbloomError::InvalidFalsePositiveRate(0.0));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum FastbloomError {
+    /// The filter would have no bits.
+    ZeroBits,
+    /// The bits cannot be padded, and their number of `u64`s is not a multiple of the block size.
+    NotWholeBlocks {
+        /// The number of `u64`s of bits.
+        num_u64s: usize,
+        /// The block size in bits.
+        block_size_bits: usize,
+    },
+    /// The bits to convert are not a whole number of blocks.
+    NotWholeBlockBits {
+        /// The number of bits.
+        num_bits: usize,
+        /// The block size in bits.
+        block_size_bits: usize,
+    },
+    /// The number of hashes per item is 0.
+    InvalidHashes(u32),
+    /// The target false positive rate is not greater than 0.
+    InvalidFalsePositiveRate(f64),
+    /// Bytes to deserialize are not a whole, non-zero number of `u64`s.
+    InvalidBytes {
+        /// The number of bytes.
+        len: usize,
+    },
+    /// Bytes to borrow as `u64`s are not aligned to 8 bytes, or the target is big-endian,
+    /// so they are not `u64`s in the right order.
+    UnalignedBytes,
+    /// A serialized filter has a different block size than the filter to deserialize it into.
+    MismatchedBlockSize {
+        /// The block size in bits of the serialized filter.
+        block_size_bits: usize,
+    },
+    /// A serialized filter, or [`Delta`](crate::Delta), has invalid parameters, e.g. a prefix length that does not fit
+    /// in a `usize`.
+    InvalidParameters,
+    /// Filters to combine have different numbers of bits or hashes, index strategies, or two-choice insertion,
+    /// so the same item sets different bits in each.
+    MismatchedFilters,
+    /// The filter has reached its saturation threshold. See [`SaturationMonitor`](crate::SaturationMonitor).
+    Saturated {
+        /// The fraction of the filter's bits that are set.
+        fill_ratio: f64,
+    },
+    /// There is no GPU, or it cannot hold the filter. See [`GpuBloomFilter`](crate::GpuBloomFilter).
+    #[cfg(feature = "wgpu")]
+    GpuUnavailable,
+}
+
+impl fmt::Display for FastbloomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ZeroBits => f.write_str("a Bloom filter must have at least one bit"),
+            Self::NotWholeBlocks {
+                num_u64s,
+                block_size_bits,
+            } => write!(
+                f,
+                "the number of u64s must be a multiple of the block size: {num_u64s} u64s with {block_size_bits} bit blocks"
+            ),
+            Self::NotWholeBlockBits {
+                num_bits,
+                block_size_bits,
+            } => write!(
+                f,
+                "the number of bits must be a multiple of the block size: {num_bits} bits with {block_size_bits} bit blocks"
+            ),
+            Self::InvalidHashes(num_hashes) => {
+                write!(f, "invalid number of hashes per item: {num_hashes}")
+            }
+            Self::InvalidFalsePositiveRate(fp) => {
+                write!(f, "the false positive rate must be greater than 0, got {fp}")
+            }
+            Self::InvalidBytes { len } => write!(
+                f,
+                "{len} bytes are not a whole, non-zero number of u64s"
+            ),
+            Self::UnalignedBytes => f.write_str(
+                "bytes can only be borrowed as u64s if aligned to 8 bytes on a little-endian target",
+            ),
+            Self::MismatchedBlockSize { block_size_bits } => write!(
+                f,
+                "the serialized filter has a different block size: {block_size_bits} bits"
+            ),
+            Self::InvalidParameters => f.write_str("the serialized parameters are invalid"),
+            Self::MismatchedFilters => {
+                f.write_str("the filters have different numbers of bits or hashes, or different parameters")
+            }
+            Self::Saturated { fill_ratio } => write!(
+                f,
+                "the filter is saturated: {:.1}% of its bits are set",
+                fill_ratio * 100.0
+            ),
+            #[cfg(feature = "wgpu")]
+            Self::GpuUnavailable => f.write_str("no GPU is available that can hold the filter"),
+        }
+    }
+}
+
+impl std::error::Error for FastbloomError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BloomFilter;
+
+    #[test]
+    fn try_constructors() {
+        assert_eq!(
+            BloomFilter::try_with_num_bits(0).unwrap_err(),
+            FastbloomError::ZeroBits
+        );
+        assert_eq!(
+            BloomFilter::try_from_vec(Vec::new()).unwrap_err(),
+            FastbloomError::ZeroBits
+        );
+        assert_eq!(
+            BloomFilter::try_from_slice(&[]).unwrap_err(),
+            FastbloomError::ZeroBits
+        );
+        assert!(BloomFilter::try_with_false_pos(f64::NAN).is_err());
+        assert_eq!(
+            BloomFilter::try_with_num_bits(64)
+                .unwrap()
+                .try_hashes(0)
+                .unwrap_err(),
+            FastbloomError::InvalidHashes(0)
+        );
+
+        let bits = [0u64; 3];
+        let err = BloomFilter::try_from_slice(&bits)
+            .unwrap()
+            .block_size_128()
+            .try_expected_items(10)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            FastbloomError::NotWholeBlocks {
+                num_u64s: 3,
+                block_size_bits: 128
+            }
+        );
+        assert!(err.to_string().contains("multiple of the block size"));
+
+        let bloom = BloomFilter::try_from_vec(vec![0; 3])
+            .unwrap()
+            .try_expected_items(10)
+            .unwrap();
+        assert_eq!(bloom.num_bits(), 512);
+    }
+}
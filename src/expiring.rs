@@ -0,0 +1,220 @@
+use crate::{BloomFilter, WindowedBloomFilter};
+use std::hash::{BuildHasher, Hash};
+use std::time::{Duration, Instant};
+
+/// A Bloom filter whose items expire after a time-to-live (TTL).
+///
+/// `ExpiringBloomFilter` is made of a fixed number of time buckets, each a [`BloomFilter`] covering `ttl / num_buckets` of time.
+/// Items are inserted into the bucket for the current time, and whole buckets are expired (cleared) once they age out of the TTL window.
+///
+/// [`contains`](Self::contains) only reports items inserted within the last `ttl`.
+/// Because buckets expire as a whole, an item is guaranteed to be contained for at least `ttl - ttl / num_buckets` after it was inserted.
+/// More buckets give finer expiration granularity, at the cost of memory and slower membership checks.
+///
+/// # Examples
+/// ```
+/// use fastbloom::{BloomFilter, ExpiringBloomFilter};
+/// use std::time::Duration;
+///
+/// let filter = BloomFilter::with_false_pos(0.001).expected_items(1000);
+/// let mut expiring = ExpiringBloomFilter::new(filter, Duration::from_secs(60), 6);
+/// expiring.insert("42");
+/// assert!(expiring.contains("42"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ExpiringBloomFilter<const BLOCK_SIZE_BITS: usize = 512, S = crate::DefaultHasher> {
+    /// Window of buckets, keyed by the number of bucket durations since `start`
+    buckets: WindowedBloomFilter<BLOCK_SIZE_BITS, S>,
+    bucket_duration: Duration,
+    start: Instant,
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher + Clone> ExpiringBloomFilter<BLOCK_SIZE_BITS, S> {
+    /// Creates a new `ExpiringBloomFilter` with `num_buckets` buckets using `filter` as the template for each bucket.
+    /// Items expire after `ttl`.
+    ///
+    /// `filter` is cleared before use.
+    ///
+    /// # Panics
+    /// Panics if `num_buckets` is not in `1..=u32::MAX`, or if `ttl` divided by `num_buckets` is zero.
+    pub fn new(filter: BloomFilter<BLOCK_SIZE_BITS, S>, ttl: Duration, num_buckets: usize) -> Self {
+        Self::new_at(filter, ttl, num_buckets, Instant::now())
+    }
+
+    /// Like [`ExpiringBloomFilter::new`], but the filter's clock starts at `now`.
+    pub fn new_at(
+        filter: BloomFilter<BLOCK_SIZE_BITS, S>,
+        ttl: Duration,
+        num_buckets: usize,
+        now: Instant,
+    ) -> Self {
+        assert!(
+            (1..=u32::MAX as usize).contains(&num_buckets),
+            "num_buckets must be in 1..=u32::MAX"
+        );
+        let bucket_duration = ttl / num_buckets as u32;
+        assert!(!bucket_duration.is_zero());
+        Self {
+            buckets: WindowedBloomFilter::new(filter, num_buckets),
+            bucket_duration,
+            start: now,
+        }
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> ExpiringBloomFilter<BLOCK_SIZE_BITS, S> {
+    /// Returns the epoch, i.e. the number of bucket durations since `start`, `now` falls into.
+    #[inline]
+    fn epoch(&self, now: Instant) -> u64 {
+        (now.saturating_duration_since(self.start).as_nanos() / self.bucket_duration.as_nanos())
+            as u64
+    }
+
+    /// Inserts an element into the bucket for the current time.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item may have been previously in the filter and not yet expired (indicating a potential false positive),
+    /// `false` otherwise.
+    #[inline]
+    pub fn insert(&mut self, val: &(impl Hash + ?Sized)) -> bool {
+        self.insert_at(val, Instant::now())
+    }
+
+    /// Inserts an element into the bucket for time `now`.
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::{BloomFilter, ExpiringBloomFilter};
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let start = Instant::now();
+    /// let filter = BloomFilter::with_num_bits(1024).hashes(4);
+    /// let mut expiring = ExpiringBloomFilter::new_at(filter, Duration::from_secs(10), 10, start);
+    /// expiring.insert_at(&1, start);
+    /// assert!(expiring.contains_at(&1, start + Duration::from_secs(5)));
+    /// assert!(!expiring.contains_at(&1, start + Duration::from_secs(10)));
+    /// ```
+    #[inline]
+    pub fn insert_at(&mut self, val: &(impl Hash + ?Sized), now: Instant) -> bool {
+        self.buckets.insert(self.epoch(now), val)
+    }
+
+    /// Checks if an element is possibly in the filter and not yet expired.
+    #[inline]
+    pub fn contains(&self, val: &(impl Hash + ?Sized)) -> bool {
+        self.contains_at(val, Instant::now())
+    }
+
+    /// Checks if an element is possibly in the filter and not yet expired at time `now`.
+    #[inline]
+    pub fn contains_at(&self, val: &(impl Hash + ?Sized), now: Instant) -> bool {
+        self.buckets.contains_at(self.epoch(now), val)
+    }
+
+    /// Clears all buckets whose items have expired at time `now`.
+    ///
+    /// Expired buckets are never checked by [`contains`](Self::contains), so this is
+    /// only needed to eagerly reset their memory.
+    #[inline]
+    pub fn expire_at(&mut self, now: Instant) {
+        self.buckets.advance(self.epoch(now));
+    }
+
+    /// Returns the amount of time items are contained for before expiring.
+    #[inline]
+    pub fn ttl(&self) -> Duration {
+        self.bucket_duration * self.buckets.window() as u32
+    }
+
+    /// Returns the number of time buckets.
+    #[inline]
+    pub fn num_buckets(&self) -> usize {
+        self.buckets.window()
+    }
+
+    /// Returns the span of time each bucket covers.
+    #[inline]
+    pub fn bucket_duration(&self) -> Duration {
+        self.bucket_duration
+    }
+
+    /// Returns the number of bytes this `ExpiringBloomFilter` uses, including the bits of all buckets.
+    /// See [`BloomFilter::memory_usage`].
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>() - std::mem::size_of::<WindowedBloomFilter<BLOCK_SIZE_BITS, S>>()
+            + self.buckets.memory_usage()
+    }
+
+    /// Clear all buckets, removing all items.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.buckets.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expiring(start: Instant, ttl_secs: u64, num_buckets: usize) -> ExpiringBloomFilter {
+        let filter = BloomFilter::with_false_pos(0.0001)
+            .seed(&42)
+            .expected_items(1000);
+        ExpiringBloomFilter::new_at(filter, Duration::from_secs(ttl_secs), num_buckets, start)
+    }
+
+    #[test]
+    fn contained_within_ttl() {
+        let start = Instant::now();
+        let mut filter = expiring(start, 60, 6);
+        for sec in 0..120 {
+            let now = start + Duration::from_secs(sec);
+            filter.insert_at(&sec, now);
+            // Guaranteed for at least ttl - bucket duration
+            for prev in sec.saturating_sub(50)..=sec {
+                assert!(filter.contains_at(&prev, now));
+            }
+        }
+    }
+
+    #[test]
+    fn expired_after_ttl() {
+        let start = Instant::now();
+        let mut filter = expiring(start, 60, 6);
+        for sec in 0..120 {
+            let now = start + Duration::from_secs(sec);
+            filter.insert_at(&sec, now);
+            for prev in 0..sec.saturating_sub(59) {
+                assert!(!filter.contains_at(&prev, now));
+            }
+        }
+    }
+
+    #[test]
+    fn expire_clears_buckets() {
+        let start = Instant::now();
+        let mut filter = expiring(start, 10, 2);
+        filter.insert_at(&1, start);
+        filter.expire_at(start + Duration::from_secs(10));
+        assert!(!filter.contains_at(&1, start));
+    }
+
+    #[test]
+    fn nothing_after_clear() {
+        let start = Instant::now();
+        let mut filter = expiring(start, 10, 5);
+        for i in 0..100 {
+            filter.insert_at(&i, start);
+        }
+        filter.clear();
+        assert!(!(0..100).any(|i| filter.contains_at(&i, start)));
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    #[should_panic(expected = "num_buckets")]
+    fn too_many_buckets() {
+        expiring(Instant::now(), 1 << 40, 1 << 32);
+    }
+}
@@ -0,0 +1,205 @@
+use crate::bit_vector::BlockedBitVec;
+use crate::{
+    block_index, get_orginal_hashes, AtomicCounters, BloomFilter, DefaultHasher, IndexStrategy,
+};
+use std::hash::{BuildHasher, Hash};
+
+/// A [`BloomFilter`] with a number of hashes per item, `NUM_HASHES`, fixed at compile time.
+///
+/// Each item sets `NUM_HASHES` single bits, chosen as by a [`BloomFilter`] without sparse hashes. Since the number
+/// of hashes is a constant, the loops of [`insert`](Self::insert) and [`contains`](Self::contains) are fully unrolled,
+/// and there is no branch on how the hashes are split, which makes queries faster for deployments with a fixed
+/// configuration. A [`BloomFilter`] spends fewer operations per bit for many hashes per item with sparse hashes,
+/// so `FixedHashesBloomFilter` is best suited to few hashes per item, e.g. up to about 10.
+///
+/// Constructed via [`BuilderWithBits::fixed_hashes`](crate::BuilderWithBits::fixed_hashes).
+/// It converts into a [`BloomFilter`] with the same bits and items, e.g. to serialize it.
+///
+/// # Examples
+/// ```
+/// use fastbloom::BloomFilter;
+///
+/// let mut filter = BloomFilter::with_num_bits(8192).fixed_hashes::<7>();
+/// filter.insert("42");
+/// assert!(filter.contains("42"));
+/// assert_eq!(filter.num_hashes(), 7);
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FixedHashesBloomFilter<
+    const BLOCK_SIZE_BITS: usize = 512,
+    const NUM_HASHES: usize = 8,
+    S = DefaultHasher,
+> {
+    pub(crate) bits: BlockedBitVec<BLOCK_SIZE_BITS>,
+    pub(crate) hasher: S,
+}
+
+impl<const BLOCK_SIZE_BITS: usize, const NUM_HASHES: usize, S: BuildHasher>
+    FixedHashesBloomFilter<BLOCK_SIZE_BITS, NUM_HASHES, S>
+{
+    /// Inserts an element into the Bloom filter.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item may have been previously in the Bloom filter (indicating a potential false positive),
+    /// `false` otherwise.
+    #[inline]
+    pub fn insert(&mut self, val: &(impl Hash + ?Sized)) -> bool {
+        let [mut h1, h2] = get_orginal_hashes(&self.hasher, val);
+        let num_blocks = self.num_blocks();
+        let mut previously_contained = true;
+        for _ in 0..NUM_HASHES {
+            let block = self.bits.get_block_mut(block_index(num_blocks, h1));
+            let bit_index = BloomFilter::<BLOCK_SIZE_BITS, S>::bit_index(&mut h1, h2);
+            previously_contained &=
+                BlockedBitVec::<BLOCK_SIZE_BITS>::set_for_block(block, bit_index);
+        }
+        previously_contained
+    }
+
+    /// Checks if an element is possibly in the Bloom filter.
+    #[inline]
+    pub fn contains(&self, val: &(impl Hash + ?Sized)) -> bool {
+        let [mut h1, h2] = get_orginal_hashes(&self.hasher, val);
+        let num_blocks = self.num_blocks();
+        (0..NUM_HASHES).all(|_| {
+            let block = self.bits.get_block(block_index(num_blocks, h1));
+            let bit_index = BloomFilter::<BLOCK_SIZE_BITS, S>::bit_index(&mut h1, h2);
+            BlockedBitVec::<BLOCK_SIZE_BITS>::check_for_block(block, bit_index)
+        })
+    }
+
+    /// Returns the number of hashes per item, `NUM_HASHES`.
+    #[inline]
+    pub fn num_hashes(&self) -> u32 {
+        NUM_HASHES as u32
+    }
+
+    /// Returns the total number of in-memory bits supporting the Bloom filter.
+    pub fn num_bits(&self) -> usize {
+        self.num_blocks() * BLOCK_SIZE_BITS
+    }
+
+    /// Returns the total number of in-memory blocks supporting the Bloom filter.
+    /// Each block is `BLOCK_SIZE_BITS` bits.
+    pub fn num_blocks(&self) -> usize {
+        self.bits.num_blocks()
+    }
+
+    /// Returns a `u64` slice of this `FixedHashesBloomFilter`’s contents.
+    #[inline]
+    pub fn as_slice(&self) -> &[u64] {
+        self.bits.as_slice()
+    }
+
+    /// Returns the number of bytes this `FixedHashesBloomFilter` uses, including its bits.
+    /// See [`BloomFilter::memory_usage`].
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>() + self.bits.heap_bytes()
+    }
+
+    /// Clear all of the bits in the Bloom filter, removing all items.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.bits.clear();
+    }
+}
+
+impl<T, const BLOCK_SIZE_BITS: usize, const NUM_HASHES: usize, S: BuildHasher> Extend<T>
+    for FixedHashesBloomFilter<BLOCK_SIZE_BITS, NUM_HASHES, S>
+where
+    T: Hash,
+{
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.insert(&val);
+        }
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, const NUM_HASHES: usize, S: BuildHasher> PartialEq
+    for FixedHashesBloomFilter<BLOCK_SIZE_BITS, NUM_HASHES, S>
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.bits == other.bits
+    }
+}
+impl<const BLOCK_SIZE_BITS: usize, const NUM_HASHES: usize, S: BuildHasher> Eq
+    for FixedHashesBloomFilter<BLOCK_SIZE_BITS, NUM_HASHES, S>
+{
+}
+
+/// Converts a `FixedHashesBloomFilter` into a [`BloomFilter`] with the same bits, hasher, and items,
+/// which sets `NUM_HASHES` single bits per item and no sparse hashes.
+impl<const BLOCK_SIZE_BITS: usize, const NUM_HASHES: usize, S>
+    From<FixedHashesBloomFilter<BLOCK_SIZE_BITS, NUM_HASHES, S>>
+    for BloomFilter<BLOCK_SIZE_BITS, S>
+{
+    fn from(filter: FixedHashesBloomFilter<BLOCK_SIZE_BITS, NUM_HASHES, S>) -> Self {
+        BloomFilter {
+            bits: filter.bits,
+            target_hashes: NUM_HASHES as u64,
+            num_rounds: None,
+            num_hashes: NUM_HASHES as u64,
+            two_choice: false,
+            index_strategy: IndexStrategy::DoubleHashing,
+            prefix: None,
+            hasher: filter.hasher,
+            counters: AtomicCounters::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BloomFilter;
+
+    #[test]
+    fn inserts_always_contained() {
+        fn inserts_always_contained_<const N: usize>() {
+            let mut filter = BloomFilter::new_builder::<N>(1 << 14)
+                .seed(&42)
+                .fixed_hashes::<5>();
+            for x in 0..1000 {
+                assert!(!filter.contains(&(x + 1000)) || filter.insert(&(x + 1000)));
+                filter.insert(&x);
+                assert!(filter.contains(&x));
+            }
+            assert!((0..1000).all(|x| filter.contains(&x)));
+        }
+        inserts_always_contained_::<64>();
+        inserts_always_contained_::<128>();
+        inserts_always_contained_::<256>();
+        inserts_always_contained_::<512>();
+    }
+
+    #[test]
+    fn same_as_bloom_filter() {
+        let mut filter = BloomFilter::with_num_bits(1 << 14)
+            .block_size_256()
+            .seed(&3)
+            .fixed_hashes::<3>();
+        filter.extend(0..1000);
+        let mut expected = BloomFilter::with_num_bits(1 << 14)
+            .block_size_256()
+            .seed(&3)
+            .hashes(3);
+        assert_eq!(expected.num_rounds(), None);
+        expected.extend(0..1000);
+        assert_eq!(filter.as_slice(), expected.as_slice());
+
+        let converted = BloomFilter::from(filter);
+        assert_eq!(converted, expected);
+        assert!((0..1000).all(|x| converted.contains(&x)));
+    }
+
+    #[test]
+    fn nothing_after_clear() {
+        let mut filter = BloomFilter::with_num_bits(1024).fixed_hashes::<4>();
+        filter.extend(0..100);
+        filter.clear();
+        assert!(!(0..100).any(|x| filter.contains(&x)));
+    }
+}
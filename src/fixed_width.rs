@@ -0,0 +1,151 @@
+use crate::{BitStorage, BlockedBitVec, BloomFilter, IndexStrategy, Prefix};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A [`BloomFilter`] that is serialized with its bits as fixed-width little-endian `u64`s in binary formats.
+///
+/// `BloomFilter`s serialize their bits as a sequence of `u64`s, which formats with variable-length integers,
+/// e.g. postcard, encode in up to 10 bytes each. `FixedWidth` serializes them as a byte string of 8 bytes
+/// per `u64` instead, which is smaller for all but sparse filters and is read without decoding each `u64`.
+/// Human-readable formats, e.g. JSON, are unaffected.
+///
+/// This is opt-in, since it changes the serialized form: a filter serialized with `FixedWidth` in a binary format
+/// must be deserialized with `FixedWidth`.
+///
+/// # Examples
+/// ```
+/// use fastbloom::{BloomFilter, FixedWidth};
+///
+/// let filter = BloomFilter::with_num_bits(1024).seed(&1).items(0..100);
+/// let bytes = postcard::to_allocvec(&FixedWidth(&filter)).unwrap();
+/// let FixedWidth(back): FixedWidth<BloomFilter> = postcard::from_bytes(&bytes).unwrap();
+/// assert_eq!(back, filter);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedWidth<F>(pub F);
+
+/// The serialized form of a `BloomFilter`, with the bits serialized with `serialize_fixed_width`.
+#[derive(Serialize)]
+#[serde(
+    rename = "BloomFilter",
+    bound(serialize = "S: Serialize, T: BitStorage")
+)]
+struct FilterRef<'a, const BLOCK_SIZE_BITS: usize, S, T> {
+    #[serde(serialize_with = "serialize_bits")]
+    bits: &'a BlockedBitVec<BLOCK_SIZE_BITS, T>,
+    target_hashes: u64,
+    num_rounds: Option<u64>,
+    num_hashes: u64,
+    hasher: &'a S,
+    two_choice: bool,
+    index_strategy: IndexStrategy,
+    prefix: Option<Prefix>,
+}
+
+fn serialize_bits<const BLOCK_SIZE_BITS: usize, T: BitStorage, S: Serializer>(
+    bits: &&BlockedBitVec<BLOCK_SIZE_BITS, T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    bits.serialize_fixed_width(serializer)
+}
+
+/// The deserialized form of a `FilterRef`.
+#[derive(Deserialize)]
+#[serde(
+    rename = "BloomFilter",
+    bound(deserialize = "S: Deserialize<'de>, T: From<Vec<u64>>")
+)]
+struct Filter<const BLOCK_SIZE_BITS: usize, S, T> {
+    #[serde(deserialize_with = "BlockedBitVec::deserialize_fixed_width")]
+    bits: BlockedBitVec<BLOCK_SIZE_BITS, T>,
+    target_hashes: u64,
+    num_rounds: Option<u64>,
+    num_hashes: u64,
+    hasher: S,
+    #[serde(default)]
+    two_choice: bool,
+    #[serde(default)]
+    index_strategy: IndexStrategy,
+    #[serde(default)]
+    prefix: Option<Prefix>,
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: Serialize, T: BitStorage> Serialize
+    for FixedWidth<&BloomFilter<BLOCK_SIZE_BITS, S, T>>
+{
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        let filter = self.0;
+        FilterRef {
+            bits: &filter.bits,
+            target_hashes: filter.target_hashes,
+            num_rounds: filter.num_rounds,
+            num_hashes: filter.num_hashes,
+            hasher: &filter.hasher,
+            two_choice: filter.two_choice,
+            index_strategy: filter.index_strategy,
+            prefix: filter.prefix,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: Serialize, T: BitStorage> Serialize
+    for FixedWidth<BloomFilter<BLOCK_SIZE_BITS, S, T>>
+{
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        FixedWidth(&self.0).serialize(serializer)
+    }
+}
+
+impl<'de, const BLOCK_SIZE_BITS: usize, S: Deserialize<'de>, T: From<Vec<u64>>> Deserialize<'de>
+    for FixedWidth<BloomFilter<BLOCK_SIZE_BITS, S, T>>
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let filter = Filter::deserialize(deserializer)?;
+        Ok(FixedWidth(BloomFilter {
+            bits: filter.bits,
+            target_hashes: filter.target_hashes,
+            num_rounds: filter.num_rounds,
+            num_hashes: filter.num_hashes,
+            hasher: filter.hasher,
+            two_choice: filter.two_choice,
+            index_strategy: filter.index_strategy,
+            prefix: filter.prefix,
+            counters: Default::default(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_width_binary() {
+        let filter = BloomFilter::from_vec(vec![u64::MAX; 8])
+            .seed(&1)
+            .two_choice()
+            .index_strategy(IndexStrategy::EnhancedDoubleHashing)
+            .prefix(Prefix::Delimiter(b'/'))
+            .hashes(4);
+        let bytes = postcard::to_allocvec(&FixedWidth(&filter)).unwrap();
+        // length and 64 bytes of bits, then the parameters
+        assert_eq!(bytes[..3], [64, 0xff, 0xff]);
+        assert!(bytes.len() < postcard::to_allocvec(&filter).unwrap().len());
+        let FixedWidth(back): FixedWidth<BloomFilter> = postcard::from_bytes(&bytes).unwrap();
+        assert_eq!(back, filter);
+
+        let mut partial_word = bytes.clone();
+        partial_word[0] = 63;
+        partial_word.remove(1);
+        assert!(postcard::from_bytes::<FixedWidth<BloomFilter>>(&partial_word).is_err());
+    }
+
+    #[test]
+    fn fixed_width_human_readable() {
+        let filter = BloomFilter::with_num_bits(1024).seed(&1).items(0..100);
+        let json = serde_json::to_value(FixedWidth(&filter)).unwrap();
+        assert_eq!(json, serde_json::to_value(&filter).unwrap());
+        let FixedWidth(back): FixedWidth<BloomFilter> = serde_json::from_value(json).unwrap();
+        assert_eq!(back, filter);
+    }
+}
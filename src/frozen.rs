@@ -0,0 +1,208 @@
+use crate::{BitStorage, BloomFilter, DefaultHasher};
+use std::hash::{BuildHasher, Hash};
+
+/// A 64 byte cache line of bits.
+#[derive(Debug, Clone, Copy)]
+#[repr(C, align(64))]
+struct CacheLine([u64; 8]);
+
+/// Bits stored in cache line aligned memory, padded to a whole number of cache lines,
+/// so that no block spans two cache lines and blocks are aligned for SIMD loads.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "Vec<u64>", into = "Vec<u64>")
+)]
+struct AlignedBits {
+    lines: Box<[CacheLine]>,
+    /// The number of `u64`s in use, excluding padding
+    len: usize,
+}
+
+impl BitStorage for AlignedBits {
+    #[inline]
+    fn as_slice(&self) -> &[u64] {
+        // SAFETY: `CacheLine` is a `repr(C)` `[u64; 8]`, so `lines` is `8 * lines.len()` contiguous `u64`s,
+        // and `len` is at most that.
+        unsafe { std::slice::from_raw_parts(self.lines.as_ptr().cast::<u64>(), self.len) }
+    }
+
+    #[inline]
+    fn heap_bytes(&self) -> usize {
+        std::mem::size_of_val::<[CacheLine]>(&self.lines)
+    }
+}
+
+impl From<&[u64]> for AlignedBits {
+    fn from(bits: &[u64]) -> Self {
+        let lines = bits
+            .chunks(8)
+            .map(|chunk| {
+                let mut line = [0; 8];
+                line[..chunk.len()].copy_from_slice(chunk);
+                CacheLine(line)
+            })
+            .collect();
+        Self {
+            lines,
+            len: bits.len(),
+        }
+    }
+}
+
+impl From<Vec<u64>> for AlignedBits {
+    fn from(bits: Vec<u64>) -> Self {
+        bits.as_slice().into()
+    }
+}
+
+impl From<AlignedBits> for Vec<u64> {
+    fn from(bits: AlignedBits) -> Self {
+        bits.as_slice().to_vec()
+    }
+}
+
+/// An immutable [`BloomFilter`] laid out for query throughput.
+///
+/// Constructed via [`BloomFilter::freeze`]. The bits are copied into 64 byte aligned memory, so that
+/// every block is in a single cache line and the SIMD loads of membership checks are aligned,
+/// and there is no spare capacity. Since it cannot be modified, a `FrozenBloomFilter` can be shared
+/// across threads by reference or in an [`Arc`](std::sync::Arc) without locking.
+///
+/// Membership checks give the same results as the [`BloomFilter`] it was frozen from.
+///
+/// # Examples
+/// ```
+/// use fastbloom::BloomFilter;
+///
+/// let frozen = BloomFilter::with_false_pos(0.001).items([1, 2, 3]).freeze();
+/// assert!(frozen.contains(&1));
+///
+/// let mut filter = frozen.thaw();
+/// filter.insert(&4);
+/// assert!(filter.contains(&4));
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FrozenBloomFilter<const BLOCK_SIZE_BITS: usize = 512, S = DefaultHasher> {
+    filter: BloomFilter<BLOCK_SIZE_BITS, S, AlignedBits>,
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher, T: BitStorage>
+    BloomFilter<BLOCK_SIZE_BITS, S, T>
+{
+    /// "Freezes" this `BloomFilter` into a read-only [`FrozenBloomFilter`], copying its bits into cache line aligned memory.
+    pub fn freeze(self) -> FrozenBloomFilter<BLOCK_SIZE_BITS, S> {
+        FrozenBloomFilter {
+            filter: self.map_storage(|bits| AlignedBits::from(bits.as_slice())),
+        }
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> FrozenBloomFilter<BLOCK_SIZE_BITS, S> {
+    /// Checks if an element is possibly in the Bloom filter.
+    #[inline]
+    pub fn contains(&self, val: &(impl Hash + ?Sized)) -> bool {
+        self.filter.contains(val)
+    }
+
+    /// Checks if any key with the same prefix as `key` was possibly inserted.
+    /// See [`BloomFilter::contains_prefix`].
+    #[inline]
+    pub fn contains_prefix(&self, key: &(impl AsRef<[u8]> + ?Sized)) -> bool {
+        self.filter.contains_prefix(key)
+    }
+
+    /// Returns the number of hashes per item.
+    #[inline]
+    pub fn num_hashes(&self) -> u32 {
+        self.filter.num_hashes()
+    }
+
+    /// Returns the total number of in-memory bits supporting the Bloom filter.
+    pub fn num_bits(&self) -> usize {
+        self.filter.num_bits()
+    }
+
+    /// Returns the total number of in-memory blocks supporting the Bloom filter.
+    /// Each block is `BLOCK_SIZE_BITS` bits.
+    pub fn num_blocks(&self) -> usize {
+        self.filter.num_blocks()
+    }
+
+    /// Returns a `u64` slice of this `FrozenBloomFilter`’s contents.
+    #[inline]
+    pub fn as_slice(&self) -> &[u64] {
+        self.filter.as_slice()
+    }
+
+    /// Returns the number of bytes the bits have allocated on the heap, including padding to whole cache lines.
+    #[inline]
+    pub fn heap_bytes(&self) -> usize {
+        self.filter.heap_bytes()
+    }
+
+    /// Returns the number of bytes this `FrozenBloomFilter` uses, including its bits.
+    /// See [`BloomFilter::memory_usage`].
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>() + self.heap_bytes()
+    }
+
+    /// Converts this `FrozenBloomFilter` back into a modifiable [`BloomFilter`], copying its bits.
+    pub fn thaw(self) -> BloomFilter<BLOCK_SIZE_BITS, S> {
+        self.filter.map_storage(Vec::from)
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> PartialEq
+    for FrozenBloomFilter<BLOCK_SIZE_BITS, S>
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.filter == other.filter
+    }
+}
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> Eq for FrozenBloomFilter<BLOCK_SIZE_BITS, S> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frozen_matches_filter() {
+        fn frozen_matches_filter_<const N: usize>() {
+            let filter = BloomFilter::new_with_false_pos::<N>(0.01)
+                .seed(&42)
+                .items(0..1000);
+            let frozen = filter.clone().freeze();
+            assert_eq!(frozen.as_slice(), filter.as_slice());
+            assert_eq!(frozen.num_blocks(), filter.num_blocks());
+            assert_eq!(frozen.as_slice().as_ptr() as usize % 64, 0);
+            for x in 0..2000 {
+                assert_eq!(frozen.contains(&x), filter.contains(&x));
+            }
+            assert_eq!(frozen.thaw(), filter);
+        }
+        frozen_matches_filter_::<512>();
+        frozen_matches_filter_::<256>();
+        frozen_matches_filter_::<128>();
+        frozen_matches_filter_::<64>();
+    }
+
+    #[test]
+    fn padded_to_cache_lines() {
+        let frozen = BloomFilter::with_num_bits(65)
+            .block_size_64()
+            .hashes(4)
+            .freeze();
+        assert_eq!(frozen.num_blocks(), 2);
+        assert_eq!(frozen.as_slice().len(), 2);
+        assert_eq!(frozen.heap_bytes(), 64);
+    }
+
+    #[test]
+    fn send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<FrozenBloomFilter>();
+    }
+}
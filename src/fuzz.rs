@@ -0,0 +1,140 @@
+//! [`Arbitrary`] implementations for filters and their builders, for fuzzing code that consumes them.
+
+use crate::{
+    BloomFilter, BuilderWithBits, BuilderWithFalsePositiveRate, DefaultHasher, IndexStrategy,
+    Prefix,
+};
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+/// The most blocks of an arbitrary filter, so that fuzz inputs stay small.
+const MAX_BLOCKS: usize = 256;
+
+impl<'a> Arbitrary<'a> for DefaultHasher {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(DefaultHasher::with_keys(u.arbitrary()?, u.arbitrary()?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for IndexStrategy {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(if u.arbitrary()? {
+            IndexStrategy::EnhancedDoubleHashing
+        } else {
+            IndexStrategy::DoubleHashing
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Prefix {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(if u.arbitrary()? {
+            Prefix::Len(u.arbitrary::<u8>()? as usize)
+        } else {
+            Prefix::Delimiter(u.arbitrary()?)
+        })
+    }
+}
+
+/// Returns the options shared by both builders: two-choice mode, index strategy, cap on rounds, and prefix.
+fn arbitrary_options(
+    u: &mut Unstructured<'_>,
+) -> Result<(bool, IndexStrategy, u64, Option<Prefix>)> {
+    Ok((
+        u.arbitrary()?,
+        u.arbitrary()?,
+        u.int_in_range(0..=32)?,
+        u.arbitrary()?,
+    ))
+}
+
+/// Bits of 1 to 256 whole blocks, which may already have bits set, with any of the builder's options.
+impl<'a, const BLOCK_SIZE_BITS: usize, S: Arbitrary<'a>> Arbitrary<'a>
+    for BuilderWithBits<BLOCK_SIZE_BITS, S>
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let num_blocks = u.int_in_range(1..=MAX_BLOCKS)?;
+        let data = (0..num_blocks * BLOCK_SIZE_BITS / 64)
+            .map(|_| u.arbitrary())
+            .collect::<Result<_>>()?;
+        let (two_choice, index_strategy, max_rounds, prefix) = arbitrary_options(u)?;
+        Ok(Self {
+            data,
+            hasher: u.arbitrary()?,
+            two_choice,
+            index_strategy,
+            max_rounds,
+            prefix,
+        })
+    }
+}
+
+/// A false positive rate from about 0.8 down to 10⁻⁶, with any of the builder's options.
+impl<'a, const BLOCK_SIZE_BITS: usize, S: Arbitrary<'a>> Arbitrary<'a>
+    for BuilderWithFalsePositiveRate<BLOCK_SIZE_BITS, S>
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let desired_fp_rate = 10f64.powf(-(u.int_in_range(1..=60u8)? as f64) / 10.0);
+        let (two_choice, index_strategy, max_rounds, prefix) = arbitrary_options(u)?;
+        Ok(Self {
+            desired_fp_rate,
+            hasher: u.arbitrary()?,
+            two_choice,
+            index_strategy,
+            max_rounds,
+            prefix,
+        })
+    }
+}
+
+/// A filter from an arbitrary [`BuilderWithBits`], with 1 to `BLOCK_SIZE_BITS` hashes per item.
+///
+/// # Examples
+/// ```
+/// use arbitrary::{Arbitrary, Unstructured};
+/// use fastbloom::BloomFilter;
+///
+/// let mut u = Unstructured::new(&[7; 1024]);
+/// let mut bloom = BloomFilter::<64>::arbitrary(&mut u).unwrap();
+/// bloom.insert("fuzz");
+/// assert!(bloom.contains("fuzz"));
+/// ```
+impl<'a, const BLOCK_SIZE_BITS: usize, S: Arbitrary<'a> + std::hash::BuildHasher> Arbitrary<'a>
+    for BloomFilter<BLOCK_SIZE_BITS, S>
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let builder = BuilderWithBits::<BLOCK_SIZE_BITS, S>::arbitrary(u)?;
+        Ok(builder.hashes(u.int_in_range(1..=BLOCK_SIZE_BITS as u32)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    #[test]
+    fn arbitrary_filters_are_valid() {
+        fn arbitrary_filters_are_valid_<const N: usize>() {
+            let mut rng = StdRng::seed_from_u64(N as u64);
+            for _ in 0..100 {
+                let data: Vec<u8> = (0..rng.gen_range(0..4096)).map(|_| rng.gen()).collect();
+                let mut u = Unstructured::new(&data);
+                let mut bloom = BloomFilter::<N>::arbitrary(&mut u).unwrap();
+                assert!(bloom.num_blocks() >= 1 && bloom.num_blocks() <= MAX_BLOCKS);
+                assert!(bloom.num_hashes() >= 1);
+                bloom.extend(0..10);
+                assert!((0..10).all(|x| bloom.contains(&x)));
+
+                let mut bloom = BuilderWithFalsePositiveRate::<N>::arbitrary(&mut u)
+                    .unwrap()
+                    .expected_items(100);
+                bloom.extend(0..100);
+                assert!((0..100).all(|x| bloom.contains(&x)));
+            }
+        }
+        arbitrary_filters_are_valid_::<512>();
+        arbitrary_filters_are_valid_::<256>();
+        arbitrary_filters_are_valid_::<128>();
+        arbitrary_filters_are_valid_::<64>();
+    }
+}
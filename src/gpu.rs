@@ -0,0 +1,388 @@
+//! A backend that answers batches of membership queries on the GPU, with `wgpu`.
+
+use crate::{
+    derive_hashes, get_orginal_hashes, BloomFilter, DefaultHasher, FastbloomError, IndexStrategy,
+};
+use std::hash::{BuildHasher, Hash};
+use wgpu::util::DeviceExt;
+
+/// The number of queries each GPU invocation group checks.
+const WORKGROUP_SIZE: u32 = 64;
+
+/// The most workgroups per dimension of a dispatch, as required of every device by WebGPU.
+const MAX_WORKGROUPS_PER_DIMENSION: u32 = 65535;
+
+/// The bytes uploaded per `u64` of bits an item must have set: its index, and its mask as two `u32`s.
+const PROBE_BYTES: usize = 16;
+
+/// Checks each query's probes against the bits, which are `u64`s as pairs of little-endian `u32`s.
+///
+/// A query is a group of `candidates` probe sets, of `probes_per_candidate` probes each, and is contained
+/// if all the bits of any of its candidates are set, as for two-choice filters.
+const SHADER: &str = r#"
+struct Params {
+    num_queries: u32,
+    probes_per_candidate: u32,
+    candidates: u32,
+    _padding: u32,
+}
+
+@group(0) @binding(0) var<storage, read> bits: array<u32>;
+@group(0) @binding(1) var<storage, read> probes: array<vec4<u32>>;
+@group(0) @binding(2) var<storage, read_write> results: array<u32>;
+@group(0) @binding(3) var<uniform> params: Params;
+
+@compute @workgroup_size(64)
+fn main(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+    let query = id.y * num_workgroups.x * 64u + id.x;
+    if (query >= params.num_queries) {
+        return;
+    }
+    var found = 0u;
+    for (var c = 0u; c < params.candidates; c++) {
+        let start = (query * params.candidates + c) * params.probes_per_candidate;
+        var missing = 0u;
+        for (var p = 0u; p < params.probes_per_candidate; p++) {
+            let probe = probes[start + p];
+            missing |= (probe.z & ~bits[probe.x * 2u]) | (probe.w & ~bits[probe.x * 2u + 1u]);
+        }
+        if (missing == 0u) {
+            found = 1u;
+        }
+    }
+    results[query] = found;
+}
+"#;
+
+/// A copy of a [`BloomFilter`]'s bits on the GPU, which answers large batches of membership queries per dispatch.
+///
+/// The bits are uploaded once, when the `GpuBloomFilter` is created. Items are hashed on the CPU with the filter's
+/// hasher, and the GPU then checks their bits, which for filters larger than the CPU's caches is what limits the rate
+/// of queries. Each batch is split into as many dispatches as the device's buffer limits require, and its results
+/// are the same as those of [`BloomFilter::contains`]. Later inserts into the filter are not seen by the GPU copy.
+///
+/// # Examples
+/// ```no_run
+/// use fastbloom::{BloomFilter, GpuBloomFilter};
+///
+/// let bloom = BloomFilter::with_false_pos(0.001).items(0..1_000_000);
+/// let gpu = GpuBloomFilter::new(&bloom).unwrap();
+/// let queries: Vec<u64> = (0..10_000_000).collect();
+/// let results = gpu.contains_batch(&queries);
+/// assert!(results[..1_000_000].iter().all(|&x| x));
+/// ```
+pub struct GpuBloomFilter<const BLOCK_SIZE_BITS: usize = 512, S = DefaultHasher> {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    bits: wgpu::Buffer,
+    /// The most bytes of probes or results bound for a dispatch
+    max_binding_size: usize,
+    num_blocks: usize,
+    two_choice: bool,
+    index_strategy: IndexStrategy,
+    num_hashes: u64,
+    num_rounds: Option<u64>,
+    hasher: S,
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher + Clone> GpuBloomFilter<BLOCK_SIZE_BITS, S> {
+    /// Uploads the bits of `filter` to the default GPU, blocking until it is ready to answer queries.
+    ///
+    /// # Errors
+    /// Returns [`FastbloomError::GpuUnavailable`] if there is no GPU, or it cannot hold the bits of `filter`.
+    pub fn new(filter: &BloomFilter<BLOCK_SIZE_BITS, S>) -> Result<Self, FastbloomError> {
+        pollster::block_on(Self::new_async(filter))
+    }
+
+    /// Like [`new`](Self::new), but without blocking.
+    ///
+    /// # Errors
+    /// Returns [`FastbloomError::GpuUnavailable`] if there is no GPU, or it cannot hold the bits of `filter`.
+    pub async fn new_async(
+        filter: &BloomFilter<BLOCK_SIZE_BITS, S>,
+    ) -> Result<Self, FastbloomError> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                ..Default::default()
+            })
+            .await
+            .map_err(|_| FastbloomError::GpuUnavailable)?;
+        let limits = adapter.limits();
+        let bits: Vec<u8> = filter
+            .as_slice()
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect();
+        if bits.len() as u64 > limits.max_storage_buffer_binding_size as u64
+            || filter.num_blocks() as u64 * (BLOCK_SIZE_BITS / 64) as u64 > u32::MAX as u64 / 2
+        {
+            return Err(FastbloomError::GpuUnavailable);
+        }
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor {
+                label: Some("fastbloom"),
+                required_limits: limits.clone(),
+                ..Default::default()
+            })
+            .await
+            .map_err(|_| FastbloomError::GpuUnavailable)?;
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("fastbloom contains"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("fastbloom contains"),
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        let bits = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("fastbloom bits"),
+            contents: &bits,
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let max_binding_size = limits
+            .max_storage_buffer_binding_size
+            .min(limits.max_buffer_size.min(u32::MAX as u64) as u32)
+            as usize;
+        Ok(Self {
+            device,
+            queue,
+            pipeline,
+            bits,
+            max_binding_size,
+            num_blocks: filter.num_blocks(),
+            two_choice: filter.two_choice,
+            index_strategy: filter.index_strategy,
+            num_hashes: filter.num_hashes,
+            num_rounds: filter.num_rounds,
+            hasher: filter.hasher.clone(),
+        })
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> GpuBloomFilter<BLOCK_SIZE_BITS, S> {
+    /// Checks which of `items` are possibly in the filter, on the GPU, blocking until the results are read back.
+    ///
+    /// # Returns
+    ///
+    /// For each item, in order, whether it is possibly in the filter, as [`BloomFilter::contains`].
+    pub fn contains_batch<T: Hash>(&self, items: &[T]) -> Vec<bool> {
+        self.contains_hashes(
+            items
+                .iter()
+                .map(|item| get_orginal_hashes(&self.hasher, item)),
+        )
+    }
+
+    /// Like [`contains_batch`](Self::contains_batch), but for keys that are already hashed,
+    /// as [`BloomFilter::contains_hash`].
+    pub fn contains_hash_batch(&self, hashes: &[u64]) -> Vec<bool> {
+        self.contains_hashes(hashes.iter().map(|&hash| derive_hashes(hash)))
+    }
+
+    /// Returns the number of bytes of bits on the GPU.
+    #[inline]
+    pub fn gpu_bytes(&self) -> u64 {
+        self.bits.size()
+    }
+
+    fn contains_hashes(&self, hashes: impl ExactSizeIterator<Item = [u64; 2]>) -> Vec<bool> {
+        let candidates = if self.two_choice { 2 } else { 1 };
+        let probes_per_candidate =
+            self.num_hashes as usize + self.num_rounds.map_or(0, |_| BLOCK_SIZE_BITS / 64);
+        let query_bytes = (candidates * probes_per_candidate * PROBE_BYTES).max(1);
+        let max_queries = (self.max_binding_size / query_bytes)
+            .min(WORKGROUP_SIZE as usize * MAX_WORKGROUPS_PER_DIMENSION as usize * 1024)
+            .max(1);
+
+        let mut results = Vec::with_capacity(hashes.len());
+        let mut probes = Vec::new();
+        let mut num_queries = 0;
+        for [h1, h2] in hashes {
+            let mut push = |h1| {
+                for (index, mask) in BloomFilter::<BLOCK_SIZE_BITS, S>::hash_masks(
+                    self.num_blocks,
+                    self.two_choice,
+                    self.index_strategy,
+                    self.num_hashes,
+                    self.num_rounds,
+                    h1,
+                    h2,
+                ) {
+                    probes.extend([index as u32, 0, mask as u32, (mask >> 32) as u32]);
+                }
+            };
+            push(h1);
+            if self.two_choice {
+                push(BloomFilter::<BLOCK_SIZE_BITS, S>::alternate_hash(h1, h2));
+            }
+            num_queries += 1;
+            if num_queries == max_queries {
+                self.dispatch(
+                    &probes,
+                    num_queries,
+                    probes_per_candidate,
+                    candidates,
+                    &mut results,
+                );
+                probes.clear();
+                num_queries = 0;
+            }
+        }
+        if num_queries > 0 {
+            self.dispatch(
+                &probes,
+                num_queries,
+                probes_per_candidate,
+                candidates,
+                &mut results,
+            );
+        }
+        results
+    }
+
+    /// Checks `num_queries` queries of `probes` in one dispatch, and appends their results to `results`.
+    fn dispatch(
+        &self,
+        probes: &[u32],
+        num_queries: usize,
+        probes_per_candidate: usize,
+        candidates: usize,
+        results: &mut Vec<bool>,
+    ) {
+        let words_as_bytes = |words: &[u32]| -> Vec<u8> {
+            words.iter().flat_map(|word| word.to_le_bytes()).collect()
+        };
+        let params = [
+            num_queries as u32,
+            probes_per_candidate as u32,
+            candidates as u32,
+            0,
+        ];
+        let params = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("fastbloom params"),
+                contents: &words_as_bytes(&params),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+        // Bindings must not be empty, e.g. for filters with no hashes.
+        let probes = if probes.is_empty() {
+            &[0; 4][..]
+        } else {
+            probes
+        };
+        let probes = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("fastbloom probes"),
+                contents: &words_as_bytes(probes),
+                usage: wgpu::BufferUsages::STORAGE,
+            });
+        let results_size = (num_queries * 4) as u64;
+        let output = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("fastbloom results"),
+            size: results_size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("fastbloom readback"),
+            size: results_size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("fastbloom contains"),
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.bits.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: probes.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: output.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: params.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            let workgroups = (num_queries as u32).div_ceil(WORKGROUP_SIZE);
+            let x = workgroups.min(MAX_WORKGROUPS_PER_DIMENSION);
+            pass.dispatch_workgroups(x, workgroups.div_ceil(x), 1);
+        }
+        encoder.copy_buffer_to_buffer(&output, 0, &readback, 0, results_size);
+        self.queue.submit([encoder.finish()]);
+
+        let slice = readback.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |_| {});
+        self.device
+            .poll(wgpu::PollType::Wait)
+            .expect("the GPU did not finish the dispatch");
+        results.extend(
+            slice
+                .get_mapped_range()
+                .chunks_exact(4)
+                .map(|word| word != [0; 4]),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_as_cpu() {
+        fn same_as_cpu_<const N: usize>(two_choice: bool, num_hashes: u32) {
+            let mut builder = BloomFilter::new_builder::<N>(1 << 14).seed(&1);
+            if two_choice {
+                builder = builder.two_choice();
+            }
+            let mut bloom = builder.hashes(num_hashes);
+            bloom.extend(0..1000u64);
+            // There may be no GPU, e.g. in CI.
+            let Ok(gpu) = GpuBloomFilter::new(&bloom) else {
+                return;
+            };
+            let queries: Vec<u64> = (0..5000).collect();
+            let expected: Vec<bool> = queries.iter().map(|x| bloom.contains(x)).collect();
+            assert!(expected[..1000].iter().all(|&x| x));
+            assert_eq!(gpu.contains_batch(&queries), expected);
+            let expected: Vec<bool> = queries.iter().map(|&x| bloom.contains_hash(x)).collect();
+            assert_eq!(gpu.contains_hash_batch(&queries), expected);
+            assert!(gpu.contains_batch::<u64>(&[]).is_empty());
+        }
+        for two_choice in [false, true] {
+            same_as_cpu_::<64>(two_choice, 3);
+            same_as_cpu_::<128>(two_choice, 20);
+            same_as_cpu_::<256>(two_choice, 7);
+            same_as_cpu_::<512>(two_choice, 40);
+        }
+    }
+}
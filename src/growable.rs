@@ -0,0 +1,410 @@
+use crate::{get_orginal_hashes, DefaultHasher};
+use std::hash::{BuildHasher, Hash};
+
+/// The number of entries per bucket. Each entry is 16 bits, so a bucket is one `u64`.
+const BUCKET_LEN: usize = 4;
+/// The number of hash bits an item's bucket indexes are taken from, i.e. the max level.
+const PATH_BITS: u32 = 48;
+/// The max number of tail bits stored per entry, i.e. the number of doublings an entry survives without losing precision.
+const TAIL_BITS: u32 = 7;
+/// The max load (entries per slot) a new filter is sized for.
+const MAX_LOAD: f64 = 0.9;
+/// The max number of entries displaced by one insert before the filter grows.
+const MAX_KICKS: usize = 500;
+
+/// An entry: an 8 bit fingerprint in the upper byte, and in the lower byte up to `TAIL_BITS` tail bits
+/// below a sentinel 1 bit marking the tail length. Empty slots are 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Entry(u16);
+
+impl Entry {
+    #[inline]
+    fn new(fp: u8, tail: u64, len: u32) -> Self {
+        Self(((fp as u16) << 8) | (1 << len) as u16 | tail as u16)
+    }
+
+    #[inline]
+    fn fp(self) -> u8 {
+        (self.0 >> 8) as u8
+    }
+
+    #[inline]
+    fn len(self) -> u32 {
+        7 - (self.0 as u8).leading_zeros()
+    }
+
+    #[inline]
+    fn tail(self) -> u64 {
+        (self.0 as u64) & ((1 << self.len()) - 1)
+    }
+}
+
+/// A cuckoo filter that grows by doubling without the original items, in the style of the Taffy cuckoo filter.
+///
+/// Each item has a 48 bit "path" hash, and at `level` `k` the filter has `2^k` buckets: an item's bucket is the first `k` bits
+/// of its path. Alongside an 8 bit fingerprint, entries store up to 7 following "tail" bits of the path.
+/// When the filter doubles, each entry moves to one of the two buckets its bucket splits into, chosen by (and consuming)
+/// its first tail bit. Entries that have run out of tail bits are copied into both buckets, so false positives
+/// slowly increase for items that were inserted many doublings ago.
+///
+/// As in other cuckoo filters, an item can be in one of two buckets, and items can be [`remove`](Self::remove)d.
+/// The filter grows automatically once an insert can't find room.
+///
+/// See <https://arxiv.org/abs/2108.07920>.
+///
+/// # Examples
+/// ```
+/// use fastbloom::GrowableCuckooFilter;
+///
+/// let mut filter = GrowableCuckooFilter::new(10);
+/// filter.extend(0..1000);
+/// assert!((0..1000).all(|x| filter.contains(&x)));
+/// assert!(filter.remove(&1));
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GrowableCuckooFilter<S = DefaultHasher> {
+    /// `2^level` buckets of `BUCKET_LEN` 16 bit entries
+    buckets: Vec<u64>,
+    level: u32,
+    /// The number of items inserted minus the number of items removed
+    len: usize,
+    hasher: S,
+}
+
+impl GrowableCuckooFilter {
+    /// Creates a new empty `GrowableCuckooFilter` initially sized for `expected_items` items,
+    /// using the default hasher with a random seed.
+    pub fn new(expected_items: usize) -> Self {
+        Self::with_hasher(expected_items, Default::default())
+    }
+
+    /// Sets the seed of the hasher.
+    ///
+    /// # Panics
+    /// Panics if the filter is not empty.
+    pub fn seed(self, seed: &u128) -> Self {
+        assert!(self.is_empty());
+        Self {
+            hasher: DefaultHasher::seeded(&seed.to_be_bytes()),
+            ..self
+        }
+    }
+}
+
+impl<S: BuildHasher> GrowableCuckooFilter<S> {
+    /// Creates a new empty `GrowableCuckooFilter` initially sized for `expected_items` items, using `hasher` to hash items.
+    pub fn with_hasher(expected_items: usize, hasher: S) -> Self {
+        let num_buckets = (expected_items as f64 / (BUCKET_LEN as f64 * MAX_LOAD)).ceil() as usize;
+        let level = num_buckets.max(1).next_power_of_two().trailing_zeros();
+        Self {
+            buckets: vec![0; 1 << level],
+            level,
+            len: 0,
+            hasher,
+        }
+    }
+
+    /// Returns the number of tail bits stored for new entries at the current level.
+    #[inline]
+    fn tail_len(&self) -> u32 {
+        std::cmp::min(TAIL_BITS, PATH_BITS - self.level)
+    }
+
+    /// The mask XOR'd with an entry's path to get the path of its other bucket.
+    #[inline]
+    fn alternate_mask(fp: u8) -> u64 {
+        ((fp as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15)) >> (64 - PATH_BITS)
+    }
+
+    /// Returns an item's fingerprint and the path of its first bucket.
+    #[inline]
+    fn fingerprint_and_path(&self, val: &(impl Hash + ?Sized)) -> (u8, u64) {
+        let [h1, _] = get_orginal_hashes(&self.hasher, val);
+        (h1 as u8, h1 >> (64 - PATH_BITS))
+    }
+
+    /// Returns the bucket and entry of an item at the current level, given its `path`.
+    #[inline]
+    fn locate(&self, fp: u8, path: u64) -> (usize, Entry) {
+        let len = self.tail_len();
+        let known = path >> (PATH_BITS - self.level - len);
+        (
+            (known >> len) as usize,
+            Entry::new(fp, known & ((1 << len) - 1), len),
+        )
+    }
+
+    /// Returns the other bucket of `entry` in `bucket`, and the entry there.
+    #[inline]
+    fn alternate(&self, bucket: usize, entry: Entry) -> (usize, Entry) {
+        let len = entry.len();
+        let known_len = self.level + len;
+        let mask = Self::alternate_mask(entry.fp()) >> (PATH_BITS - known_len);
+        let known = (((bucket as u64) << len) | entry.tail()) ^ mask;
+        let alternate = Entry::new(entry.fp(), known & ((1 << len) - 1), len);
+        ((known >> len) as usize, alternate)
+    }
+
+    #[inline]
+    fn slot(&self, bucket: usize, i: usize) -> Entry {
+        Entry((self.buckets[bucket] >> (16 * i)) as u16)
+    }
+
+    #[inline]
+    fn set_slot(&mut self, bucket: usize, i: usize, entry: Entry) {
+        let shift = 16 * i;
+        self.buckets[bucket] =
+            (self.buckets[bucket] & !(0xffff << shift)) | ((entry.0 as u64) << shift);
+    }
+
+    /// Returns the slot in `bucket` of an entry matching an item's `fp` and `path`.
+    /// An entry matches if its fingerprint is equal and its tail is a prefix of the rest of the path.
+    #[inline]
+    fn find(&self, bucket: usize, fp: u8, path: u64) -> Option<usize> {
+        (0..BUCKET_LEN).find(|&i| {
+            let entry = self.slot(bucket, i);
+            entry.0 != 0 && entry.fp() == fp && {
+                let len = entry.len();
+                (path >> (PATH_BITS - self.level - len)) & ((1 << len) - 1) == entry.tail()
+            }
+        })
+    }
+
+    /// Adds `entry` to `bucket`, displacing other entries cuckoo style if both of its buckets are full.
+    ///
+    /// Returns the entry left without a slot if no room was found after `MAX_KICKS` displacements.
+    fn insert_entry(&mut self, bucket: usize, entry: Entry) -> Option<(usize, Entry)> {
+        let (mut bucket, mut entry) = (bucket, entry);
+        let mut rng = (bucket as u64 ^ ((entry.0 as u64) << 32)) | 1;
+        for kick in 0..=MAX_KICKS {
+            if let Some(i) = (0..BUCKET_LEN).find(|&i| self.slot(bucket, i).0 == 0) {
+                self.set_slot(bucket, i, entry);
+                return None;
+            }
+            if kick == 0 {
+                (bucket, entry) = self.alternate(bucket, entry);
+                continue;
+            }
+            rng ^= rng << 13;
+            rng ^= rng >> 7;
+            rng ^= rng << 17;
+            let i = (rng % BUCKET_LEN as u64) as usize;
+            let evicted = self.slot(bucket, i);
+            self.set_slot(bucket, i, entry);
+            (bucket, entry) = self.alternate(bucket, evicted);
+        }
+        Some((bucket, entry))
+    }
+
+    /// Doubles the number of buckets, moving each entry to one of the two buckets its bucket splits into.
+    ///
+    /// Entries are moved using their tail bits, so no items need to be rehashed.
+    pub fn grow(&mut self) {
+        self.grow_with(Vec::new());
+    }
+
+    /// Doubles the number of buckets and inserts `pending` entries (at the current level) as well, growing again if needed.
+    fn grow_with(&mut self, mut pending: Vec<(usize, Entry)>) {
+        while self.level < PATH_BITS {
+            let old = std::mem::replace(&mut self.buckets, vec![0; 2 << self.level]);
+            self.level += 1;
+            let entries = old
+                .iter()
+                .enumerate()
+                .flat_map(|(bucket, &x)| {
+                    (0..BUCKET_LEN)
+                        .map(move |i| (bucket, Entry((x >> (16 * i)) as u16)))
+                        .filter(|(_, entry)| entry.0 != 0)
+                })
+                .chain(std::mem::take(&mut pending))
+                .collect::<Vec<_>>();
+            for (bucket, entry) in entries {
+                let len = entry.len();
+                let children = if len == 0 {
+                    vec![(2 * bucket, entry), (2 * bucket + 1, entry)]
+                } else {
+                    let child = 2 * bucket + (entry.tail() >> (len - 1)) as usize;
+                    let tail = entry.tail() & ((1 << (len - 1)) - 1);
+                    vec![(child, Entry::new(entry.fp(), tail, len - 1))]
+                };
+                for (child, entry) in children {
+                    if let Some(homeless) = self.insert_entry(child, entry) {
+                        pending.push(homeless);
+                    }
+                }
+            }
+            if pending.is_empty() {
+                return;
+            }
+        }
+        panic!("GrowableCuckooFilter can't grow past 2^{PATH_BITS} buckets");
+    }
+
+    /// Inserts an element into the filter, growing it if there is no room.
+    /// Inserting an item twice stores it twice, so it needs to be removed twice.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item may have been previously in the filter (indicating a potential false positive),
+    /// `false` otherwise.
+    pub fn insert(&mut self, val: &(impl Hash + ?Sized)) -> bool {
+        let previously_contained = self.contains(val);
+        let (fp, path) = self.fingerprint_and_path(val);
+        let (bucket, entry) = self.locate(fp, path);
+        if let Some(homeless) = self.insert_entry(bucket, entry) {
+            self.grow_with(vec![homeless]);
+        }
+        self.len += 1;
+        previously_contained
+    }
+
+    /// Checks if an element is possibly in the filter.
+    pub fn contains(&self, val: &(impl Hash + ?Sized)) -> bool {
+        let (fp, path) = self.fingerprint_and_path(val);
+        let alternate_path = path ^ Self::alternate_mask(fp);
+        let (bucket, _) = self.locate(fp, path);
+        let (alternate, _) = self.locate(fp, alternate_path);
+        self.find(bucket, fp, path).is_some() || self.find(alternate, fp, alternate_path).is_some()
+    }
+
+    /// Removes one copy of an element from the filter.
+    ///
+    /// Only items that were inserted should be removed: removing a false positive removes another item's entry,
+    /// causing a false negative.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item was possibly in the filter and was removed, `false` otherwise.
+    pub fn remove(&mut self, val: &(impl Hash + ?Sized)) -> bool {
+        let (fp, path) = self.fingerprint_and_path(val);
+        let alternate_path = path ^ Self::alternate_mask(fp);
+        for path in [path, alternate_path] {
+            let (bucket, _) = self.locate(fp, path);
+            if let Some(i) = self.find(bucket, fp, path) {
+                self.set_slot(bucket, i, Entry(0));
+                self.len = self.len.saturating_sub(1);
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Returns the number of items in the filter.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the filter contains no items.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of buckets. Each bucket holds 4 entries of 16 bits.
+    #[inline]
+    pub fn num_buckets(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// Returns the number of bytes this `GrowableCuckooFilter` uses, including its buckets.
+    /// See [`BloomFilter::memory_usage`](crate::BloomFilter::memory_usage).
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>() + self.buckets.capacity() * std::mem::size_of::<u64>()
+    }
+
+    /// Clear the filter, removing all items. The number of buckets is unchanged.
+    #[inline]
+    pub fn clear(&mut self) {
+        for x in self.buckets.iter_mut() {
+            *x = 0;
+        }
+        self.len = 0;
+    }
+}
+
+impl<T, S: BuildHasher> Extend<T> for GrowableCuckooFilter<S>
+where
+    T: Hash,
+{
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.insert(&val);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alternate_is_involution() {
+        let mut filter = GrowableCuckooFilter::new(1000).seed(&42);
+        for _ in 0..3 {
+            for bucket in 0..filter.num_buckets() {
+                for fp in [0, 1, 42, u8::MAX] {
+                    for len in 0..=filter.tail_len() {
+                        let entry = Entry::new(fp, (bucket as u64) & ((1 << len) - 1), len);
+                        let (alternate, alternate_entry) = filter.alternate(bucket, entry);
+                        assert_eq!(
+                            filter.alternate(alternate, alternate_entry),
+                            (bucket, entry)
+                        );
+                    }
+                }
+            }
+            filter.grow();
+        }
+    }
+
+    #[test]
+    fn inserts_contained_across_growth() {
+        let num_items = 100_000usize;
+        let mut filter = GrowableCuckooFilter::new(10).seed(&42);
+        for x in 0..num_items {
+            filter.insert(&x);
+        }
+        assert!(filter.num_buckets() >= num_items / BUCKET_LEN);
+        assert!((0..num_items).all(|x| filter.contains(&x)));
+        assert_eq!(filter.len(), num_items);
+    }
+
+    #[test]
+    fn false_pos_rate() {
+        let num_items = 10_000usize;
+        let mut filter = GrowableCuckooFilter::new(num_items).seed(&42);
+        filter.extend(0..num_items);
+        let false_pos = (num_items..11 * num_items)
+            .filter(|x| filter.contains(x))
+            .count();
+        assert!((false_pos as f64) < 0.001 * (10 * num_items) as f64);
+    }
+
+    #[test]
+    fn remove_after_growth() {
+        let num_items = 10_000usize;
+        let mut filter = GrowableCuckooFilter::new(num_items / 8).seed(&42);
+        filter.extend(0..num_items);
+        for x in (0..num_items).step_by(2) {
+            assert!(filter.remove(&x));
+        }
+        assert!((1..num_items).step_by(2).all(|x| filter.contains(&x)));
+        let still_contained = (0..num_items)
+            .step_by(2)
+            .filter(|x| filter.contains(x))
+            .count();
+        assert!(still_contained < num_items / 100);
+    }
+
+    #[test]
+    fn nothing_after_clear() {
+        let mut filter = GrowableCuckooFilter::new(100).seed(&42);
+        filter.extend(0..100);
+        filter.clear();
+        assert!(!(0..100).any(|x| filter.contains(&x)));
+        assert!(filter.is_empty());
+    }
+}
@@ -0,0 +1,204 @@
+use crate::{BloomFilter, DefaultHasher, FastbloomError};
+use std::hash::{BuildHasher, Hash};
+
+/// A grow-only set (G-Set) CRDT of possibly inserted items, for replicas that converge by gossiping their state.
+///
+/// The state is a [`BloomFilter`], and [`merge`](Self::merge) is the bitwise OR of two states. Since OR is
+/// commutative, associative, and idempotent, replicas that have received each other's states, in any order,
+/// any number of times, and through any number of intermediaries, have the same bits: they converge,
+/// without coordination, to the filter of all items inserted at any replica.
+///
+/// An item inserted at a replica is contained by it from then on, and by every replica it is merged into:
+/// items are never removed, and [`contains`](Self::contains) has no false negatives.
+/// As a Bloom filter, the merged set can contain items that were never inserted, with the false positive rate
+/// of one filter with all the inserted items.
+///
+/// All replicas must start from the same empty filter, e.g. built with the same builder and seed, so that
+/// they have the same size, hashes, and hasher. Merging states of different sizes or hashes is an error,
+/// checked by [`merge`](Self::merge); different hashers cannot be detected, so the seed must be agreed upfront.
+///
+/// # Examples
+/// ```
+/// use fastbloom::{BloomFilter, GSetFilter};
+///
+/// let empty = GSetFilter::new(BloomFilter::with_num_bits(4096).seed(&42).hashes(4));
+/// let mut a = empty.clone();
+/// let mut b = empty.clone();
+/// a.insert("apple");
+/// b.insert("banana");
+///
+/// // Gossip in both directions, in any order
+/// let a_state = a.clone();
+/// a.merge(&b).unwrap();
+/// b.merge(&a_state).unwrap();
+/// assert_eq!(a, b);
+/// assert!(a.contains("apple") && a.contains("banana"));
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GSetFilter<const BLOCK_SIZE_BITS: usize = 512, S = DefaultHasher> {
+    state: BloomFilter<BLOCK_SIZE_BITS, S>,
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> GSetFilter<BLOCK_SIZE_BITS, S> {
+    /// Creates a `GSetFilter` whose state is `filter`, including the items already in it.
+    pub fn new(filter: BloomFilter<BLOCK_SIZE_BITS, S>) -> Self {
+        Self { state: filter }
+    }
+
+    /// Inserts an element at this replica.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item may have been previously in the set (indicating a potential false positive),
+    /// `false` otherwise.
+    #[inline]
+    pub fn insert(&mut self, val: &(impl Hash + ?Sized)) -> bool {
+        self.state.insert(val)
+    }
+
+    /// Checks if an element is possibly in the set, i.e. was possibly inserted at this replica
+    /// or any replica merged into it.
+    #[inline]
+    pub fn contains(&self, val: &(impl Hash + ?Sized)) -> bool {
+        self.state.contains(val)
+    }
+
+    /// Merges the state of another replica into this one: the least upper bound of the two states.
+    ///
+    /// Merging is commutative (`a.merge(b)` and `b.merge(a)` give the same state), associative,
+    /// and idempotent (merging a state again, or a state already merged, changes nothing),
+    /// so states can be delivered in any order, duplicated, or relayed.
+    ///
+    /// # Errors
+    /// Returns [`FastbloomError::MismatchedFilters`], leaving this state unchanged,
+    /// if the replicas have different numbers of bits or hashes.
+    pub fn merge(&mut self, other: &Self) -> Result<(), FastbloomError> {
+        self.state.try_union(&other.state)
+    }
+
+    /// Returns `true` if every bit of this state is set in `other`, i.e. `other` has merged this state,
+    /// and this state is before or equal to `other` in the order states grow in.
+    ///
+    /// # Panics
+    /// Panics if the replicas have different numbers of bits.
+    pub fn is_subset(&self, other: &Self) -> bool {
+        assert_eq!(self.state.num_bits(), other.state.num_bits());
+        self.state
+            .as_slice()
+            .iter()
+            .zip(other.state.as_slice())
+            .all(|(a, b)| a & b == *a)
+    }
+
+    /// Returns the state of this replica, to be sent to other replicas.
+    #[inline]
+    pub fn state(&self) -> &BloomFilter<BLOCK_SIZE_BITS, S> {
+        &self.state
+    }
+
+    /// Returns the state of this replica.
+    #[inline]
+    pub fn into_inner(self) -> BloomFilter<BLOCK_SIZE_BITS, S> {
+        self.state
+    }
+
+    /// Returns the number of bytes this `GSetFilter` uses, including the bits of its state.
+    /// See [`BloomFilter::memory_usage`].
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>() + self.state.heap_bytes()
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> From<BloomFilter<BLOCK_SIZE_BITS, S>>
+    for GSetFilter<BLOCK_SIZE_BITS, S>
+{
+    fn from(filter: BloomFilter<BLOCK_SIZE_BITS, S>) -> Self {
+        Self::new(filter)
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> PartialEq for GSetFilter<BLOCK_SIZE_BITS, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.state == other.state
+    }
+}
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> Eq for GSetFilter<BLOCK_SIZE_BITS, S> {}
+
+impl<T, const BLOCK_SIZE_BITS: usize, S: BuildHasher> Extend<T> for GSetFilter<BLOCK_SIZE_BITS, S>
+where
+    T: Hash,
+{
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.state.extend(iter);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replicas(n: usize) -> Vec<GSetFilter> {
+        let empty = GSetFilter::new(BloomFilter::with_num_bits(1 << 14).seed(&7).hashes(5));
+        (0..n)
+            .map(|i| {
+                let mut replica = empty.clone();
+                replica.extend(i * 100..(i + 1) * 100);
+                replica
+            })
+            .collect()
+    }
+
+    #[test]
+    fn merge_is_commutative_associative_idempotent() {
+        let [a, b, c]: [GSetFilter; 3] = replicas(3).try_into().unwrap();
+
+        let mut ab = a.clone();
+        ab.merge(&b).unwrap();
+        let mut ba = b.clone();
+        ba.merge(&a).unwrap();
+        assert_eq!(ab, ba);
+
+        let mut ab_c = ab.clone();
+        ab_c.merge(&c).unwrap();
+        let mut bc = b.clone();
+        bc.merge(&c).unwrap();
+        let mut a_bc = a.clone();
+        a_bc.merge(&bc).unwrap();
+        assert_eq!(ab_c, a_bc);
+
+        let mut again = ab_c.clone();
+        again.merge(&ab_c).unwrap();
+        again.merge(&a).unwrap();
+        assert_eq!(again, ab_c);
+
+        assert!(a.is_subset(&ab_c) && c.is_subset(&ab_c));
+        assert!(!ab_c.is_subset(&a));
+    }
+
+    #[test]
+    fn gossip_converges() {
+        let mut replicas = replicas(5);
+        // Each replica sends its state to the next, around the ring twice
+        for _ in 0..2 {
+            for i in 0..replicas.len() {
+                let state = replicas[i].clone();
+                let next = (i + 1) % replicas.len();
+                replicas[next].merge(&state).unwrap();
+            }
+        }
+        assert!(replicas.windows(2).all(|w| w[0] == w[1]));
+        assert!((0..500usize).all(|x| replicas[3].contains(&x)));
+    }
+
+    #[test]
+    fn merge_mismatched() {
+        let mut a = GSetFilter::new(BloomFilter::with_num_bits(1024).hashes(4));
+        a.insert(&1);
+        let before = a.clone();
+        let b = GSetFilter::new(BloomFilter::with_num_bits(1024).hashes(5));
+        assert_eq!(a.merge(&b), Err(FastbloomError::MismatchedFilters));
+        assert_eq!(a, before);
+    }
+}
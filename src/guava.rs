@@ -0,0 +1,282 @@
+use crate::murmur3::murmur3_x64_128;
+
+/// How a [`GuavaBloomFilter`] derives the bits of an item from its hash, Guava's `BloomFilterStrategies`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GuavaStrategy {
+    /// `MURMUR128_MITZ_32`, which only uses 64 bits of the hash. Only used by filters from old Guava versions.
+    Murmur128Mitz32,
+    /// `MURMUR128_MITZ_64`, the strategy of Guava 13 and later.
+    Murmur128Mitz64,
+}
+
+/// A Bloom filter in the serialized form of Google Guava's `BloomFilter`, as written by `BloomFilter#writeTo`
+/// and read by `BloomFilter.readFrom`.
+///
+/// Items are hashed with Guava's `Hashing.murmur3_128()` of the bytes their `Funnel` puts:
+/// - `Funnels.byteArrayFunnel()`: the bytes themselves.
+/// - `Funnels.stringFunnel(UTF_8)`: the UTF-8 bytes of the string, i.e. `s.as_bytes()`.
+/// - `Funnels.unencodedCharsFunnel()`: the UTF-16 code units of the string, each as 2 little-endian bytes.
+/// - `Funnels.integerFunnel()` and `Funnels.longFunnel()`: the 4 or 8 little-endian bytes of the number, e.g. `x.to_le_bytes()`.
+///
+/// So filters can be shared with JVM services in both directions.
+///
+/// # Examples
+/// ```
+/// use fastbloom::GuavaBloomFilter;
+///
+/// let mut filter = GuavaBloomFilter::new_with_false_pos(0.01, 1000);
+/// filter.insert("alice"); // BloomFilter<CharSequence> with Funnels.stringFunnel(UTF_8)
+/// filter.insert(&42i64.to_le_bytes()); // BloomFilter<Long> with Funnels.longFunnel()
+///
+/// // written by BloomFilter#writeTo
+/// let bytes = filter.to_bytes();
+/// let filter = GuavaBloomFilter::from_bytes(&bytes).unwrap();
+/// assert!(filter.contains("alice"));
+/// assert!(filter.contains(&42i64.to_le_bytes()));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GuavaBloomFilter {
+    bits: Vec<u64>,
+    num_hashes: u32,
+    strategy: GuavaStrategy,
+}
+
+impl GuavaBloomFilter {
+    /// Creates an empty `GuavaBloomFilter` sized for `expected_num_items` to meet the false positive rate `fp`,
+    /// the same way as Guava's `BloomFilter.create(funnel, expectedInsertions, fpp)`.
+    ///
+    /// # Panics
+    /// Panics if `fp` is not between 0 and 1.
+    pub fn new_with_false_pos(fp: f64, expected_num_items: usize) -> Self {
+        assert!(fp > 0.0 && fp < 1.0, "invalid false positive rate: {fp}");
+        let n = expected_num_items.max(1) as f64;
+        let ln2 = f64::ln(2.0);
+        let num_bits = ((-n * fp.ln() / (ln2 * ln2)) as usize).max(1);
+        let num_hashes = ((num_bits as f64 / n * ln2).round() as u32).clamp(1, 255);
+        Self {
+            bits: vec![0; num_bits.div_ceil(64)],
+            num_hashes,
+            strategy: GuavaStrategy::Murmur128Mitz64,
+        }
+    }
+
+    /// Creates a `GuavaBloomFilter` from its serialized form, as written by `BloomFilter#writeTo`.
+    /// Any bytes after the filter are ignored.
+    ///
+    /// Returns `None` if the bytes are truncated, the strategy is unknown, or there are no bits.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let (&[strategy, num_hashes], rest) = bytes.split_first_chunk::<2>()?;
+        let strategy = match strategy {
+            0 => GuavaStrategy::Murmur128Mitz32,
+            1 => GuavaStrategy::Murmur128Mitz64,
+            _ => return None,
+        };
+        let (len, rest) = rest.split_first_chunk::<4>()?;
+        let len = usize::try_from(i32::from_be_bytes(*len)).ok()?;
+        if len == 0 {
+            return None;
+        }
+        let bits = rest
+            .get(..len.checked_mul(8)?)?
+            .chunks_exact(8)
+            .map(|word| u64::from_be_bytes(word.try_into().unwrap()))
+            .collect();
+        Some(Self {
+            bits,
+            num_hashes: u32::from(num_hashes),
+            strategy,
+        })
+    }
+
+    /// Returns the serialized form of this `GuavaBloomFilter`, as read by `BloomFilter.readFrom`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(6 + 8 * self.bits.len());
+        bytes.push(self.strategy as u8);
+        bytes.push(self.num_hashes as u8);
+        bytes.extend_from_slice(&(self.bits.len() as i32).to_be_bytes());
+        for word in &self.bits {
+            bytes.extend_from_slice(&word.to_be_bytes());
+        }
+        bytes
+    }
+
+    /// Returns the indexes of the bits of an item, as Guava's strategy derives them from its hash.
+    #[inline]
+    fn bit_indexes(&self, val: &[u8]) -> impl Iterator<Item = usize> {
+        let [h1, h2] = murmur3_x64_128(val, 0);
+        let num_bits = self.num_bits() as u64;
+        let strategy = self.strategy;
+        (1..=self.num_hashes).map(move |i| {
+            let index = match strategy {
+                GuavaStrategy::Murmur128Mitz32 => {
+                    let combined =
+                        (h1 as i32).wrapping_add((i as i32).wrapping_mul((h1 >> 32) as i32));
+                    u64::from(if combined < 0 { !combined } else { combined } as u32) % num_bits
+                }
+                GuavaStrategy::Murmur128Mitz64 => {
+                    let combined = h1.wrapping_add(u64::from(i - 1).wrapping_mul(h2));
+                    (combined & i64::MAX as u64) % num_bits
+                }
+            };
+            index as usize
+        })
+    }
+
+    /// Inserts the funnelled bytes of an item into the Bloom filter.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item may have been previously in the Bloom filter (indicating a potential false positive),
+    /// `false` otherwise.
+    #[inline]
+    pub fn insert(&mut self, val: &(impl AsRef<[u8]> + ?Sized)) -> bool {
+        let mut previously_contained = true;
+        for index in self.bit_indexes(val.as_ref()) {
+            let bit = 1 << (index % 64);
+            previously_contained &= self.bits[index / 64] & bit != 0;
+            self.bits[index / 64] |= bit;
+        }
+        previously_contained
+    }
+
+    /// Checks if the funnelled bytes of an item are possibly in the Bloom filter.
+    #[inline]
+    pub fn contains(&self, val: &(impl AsRef<[u8]> + ?Sized)) -> bool {
+        self.bit_indexes(val.as_ref())
+            .all(|index| self.bits[index / 64] & (1 << (index % 64)) != 0)
+    }
+
+    /// Returns the strategy deriving the bits of an item from its hash.
+    #[inline]
+    pub fn strategy(&self) -> GuavaStrategy {
+        self.strategy
+    }
+
+    /// Returns the number of hashes per item.
+    #[inline]
+    pub fn num_hashes(&self) -> u32 {
+        self.num_hashes
+    }
+
+    /// Returns the total number of in-memory bits supporting the Bloom filter.
+    pub fn num_bits(&self) -> usize {
+        self.bits.len() * 64
+    }
+
+    /// Returns a `u64` slice of this `GuavaBloomFilter`’s contents.
+    #[inline]
+    pub fn as_slice(&self) -> &[u64] {
+        &self.bits
+    }
+
+    /// Clear all of the bits in the Bloom filter, removing all items.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.bits.fill(0);
+    }
+}
+
+impl<T: AsRef<[u8]>> Extend<T> for GuavaBloomFilter {
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.insert(&val);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The bytes `Funnels.unencodedCharsFunnel()` puts for `s`.
+    fn unencoded_chars(s: &str) -> Vec<u8> {
+        s.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    /// Checks a filter built like in Guava's `BloomFilterTest.testCreateAndCheckBloomFilterWithKnownFalsePositives`.
+    fn known_false_positives(
+        strategy: GuavaStrategy,
+        funnel: fn(&str) -> Vec<u8>,
+        false_positives: &[u32],
+        num_false_positives: usize,
+    ) {
+        let num_items = 1_000_000;
+        let mut filter = GuavaBloomFilter::new_with_false_pos(0.03, num_items);
+        filter.strategy = strategy;
+        filter.extend(
+            (0..2 * num_items)
+                .step_by(2)
+                .map(|i| funnel(&i.to_string())),
+        );
+        assert!((0..2 * num_items)
+            .step_by(2)
+            .all(|i| filter.contains(&funnel(&i.to_string()))));
+        let actual = (1..900)
+            .step_by(2)
+            .filter(|i: &u32| filter.contains(&funnel(&i.to_string())))
+            .collect::<Vec<_>>();
+        assert_eq!(actual, false_positives);
+        let actual = (1..2 * num_items)
+            .step_by(2)
+            .filter(|i| filter.contains(&funnel(&i.to_string())))
+            .count();
+        assert_eq!(actual, num_false_positives);
+    }
+
+    #[test]
+    fn known_false_positives_mitz_32() {
+        known_false_positives(
+            GuavaStrategy::Murmur128Mitz32,
+            unencoded_chars,
+            &[
+                49, 51, 59, 163, 199, 321, 325, 363, 367, 469, 545, 561, 727, 769, 773, 781,
+            ],
+            29824,
+        );
+    }
+
+    #[test]
+    fn known_false_positives_mitz_64() {
+        known_false_positives(
+            GuavaStrategy::Murmur128Mitz64,
+            unencoded_chars,
+            &[15, 25, 287, 319, 381, 399, 421, 465, 529, 697, 767, 857],
+            30104,
+        );
+    }
+
+    #[test]
+    fn known_false_positives_utf8() {
+        known_false_positives(
+            GuavaStrategy::Murmur128Mitz64,
+            |s| s.as_bytes().to_vec(),
+            &[89, 129, 471, 723, 751, 835, 871],
+            29763,
+        );
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let mut filter = GuavaBloomFilter::new_with_false_pos(0.01, 100);
+        filter.extend((0..100i64).map(i64::to_le_bytes));
+        let bytes = filter.to_bytes();
+        assert_eq!(bytes[..6], [1, 7, 0, 0, 0, 15]);
+        assert_eq!(bytes.len(), 6 + 8 * 15);
+        assert_eq!(GuavaBloomFilter::from_bytes(&bytes), Some(filter));
+    }
+
+    #[test]
+    fn rejects_malformed() {
+        let bytes = GuavaBloomFilter::new_with_false_pos(0.01, 100).to_bytes();
+        assert!(GuavaBloomFilter::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+        assert!(GuavaBloomFilter::from_bytes(&bytes[..1]).is_none());
+        assert!(
+            GuavaBloomFilter::from_bytes(&[2, 7, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0]).is_none()
+        );
+        assert!(GuavaBloomFilter::from_bytes(&[1, 7, 0, 0, 0, 0]).is_none());
+        assert!(GuavaBloomFilter::from_bytes(&[1, 7, 0xff, 0xff, 0xff, 0xff]).is_none());
+    }
+}
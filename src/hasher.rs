@@ -1,8 +1,16 @@
-use siphasher::sip::SipHasher13;
+use siphasher::sip::{SipHasher13, SipHasher24};
 use std::hash::{BuildHasher, Hasher};
 
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
 pub struct CloneBuildHasher<H: Hasher + Clone> {
     hasher: H,
 }
@@ -34,11 +42,61 @@ impl DefaultHasher {
             hasher: RandomDefaultHasher::seeded(seed),
         }
     }
+
+    /// Returns the keys of the SipHash-1-3 hasher.
+    pub(crate) fn keys(&self) -> (u64, u64) {
+        self.hasher.0.keys()
+    }
+
+    /// Creates a `DefaultHasher` from the keys of its SipHash-1-3 hasher.
+    pub(crate) fn with_keys(k0: u64, k1: u64) -> Self {
+        Self {
+            hasher: RandomDefaultHasher(SipHasher13::new_with_keys(k0, k1)),
+        }
+    }
+}
+
+/// A [`DefaultHasher`] constructible in const contexts, from the keys of its SipHash-1-3 hasher.
+///
+/// Items hash the same as with the `DefaultHasher` with the same keys, but the SipHash-1-3 hasher
+/// is keyed for each item instead of cloned. Used by [`StaticBloomFilter`](crate::StaticBloomFilter).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConstDefaultHasher {
+    k0: u64,
+    k1: u64,
+}
+
+impl ConstDefaultHasher {
+    /// Creates a `ConstDefaultHasher` from the keys of a [`DefaultHasher`]'s SipHash-1-3 hasher.
+    pub const fn with_keys(k0: u64, k1: u64) -> Self {
+        Self { k0, k1 }
+    }
+
+    /// Creates a `ConstDefaultHasher` hashing items as the builders' [`seed`](crate::BuilderWithBits::seed) does.
+    pub const fn with_seed(seed: &u128) -> Self {
+        let [a, b, c, d, e, f, g, h, rest @ ..] = seed.to_be_bytes();
+        Self::with_keys(
+            u64::from_le_bytes([a, b, c, d, e, f, g, h]),
+            u64::from_le_bytes(rest),
+        )
+    }
+}
+
+impl BuildHasher for ConstDefaultHasher {
+    type Hasher = RandomDefaultHasher;
+    #[inline]
+    fn build_hasher(&self) -> Self::Hasher {
+        RandomDefaultHasher(SipHasher13::new_with_keys(self.k0, self.k1))
+    }
 }
 
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub struct RandomDefaultHasher(SipHasher13);
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+pub struct RandomDefaultHasher(#[cfg_attr(feature = "rkyv", rkyv(with = SipKeys))] SipHasher13);
 
 impl RandomDefaultHasher {
     #[inline]
@@ -47,24 +105,125 @@ impl RandomDefaultHasher {
     }
 }
 
-impl Default for RandomDefaultHasher {
-    #[inline]
+/// A keyed SipHash-2-4 hasher for `BloomFilter`, for filters fed by untrusted input.
+///
+/// Like [`DefaultHasher`], which is keyed SipHash-1-3, items hash differently under each key, so without the key
+/// an adversary cannot craft items that collide to raise the false positive rate. SipHash-2-4 has more rounds
+/// and a larger security margin than SipHash-1-3, at the cost of slower hashing.
+/// `SipHash24::default()` uses a random key.
+///
+/// # Examples
+/// ```
+/// use fastbloom::{BloomFilter, SipHash24};
+///
+/// let bloom = BloomFilter::with_num_bits(1024)
+///     .hasher(SipHash24::with_key(&[7; 16]))
+///     .items(["42"]);
+/// assert!(bloom.contains("42"));
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SipHash24(SipHasher24);
+
+impl SipHash24 {
+    /// Creates a `SipHash24` with a 128 bit key.
+    pub fn with_key(key: &[u8; 16]) -> Self {
+        Self(SipHasher24::new_with_key(key))
+    }
+}
+
+impl Default for SipHash24 {
     fn default() -> Self {
-        let mut seed = [0u8; 16];
+        Self::with_key(&random_seed())
+    }
+}
 
-        #[cfg(not(feature = "rand"))]
-        {
-            getrandom::getrandom(&mut seed)
-                .expect("Unable to obtain entropy from OS/Hardware sources");
-        }
-        #[cfg(feature = "rand")]
-        {
-            use rand::RngCore;
+impl BuildHasher for SipHash24 {
+    type Hasher = SipHasher24;
+    #[inline]
+    fn build_hasher(&self) -> Self::Hasher {
+        self.0
+    }
+}
 
-            rand::thread_rng().fill_bytes(&mut seed);
-        }
+/// Archives a `SipHasher13` that has not written any bytes as its keys.
+#[cfg(feature = "rkyv")]
+struct SipKeys;
+
+#[cfg(feature = "rkyv")]
+impl rkyv::with::ArchiveWith<SipHasher13> for SipKeys {
+    type Archived = rkyv::Archived<[u64; 2]>;
+    type Resolver = rkyv::Resolver<[u64; 2]>;
+
+    fn resolve_with(
+        field: &SipHasher13,
+        resolver: Self::Resolver,
+        out: rkyv::Place<Self::Archived>,
+    ) {
+        let (k0, k1) = field.keys();
+        rkyv::Archive::resolve(&[k0, k1], resolver, out);
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl<S: rkyv::rancor::Fallible + ?Sized> rkyv::with::SerializeWith<SipHasher13, S> for SipKeys {
+    fn serialize_with(field: &SipHasher13, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        let (k0, k1) = field.keys();
+        rkyv::Serialize::serialize(&[k0, k1], serializer)
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl<D: rkyv::rancor::Fallible + ?Sized>
+    rkyv::with::DeserializeWith<rkyv::Archived<[u64; 2]>, SipHasher13, D> for SipKeys
+{
+    fn deserialize_with(
+        field: &rkyv::Archived<[u64; 2]>,
+        _: &mut D,
+    ) -> Result<SipHasher13, D::Error> {
+        let [k0, k1] = field.map(|k| k.to_native());
+        Ok(SipHasher13::new_with_keys(k0, k1))
+    }
+}
+
+/// Serialized as the keys of its hasher, which never has written any bytes.
+#[cfg(feature = "borsh")]
+impl borsh::BorshSerialize for RandomDefaultHasher {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.0.keys().serialize(writer)
+    }
+}
 
-        Self::seeded(&seed)
+#[cfg(feature = "borsh")]
+impl borsh::BorshDeserialize for RandomDefaultHasher {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let (k0, k1) = <(u64, u64)>::deserialize_reader(reader)?;
+        Ok(Self(SipHasher13::new_with_keys(k0, k1)))
+    }
+}
+
+/// Returns a random seed for a hasher.
+pub(crate) fn random_seed() -> [u8; 16] {
+    let mut seed = [0u8; 16];
+
+    #[cfg(not(feature = "rand"))]
+    {
+        getrandom::getrandom(&mut seed).expect("Unable to obtain entropy from OS/Hardware sources");
+    }
+    #[cfg(feature = "rand")]
+    {
+        use rand::RngCore;
+
+        rand::thread_rng().fill_bytes(&mut seed);
+    }
+
+    seed
+}
+
+impl Default for RandomDefaultHasher {
+    #[inline]
+    fn default() -> Self {
+        Self::seeded(&random_seed())
     }
 }
 
@@ -129,9 +288,9 @@ impl Hasher for RandomDefaultHasher {
 
 #[cfg(test)]
 mod test {
-    use crate::hasher::RandomDefaultHasher;
-    use siphasher::sip::SipHasher13;
-    use std::hash::Hasher;
+    use crate::hasher::{RandomDefaultHasher, SipHash24};
+    use siphasher::sip::{SipHasher13, SipHasher24};
+    use std::hash::{BuildHasher, Hasher};
     fn hash_all(mut x: impl Hasher) -> u64 {
         x.write(&[1; 16]);
         x.write_u8(1);
@@ -149,6 +308,17 @@ mod test {
         x.finish()
     }
 
+    #[test]
+    fn test_sip24() {
+        let key = [3; 16];
+        let h = SipHash24::with_key(&key).build_hasher();
+        assert_eq!(hash_all(h), hash_all(SipHasher24::new_with_key(&key)));
+        assert_ne!(
+            SipHash24::default().hash_one("42"),
+            SipHash24::default().hash_one("42")
+        );
+    }
+
     #[test]
     fn test_hasher() {
         let h1 = RandomDefaultHasher::seeded(&[0; 16]);
@@ -0,0 +1,269 @@
+use crate::{
+    BitStorage, BitStorageMut, BloomFilter, BuilderWithBits, DefaultHasher, IndexStrategy,
+};
+use std::fmt;
+use std::io;
+use std::ptr::{self, NonNull};
+
+/// The size of a huge page on x86-64 and aarch64 with 4 KiB base pages.
+const HUGE_PAGE_BYTES: usize = 2 << 20;
+
+/// How the bits of [`HugePageBits`] are backed by huge pages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HugePages {
+    /// Transparent huge pages, requested with `madvise(MADV_HUGEPAGE)`.
+    ///
+    /// The kernel backs the bits with huge pages when it can, e.g. when `/sys/kernel/mm/transparent_hugepage/enabled`
+    /// is `madvise` or `always`, and otherwise with base pages, so mapping only fails if memory runs out.
+    Transparent,
+    /// Explicit huge pages, mapped with `MAP_HUGETLB` from the pool reserved in `/proc/sys/vm/nr_hugepages`.
+    ///
+    /// Mapping fails if too few huge pages are reserved.
+    Explicit,
+}
+
+/// A [`BitStorage`] mapped in huge pages, which cuts the TLB misses of random queries to large filters.
+///
+/// With 4 KiB pages, each query to a multi-gigabyte filter likely misses the TLB, as well as the cache,
+/// and walks the page table. 2 MiB pages cover 512 times as much memory per TLB entry.
+/// The bits are mapped in whole huge pages, so up to 2 MiB more than the bits may be mapped;
+/// huge pages are best suited to filters of hundreds of megabytes or more.
+///
+/// Constructed via [`BloomFilter::with_huge_pages`], or [`HugePageBits::from_slice`] to copy existing bits.
+/// Available on Linux with the `hugepages` feature.
+pub struct HugePageBits {
+    ptr: NonNull<u64>,
+    /// The number of `u64`s of bits. Any `u64`s after them in the mapping are 0.
+    len: usize,
+    mapped_bytes: usize,
+    huge_pages: HugePages,
+}
+
+// SAFETY: `HugePageBits` owns its mapping, like a `Box<[u64]>`.
+unsafe impl Send for HugePageBits {}
+// SAFETY: as for `Send`; shared access is read-only.
+unsafe impl Sync for HugePageBits {}
+
+impl HugePageBits {
+    /// Maps `len` `u64`s of unset bits in huge pages.
+    ///
+    /// # Errors
+    /// Returns the error of `mmap`, e.g. if memory runs out, or for [`HugePages::Explicit`], too few huge pages are reserved.
+    pub fn zeroed(len: usize, huge_pages: HugePages) -> io::Result<Self> {
+        let bytes = len
+            .checked_mul(8)
+            .and_then(|bytes| bytes.max(1).checked_next_multiple_of(HUGE_PAGE_BYTES))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::OutOfMemory, "too many bits"))?;
+        let ptr = match huge_pages {
+            HugePages::Transparent => map_transparent(bytes)?,
+            HugePages::Explicit => map(bytes, libc::MAP_HUGETLB)?,
+        };
+        Ok(Self {
+            ptr: ptr.cast(),
+            len,
+            mapped_bytes: bytes,
+            huge_pages,
+        })
+    }
+
+    /// Copies `bits` into huge pages.
+    ///
+    /// # Errors
+    /// Returns an error if the huge pages cannot be mapped, as for [`zeroed`](Self::zeroed).
+    pub fn from_slice(bits: &[u64], huge_pages: HugePages) -> io::Result<Self> {
+        let mut mapped = Self::zeroed(bits.len(), huge_pages)?;
+        mapped.as_mut_slice().copy_from_slice(bits);
+        Ok(mapped)
+    }
+
+    /// Returns how the bits are backed by huge pages.
+    #[inline]
+    pub fn huge_pages(&self) -> HugePages {
+        self.huge_pages
+    }
+}
+
+/// Returns an anonymous, zeroed mapping of `bytes` with the extra `flags`.
+fn map(bytes: usize, flags: libc::c_int) -> io::Result<NonNull<u8>> {
+    // SAFETY: an anonymous mapping at an address chosen by the kernel does not alias any memory.
+    let ptr = unsafe {
+        libc::mmap(
+            ptr::null_mut(),
+            bytes,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | flags,
+            -1,
+            0,
+        )
+    };
+    if ptr == libc::MAP_FAILED {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(NonNull::new(ptr.cast()).unwrap())
+}
+
+/// Returns an anonymous, zeroed mapping of `bytes`, a multiple of the huge page size, aligned to a huge page,
+/// and advised to be backed by transparent huge pages.
+///
+/// The kernel only backs aligned huge pages of a mapping with huge pages, so one huge page more is mapped
+/// and the unaligned ends are unmapped.
+fn map_transparent(bytes: usize) -> io::Result<NonNull<u8>> {
+    let padded = bytes
+        .checked_add(HUGE_PAGE_BYTES)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::OutOfMemory, "too many bits"))?;
+    let ptr = map(padded, 0)?.as_ptr();
+    let head = (ptr as usize).next_multiple_of(HUGE_PAGE_BYTES) - ptr as usize;
+    // SAFETY: the head and tail are within the mapping, page aligned, and not yet referenced.
+    unsafe {
+        let aligned = ptr.add(head);
+        if head > 0 {
+            libc::munmap(ptr.cast(), head);
+        }
+        libc::munmap(aligned.add(bytes).cast(), HUGE_PAGE_BYTES - head);
+        // The advice only fails if transparent huge pages are unsupported, which leaves base pages.
+        libc::madvise(aligned.cast(), bytes, libc::MADV_HUGEPAGE);
+        Ok(NonNull::new_unchecked(aligned))
+    }
+}
+
+impl Drop for HugePageBits {
+    fn drop(&mut self) {
+        // SAFETY: the mapping is `mapped_bytes` from `ptr`, and no longer referenced.
+        unsafe { libc::munmap(self.ptr.as_ptr().cast(), self.mapped_bytes) };
+    }
+}
+
+/// # Panics
+/// Panics if the huge pages cannot be mapped, as cloning a `Vec` aborts if memory runs out.
+impl Clone for HugePageBits {
+    fn clone(&self) -> Self {
+        Self::from_slice(self.as_slice(), self.huge_pages).expect("failed to map huge pages")
+    }
+}
+
+impl fmt::Debug for HugePageBits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HugePageBits")
+            .field("len", &self.len)
+            .field("mapped_bytes", &self.mapped_bytes)
+            .field("huge_pages", &self.huge_pages)
+            .finish()
+    }
+}
+
+/// Maps the bits in transparent huge pages, e.g. when deserializing a filter.
+///
+/// # Panics
+/// Panics if the huge pages cannot be mapped.
+impl From<Vec<u64>> for HugePageBits {
+    fn from(bits: Vec<u64>) -> Self {
+        Self::from_slice(&bits, HugePages::Transparent).expect("failed to map huge pages")
+    }
+}
+
+impl From<HugePageBits> for Vec<u64> {
+    fn from(bits: HugePageBits) -> Self {
+        bits.as_slice().to_vec()
+    }
+}
+
+impl BitStorage for HugePageBits {
+    #[inline]
+    fn as_slice(&self) -> &[u64] {
+        // SAFETY: the mapping is `len` or more zeroed or written `u64`s.
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+
+    #[inline]
+    fn heap_bytes(&self) -> usize {
+        self.mapped_bytes
+    }
+
+    /// The mapping is whole huge pages, so the padding always fits in it.
+    fn pad_to(mut self, block_len: usize) -> Self {
+        self.len = self.len.next_multiple_of(block_len);
+        self
+    }
+}
+
+impl BitStorageMut for HugePageBits {
+    #[inline]
+    fn as_mut_slice(&mut self) -> &mut [u64] {
+        // SAFETY: as for `as_slice`, and the mapping is borrowed mutably.
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl BloomFilter {
+    /// Creates a new instance of [`BuilderWithBits`] to construct a `BloomFilter` with `num_bits` number of bits,
+    /// mapped in huge pages as chosen by `huge_pages`.
+    ///
+    /// The bits are mapped directly, zeroed by the kernel, so unlike converting a filter's storage,
+    /// a multi-gigabyte filter is never in memory twice.
+    ///
+    /// # Errors
+    /// Returns an error if the huge pages cannot be mapped, see [`HugePages`].
+    ///
+    /// # Panics
+    /// Panics if the number of bits, `num_bits`, is 0.
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::{BloomFilter, HugePages};
+    ///
+    /// let mut bloom = BloomFilter::with_huge_pages(1 << 20, HugePages::Transparent)
+    ///     .unwrap()
+    ///     .hashes(7);
+    /// bloom.insert(&1);
+    /// assert!(bloom.contains(&1));
+    /// ```
+    pub fn with_huge_pages(
+        num_bits: usize,
+        huge_pages: HugePages,
+    ) -> io::Result<BuilderWithBits<512, DefaultHasher, HugePageBits>> {
+        assert!(num_bits > 0);
+        Ok(BuilderWithBits {
+            data: HugePageBits::zeroed(num_bits.div_ceil(64), huge_pages)?,
+            hasher: Default::default(),
+            two_choice: false,
+            index_strategy: IndexStrategy::DoubleHashing,
+            max_rounds: 32,
+            prefix: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bit_vector::BlockedBitVec;
+
+    #[test]
+    fn transparent_huge_pages() {
+        let orig = BloomFilter::with_num_bits(1 << 16).seed(&1).items(0..1000);
+        let mut bloom = BloomFilter::with_huge_pages(1 << 16, HugePages::Transparent)
+            .unwrap()
+            .seed(&1)
+            .hashes(orig.num_hashes());
+        assert!(!(0..1000).any(|x| bloom.contains(&x)));
+        bloom.extend(0..1000);
+        assert_eq!(bloom.as_slice(), orig.as_slice());
+        assert_eq!(bloom.heap_bytes(), HUGE_PAGE_BYTES);
+        assert_eq!(bloom.as_slice().as_ptr() as usize % HUGE_PAGE_BYTES, 0);
+
+        let cloned = bloom.clone();
+        assert_eq!(cloned, bloom);
+        let bits = HugePageBits::from_slice(&[1; 9], HugePages::Transparent).unwrap();
+        let padded = BlockedBitVec::<512, _>::from_storage(bits);
+        assert_eq!(padded.as_slice(), [&[1; 9][..], &[0; 7]].concat());
+    }
+
+    #[test]
+    fn explicit_huge_pages() {
+        // Without reserved huge pages, e.g. in CI, mapping fails instead of falling back.
+        if let Ok(bits) = HugePageBits::from_slice(&[1, 2, 3], HugePages::Explicit) {
+            assert_eq!(bits.as_slice(), [1, 2, 3]);
+            assert_eq!(bits.huge_pages(), HugePages::Explicit);
+        }
+    }
+}
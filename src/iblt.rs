@@ -0,0 +1,280 @@
+use crate::{block_index, get_orginal_hashes, sparse_hash::SparseHash, DefaultHasher};
+use std::hash::BuildHasher;
+
+/// The number of cells each key is added to. Each key is added to one cell in each of `NUM_HASHES` equal partitions.
+const NUM_HASHES: usize = 3;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Cell {
+    /// The number of keys inserted minus the number of keys removed
+    count: i64,
+    /// XOR of all keys in the cell
+    key_sum: u64,
+    /// XOR of the checksums of all keys in the cell
+    hash_sum: u64,
+}
+
+impl Cell {
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.count == 0 && self.key_sum == 0 && self.hash_sum == 0
+    }
+
+    #[inline]
+    fn toggle(&mut self, key: u64, checksum: u64, count: i64) {
+        self.count += count;
+        self.key_sum ^= key;
+        self.hash_sum ^= checksum;
+    }
+}
+
+/// An Invertible Bloom Lookup Table (IBLT) of `u64` keys, used for set reconciliation.
+///
+/// Each key is added to a few cells, and each cell keeps a count, the XOR of its keys, and the XOR of its keys' checksums.
+/// Unlike a [`BloomFilter`](crate::BloomFilter), keys can be removed, and if few enough keys remain,
+/// all of them can be listed with [`decode`](Self::decode).
+///
+/// To reconcile two sets, each replica builds an `Iblt` of its keys with the same number of cells and hasher.
+/// [`subtract`](Self::subtract)ing one from the other leaves only the symmetric difference, which is then decoded.
+/// The size of the `Iblt` depends only on the size of the difference, not of the sets: decoding reliably succeeds
+/// when there are at least 1.5 times as many cells as differing keys.
+///
+/// See <https://arxiv.org/abs/1101.2245>.
+///
+/// # Examples
+/// ```
+/// use fastbloom::Iblt;
+///
+/// let mut a = Iblt::new(60).seed(&42);
+/// let mut b = Iblt::new(60).seed(&42);
+/// a.extend(0..1000);
+/// b.extend(5..1005);
+///
+/// a.subtract(&b);
+/// let (mut only_a, mut only_b) = a.decode().unwrap();
+/// only_a.sort();
+/// only_b.sort();
+/// assert_eq!(only_a, vec![0, 1, 2, 3, 4]);
+/// assert_eq!(only_b, vec![1000, 1001, 1002, 1003, 1004]);
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Iblt<S = DefaultHasher> {
+    cells: Vec<Cell>,
+    hasher: S,
+}
+
+impl Iblt {
+    /// Creates a new empty `Iblt` with at least `num_cells` cells, using the default hasher with a random seed.
+    ///
+    /// # Panics
+    /// Panics if `num_cells` is 0.
+    pub fn new(num_cells: usize) -> Self {
+        Self::with_hasher(num_cells, Default::default())
+    }
+
+    /// Sets the seed of the hasher. Replicas must use the same seed to [`subtract`](Self::subtract) each other.
+    ///
+    /// # Panics
+    /// Panics if the `Iblt` is not empty.
+    pub fn seed(self, seed: &u128) -> Self {
+        assert!(self.is_empty());
+        Self {
+            cells: self.cells,
+            hasher: DefaultHasher::seeded(&seed.to_be_bytes()),
+        }
+    }
+}
+
+impl<S: BuildHasher> Iblt<S> {
+    /// Creates a new empty `Iblt` with at least `num_cells` cells, using `hasher` to hash keys.
+    /// The number of cells is rounded up to a multiple of 3.
+    ///
+    /// # Panics
+    /// Panics if `num_cells` is 0.
+    pub fn with_hasher(num_cells: usize, hasher: S) -> Self {
+        assert!(num_cells > 0);
+        let num_cells = num_cells.div_ceil(NUM_HASHES) * NUM_HASHES;
+        Self {
+            cells: vec![Cell::default(); num_cells],
+            hasher,
+        }
+    }
+
+    /// Returns the checksum and the cell indexes of a key.
+    #[inline]
+    fn hashes(&self, key: u64) -> (u64, [usize; NUM_HASHES]) {
+        let [mut h1, h2] = get_orginal_hashes(&self.hasher, &key);
+        let checksum = h1;
+        let partition_len = self.cells.len() / NUM_HASHES;
+        let mut indexes = [0; NUM_HASHES];
+        for (i, index) in indexes.iter_mut().enumerate() {
+            *index = i * partition_len + block_index(partition_len, u64::next_hash(&mut h1, h2));
+        }
+        (checksum, indexes)
+    }
+
+    #[inline]
+    fn toggle(&mut self, key: u64, count: i64) {
+        let (checksum, indexes) = self.hashes(key);
+        for index in indexes {
+            self.cells[index].toggle(key, checksum, count);
+        }
+    }
+
+    /// Inserts a key.
+    #[inline]
+    pub fn insert(&mut self, key: u64) {
+        self.toggle(key, 1);
+    }
+
+    /// Removes a key. The key does not need to have been inserted: after removing a key that was never inserted,
+    /// [`decode`](Self::decode) lists it as removed.
+    #[inline]
+    pub fn remove(&mut self, key: u64) {
+        self.toggle(key, -1);
+    }
+
+    /// Subtracts `other` from this `Iblt`, cell by cell, leaving the keys only in this `Iblt` as inserted
+    /// and the keys only in `other` as removed.
+    ///
+    /// Both must have the same number of cells and hash keys the same way, otherwise decoding will fail.
+    ///
+    /// # Panics
+    /// Panics if `other` has a different number of cells.
+    pub fn subtract(&mut self, other: &Self) {
+        assert_eq!(self.cells.len(), other.cells.len());
+        for (cell, other) in self.cells.iter_mut().zip(other.cells.iter()) {
+            cell.toggle(other.key_sum, other.hash_sum, -other.count);
+        }
+    }
+
+    /// Lists all keys by repeatedly "peeling" cells that contain exactly one key.
+    ///
+    /// # Returns
+    ///
+    /// The keys with a positive count, i.e. inserted, and the keys with a negative count, i.e. removed,
+    /// or `None` if not all keys could be listed, which becomes likely once there are more than
+    /// about `num_cells / 1.5` keys.
+    pub fn decode(&self) -> Option<(Vec<u64>, Vec<u64>)> {
+        let mut cells = self.cells.clone();
+        let mut inserted = Vec::new();
+        let mut removed = Vec::new();
+        let mut pure: Vec<usize> = (0..cells.len()).collect();
+        while let Some(i) = pure.pop() {
+            let cell = cells[i];
+            if cell.count.abs() != 1 {
+                continue;
+            }
+            let (checksum, indexes) = self.hashes(cell.key_sum);
+            if checksum != cell.hash_sum {
+                continue;
+            }
+            if cell.count == 1 {
+                inserted.push(cell.key_sum);
+            } else {
+                removed.push(cell.key_sum);
+            }
+            for index in indexes {
+                cells[index].toggle(cell.key_sum, checksum, -cell.count);
+                pure.push(index);
+            }
+        }
+        if cells.iter().all(Cell::is_empty) {
+            Some((inserted, removed))
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if no keys are inserted or removed.
+    pub fn is_empty(&self) -> bool {
+        self.cells.iter().all(Cell::is_empty)
+    }
+
+    /// Returns the number of cells.
+    #[inline]
+    pub fn num_cells(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Returns the number of bytes this `Iblt` uses, including all cells.
+    /// See [`BloomFilter::memory_usage`](crate::BloomFilter::memory_usage).
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>() + self.cells.capacity() * std::mem::size_of::<Cell>()
+    }
+
+    /// Clear all cells, removing all keys.
+    #[inline]
+    pub fn clear(&mut self) {
+        for cell in self.cells.iter_mut() {
+            *cell = Cell::default();
+        }
+    }
+}
+
+impl<S: BuildHasher> Extend<u64> for Iblt<S> {
+    #[inline]
+    fn extend<I: IntoIterator<Item = u64>>(&mut self, iter: I) {
+        for key in iter {
+            self.insert(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    #[test]
+    fn decode_difference() {
+        let mut rng = StdRng::seed_from_u64(42);
+        for num_diff in [1, 10, 100, 1000] {
+            let shared: Vec<u64> = (0..10_000).map(|_| rng.gen()).collect();
+            let only_a: Vec<u64> = (0..num_diff).map(|_| rng.gen()).collect();
+            let only_b: Vec<u64> = (0..num_diff).map(|_| rng.gen()).collect();
+            let mut a = Iblt::new(4 * num_diff).seed(&7);
+            let mut b = Iblt::new(4 * num_diff).seed(&7);
+            a.extend(shared.iter().chain(only_a.iter()).copied());
+            b.extend(shared.iter().chain(only_b.iter()).copied());
+            a.subtract(&b);
+            let (mut inserted, mut removed) = a.decode().unwrap();
+            inserted.sort();
+            removed.sort();
+            let (mut only_a, mut only_b) = (only_a, only_b);
+            only_a.sort();
+            only_b.sort();
+            assert_eq!(inserted, only_a);
+            assert_eq!(removed, only_b);
+        }
+    }
+
+    #[test]
+    fn too_many_keys_fail() {
+        let mut iblt = Iblt::new(30).seed(&7);
+        iblt.extend(0..1000);
+        assert!(iblt.decode().is_none());
+    }
+
+    #[test]
+    fn remove_cancels_insert() {
+        let mut iblt = Iblt::new(30).seed(&7);
+        iblt.extend(0..1000);
+        for key in 0..1000 {
+            iblt.remove(key);
+        }
+        assert!(iblt.is_empty());
+        assert_eq!(iblt.decode(), Some((vec![], vec![])));
+    }
+
+    #[test]
+    fn nothing_after_clear() {
+        let mut iblt = Iblt::new(30).seed(&7);
+        iblt.extend(0..10);
+        iblt.clear();
+        assert!(iblt.is_empty());
+        assert_eq!(iblt.num_cells(), 30);
+    }
+}
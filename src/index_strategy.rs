@@ -0,0 +1,46 @@
+/// How the bit indexes of an item's single bit hashes are derived from its two hashes, `h1` and `h2`.
+///
+/// The sparse hashes, which set many bits of a block at once, are the same for both strategies.
+/// Filters must be checked with the strategy they were built with, so it is part of their serialized parameters.
+/// Since the filters are otherwise the same, the strategy can be chosen by measuring the false positive rate
+/// of each on the application's keys.
+///
+/// See [`BuilderWithBits::index_strategy`](crate::BuilderWithBits::index_strategy) and
+/// [`BuilderWithFalsePositiveRate::index_strategy`](crate::BuilderWithFalsePositiveRate::index_strategy).
+///
+/// # Examples
+/// ```
+/// use fastbloom::{BloomFilter, IndexStrategy};
+///
+/// for strategy in [IndexStrategy::DoubleHashing, IndexStrategy::EnhancedDoubleHashing] {
+///     let bloom = BloomFilter::with_false_pos(0.01)
+///         .block_size_64()
+///         .index_strategy(strategy)
+///         .items(0..1000);
+///     let fp = (1000..101_000).filter(|x| bloom.contains(x)).count() as f64 / 100_000.0;
+///     assert!(fp < 0.05);
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub enum IndexStrategy {
+    /// Each hash is the previous one plus `h2`, rotated left by 5 bits, so that the upper bits of the sum
+    /// reach the bit index. This is the default.
+    #[default]
+    DoubleHashing,
+    /// Kirsch–Mitzenmacher enhanced double hashing: the `i`th hash is `h1 + i * h2 + (i³ - i) / 6`.
+    ///
+    /// The cubic term spreads the bit indexes of an item even when `h2` is a multiple of the block size,
+    /// where plain double hashing would set the same bit repeatedly, at the cost of one more addition per hash.
+    ///
+    /// See <https://www.eecs.harvard.edu/~michaelm/postscripts/rsa2008.pdf>.
+    EnhancedDoubleHashing,
+}
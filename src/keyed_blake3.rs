@@ -0,0 +1,172 @@
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+
+/// A secret-keyed BLAKE3 hasher for `BloomFilter`, for filters shared with parties who must not query them.
+///
+/// Item hashes are BLAKE3 keyed with a 32 byte secret, a pseudorandom function: without the key, the hash of an
+/// item, and so its bits, cannot be computed, so a party holding only the filter's bits cannot confirm the
+/// membership of guessed items or enumerate likely members. Parties given the key can query the filter as usual.
+///
+/// Share only the bits, e.g. [`as_bytes`](crate::BloomFilter::as_bytes) or [`write_to`](crate::BloomFilter::write_to),
+/// and the number of hashes. `KeyedBlake3` does not implement `serde::Serialize`, and its `Debug` output omits the key,
+/// so the key cannot leak by serializing or logging the filter.
+///
+/// Each write of an item is hashed in order, with integers little-endian and `usize`s and `isize`s as 64 bits,
+/// and the hash is the first 8 bytes of the output, little-endian. So [`insert_bytes`](crate::BloomFilter::insert_bytes)
+/// hashes a key to the first 8 bytes of BLAKE3's `keyed_hash` of it, which other languages can compute.
+///
+/// Requires the `blake3` feature.
+///
+/// # Examples
+/// ```
+/// use fastbloom::{BloomFilter, KeyedBlake3};
+///
+/// let key = [7; 32]; // a secret shared only with authorized parties
+/// let bloom = BloomFilter::with_false_pos(0.001)
+///     .hasher(KeyedBlake3::new(&key))
+///     .items(["alice", "bob"]);
+/// let shared = bloom.as_bytes().into_owned();
+///
+/// // an authorized party with the key
+/// let received = BloomFilter::from_bytes(&shared)
+///     .unwrap()
+///     .hasher(KeyedBlake3::new(&key))
+///     .hashes(bloom.num_hashes());
+/// assert!(received.contains("alice"));
+/// ```
+#[derive(Clone)]
+pub struct KeyedBlake3 {
+    key: [u8; 32],
+}
+
+impl KeyedBlake3 {
+    /// Creates a `KeyedBlake3` with the 32 byte secret `key`.
+    pub fn new(key: &[u8; 32]) -> Self {
+        Self { key: *key }
+    }
+}
+
+impl fmt::Debug for KeyedBlake3 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyedBlake3").finish_non_exhaustive()
+    }
+}
+
+impl BuildHasher for KeyedBlake3 {
+    type Hasher = KeyedBlake3Hasher;
+    #[inline]
+    fn build_hasher(&self) -> Self::Hasher {
+        KeyedBlake3Hasher(blake3::Hasher::new_keyed(&self.key))
+    }
+}
+
+/// The [`Hasher`] built by [`KeyedBlake3`].
+#[derive(Clone)]
+pub struct KeyedBlake3Hasher(blake3::Hasher);
+
+impl fmt::Debug for KeyedBlake3Hasher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyedBlake3Hasher").finish_non_exhaustive()
+    }
+}
+
+impl Hasher for KeyedBlake3Hasher {
+    #[inline]
+    fn finish(&self) -> u64 {
+        let mut hash = [0; 8];
+        self.0.finalize_xof().fill(&mut hash);
+        u64::from_le_bytes(hash)
+    }
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+    #[inline]
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+    #[inline]
+    fn write_i16(&mut self, i: i16) {
+        self.write(&i.to_le_bytes());
+    }
+    #[inline]
+    fn write_i32(&mut self, i: i32) {
+        self.write(&i.to_le_bytes());
+    }
+    #[inline]
+    fn write_i64(&mut self, i: i64) {
+        self.write(&i.to_le_bytes());
+    }
+    #[inline]
+    fn write_i128(&mut self, i: i128) {
+        self.write(&i.to_le_bytes());
+    }
+    #[inline]
+    fn write_isize(&mut self, i: isize) {
+        self.write_i64(i as i64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BloomFilter;
+
+    #[test]
+    fn keyed_hashes() {
+        let key = [7; 32];
+        let hasher = KeyedBlake3::new(&key);
+        let expected = blake3::keyed_hash(&key, &42u64.to_le_bytes());
+        assert_eq!(
+            hasher.hash_one(42u64).to_le_bytes(),
+            expected.as_bytes()[..8]
+        );
+        assert_ne!(
+            KeyedBlake3::new(&[8; 32]).hash_one(42u64),
+            hasher.hash_one(42u64)
+        );
+        assert!(!format!("{hasher:?}").contains('7'));
+    }
+
+    #[test]
+    fn query_needs_key() {
+        let key = [7; 32];
+        let mut bloom = BloomFilter::with_num_bits(1 << 14)
+            .hasher(KeyedBlake3::new(&key))
+            .hashes(6);
+        for x in 0..100u64 {
+            bloom.insert_bytes(&x.to_le_bytes());
+        }
+        let shared = bloom.as_bytes().into_owned();
+
+        let with_key = BloomFilter::from_bytes(&shared)
+            .unwrap()
+            .hasher(KeyedBlake3::new(&key))
+            .hashes(6);
+        assert!((0..100u64).all(|x| with_key.contains_bytes(&x.to_le_bytes())));
+        let guessed = BloomFilter::from_bytes(&shared)
+            .unwrap()
+            .hasher(KeyedBlake3::new(&[0; 32]))
+            .hashes(6);
+        let hits = (0..100u64)
+            .filter(|x| guessed.contains_bytes(&x.to_le_bytes()))
+            .count();
+        assert!(hits < 5, "{hits}");
+    }
+}
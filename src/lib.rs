@@ -1,16 +1,173 @@
 #![allow(rustdoc::bare_urls)]
 #![doc = include_str!("../README.md")]
 
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
 use std::hash::{BuildHasher, Hash, Hasher};
+use std::sync::Arc;
+use wide::{u64x2, u64x4};
 mod hasher;
-pub use hasher::DefaultHasher;
+pub use hasher::{ConstDefaultHasher, DefaultHasher, SipHash24};
+#[cfg(any(feature = "xxh3", feature = "wyhash"))]
+mod seeded_hash;
+#[cfg(feature = "wyhash")]
+pub use seeded_hash::{WyHash, WyHasher};
+#[cfg(feature = "xxh3")]
+pub use seeded_hash::{Xxh3, Xxh3Hasher};
+#[cfg(feature = "blake3")]
+mod keyed_blake3;
+#[cfg(feature = "blake3")]
+pub use keyed_blake3::{KeyedBlake3, KeyedBlake3Hasher};
+mod error;
+pub use error::FastbloomError;
+mod approximate_set;
+pub use approximate_set::ApproximateSet;
 mod builder;
 pub use builder::{BuilderWithBits, BuilderWithFalsePositiveRate};
 mod bit_vector;
-use bit_vector::BlockedBitVec;
+pub use bit_vector::{AlignedBits, BitStorage, BitStorageMut, BlockedBitVec};
 mod sparse_hash;
 use sparse_hash::SparseHash;
-use wide::{u64x2, u64x4};
+mod prepared;
+mod simd;
+pub use prepared::PreparedKey;
+mod aging;
+pub use aging::AgingBloomFilter;
+mod expiring;
+pub use expiring::ExpiringBloomFilter;
+mod windowed;
+pub use windowed::WindowedBloomFilter;
+mod deletable;
+pub use deletable::DeletableBloomFilter;
+mod spectral;
+pub use spectral::SpectralBloomFilter;
+mod cascade;
+pub use cascade::FilterCascade;
+mod iblt;
+pub use iblt::Iblt;
+mod attenuated;
+pub use attenuated::AttenuatedBloomFilter;
+mod morton;
+pub use morton::MortonFilter;
+mod growable;
+pub use growable::GrowableCuckooFilter;
+mod register;
+pub use register::RegisterBlockedBloomFilter;
+mod fixed;
+pub use fixed::FixedHashesBloomFilter;
+mod word32;
+pub use word32::U32BloomFilter;
+#[cfg(feature = "build")]
+mod embed;
+mod static_filter;
+#[doc(hidden)]
+pub use static_filter::__include_bloom_padding;
+pub use static_filter::StaticBloomFilter;
+mod prefix;
+pub use prefix::Prefix;
+mod index_strategy;
+pub use index_strategy::IndexStrategy;
+mod range;
+pub use range::RangeFilter;
+mod shard;
+pub use shard::BloomShard;
+mod dyn_filter;
+pub use dyn_filter::DynBloomFilter;
+mod frozen;
+pub use frozen::FrozenBloomFilter;
+mod counting_builder;
+pub use counting_builder::CountingBuilder;
+mod rocksdb;
+pub use rocksdb::RocksDbBloomFilter;
+mod guava;
+mod murmur3;
+pub use guava::{GuavaBloomFilter, GuavaStrategy};
+mod spark;
+pub use spark::SparkBloomFilter;
+mod redis_bloom;
+pub use redis_bloom::RedisBloomFilter;
+mod cassandra;
+pub use cassandra::CassandraBloomFilter;
+mod sip_bloom;
+pub use sip_bloom::SipBloomFilter;
+mod block_stats;
+pub use block_stats::BlockStats;
+mod cardinality;
+mod counters;
+use counters::AtomicCounters;
+#[cfg(feature = "counters")]
+pub use counters::Counters;
+mod delta;
+pub use delta::Delta;
+mod gset;
+pub use gset::GSetFilter;
+mod saturation;
+pub use saturation::SaturationMonitor;
+mod stats;
+pub use stats::FilterStats;
+mod typed;
+pub use typed::BloomSet;
+#[cfg(feature = "parquet")]
+mod parquet;
+mod stream;
+mod text;
+#[cfg(feature = "parquet")]
+pub use parquet::ParquetBloomFilter;
+#[cfg(feature = "scalable")]
+mod scalable;
+#[cfg(feature = "scalable")]
+pub use scalable::ScalableBloomFilter;
+#[cfg(feature = "serde")]
+mod fixed_width;
+#[cfg(feature = "serde")]
+pub use fixed_width::FixedWidth;
+#[cfg(feature = "rkyv")]
+mod archive;
+#[cfg(feature = "zstd")]
+mod compression;
+#[cfg(feature = "prost")]
+pub mod proto;
+#[cfg(feature = "wasm")]
+mod wasm;
+#[cfg(feature = "wasm")]
+pub use wasm::WasmBloomFilter;
+#[cfg(feature = "pyo3")]
+mod python;
+#[cfg(feature = "pyo3")]
+pub use python::PyBloomFilter;
+#[cfg(all(feature = "hugepages", target_os = "linux"))]
+mod huge_pages;
+#[cfg(all(feature = "hugepages", target_os = "linux"))]
+pub use huge_pages::{HugePageBits, HugePages};
+#[cfg(feature = "allocator-api2")]
+mod allocator;
+#[cfg(feature = "allocator-api2")]
+pub use allocator::AllocBloomFilter;
+#[cfg(feature = "arbitrary")]
+mod fuzz;
+#[cfg(feature = "rand")]
+mod privacy;
+#[cfg(feature = "proptest")]
+pub mod strategy;
+#[cfg(feature = "rand")]
+pub use privacy::{RandomizedResponse, ReportAggregator};
+#[cfg(feature = "verify")]
+mod verify;
+#[cfg(feature = "verify")]
+pub use verify::VerifyingBloomFilter;
+#[cfg(feature = "bitvec")]
+mod bit_slice;
+#[cfg(feature = "roaring")]
+mod sparse_filter;
+#[cfg(feature = "roaring")]
+pub use sparse_filter::{SparseBloomFilter, DEFAULT_DENSITY_THRESHOLD};
+#[cfg(feature = "wgpu")]
+mod gpu;
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(feature = "wgpu")]
+pub use gpu::GpuBloomFilter;
 
 /// A space efficient approximate membership set data structure.
 /// False positives from [`contains`](Self::contains) are possible, but false negatives
@@ -49,10 +206,26 @@ use wide::{u64x2, u64x4};
 ///     .hasher(RandomState::default())
 ///     .items(["42", "🦀"]);
 /// ```
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub struct BloomFilter<const BLOCK_SIZE_BITS: usize = 512, S = DefaultHasher> {
-    bits: BlockedBitVec<BLOCK_SIZE_BITS>,
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct BloomFilter<const BLOCK_SIZE_BITS: usize = 512, S = DefaultHasher, T = Vec<u64>> {
+    #[cfg_attr(
+        feature = "serde",
+        serde(bound(serialize = "T: BitStorage", deserialize = "T: From<Vec<u64>>"))
+    )]
+    #[cfg_attr(
+        feature = "borsh",
+        borsh(bound(serialize = "T: BitStorage", deserialize = "T: From<Vec<u64>>"))
+    )]
+    bits: BlockedBitVec<BLOCK_SIZE_BITS, T>,
     /// The total target hashes per item that is specified by user or optimized to maximize accuracy
     target_hashes: u64,
     /// The target number of bits to set/check per u64 per item when inserting/checking an item.
@@ -61,6 +234,21 @@ pub struct BloomFilter<const BLOCK_SIZE_BITS: usize = 512, S = DefaultHasher> {
     /// These hashes are in addition to `num_rounds` to make up for rounding errors.
     num_hashes: u64,
     hasher: S,
+    // Fields added in 0.8 follow `hasher`, so that the fields serialized by 0.7 keep their positions.
+    /// Whether each item is inserted into the less occupied of two candidate blocks.
+    #[cfg_attr(feature = "serde", serde(default))]
+    two_choice: bool,
+    /// How the bit indexes of the `num_hashes` single bit hashes are derived.
+    #[cfg_attr(feature = "serde", serde(default))]
+    index_strategy: IndexStrategy,
+    /// The prefix of byte string keys also inserted by [`BloomFilter::insert_with_prefix`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    prefix: Option<Prefix>,
+    /// The counts of inserts and queries, with the `counters` feature, which are not serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[cfg_attr(feature = "rkyv", rkyv(with = rkyv::with::Skip))]
+    #[cfg_attr(feature = "borsh", borsh(skip))]
+    counters: AtomicCounters,
 }
 
 impl BloomFilter {
@@ -72,6 +260,10 @@ impl BloomFilter {
         BuilderWithBits::<BLOCK_SIZE_BITS> {
             data: vec![0; num_u64s],
             hasher: Default::default(),
+            two_choice: false,
+            index_strategy: IndexStrategy::DoubleHashing,
+            max_rounds: 32,
+            prefix: None,
         }
     }
 
@@ -82,6 +274,24 @@ impl BloomFilter {
         BuilderWithBits::<BLOCK_SIZE_BITS> {
             data: vec,
             hasher: Default::default(),
+            two_choice: false,
+            index_strategy: IndexStrategy::DoubleHashing,
+            max_rounds: 32,
+            prefix: None,
+        }
+    }
+
+    fn new_from_slice<const BLOCK_SIZE_BITS: usize>(
+        slice: &[u64],
+    ) -> BuilderWithBits<BLOCK_SIZE_BITS, DefaultHasher, &[u64]> {
+        assert!(!slice.is_empty());
+        BuilderWithBits::<BLOCK_SIZE_BITS, DefaultHasher, &[u64]> {
+            data: slice,
+            hasher: Default::default(),
+            two_choice: false,
+            index_strategy: IndexStrategy::DoubleHashing,
+            max_rounds: 32,
+            prefix: None,
         }
     }
 
@@ -92,6 +302,10 @@ impl BloomFilter {
         BuilderWithFalsePositiveRate::<BLOCK_SIZE_BITS> {
             desired_fp_rate: fp,
             hasher: Default::default(),
+            two_choice: false,
+            index_strategy: IndexStrategy::DoubleHashing,
+            max_rounds: 32,
+            prefix: None,
         }
     }
 
@@ -142,8 +356,259 @@ impl BloomFilter {
     pub fn from_vec(bit_vec: Vec<u64>) -> BuilderWithBits<512> {
         BloomFilter::new_from_vec::<512>(bit_vec)
     }
+
+    /// Creates a new instance of [`BuilderWithBits`] to construct a [`BloomFilterRef`] reading its bits from `slice`,
+    /// without copying them.
+    ///
+    /// Unlike [`from_vec`](Self::from_vec), `slice` cannot be padded, so its length must be a multiple of the block size.
+    ///
+    /// # Panics
+    /// Panics if `slice` is empty, or when constructing the filter, if the length of `slice` is not a multiple of the block size.
+    /// # Examples
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let orig = BloomFilter::with_false_pos(0.001).seed(&42).items([1, 2]);
+    /// let num_hashes = orig.num_hashes();
+    /// let frame: &[u64] = orig.as_slice(); // e.g. inside a zero-copy message
+    /// let view = BloomFilter::from_slice(frame).seed(&42).hashes(num_hashes);
+    ///
+    /// assert!(view.contains(&1));
+    /// assert!(view.contains(&2));
+    /// ```
+    pub fn from_slice(slice: &[u64]) -> BuilderWithBits<512, DefaultHasher, &[u64]> {
+        BloomFilter::new_from_slice::<512>(slice)
+    }
+
+    /// Creates a new instance of [`BuilderWithBits`] to construct a `BloomFilter` from `bytes`,
+    /// in the little-endian layout of [`as_bytes`](BloomFilter::as_bytes).
+    ///
+    /// As with [`from_vec`](Self::from_vec), the bits will be padded with `0u64`s to fit the block size.
+    ///
+    /// Returns `None` if `bytes` is empty or its length is not a multiple of 8.
+    /// # Examples
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let orig = BloomFilter::with_false_pos(0.001).seed(&42).items([1, 2]);
+    /// let num_hashes = orig.num_hashes();
+    /// let bytes = orig.as_bytes().into_owned(); // e.g. written to a file
+    /// let new = BloomFilter::from_bytes(&bytes).unwrap().seed(&42).hashes(num_hashes);
+    ///
+    /// assert!(new.contains(&1));
+    /// assert!(new.contains(&2));
+    /// ```
+    pub fn from_bytes(bytes: &[u8]) -> Option<BuilderWithBits<512>> {
+        let words = bytes.chunks_exact(8);
+        if bytes.is_empty() || !words.remainder().is_empty() {
+            return None;
+        }
+        let bit_vec = words
+            .map(|word| u64::from_le_bytes(word.try_into().unwrap()))
+            .collect();
+        Some(BloomFilter::new_from_vec::<512>(bit_vec))
+    }
+
+    /// Like [`with_false_pos`](Self::with_false_pos), but returns an error instead of panicking.
+    ///
+    /// # Errors
+    /// Returns [`FastbloomError::InvalidFalsePositiveRate`] if `fp` is not greater than 0.
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::try_with_false_pos(0.001).unwrap().expected_items(1000);
+    /// assert!(BloomFilter::try_with_false_pos(-1.0).is_err());
+    /// ```
+    pub fn try_with_false_pos(
+        fp: f64,
+    ) -> Result<BuilderWithFalsePositiveRate<512>, FastbloomError> {
+        if fp.is_nan() || fp <= 0.0 {
+            return Err(FastbloomError::InvalidFalsePositiveRate(fp));
+        }
+        Ok(BloomFilter::new_with_false_pos::<512>(fp))
+    }
+
+    /// Like [`with_num_bits`](Self::with_num_bits), but returns an error instead of panicking.
+    ///
+    /// # Errors
+    /// Returns [`FastbloomError::ZeroBits`] if `num_bits` is 0.
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::{BloomFilter, FastbloomError};
+    ///
+    /// let bloom = BloomFilter::try_with_num_bits(1024).unwrap().try_hashes(4).unwrap();
+    /// assert_eq!(BloomFilter::try_with_num_bits(0).unwrap_err(), FastbloomError::ZeroBits);
+    /// ```
+    pub fn try_with_num_bits(num_bits: usize) -> Result<BuilderWithBits<512>, FastbloomError> {
+        if num_bits == 0 {
+            return Err(FastbloomError::ZeroBits);
+        }
+        Ok(BloomFilter::new_builder::<512>(num_bits))
+    }
+
+    /// Like [`from_vec`](Self::from_vec), but returns an error instead of panicking.
+    ///
+    /// # Errors
+    /// Returns [`FastbloomError::ZeroBits`] if `bit_vec` is empty.
+    pub fn try_from_vec(bit_vec: Vec<u64>) -> Result<BuilderWithBits<512>, FastbloomError> {
+        if bit_vec.is_empty() {
+            return Err(FastbloomError::ZeroBits);
+        }
+        Ok(BloomFilter::new_from_vec::<512>(bit_vec))
+    }
+
+    /// Like [`from_slice`](Self::from_slice), but returns an error instead of panicking.
+    ///
+    /// Use [`BuilderWithBits::try_hashes`] or [`BuilderWithBits::try_expected_items`] to also check, without panicking,
+    /// that the length of `slice` is a multiple of the block size.
+    ///
+    /// # Errors
+    /// Returns [`FastbloomError::ZeroBits`] if `slice` is empty.
+    pub fn try_from_slice(
+        slice: &[u64],
+    ) -> Result<BuilderWithBits<512, DefaultHasher, &[u64]>, FastbloomError> {
+        if slice.is_empty() {
+            return Err(FastbloomError::ZeroBits);
+        }
+        Ok(BloomFilter::new_from_slice::<512>(slice))
+    }
+
+    /// Like [`from_bytes`](Self::from_bytes), but returns an error instead of `None`.
+    ///
+    /// # Errors
+    /// Returns [`FastbloomError::InvalidBytes`] if `bytes` is empty or its length is not a multiple of 8.
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::{BloomFilter, FastbloomError};
+    ///
+    /// let err = BloomFilter::try_from_bytes(&[1, 2, 3]).unwrap_err();
+    /// assert_eq!(err, FastbloomError::InvalidBytes { len: 3 });
+    /// ```
+    pub fn try_from_bytes(bytes: &[u8]) -> Result<BuilderWithBits<512>, FastbloomError> {
+        BloomFilter::from_bytes(bytes).ok_or(FastbloomError::InvalidBytes { len: bytes.len() })
+    }
+
+    /// Creates an empty `BloomFilter` with at least `num_bits` bits and `num_hashes` hashes per item, with a random seed.
+    ///
+    /// This is a shorthand for [`BloomFilter::with_num_bits(num_bits).hashes(num_hashes)`](BuilderWithBits::hashes).
+    ///
+    /// # Panics
+    /// Panics if the number of bits, `num_bits`, is 0.
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let mut bloom = BloomFilter::new(1024, 4);
+    /// bloom.insert(&1);
+    /// assert!(bloom.contains(&1));
+    /// ```
+    pub fn new(num_bits: usize, num_hashes: u32) -> Self {
+        BloomFilter::with_num_bits(num_bits).hashes(num_hashes)
+    }
+
+    /// Creates an empty `BloomFilter` with at least `num_bits` bits, `num_hashes` hashes per item, and `seed`.
+    ///
+    /// This is a shorthand for [`BloomFilter::with_num_bits(num_bits).seed(seed).hashes(num_hashes)`](BuilderWithBits::hashes).
+    ///
+    /// # Panics
+    /// Panics if the number of bits, `num_bits`, is 0.
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::new_seeded(1024, 4, &42);
+    /// assert_eq!(bloom, BloomFilter::with_num_bits(1024).seed(&42).hashes(4));
+    /// ```
+    pub fn new_seeded(num_bits: usize, num_hashes: u32, seed: &u128) -> Self {
+        BloomFilter::with_num_bits(num_bits)
+            .seed(seed)
+            .hashes(num_hashes)
+    }
+
+    /// Constructs a `BloomFilter` containing all values in `items`, sized for `items.len()` to meet the false positive rate `fp`.
+    ///
+    /// This is a shorthand for [`BloomFilter::with_false_pos(fp).items(items)`](BuilderWithFalsePositiveRate::items).
+    ///
+    /// # Panics
+    /// Panics if the false positive rate, `fp`, is 0.
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::BloomFilter;
+    /// use std::collections::HashSet;
+    ///
+    /// let set: HashSet<u64> = (0..1000).collect();
+    /// let bloom = BloomFilter::from_collection(&set, 0.001);
+    /// assert!(set.iter().all(|x| bloom.contains(x)));
+    /// ```
+    pub fn from_collection<I: IntoIterator<IntoIter = impl ExactSizeIterator<Item = impl Hash>>>(
+        items: I,
+        fp: f64,
+    ) -> Self {
+        BloomFilter::with_false_pos(fp).items(items)
+    }
 }
 
+/// A read-only [`BloomFilter`] borrowing its bits, e.g. from a zero-copy frame.
+///
+/// Constructed via [`BloomFilter::from_slice`]. Membership checks are the same as for an owned [`BloomFilter`]
+/// with the same bits, block size, number of hashes, and hasher, but items cannot be inserted.
+pub type BloomFilterRef<'a, const BLOCK_SIZE_BITS: usize = 512, S = DefaultHasher> =
+    BloomFilter<BLOCK_SIZE_BITS, S, &'a [u64]>;
+
+/// A read-only [`BloomFilter`] whose bits are shared behind an [`Arc`].
+///
+/// Constructed via [`BloomFilter::into_shared`]. Cloning only increments a reference count instead of copying the bits,
+/// so one large filter can be cheaply handed to many threads or tasks.
+pub type SharedBloomFilter<const BLOCK_SIZE_BITS: usize = 512, S = DefaultHasher> =
+    BloomFilter<BLOCK_SIZE_BITS, S, Arc<[u64]>>;
+
+/// A [`BloomFilter`] that borrows its bits until the first insert, when they are copied.
+///
+/// Useful when a filter is usually only read, e.g. from a config blob, but occasionally updated.
+///
+/// # Examples
+/// ```
+/// use fastbloom::{BloomFilter, CowBloomFilter};
+///
+/// let orig = BloomFilter::with_false_pos(0.001).seed(&42).items([1, 2]);
+/// let blob: &[u64] = orig.as_slice();
+/// let mut filter: CowBloomFilter = BloomFilter::from_slice(blob)
+///     .seed(&42)
+///     .hashes(orig.num_hashes())
+///     .into_storage();
+/// assert!(filter.contains(&1));
+///
+/// filter.insert(&3); // copies the bits
+/// assert!(filter.contains(&3));
+/// assert!(!orig.contains(&3));
+/// ```
+pub type CowBloomFilter<'a, const BLOCK_SIZE_BITS: usize = 512, S = DefaultHasher> =
+    BloomFilter<BLOCK_SIZE_BITS, S, Cow<'a, [u64]>>;
+
+/// A [`BloomFilter`] whose bits start at a cache line boundary, so each block is within one cache line.
+///
+/// Constructed via [`BuilderWithBits::cache_aligned`], or from any filter with
+/// [`into_storage`](BloomFilter::into_storage). See [`AlignedBits`].
+///
+/// # Examples
+/// ```
+/// use fastbloom::{AlignedBloomFilter, BloomFilter};
+///
+/// let bloom = BloomFilter::with_false_pos(0.001).items([1, 2, 3]);
+/// let aligned: AlignedBloomFilter = bloom.into_storage();
+/// assert!(aligned.contains(&1));
+/// assert_eq!(aligned.as_slice().as_ptr() as usize % 64, 0);
+/// ```
+pub type AlignedBloomFilter<const BLOCK_SIZE_BITS: usize = 512, S = DefaultHasher> =
+    BloomFilter<BLOCK_SIZE_BITS, S, AlignedBits>;
+
 const fn validate_block_size(size: usize) -> usize {
     match size {
         64 | 128 | 256 | 512 => size,
@@ -151,7 +616,9 @@ const fn validate_block_size(size: usize) -> usize {
     }
 }
 
-impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> BloomFilter<BLOCK_SIZE_BITS, S> {
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher, T: BitStorage>
+    BloomFilter<BLOCK_SIZE_BITS, S, T>
+{
     /// Used to grab the last N bits from a hash.
     const BIT_INDEX_MASK: u64 = (validate_block_size(BLOCK_SIZE_BITS) - 1) as u64;
 
@@ -187,73 +654,82 @@ impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> BloomFilter<BLOCK_SIZE_BITS,
         (h & Self::BIT_INDEX_MASK) as usize
     }
 
-    /// Inserts an element into the Bloom filter.
-    ///
-    /// # Returns
+    /// Returns the bit index of the `i`th single bit hash of an item, counting from 1, with enhanced double hashing
+    /// if `ENHANCED`, or otherwise as [`bit_index`](Self::bit_index).
     ///
-    /// `true` if the item may have been previously in the Bloom filter (indicating a potential false positive),
-    /// `false` otherwise.
+    /// `step` starts as `hash2`, and grows by `i` after each hash, so that the `i`th `hash1` is
+    /// `hash1 + i * hash2 + (i³ - i) / 6`. See [`IndexStrategy::EnhancedDoubleHashing`].
+    #[inline(always)]
+    fn bit_index_for<const ENHANCED: bool>(
+        hash1: &mut u64,
+        hash2: u64,
+        step: &mut u64,
+        i: u64,
+    ) -> usize {
+        if ENHANCED {
+            *hash1 = hash1.wrapping_add(*step);
+            *step = step.wrapping_add(i);
+            (*hash1 & Self::BIT_INDEX_MASK) as usize
+        } else {
+            Self::bit_index(hash1, hash2)
+        }
+    }
+
+    /// Returns the second candidate for an item's `h1` in two-choice mode.
+    /// The upper 32 bits, which select the block, differ from `h1`.
+    #[inline]
+    fn alternate_hash(h1: u64, h2: u64) -> u64 {
+        h1 ^ h2.rotate_left(32) ^ (1 << 63)
+    }
+
+    /// Returns the block index for the next hash of an item whose first block index is `first`.
     ///
-    /// # Examples
-    /// ```
-    /// use fastbloom::BloomFilter;
+    /// In two-choice mode all of an item's bits are in its first block, so that the choice of block decides
+    /// how crowded the bits are. Otherwise, each traditional hash may select a different block.
+    #[inline]
+    fn item_block_index(two_choice: bool, num_blocks: usize, first: usize, h1: u64) -> usize {
+        if two_choice {
+            first
+        } else {
+            block_index(num_blocks, h1)
+        }
+    }
+
+    /// As [`item_block_index`](Self::item_block_index), with two-choice mode known at compile time.
+    #[inline(always)]
+    fn item_block_index_for<const TWO_CHOICE: bool>(&self, first: usize, h1: u64) -> usize {
+        if TWO_CHOICE {
+            first
+        } else {
+            block_index(self.num_blocks(), h1)
+        }
+    }
+
+    /// Returns the `h1` an item is, or would be, inserted with.
     ///
-    /// let mut bloom = BloomFilter::with_num_bits(1024).hashes(4);
-    /// bloom.insert(&2);
-    /// assert!(bloom.contains(&2));
-    /// ```
+    /// In two-choice mode this is the candidate already containing the item,
+    /// or otherwise the candidate whose first block has fewer bits set.
     #[inline]
-    pub fn insert(&mut self, val: &(impl Hash + ?Sized)) -> bool {
-        let [mut h1, h2] = get_orginal_hashes(&self.hasher, val);
-        let mut previously_contained = true;
-        for _ in 0..self.num_hashes {
-            // Set bits the traditional way--1 bit per composed hash
-            let index = block_index(self.num_blocks(), h1);
-            let block = &mut self.bits.get_block_mut(index);
-            previously_contained &= BlockedBitVec::<BLOCK_SIZE_BITS>::set_for_block(
-                block,
-                Self::bit_index(&mut h1, h2),
-            );
+    fn candidate(&self, h1: u64, h2: u64) -> u64 {
+        if !self.two_choice {
+            return h1;
         }
-        if let Some(num_rounds) = self.num_rounds {
-            // Set many bits in parallel using a sparse hash
-            let index = block_index(self.num_blocks(), h1);
-            match BLOCK_SIZE_BITS {
-                128 => {
-                    let mut hashes_1 = u64x2::h1(&mut h1, h2);
-                    let hashes_2 = u64x2::h2(h2);
-                    let data = u64x2::sparse_hash(&mut hashes_1, hashes_2, num_rounds);
-                    previously_contained &= u64x2::matches(self.bits.get_block(index), data);
-                    u64x2::set(self.bits.get_block_mut(index), data);
-                }
-                256 => {
-                    let mut hashes_1 = u64x4::h1(&mut h1, h2);
-                    let hashes_2 = u64x4::h2(h2);
-                    let data = u64x4::sparse_hash(&mut hashes_1, hashes_2, num_rounds);
-                    previously_contained &= u64x4::matches(self.bits.get_block(index), data);
-                    u64x4::set(self.bits.get_block_mut(index), data);
-                }
-                512 => {
-                    let hashes_2 = u64x4::h2(h2);
-                    let mut hashes_1 = u64x4::h1(&mut h1, h2);
-                    for i in 0..2 {
-                        let data = u64x4::sparse_hash(&mut hashes_1, hashes_2, num_rounds);
-                        previously_contained &=
-                            u64x4::matches(&self.bits.get_block(index)[4 * i..], data);
-                        u64x4::set(&mut self.bits.get_block_mut(index)[4 * i..], data);
-                    }
-                }
-                _ => {
-                    for i in 0..self.bits.get_block(index).len() {
-                        let data = u64::sparse_hash(&mut h1, h2, num_rounds);
-                        let block = &mut self.bits.get_block_mut(index);
-                        previously_contained &= (block[i] & data) == data;
-                        block[i] |= data;
-                    }
-                }
-            }
+        let alternate = Self::alternate_hash(h1, h2);
+        if self.contains_hashes(h1, h2) {
+            return h1;
+        }
+        if self.contains_hashes(alternate, h2) {
+            return alternate;
+        }
+        let load = |h: u64| -> u32 {
+            let block = self.bits.get_block(block_index(self.num_blocks(), h));
+            block.iter().map(|x| x.count_ones()).sum()
+        };
+        if load(h1) <= load(alternate) {
+            h1
+        } else {
+            alternate
         }
-        previously_contained
     }
 
     /// Checks if an element is possibly in the Bloom filter.
@@ -272,78 +748,1036 @@ impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> BloomFilter<BLOCK_SIZE_BITS,
     /// ```
     #[inline]
     pub fn contains(&self, val: &(impl Hash + ?Sized)) -> bool {
-        let [mut h1, h2] = get_orginal_hashes(&self.hasher, val);
-        (0..self.num_hashes).into_iter().all(|_| {
-            // Set bits the traditional way--1 bit per composed hash
-            let index = block_index(self.num_blocks(), h1);
-            let block = &self.bits.get_block(index);
-            BlockedBitVec::<BLOCK_SIZE_BITS>::check_for_block(block, Self::bit_index(&mut h1, h2))
-        }) && (if let Some(num_rounds) = self.num_rounds {
-            // Set many bits in parallel using a sparse hash
-            let index = block_index(self.num_blocks(), h1);
-            let block = &self.bits.get_block(index);
-            match BLOCK_SIZE_BITS {
-                128 => {
-                    let mut hashes_1 = u64x2::h1(&mut h1, h2);
-                    let hashes_2 = u64x2::h2(h2);
-                    let data = u64x2::sparse_hash(&mut hashes_1, hashes_2, num_rounds);
-                    u64x2::matches(block, data)
-                }
-                256 => {
-                    let mut hashes_1 = u64x4::h1(&mut h1, h2);
-                    let hashes_2 = u64x4::h2(h2);
-                    let data = u64x4::sparse_hash(&mut hashes_1, hashes_2, num_rounds);
-                    u64x4::matches(block, data)
-                }
-                512 => {
-                    let mut hashes_1 = u64x4::h1(&mut h1, h2);
-                    let hashes_2 = u64x4::h2(h2);
-                    (0..2).all(|i| {
-                        let data = u64x4::sparse_hash(&mut hashes_1, hashes_2, num_rounds);
-                        u64x4::matches(&block[4 * i..], data)
-                    })
-                }
-                _ => (0..block.len()).all(|i| {
-                    let data = u64::sparse_hash(&mut h1, h2, num_rounds);
-                    (block[i] & data) == data
-                }),
-            }
-        } else {
-            true
-        })
+        let [h1, h2] = get_orginal_hashes(&self.hasher, val);
+        self.contains_item(h1, h2)
     }
 
-    /// Returns the number of hashes per item.
+    /// Checks if all items in `vals` are possibly in the Bloom filter.
+    ///
+    /// Stops at the first item that is not in the filter, so later items are not hashed.
+    /// Returns `true` if `vals` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_false_pos(0.001).items(["read", "write"]);
+    /// assert!(bloom.contains_all(["read", "write"]));
+    /// assert!(!bloom.contains_all(["read", "delete"]));
+    /// ```
     #[inline]
-    pub fn num_hashes(&self) -> u32 {
-        self.target_hashes as u32
+    pub fn contains_all(&self, vals: impl IntoIterator<Item = impl Hash>) -> bool {
+        vals.into_iter().all(|val| self.contains(&val))
     }
 
-    /// Returns the total number of in-memory bits supporting the Bloom filter.
-    pub fn num_bits(&self) -> usize {
-        self.num_blocks() * BLOCK_SIZE_BITS
+    /// Checks if any item in `vals` is possibly in the Bloom filter.
+    ///
+    /// Stops at the first item that is possibly in the filter, so later items are not hashed.
+    /// Returns `false` if `vals` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_false_pos(0.001).items(["read", "write"]);
+    /// assert!(bloom.contains_any(["delete", "write"]));
+    /// assert!(!bloom.contains_any(["delete", "admin"]));
+    /// ```
+    #[inline]
+    pub fn contains_any(&self, vals: impl IntoIterator<Item = impl Hash>) -> bool {
+        vals.into_iter().any(|val| self.contains(&val))
+    }
+
+    /// Checks if the bytes `key` are possibly in the Bloom filter, as inserted by [`insert_bytes`](BloomFilter::insert_bytes).
+    ///
+    /// Unlike [`contains`](Self::contains), `key` is hashed as a single write of its bytes, independently of [`Hash`],
+    /// following the procedure in `HASHING.md`, so other languages can check filters built here and vice versa.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let mut bloom = BloomFilter::with_num_bits(1024).seed(&1).hashes(4);
+    /// bloom.insert_bytes(b"alice");
+    /// assert!(bloom.contains_bytes(b"alice"));
+    /// ```
+    #[inline]
+    pub fn contains_bytes(&self, key: &[u8]) -> bool {
+        let [h1, h2] = get_byte_hashes(&self.hasher, key);
+        self.contains_item(h1, h2)
+    }
+
+    /// Checks if an item with the 64 bit hash `hash` is possibly in the Bloom filter, as inserted by [`insert_hash`](BloomFilter::insert_hash).
+    ///
+    /// `hash` is used in place of the filter's hasher, so keys that are already hashed are not hashed again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let mut bloom = BloomFilter::with_num_bits(1024).hashes(4);
+    /// bloom.insert_hash(0x9e3779b97f4a7c15);
+    /// assert!(bloom.contains_hash(0x9e3779b97f4a7c15));
+    /// ```
+    #[inline]
+    pub fn contains_hash(&self, hash: u64) -> bool {
+        let [h1, h2] = derive_hashes(hash);
+        self.contains_item(h1, h2)
+    }
+
+    /// Checks the bits for the item with hashes `h1` and `h2` in its block, or either of its two blocks in two-choice mode.
+    #[inline]
+    pub(crate) fn contains_item(&self, h1: u64, h2: u64) -> bool {
+        let contains = self.contains_hashes(h1, h2)
+            || (self.two_choice && self.contains_hashes(Self::alternate_hash(h1, h2), h2));
+        self.counters.record_query(contains);
+        contains
+    }
+
+    #[inline]
+    fn contains_hashes(&self, h1: u64, h2: u64) -> bool {
+        use IndexStrategy::{DoubleHashing, EnhancedDoubleHashing};
+        match (self.num_rounds, self.two_choice, self.index_strategy) {
+            (Some(num_rounds), true, DoubleHashing) => {
+                self.contains_hashes_for::<true, true, false>(h1, h2, num_rounds)
+            }
+            (Some(num_rounds), false, DoubleHashing) => {
+                self.contains_hashes_for::<true, false, false>(h1, h2, num_rounds)
+            }
+            (None, true, DoubleHashing) => {
+                self.contains_hashes_for::<false, true, false>(h1, h2, 0)
+            }
+            (None, false, DoubleHashing) => {
+                self.contains_hashes_for::<false, false, false>(h1, h2, 0)
+            }
+            (Some(num_rounds), true, EnhancedDoubleHashing) => {
+                self.contains_hashes_for::<true, true, true>(h1, h2, num_rounds)
+            }
+            (Some(num_rounds), false, EnhancedDoubleHashing) => {
+                self.contains_hashes_for::<true, false, true>(h1, h2, num_rounds)
+            }
+            (None, true, EnhancedDoubleHashing) => {
+                self.contains_hashes_for::<false, true, true>(h1, h2, 0)
+            }
+            (None, false, EnhancedDoubleHashing) => {
+                self.contains_hashes_for::<false, false, true>(h1, h2, 0)
+            }
+        }
+    }
+
+    /// Checks the bits for `h1` and `h2`, with whether there is a sparse hash, two-choice mode, and the index strategy
+    /// resolved by [`contains_hashes`](Self::contains_hashes), so that the hot loop has no branches on them.
+    #[inline(always)]
+    fn contains_hashes_for<const SPARSE: bool, const TWO_CHOICE: bool, const ENHANCED: bool>(
+        &self,
+        mut h1: u64,
+        h2: u64,
+        num_rounds: u64,
+    ) -> bool {
+        let first = block_index(self.num_blocks(), h1);
+        let mut step = h2;
+        (1..=self.num_hashes).all(|i| {
+            // Set bits the traditional way--1 bit per composed hash
+            let index = self.item_block_index_for::<TWO_CHOICE>(first, h1);
+            let block = &self.bits.get_block(index);
+            let bit_index = Self::bit_index_for::<ENHANCED>(&mut h1, h2, &mut step, i);
+            BlockedBitVec::<BLOCK_SIZE_BITS>::check_for_block(block, bit_index)
+        }) && (if SPARSE {
+            // Set many bits in parallel using a sparse hash
+            let index = self.item_block_index_for::<TWO_CHOICE>(first, h1);
+            let block = &self.bits.get_block(index);
+            match BLOCK_SIZE_BITS {
+                128 => {
+                    let mut hashes_1 = u64x2::h1(&mut h1, h2);
+                    let hashes_2 = u64x2::h2(h2);
+                    let data = u64x2::sparse_hash(&mut hashes_1, hashes_2, num_rounds);
+                    u64x2::matches(block, data)
+                }
+                256 => {
+                    let mut hashes_1 = u64x4::h1(&mut h1, h2);
+                    let hashes_2 = u64x4::h2(h2);
+                    let data = u64x4::sparse_hash(&mut hashes_1, hashes_2, num_rounds);
+                    u64x4::matches(block, data)
+                }
+                512 => {
+                    let mut hashes_1 = u64x4::h1(&mut h1, h2);
+                    let hashes_2 = u64x4::h2(h2);
+                    let data = sparse_hash::sparse_hash_512(&mut hashes_1, hashes_2, num_rounds);
+                    simd::contains(block, &data)
+                }
+                _ => (0..block.len()).all(|i| {
+                    let data = u64::sparse_hash(&mut h1, h2, num_rounds);
+                    (block[i] & data) == data
+                }),
+            }
+        } else {
+            true
+        })
+    }
+
+    /// Returns the bits an item sets, as `(u64 index, mask)` pairs into [`as_slice`](Self::as_slice).
+    ///
+    /// The pairs are generated in the same order and from the same hashes as in [`insert`](Self::insert),
+    /// and a `u64` index may appear more than once. In two-choice mode, the pairs are for the candidate containing the item,
+    /// or, if neither does, the candidate it would be inserted into.
+    pub(crate) fn masks(&self, val: &(impl Hash + ?Sized)) -> Vec<(usize, u64)> {
+        let [h1, h2] = get_orginal_hashes(&self.hasher, val);
+        let h1 = self.candidate(h1, h2);
+        Self::hash_masks(
+            self.num_blocks(),
+            self.two_choice,
+            self.index_strategy,
+            self.num_hashes,
+            self.num_rounds,
+            h1,
+            h2,
+        )
+    }
+
+    /// Returns the bits an item with the hashes `h1` and `h2` sets, as in [`masks`](Self::masks),
+    /// in a filter of `num_blocks` blocks with the given hashing parameters.
+    pub(crate) fn hash_masks(
+        num_blocks: usize,
+        two_choice: bool,
+        index_strategy: IndexStrategy,
+        num_hashes: u64,
+        num_rounds: Option<u64>,
+        mut h1: u64,
+        h2: u64,
+    ) -> Vec<(usize, u64)> {
+        let block_len = BLOCK_SIZE_BITS / 64;
+        let first = block_index(num_blocks, h1);
+        let item_block_index = |h1| Self::item_block_index(two_choice, num_blocks, first, h1);
+        let mut masks = Vec::with_capacity(num_hashes as usize + block_len);
+        let mut step = h2;
+        for i in 1..=num_hashes {
+            let index = item_block_index(h1);
+            let bit_index = match index_strategy {
+                IndexStrategy::DoubleHashing => Self::bit_index(&mut h1, h2),
+                IndexStrategy::EnhancedDoubleHashing => {
+                    Self::bit_index_for::<true>(&mut h1, h2, &mut step, i)
+                }
+            };
+            masks.push((
+                index * block_len + (bit_index >> 6),
+                1u64 << (bit_index & 63),
+            ));
+        }
+        if let Some(num_rounds) = num_rounds {
+            let offset = item_block_index(h1) * block_len;
+            let data: Vec<u64> = match BLOCK_SIZE_BITS {
+                128 => {
+                    let mut hashes_1 = u64x2::h1(&mut h1, h2);
+                    let hashes_2 = u64x2::h2(h2);
+                    u64x2::sparse_hash(&mut hashes_1, hashes_2, num_rounds)
+                        .to_array()
+                        .to_vec()
+                }
+                256 => {
+                    let mut hashes_1 = u64x4::h1(&mut h1, h2);
+                    let hashes_2 = u64x4::h2(h2);
+                    u64x4::sparse_hash(&mut hashes_1, hashes_2, num_rounds)
+                        .to_array()
+                        .to_vec()
+                }
+                512 => {
+                    let hashes_2 = u64x4::h2(h2);
+                    let mut hashes_1 = u64x4::h1(&mut h1, h2);
+                    sparse_hash::sparse_hash_512(&mut hashes_1, hashes_2, num_rounds).to_vec()
+                }
+                _ => (0..block_len)
+                    .map(|_| u64::sparse_hash(&mut h1, h2, num_rounds))
+                    .collect(),
+            };
+            masks.extend(data.into_iter().enumerate().map(|(i, d)| (offset + i, d)));
+        }
+        masks
+    }
+
+    /// Returns the number of hashes per item.
+    #[inline]
+    pub fn num_hashes(&self) -> u32 {
+        self.target_hashes as u32
+    }
+
+    /// Returns the number of bits per `u64` that the sparse hash of each item targets, if used.
+    ///
+    /// Depending on [`num_hashes`](Self::num_hashes) and the block size, each item sets some bits as single bits,
+    /// and the rest as one sparse hash of about this many bits per `u64` of its block.
+    /// Filters with the same number of hashes and block size have the same number of rounds, unless built with
+    /// [`BuilderWithBits::hashes_with_rounds`], or with a cap set by [`BuilderWithBits::max_rounds`].
+    #[inline]
+    pub fn num_rounds(&self) -> Option<u64> {
+        self.num_rounds
+    }
+
+    /// Returns how the bit indexes of the single bit hashes of each item are derived.
+    #[inline]
+    pub fn index_strategy(&self) -> IndexStrategy {
+        self.index_strategy
+    }
+
+    /// Returns the number of bits per block, `BLOCK_SIZE_BITS`.
+    #[inline]
+    pub fn block_size_bits(&self) -> usize {
+        BLOCK_SIZE_BITS
+    }
+
+    /// Returns the hasher of this `BloomFilter`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::{BloomFilter, SipHash24};
+    ///
+    /// let bloom = BloomFilter::with_num_bits(1024).hasher(SipHash24::with_key(&[7; 16])).hashes(4);
+    /// let copy = BloomFilter::from_vec(bloom.as_slice().to_vec())
+    ///     .hasher(bloom.hasher().clone())
+    ///     .hashes(bloom.num_hashes());
+    /// assert_eq!(bloom, copy);
+    /// ```
+    #[inline]
+    pub fn hasher(&self) -> &S {
+        &self.hasher
+    }
+
+    /// Returns the total number of in-memory bits supporting the Bloom filter.
+    pub fn num_bits(&self) -> usize {
+        self.num_blocks() * BLOCK_SIZE_BITS
+    }
+
+    /// Returns the total number of in-memory blocks supporting the Bloom filter.
+    /// Each block is `BLOCK_SIZE_BITS` bits.
+    pub fn num_blocks(&self) -> usize {
+        self.bits.num_blocks()
+    }
+
+    /// Returns a `u64` slice of this `BloomFilter`’s contents.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let data = vec![0x517cc1b727220a95; 8];
+    /// let bloom = BloomFilter::<512>::from_vec(data.clone()).hashes(4);
+    /// assert_eq!(bloom.as_slice().to_vec(), data);
+    /// ```
+    #[inline]
+    pub fn as_slice(&self) -> &[u64] {
+        self.bits.as_slice()
+    }
+
+    /// Returns the `u64`s of the `index`th block, `BLOCK_SIZE_BITS / 64` of them, the `index`th chunk of
+    /// [`as_slice`](Self::as_slice).
+    ///
+    /// # Panics
+    /// Panics if `index` is not less than [`num_blocks`](Self::num_blocks).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::from_vec(vec![1, 2, 3, 4]).block_size_128().hashes(4);
+    /// assert_eq!(bloom.get_block(1), &[3, 4]);
+    /// ```
+    #[inline]
+    pub fn get_block(&self, index: usize) -> &[u64] {
+        self.bits.get_block(index)
+    }
+
+    /// Returns `true` if the `index`th bit of the filter is set.
+    ///
+    /// Bits are numbered as in [`as_slice`](Self::as_slice): the `index`th bit is bit `index % 64`,
+    /// from the least significant, of `u64` `index / 64`, so it is in block `index / BLOCK_SIZE_BITS`.
+    ///
+    /// # Panics
+    /// Panics if `index` is not less than [`num_bits`](Self::num_bits).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::from_vec(vec![0b10; 8]).hashes(4);
+    /// assert!(bloom.test_bit(65));
+    /// assert!(!bloom.test_bit(64));
+    /// ```
+    #[inline]
+    pub fn test_bit(&self, index: usize) -> bool {
+        self.bits.test_bit(index)
+    }
+
+    /// Returns the bytes of this `BloomFilter`’s contents, each `u64` of [`as_slice`](Self::as_slice) in little-endian order.
+    ///
+    /// The layout is the same on every target, so the bytes can be written to disk or sent over the network
+    /// and read back with [`from_bytes`](BloomFilter::from_bytes) on a target of any endianness.
+    /// On little-endian targets the bytes are borrowed; on big-endian targets they are converted into a new `Vec`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::from_vec(vec![0x0102030405060708]).block_size_64().hashes(4);
+    /// assert_eq!(*bloom.as_bytes(), [8, 7, 6, 5, 4, 3, 2, 1]);
+    /// ```
+    #[inline]
+    pub fn as_bytes(&self) -> Cow<'_, [u8]> {
+        let bits = self.as_slice();
+        if cfg!(target_endian = "little") {
+            // SAFETY: `bits` is `8 * bits.len()` initialized bytes, and `u8` has no alignment requirement.
+            Cow::Borrowed(unsafe {
+                std::slice::from_raw_parts(bits.as_ptr().cast::<u8>(), std::mem::size_of_val(bits))
+            })
+        } else {
+            Cow::Owned(bits.iter().flat_map(|word| word.to_le_bytes()).collect())
+        }
+    }
+
+    /// Returns a copy of this `BloomFilter` with `factor` times fewer blocks, containing the same items.
+    ///
+    /// Block `i` of the folded filter is the union of blocks `factor * i..factor * (i + 1)`, which is the block
+    /// that items in any of those blocks map to in a filter with `factor` times fewer blocks.
+    /// The folded filter has more bits set per block, so its false positive rate is higher; folding is best suited
+    /// to sparsely filled filters, e.g. to send a compact approximation over the network.
+    ///
+    /// # Panics
+    /// Panics if `factor` is 0 or does not divide the number of blocks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_num_bits(8192).items(0..10);
+    /// let folded = bloom.fold(4);
+    /// assert_eq!(folded.num_bits(), 2048);
+    /// assert!((0..10).all(|x| folded.contains(&x)));
+    /// ```
+    pub fn fold(&self, factor: usize) -> BloomFilter<BLOCK_SIZE_BITS, S>
+    where
+        S: Clone,
+    {
+        assert!(factor > 0);
+        assert_eq!(self.num_blocks() % factor, 0);
+        let block_len = BLOCK_SIZE_BITS / 64;
+        let mut bits = vec![0; self.as_slice().len() / factor];
+        for (i, chunk) in self.as_slice().chunks(block_len * factor).enumerate() {
+            let block = &mut bits[i * block_len..(i + 1) * block_len];
+            for other in chunk.chunks(block_len) {
+                simd::set(block, other);
+            }
+        }
+        BloomFilter {
+            bits: bits.into(),
+            target_hashes: self.target_hashes,
+            num_rounds: self.num_rounds,
+            num_hashes: self.num_hashes,
+            two_choice: self.two_choice,
+            index_strategy: self.index_strategy,
+            prefix: self.prefix,
+            hasher: self.hasher.clone(),
+            counters: AtomicCounters::new(),
+        }
+    }
+
+    /// Returns the number of bytes the bits of this `BloomFilter` have allocated on the heap.
+    ///
+    /// Filters constructed from a builder allocate exactly [`num_bits`](Self::num_bits) bits.
+    /// To also save the capacity a `Vec<u64>` keeps, e.g. for many small long-lived filters,
+    /// convert the storage to a `Box<[u64]>` with [`into_storage`](Self::into_storage).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_num_bits(1024).hashes(4);
+    /// assert_eq!(bloom.heap_bytes(), 1024 / 8);
+    ///
+    /// let boxed = bloom.into_storage::<Box<[u64]>>();
+    /// assert_eq!(boxed.heap_bytes(), 1024 / 8);
+    /// ```
+    #[inline]
+    pub fn heap_bytes(&self) -> usize {
+        self.bits.heap_bytes()
+    }
+
+    /// Returns the number of bytes this `BloomFilter` uses: its [`heap_bytes`](Self::heap_bytes), and the
+    /// `BloomFilter` itself, with its parameters, hasher, and counters, e.g. to charge filters to per-tenant quotas.
+    ///
+    /// The hasher is assumed not to allocate on the heap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_num_bits(1024).hashes(4);
+    /// assert_eq!(
+    ///     bloom.memory_usage(),
+    ///     1024 / 8 + std::mem::size_of::<BloomFilter>()
+    /// );
+    /// ```
+    #[inline]
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>() + self.heap_bytes()
+    }
+
+    /// Consumes this `BloomFilter`, returning its bits as a `Vec<u64>`, laid out as in [`as_slice`](Self::as_slice).
+    ///
+    /// The bits of a `BloomFilter` with the default `Vec<u64>` or a `Box<[u64]>` storage are returned without copying them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_num_bits(1024).seed(&1).items([1, 2]);
+    /// let num_hashes = bloom.num_hashes();
+    /// let bits = bloom.into_vec();
+    /// let bloom = BloomFilter::from_vec(bits).seed(&1).hashes(num_hashes);
+    /// assert!(bloom.contains(&1));
+    /// ```
+    #[inline]
+    pub fn into_vec(self) -> Vec<u64>
+    where
+        T: Into<Vec<u64>>,
+    {
+        self.bits.into_inner().into()
+    }
+
+    /// Converts the storage of the bits, e.g. to a `Box<[u64]>` or an `Arc<[u64]>`, keeping the filter's contents.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    /// use std::sync::Arc;
+    ///
+    /// let bloom = BloomFilter::with_false_pos(0.001).items([1, 2, 3]);
+    /// let shared = bloom.into_storage::<Arc<[u64]>>();
+    /// assert!(shared.contains(&1));
+    /// ```
+    pub fn into_storage<U: BitStorage + From<T>>(self) -> BloomFilter<BLOCK_SIZE_BITS, S, U> {
+        self.map_storage(U::from)
+    }
+
+    /// Writes the union of this filter and `other` into `target`, overwriting its bits, without allocating.
+    ///
+    /// `target` then contains all items of both filters, as if it were a clone of this filter
+    /// [`union`](Self::union)-ed with `other`, e.g. a scratch filter reused by a pipeline that merges repeatedly.
+    /// All three filters must have the same size, number of hashes, and hasher, e.g. be built from the same builder.
+    ///
+    /// # Panics
+    /// Panics if the filters have different numbers of bits or hashes, use different index strategies,
+    /// or only one inserts with two choices.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let empty = BloomFilter::with_num_bits(1024).seed(&1).hashes(4);
+    /// let (mut a, mut b, mut scratch) = (empty.clone(), empty.clone(), empty);
+    /// a.insert(&1);
+    /// b.insert(&2);
+    /// a.union_into(&b, &mut scratch);
+    /// assert!(scratch.contains(&1) && scratch.contains(&2));
+    /// ```
+    pub fn union_into<U: BitStorage, V: BitStorageMut>(
+        &self,
+        other: &BloomFilter<BLOCK_SIZE_BITS, S, U>,
+        target: &mut BloomFilter<BLOCK_SIZE_BITS, S, V>,
+    ) {
+        self.assert_compatible(target);
+        self.union_into_slice(other, target.bits.as_mut_slice());
+    }
+
+    /// Like [`union_into`](Self::union_into), but writes the bits into `out`, laid out as in
+    /// [`as_slice`](Self::as_slice).
+    ///
+    /// # Panics
+    /// Panics if the filters have different numbers of bits or hashes, use different index strategies,
+    /// only one inserts with two choices, or `out` has a different number of `u64`s.
+    pub fn union_into_slice<U: BitStorage>(
+        &self,
+        other: &BloomFilter<BLOCK_SIZE_BITS, S, U>,
+        out: &mut [u64],
+    ) {
+        self.assert_compatible(other);
+        out.copy_from_slice(self.as_slice());
+        simd::union(out, other.as_slice());
+    }
+
+    /// Writes the intersection of this filter and `other` into `target`, overwriting its bits, without allocating.
+    ///
+    /// `target` then contains all items in both filters, as if it were a clone of this filter
+    /// [`intersect`](Self::intersect)-ed with `other`. All three filters must have the same size, number of hashes,
+    /// and hasher.
+    ///
+    /// # Panics
+    /// Panics if the filters have different numbers of bits or hashes, use different index strategies,
+    /// or only one inserts with two choices.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let empty = BloomFilter::with_num_bits(1024).seed(&1).hashes(4);
+    /// let (mut a, mut b, mut scratch) = (empty.clone(), empty.clone(), empty);
+    /// a.extend([1, 2]);
+    /// b.extend([2, 3]);
+    /// a.intersect_into(&b, &mut scratch);
+    /// assert!(scratch.contains(&2));
+    /// ```
+    pub fn intersect_into<U: BitStorage, V: BitStorageMut>(
+        &self,
+        other: &BloomFilter<BLOCK_SIZE_BITS, S, U>,
+        target: &mut BloomFilter<BLOCK_SIZE_BITS, S, V>,
+    ) {
+        self.assert_compatible(target);
+        self.intersect_into_slice(other, target.bits.as_mut_slice());
+    }
+
+    /// Like [`intersect_into`](Self::intersect_into), but writes the bits into `out`, laid out as in
+    /// [`as_slice`](Self::as_slice).
+    ///
+    /// # Panics
+    /// Panics if the filters have different numbers of bits or hashes, use different index strategies,
+    /// only one inserts with two choices, or `out` has a different number of `u64`s.
+    pub fn intersect_into_slice<U: BitStorage>(
+        &self,
+        other: &BloomFilter<BLOCK_SIZE_BITS, S, U>,
+        out: &mut [u64],
+    ) {
+        self.assert_compatible(other);
+        out.copy_from_slice(self.as_slice());
+        simd::intersect(out, other.as_slice());
+    }
+
+    /// Returns `true` if `other` sets the same bits for an item as this filter, given the same hasher,
+    /// so that their bits can be combined: they have the same number of bits, the same single bit hashes and
+    /// sparse hash rounds and [`IndexStrategy`], and both or neither insert into the less occupied of two blocks.
+    ///
+    /// The split of hashes is compared rather than [`num_hashes`](Self::num_hashes), which only approximates
+    /// their total and can be the same for different splits, e.g. with [`hashes_with_rounds`](BuilderWithBits::hashes_with_rounds).
+    pub(crate) fn is_compatible<U: BitStorage>(
+        &self,
+        other: &BloomFilter<BLOCK_SIZE_BITS, S, U>,
+    ) -> bool {
+        self.num_bits() == other.num_bits()
+            && self.num_hashes == other.num_hashes
+            && self.num_rounds == other.num_rounds
+            && self.two_choice == other.two_choice
+            && self.index_strategy == other.index_strategy
+    }
+
+    /// Panics with [`FastbloomError::MismatchedFilters`] unless `other` [`is_compatible`](Self::is_compatible).
+    fn assert_compatible<U: BitStorage>(&self, other: &BloomFilter<BLOCK_SIZE_BITS, S, U>) {
+        assert!(
+            self.is_compatible(other),
+            "{}",
+            FastbloomError::MismatchedFilters
+        );
+    }
+
+    /// Converts the storage of the bits with `f`, which must keep their contents.
+    pub(crate) fn map_storage<U: BitStorage>(
+        self,
+        f: impl FnOnce(T) -> U,
+    ) -> BloomFilter<BLOCK_SIZE_BITS, S, U> {
+        BloomFilter {
+            bits: self.bits.map_storage(f),
+            target_hashes: self.target_hashes,
+            num_rounds: self.num_rounds,
+            num_hashes: self.num_hashes,
+            two_choice: self.two_choice,
+            index_strategy: self.index_strategy,
+            prefix: self.prefix,
+            hasher: self.hasher,
+            counters: self.counters,
+        }
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher, T: BitStorage> BloomFilter<BLOCK_SIZE_BITS, S, T>
+where
+    Arc<[u64]>: From<T>,
+{
+    /// "Freezes" this `BloomFilter` into a [`SharedBloomFilter`], whose clones share the same bits.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let shared = BloomFilter::with_false_pos(0.001).items([1, 2, 3]).into_shared();
+    /// let handles: Vec<_> = (0..4)
+    ///     .map(|_| {
+    ///         let shared = shared.clone();
+    ///         std::thread::spawn(move || assert!(shared.contains(&1)))
+    ///     })
+    ///     .collect();
+    /// for handle in handles {
+    ///     handle.join().unwrap();
+    /// }
+    /// ```
+    pub fn into_shared(self) -> SharedBloomFilter<BLOCK_SIZE_BITS, S> {
+        self.into_storage()
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, T: BitStorage> BloomFilter<BLOCK_SIZE_BITS, DefaultHasher, T> {
+    /// Returns the seed of this `BloomFilter`'s [`DefaultHasher`], as passed to
+    /// [`BuilderWithBits::seed`] or [`BuilderWithFalsePositiveRate::seed`].
+    ///
+    /// Filters built without a seed have a random one, which is returned, so any filter with the default hasher
+    /// can be reconstructed from its bits, number of hashes, and seed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_num_bits(1024).seed(&42).hashes(4);
+    /// assert_eq!(bloom.seed(), 42);
+    /// ```
+    pub fn seed(&self) -> u128 {
+        let (k0, k1) = self.hasher.keys();
+        let mut key = [0; 16];
+        key[..8].copy_from_slice(&k0.to_le_bytes());
+        key[8..].copy_from_slice(&k1.to_le_bytes());
+        u128::from_be_bytes(key)
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher, T: BitStorageMut>
+    BloomFilter<BLOCK_SIZE_BITS, S, T>
+{
+    /// Inserts an element into the Bloom filter.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item may have been previously in the Bloom filter (indicating a potential false positive),
+    /// `false` otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let mut bloom = BloomFilter::with_num_bits(1024).hashes(4);
+    /// bloom.insert(&2);
+    /// assert!(bloom.contains(&2));
+    /// ```
+    #[inline]
+    pub fn insert(&mut self, val: &(impl Hash + ?Sized)) -> bool {
+        let [h1, h2] = get_orginal_hashes(&self.hasher, val);
+        let h1 = self.candidate(h1, h2);
+        self.insert_hashes(h1, h2)
+    }
+
+    /// Inserts the bytes `key` into the Bloom filter.
+    ///
+    /// Unlike [`insert`](Self::insert), `key` is hashed as a single write of its bytes, independently of [`Hash`],
+    /// following the procedure in `HASHING.md`: with the default hasher, SipHash-1-3 of `key` with the filter's keys.
+    /// So Go, Java, or Python implementations of it produce byte-identical filters.
+    /// Items inserted with `insert_bytes` are checked with [`contains_bytes`](BloomFilter::contains_bytes), not [`contains`](BloomFilter::contains).
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item may have been previously in the Bloom filter (indicating a potential false positive),
+    /// `false` otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let mut bloom = BloomFilter::with_num_bits(1024).seed(&1).hashes(4);
+    /// bloom.insert_bytes(b"alice");
+    /// assert!(bloom.contains_bytes(b"alice"));
+    /// ```
+    #[inline]
+    pub fn insert_bytes(&mut self, key: &[u8]) -> bool {
+        let [h1, h2] = get_byte_hashes(&self.hasher, key);
+        let h1 = self.candidate(h1, h2);
+        self.insert_hashes(h1, h2)
+    }
+
+    /// Inserts an item with the 64 bit hash `hash` into the Bloom filter.
+    ///
+    /// Unlike [`insert`](Self::insert), the filter's hasher is not used: `hash` is the item's hash, e.g. computed upstream,
+    /// and the bits are derived from it as in step 2 onwards of `HASHING.md`. So that the false positive rate is as expected,
+    /// `hash` should be a well distributed 64 bit hash; for hashes of fewer bits, use a hasher and [`insert`](Self::insert).
+    /// Items inserted with `insert_hash` are checked with [`contains_hash`](BloomFilter::contains_hash).
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item may have been previously in the Bloom filter (indicating a potential false positive),
+    /// `false` otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let mut bloom = BloomFilter::with_num_bits(1024).hashes(4);
+    /// bloom.insert_hash(0x9e3779b97f4a7c15);
+    /// assert!(bloom.contains_hash(0x9e3779b97f4a7c15));
+    /// ```
+    #[inline]
+    pub fn insert_hash(&mut self, hash: u64) -> bool {
+        let [h1, h2] = derive_hashes(hash);
+        let h1 = self.candidate(h1, h2);
+        self.insert_hashes(h1, h2)
+    }
+
+    #[inline]
+    fn insert_hashes(&mut self, h1: u64, h2: u64) -> bool {
+        self.counters.record_insert();
+        use IndexStrategy::{DoubleHashing, EnhancedDoubleHashing};
+        match (self.num_rounds, self.two_choice, self.index_strategy) {
+            (Some(num_rounds), true, DoubleHashing) => {
+                self.insert_hashes_for::<true, true, false>(h1, h2, num_rounds)
+            }
+            (Some(num_rounds), false, DoubleHashing) => {
+                self.insert_hashes_for::<true, false, false>(h1, h2, num_rounds)
+            }
+            (None, true, DoubleHashing) => self.insert_hashes_for::<false, true, false>(h1, h2, 0),
+            (None, false, DoubleHashing) => {
+                self.insert_hashes_for::<false, false, false>(h1, h2, 0)
+            }
+            (Some(num_rounds), true, EnhancedDoubleHashing) => {
+                self.insert_hashes_for::<true, true, true>(h1, h2, num_rounds)
+            }
+            (Some(num_rounds), false, EnhancedDoubleHashing) => {
+                self.insert_hashes_for::<true, false, true>(h1, h2, num_rounds)
+            }
+            (None, true, EnhancedDoubleHashing) => {
+                self.insert_hashes_for::<false, true, true>(h1, h2, 0)
+            }
+            (None, false, EnhancedDoubleHashing) => {
+                self.insert_hashes_for::<false, false, true>(h1, h2, 0)
+            }
+        }
+    }
+
+    /// Sets the bits for `h1` and `h2`, monomorphized as [`contains_hashes_for`](Self::contains_hashes_for).
+    #[inline(always)]
+    fn insert_hashes_for<const SPARSE: bool, const TWO_CHOICE: bool, const ENHANCED: bool>(
+        &mut self,
+        mut h1: u64,
+        h2: u64,
+        num_rounds: u64,
+    ) -> bool {
+        let first = block_index(self.num_blocks(), h1);
+        let mut previously_contained = true;
+        let mut step = h2;
+        for i in 1..=self.num_hashes {
+            // Set bits the traditional way--1 bit per composed hash
+            let index = self.item_block_index_for::<TWO_CHOICE>(first, h1);
+            let block = &mut self.bits.get_block_mut(index);
+            previously_contained &= BlockedBitVec::<BLOCK_SIZE_BITS>::set_for_block(
+                block,
+                Self::bit_index_for::<ENHANCED>(&mut h1, h2, &mut step, i),
+            );
+        }
+        if SPARSE {
+            // Set many bits in parallel using a sparse hash
+            let index = self.item_block_index_for::<TWO_CHOICE>(first, h1);
+            match BLOCK_SIZE_BITS {
+                128 => {
+                    let mut hashes_1 = u64x2::h1(&mut h1, h2);
+                    let hashes_2 = u64x2::h2(h2);
+                    let data = u64x2::sparse_hash(&mut hashes_1, hashes_2, num_rounds);
+                    previously_contained &= u64x2::matches(self.bits.get_block(index), data);
+                    u64x2::set(self.bits.get_block_mut(index), data);
+                }
+                256 => {
+                    let mut hashes_1 = u64x4::h1(&mut h1, h2);
+                    let hashes_2 = u64x4::h2(h2);
+                    let data = u64x4::sparse_hash(&mut hashes_1, hashes_2, num_rounds);
+                    previously_contained &= u64x4::matches(self.bits.get_block(index), data);
+                    u64x4::set(self.bits.get_block_mut(index), data);
+                }
+                512 => {
+                    let hashes_2 = u64x4::h2(h2);
+                    let mut hashes_1 = u64x4::h1(&mut h1, h2);
+                    let data = sparse_hash::sparse_hash_512(&mut hashes_1, hashes_2, num_rounds);
+                    let block = self.bits.get_block_mut(index);
+                    previously_contained &= simd::contains(block, &data);
+                    simd::set(block, &data);
+                }
+                _ => {
+                    for i in 0..self.bits.get_block(index).len() {
+                        let data = u64::sparse_hash(&mut h1, h2, num_rounds);
+                        let block = &mut self.bits.get_block_mut(index);
+                        previously_contained &= (block[i] & data) == data;
+                        block[i] |= data;
+                    }
+                }
+            }
+        }
+        previously_contained
+    }
+
+    /// Returns a mutable `u64` slice of this `BloomFilter`’s contents, e.g. for custom bulk operations on the bits.
+    ///
+    /// Setting bits keeps all items in the filter, so OR-ing in the bits of a filter with the same
+    /// size, number of hashes, and hasher inserts its items. Clearing bits may remove items, causing false negatives.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let mut bloom = BloomFilter::with_num_bits(1024).seed(&1).hashes(4);
+    /// let mut received = BloomFilter::with_num_bits(1024).seed(&1).hashes(4);
+    /// received.extend([1, 2]);
+    /// for (x, y) in bloom.as_mut_slice().iter_mut().zip(received.as_slice()) {
+    ///     *x |= y;
+    /// }
+    /// assert!(bloom.contains(&1));
+    /// ```
+    #[inline]
+    pub fn as_mut_slice(&mut self) -> &mut [u64] {
+        self.bits.as_mut_slice()
+    }
+
+    /// Returns the `u64`s of the `index`th block, to modify. See [`get_block`](Self::get_block).
+    ///
+    /// # Panics
+    /// Panics if `index` is not less than [`num_blocks`](Self::num_blocks).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let mut bloom = BloomFilter::with_num_bits(1024).seed(&1).hashes(4);
+    /// let mut received = bloom.clone();
+    /// received.insert(&1);
+    /// for i in 0..bloom.num_blocks() {
+    ///     let block = received.get_block(i);
+    ///     if block.iter().any(|&x| x != 0) {
+    ///         bloom.get_block_mut(i).copy_from_slice(block);
+    ///     }
+    /// }
+    /// assert!(bloom.contains(&1));
+    /// ```
+    #[inline]
+    pub fn get_block_mut(&mut self, index: usize) -> &mut [u64] {
+        self.bits.get_block_mut(index)
+    }
+
+    /// Sets the `index`th bit of the filter, numbered as for [`test_bit`](Self::test_bit).
+    ///
+    /// # Returns
+    ///
+    /// `true` if the bit was already set, `false` otherwise.
+    ///
+    /// # Panics
+    /// Panics if `index` is not less than [`num_bits`](Self::num_bits).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let mut bloom = BloomFilter::with_num_bits(1024).hashes(4);
+    /// assert!(!bloom.set_bit(700));
+    /// assert!(bloom.set_bit(700));
+    /// assert!(bloom.test_bit(700));
+    /// assert_eq!(bloom.get_block(1)[2], 1 << 60);
+    /// ```
+    #[inline]
+    pub fn set_bit(&mut self, index: usize) -> bool {
+        self.bits.set_bit(index)
+    }
+
+    /// Adds all items of `other` to this filter, by setting the bits of `other` in this filter.
+    ///
+    /// The bits are OR-ed a vector at a time, e.g. a 512 bit block with two 256 bit ORs.
+    /// `other` must have the same size, number of hashes, and hasher as this filter, e.g. be built from the same builder.
+    ///
+    /// # Panics
+    /// Panics if the filters have different numbers of bits or hashes, use different index strategies,
+    /// or only one inserts with two choices.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let mut bloom = BloomFilter::with_num_bits(1024).seed(&1).hashes(4);
+    /// let mut other = bloom.clone();
+    /// bloom.insert(&1);
+    /// other.insert(&2);
+    /// bloom.union(&other);
+    /// assert!(bloom.contains(&1) && bloom.contains(&2));
+    /// ```
+    pub fn union<U: BitStorage>(&mut self, other: &BloomFilter<BLOCK_SIZE_BITS, S, U>) {
+        self.assert_compatible(other);
+        self.bits.union(&other.bits);
     }
 
-    /// Returns the total number of in-memory blocks supporting the Bloom filter.
-    /// Each block is `BLOCK_SIZE_BITS` bits.
-    pub fn num_blocks(&self) -> usize {
-        self.bits.num_blocks()
+    /// Like [`union`](Self::union), but returns an error instead of panicking, leaving this filter unchanged.
+    ///
+    /// # Errors
+    /// Returns [`FastbloomError::MismatchedFilters`] if the filters have different numbers of bits or hashes,
+    /// use different index strategies, or only one inserts with two choices.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::{BloomFilter, FastbloomError};
+    ///
+    /// let mut bloom = BloomFilter::with_num_bits(1024).hashes(4);
+    /// let other = BloomFilter::with_num_bits(2048).hashes(4);
+    /// assert_eq!(bloom.try_union(&other), Err(FastbloomError::MismatchedFilters));
+    /// ```
+    pub fn try_union<U: BitStorage>(
+        &mut self,
+        other: &BloomFilter<BLOCK_SIZE_BITS, S, U>,
+    ) -> Result<(), FastbloomError> {
+        if !self.is_compatible(other) {
+            return Err(FastbloomError::MismatchedFilters);
+        }
+        self.bits.union(&other.bits);
+        Ok(())
     }
 
-    /// Returns a `u64` slice of this `BloomFilter`’s contents.
+    /// Keeps only the bits of this filter that are also set in `other`.
+    ///
+    /// The filter then contains all items in both filters, and possibly items in only one of them:
+    /// its false positive rate is at most that of either filter, but may be higher than that of a filter
+    /// built from the items in both. The bits are AND-ed a vector at a time, as in [`union`](Self::union).
+    ///
+    /// # Panics
+    /// Panics if the filters have different numbers of bits or hashes, use different index strategies,
+    /// or only one inserts with two choices.
     ///
     /// # Examples
     ///
     /// ```
     /// use fastbloom::BloomFilter;
     ///
-    /// let data = vec![0x517cc1b727220a95; 8];
-    /// let bloom = BloomFilter::<512>::from_vec(data.clone()).hashes(4);
-    /// assert_eq!(bloom.as_slice().to_vec(), data);
+    /// let mut bloom = BloomFilter::with_num_bits(1024).seed(&1).hashes(4);
+    /// let mut other = bloom.clone();
+    /// bloom.extend([1, 2]);
+    /// other.extend([2, 3]);
+    /// bloom.intersect(&other);
+    /// assert!(bloom.contains(&2));
     /// ```
-    #[inline]
-    pub fn as_slice(&self) -> &[u64] {
-        self.bits.as_slice()
+    pub fn intersect<U: BitStorage>(&mut self, other: &BloomFilter<BLOCK_SIZE_BITS, S, U>) {
+        self.assert_compatible(other);
+        self.bits.intersect(&other.bits);
     }
 
     /// Clear all of the bits in the Bloom filter, removing all items.
@@ -353,26 +1787,163 @@ impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> BloomFilter<BLOCK_SIZE_BITS,
     }
 }
 
-impl<T, const BLOCK_SIZE_BITS: usize, S: BuildHasher> Extend<T> for BloomFilter<BLOCK_SIZE_BITS, S>
+impl<V, const BLOCK_SIZE_BITS: usize, S: BuildHasher, T: BitStorageMut> Extend<V>
+    for BloomFilter<BLOCK_SIZE_BITS, S, T>
 where
-    T: Hash,
+    V: Hash,
 {
     #[inline]
-    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+    fn extend<I: IntoIterator<Item = V>>(&mut self, iter: I) {
         for val in iter {
             self.insert(&val);
         }
     }
 }
 
-impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> PartialEq for BloomFilter<BLOCK_SIZE_BITS, S> {
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher, T: BitStorage> PartialEq
+    for BloomFilter<BLOCK_SIZE_BITS, S, T>
+{
     fn eq(&self, other: &Self) -> bool {
         self.bits == other.bits
             && self.num_hashes == other.num_hashes
             && self.num_rounds == other.num_rounds
+            && self.two_choice == other.two_choice
+            && self.index_strategy == other.index_strategy
+            && self.prefix == other.prefix
+    }
+}
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher, T: BitStorage> Eq
+    for BloomFilter<BLOCK_SIZE_BITS, S, T>
+{
+}
+
+/// The number of `u64`s shown by the `Debug` output of a `BloomFilter`.
+const DEBUG_PREVIEW_WORDS: usize = 4;
+
+/// A preview of the first [`DEBUG_PREVIEW_WORDS`] `u64`s of bits, in hex.
+struct BitsPreview<'a>(&'a [u64]);
+
+impl fmt::Debug for BitsPreview<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut list = f.debug_list();
+        for word in self.0.iter().take(DEBUG_PREVIEW_WORDS) {
+            list.entry(&format_args!("{word:#018x}"));
+        }
+        if self.0.len() > DEBUG_PREVIEW_WORDS {
+            list.entry(&format_args!(
+                "... {} more",
+                self.0.len() - DEBUG_PREVIEW_WORDS
+            ));
+        }
+        list.finish()
+    }
+}
+
+/// Shows the filter's parameters, how many bits are set, and only the first few `u64`s of its bits,
+/// so filters of any size can be logged.
+impl<const BLOCK_SIZE_BITS: usize, S: fmt::Debug, T: BitStorage> fmt::Debug
+    for BloomFilter<BLOCK_SIZE_BITS, S, T>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bits = self.bits.as_slice();
+        let num_bits = bits.len() * 64;
+        let bits_set: usize = bits.iter().map(|x| x.count_ones() as usize).sum();
+        f.debug_struct("BloomFilter")
+            .field("block_size_bits", &BLOCK_SIZE_BITS)
+            .field("num_bits", &num_bits)
+            .field("num_hashes", &self.target_hashes)
+            .field("num_rounds", &self.num_rounds)
+            .field("two_choice", &self.two_choice)
+            .field("index_strategy", &self.index_strategy)
+            .field("prefix", &self.prefix)
+            .field("bits_set", &bits_set)
+            .field(
+                "fill",
+                &format_args!("{:.2}%", 100.0 * bits_set as f64 / num_bits as f64),
+            )
+            .field("bits", &BitsPreview(bits))
+            .field("hasher", &self.hasher)
+            .finish()
+    }
+}
+
+/// The false positive rate of filters converted from collections with `From` or collected with `FromIterator`.
+const DEFAULT_FALSE_POS: f64 = 0.01;
+
+/// Constructs a `BloomFilter` containing all items of the set, sized for a false positive rate of 1%.
+/// Use [`BloomFilter::from_collection`] for other rates.
+impl<T: Hash, H> From<&HashSet<T, H>> for BloomFilter {
+    fn from(set: &HashSet<T, H>) -> Self {
+        BloomFilter::from_collection(set, DEFAULT_FALSE_POS)
+    }
+}
+
+/// Constructs a `BloomFilter` containing all items of the set, sized for a false positive rate of 1%.
+/// Use [`BloomFilter::from_collection`] for other rates.
+impl<T: Hash> From<&BTreeSet<T>> for BloomFilter {
+    fn from(set: &BTreeSet<T>) -> Self {
+        BloomFilter::from_collection(set, DEFAULT_FALSE_POS)
+    }
+}
+
+/// Borrows bytes, as from [`BloomFilter::as_bytes`], as the bits of a [`BloomFilterRef`], without copying them.
+///
+/// Borrowed bits can't be padded to a whole number of blocks, unlike with [`BloomFilter::from_bytes`],
+/// so the bytes must be whole blocks of the block size chosen on the builder, which
+/// [`try_hashes`](BuilderWithBits::try_hashes) checks. To read bytes that may be unaligned or not whole blocks,
+/// or on big-endian targets, copy them with [`BloomFilter::from_bytes`] instead.
+///
+/// # Errors
+/// Returns [`FastbloomError::InvalidBytes`] if `bytes` is not a whole, non-zero number of `u64`s,
+/// or [`FastbloomError::UnalignedBytes`] if it is not aligned to 8 bytes or the target is big-endian,
+/// so the bytes are not `u64`s in the right order.
+///
+/// # Examples
+/// ```
+/// use fastbloom::{BloomFilter, BuilderWithBits};
+///
+/// let orig = BloomFilter::with_false_pos(0.001).seed(&42).items([1, 2]);
+/// let bytes = orig.as_bytes(); // e.g. from a memory-mapped file
+/// let view = BuilderWithBits::try_from(&*bytes)
+///     .unwrap()
+///     .seed(&42)
+///     .hashes(orig.num_hashes());
+/// assert!(view.contains(&1));
+/// ```
+impl<'a> TryFrom<&'a [u8]> for BuilderWithBits<512, DefaultHasher, &'a [u64]> {
+    type Error = FastbloomError;
+
+    fn try_from(bytes: &'a [u8]) -> Result<Self, FastbloomError> {
+        if bytes.is_empty() || !bytes.len().is_multiple_of(8) {
+            return Err(FastbloomError::InvalidBytes { len: bytes.len() });
+        }
+        // SAFETY: every bit pattern is a valid `u64`.
+        let (prefix, words, _) = unsafe { bytes.align_to::<u64>() };
+        if !prefix.is_empty() || cfg!(target_endian = "big") {
+            return Err(FastbloomError::UnalignedBytes);
+        }
+        Ok(BloomFilter::from_slice(words))
+    }
+}
+
+/// Collects items into a `BloomFilter`, sized for their number to meet a false positive rate of 1%.
+///
+/// The items are first collected into a `Vec` to count them. To choose the size, false positive rate, or hasher,
+/// use a builder instead, e.g. [`BloomFilter::with_false_pos(fp).items(items)`](BuilderWithFalsePositiveRate::items).
+///
+/// # Examples
+/// ```
+/// use fastbloom::BloomFilter;
+///
+/// let bloom: BloomFilter = ["apple", "banana"].into_iter().collect();
+/// assert!(bloom.contains("apple"));
+/// ```
+impl<T: Hash> FromIterator<T> for BloomFilter {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let items: Vec<T> = iter.into_iter().collect();
+        BloomFilter::from_collection(items, DEFAULT_FALSE_POS)
     }
 }
-impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> Eq for BloomFilter<BLOCK_SIZE_BITS, S> {}
 
 /// The first two hashes of the value, h1 and h2.
 ///
@@ -397,7 +1968,20 @@ pub(crate) fn get_orginal_hashes(
 ) -> [u64; 2] {
     let mut state = hasher.build_hasher();
     val.hash(&mut state);
-    let h1 = state.finish();
+    derive_hashes(state.finish())
+}
+
+/// Returns the two original hashes of the bytes `key`, hashed as a single write independently of [`Hash`].
+#[inline]
+pub(crate) fn get_byte_hashes(hasher: &impl BuildHasher, key: &[u8]) -> [u64; 2] {
+    let mut state = hasher.build_hasher();
+    state.write(key);
+    derive_hashes(state.finish())
+}
+
+/// Derives h1 and h2 from an item's real hash, as described in [`get_orginal_hashes`].
+#[inline]
+fn derive_hashes(h1: u64) -> [u64; 2] {
     let h2 = h1.wrapping_shr(32).wrapping_mul(0x51_7c_c1_b7_27_22_0a_95); // 0xffff_ffff_ffff_ffff / 0x517c_c1b7_2722_0a95 = π
     [h1, h2]
 }
@@ -406,9 +1990,16 @@ pub(crate) fn get_orginal_hashes(
 /// The block index must be in the range `0..self.bits.num_blocks()`.
 /// This implementation is a more performant alternative to `hash % self.bits.num_blocks()`:
 /// <https://lemire.me/blog/2016/06/27/a-fast-alternative-to-the-modulo-reduction/>
+///
+/// The upper 32 bits of the hash only select among 2^32 blocks, so larger filters multiply the whole hash
+/// in 128 bits. Filters of up to 2^32 blocks keep the 32-bit multiply, and so their layout.
 #[inline]
 pub(crate) fn block_index(num_blocks: usize, hash: u64) -> usize {
-    (((hash >> 32).wrapping_mul(num_blocks as u64)) >> 32) as usize
+    if num_blocks as u64 <= 1 << 32 {
+        (((hash >> 32).wrapping_mul(num_blocks as u64)) >> 32) as usize
+    } else {
+        ((hash as u128 * num_blocks as u128) >> 64) as usize
+    }
 }
 
 #[cfg(test)]
@@ -447,16 +2038,28 @@ mod tests {
     }
 
     fn block_counts<const N: usize>(filter: &BloomFilter<N>) -> Vec<u64> {
-        (0..filter.num_blocks())
-            .map(|i| {
-                filter
-                    .bits
-                    .get_block(i)
-                    .iter()
-                    .map(|x| x.count_ones() as u64)
-                    .sum()
-            })
-            .collect()
+        filter.block_popcounts().map(u64::from).collect()
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn block_index_beyond_u32() {
+        let hashes = random_numbers(1000, 7);
+        for num_blocks in [1, 3, 1000, 1 << 32] {
+            for &h in &hashes {
+                let expected = (((h >> 32) * num_blocks as u64) >> 32) as usize;
+                assert_eq!(block_index(num_blocks, h), expected);
+            }
+        }
+        let num_blocks = 3 << 32;
+        assert!(hashes
+            .iter()
+            .any(|&h| block_index(num_blocks, h) >= 1 << 32));
+        assert!(hashes
+            .iter()
+            .all(|&h| block_index(num_blocks, h) < num_blocks));
+        assert_eq!(block_index(num_blocks, u64::MAX), num_blocks - 1);
+        assert_ne!(block_index(num_blocks, 1 << 31), block_index(num_blocks, 0));
     }
 
     #[test]
@@ -482,6 +2085,124 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_to_from_bytes() {
+        let mut b = BloomFilter::with_num_bits(1024).seed(&1).hashes(3);
+        b.extend(random_numbers(100, 1));
+        let bytes = b.as_bytes();
+        assert_eq!(bytes.len(), b.as_slice().len() * 8);
+        for (word, le) in b.as_slice().iter().zip(bytes.chunks_exact(8)) {
+            assert_eq!(word.to_le_bytes(), le);
+        }
+        let b2 = BloomFilter::from_bytes(&bytes).unwrap().seed(&1).hashes(3);
+        assert_eq!(b, b2);
+
+        let b = BloomFilter::from_bytes(&[1, 0, 0, 0, 0, 0, 0, 0x80])
+            .unwrap()
+            .hashes(3);
+        assert_eq!(b.as_slice()[0], 0x8000_0000_0000_0001);
+        assert_eq!(b.num_blocks(), 1);
+        assert!(BloomFilter::from_bytes(&[]).is_none());
+        assert!(BloomFilter::from_bytes(&[0; 9]).is_none());
+    }
+
+    #[test]
+    fn test_byte_hashing_vectors() {
+        // the test vectors of HASHING.md
+        let hasher = DefaultHasher::with_keys(1, 2);
+        assert_eq!(
+            get_byte_hashes(&hasher, b"fastbloom")[0],
+            0x63bc_c1f8_0c74_bfb8
+        );
+
+        let mut b = BloomFilter::new_builder::<64>(256)
+            .hasher(hasher.clone())
+            .hashes(3);
+        assert!(!b.insert_bytes(b"fastbloom"));
+        assert_eq!(
+            b.as_slice(),
+            [0x80_0000_0000, 0x8000, 0, 0x1000_0000_0000_0000]
+        );
+        assert!(b.contains_bytes(b"fastbloom"));
+        assert!(!b.contains(b"fastbloom".as_slice()));
+
+        let mut b = BloomFilter::new_builder::<512>(512)
+            .hasher(hasher)
+            .hashes(100);
+        assert_eq!((b.num_hashes, b.num_rounds), (4, Some(11)));
+        b.insert_bytes(b"fastbloom");
+        assert_eq!(
+            b.as_slice(),
+            [
+                0xa10201070011a205,
+                0x210400000201,
+                0x4018020400813,
+                0x100008800401017,
+                0x69001a005,
+                0x1120183202a0401,
+                0xc1024010620a13,
+                0x1800000718601017
+            ]
+        );
+    }
+
+    #[test]
+    fn test_insert_bytes_two_choice() {
+        let mut b = BloomFilter::with_num_bits(4096)
+            .block_size_64()
+            .seed(&1)
+            .two_choice()
+            .hashes(4);
+        for x in 0..200u32 {
+            b.insert_bytes(&x.to_le_bytes());
+        }
+        assert!((0..200u32).all(|x| b.contains_bytes(&x.to_le_bytes())));
+    }
+
+    #[test]
+    fn test_insert_hash() {
+        let seed = [3; 16];
+        let mut bloom = BloomFilter::with_num_bits(1 << 12)
+            .hasher(DefaultHasher::seeded(&seed))
+            .two_choice()
+            .hashes(5);
+        let hasher = DefaultHasher::seeded(&seed);
+        for x in 0..100u64 {
+            assert!(!bloom.insert_hash(hasher.hash_one(x)));
+        }
+        for x in 0..100u64 {
+            assert!(bloom.contains(&x));
+            assert!(bloom.contains_hash(hasher.hash_one(x)));
+        }
+    }
+
+    #[test]
+    fn contains_all_any() {
+        let bloom = BloomFilter::with_num_bits(4096).seed(&1).items(0..100);
+        assert!(bloom.contains_all(0..100));
+        assert!(bloom.contains_all(std::iter::empty::<u64>()));
+        assert!(!bloom.contains_any(std::iter::empty::<u64>()));
+        assert!(bloom.contains_any([1000, 2000, 50]));
+
+        let mut hashed = 0;
+        let vals = (0..100).chain(1000..2000).inspect(|_| hashed += 1);
+        assert!(!bloom.contains_all(vals));
+        assert!(hashed < 200);
+    }
+
+    #[test]
+    fn direct_constructors() {
+        let bloom = BloomFilter::new(1000, 3);
+        assert_eq!(bloom.num_bits(), 1024);
+        assert_eq!(bloom.num_hashes(), 3);
+        let mut a = BloomFilter::new_seeded(1024, 5, &9);
+        let mut b = BloomFilter::new_seeded(1024, 5, &9);
+        a.extend(0..10);
+        b.extend(0..10);
+        assert_eq!(a, b);
+        assert_eq!(a.seed(), 9);
+    }
+
     #[test]
     fn first_insert_false() {
         let mut filter = BloomFilter::with_num_bits(1202).expected_items(4);
@@ -564,6 +2285,91 @@ mod tests {
         random_inserts_always_contained_::<64>();
     }
 
+    #[test]
+    fn two_choice_inserts_always_contained() {
+        fn two_choice_inserts_always_contained_<const N: usize>() {
+            for mag in 1..5 {
+                let size = 10usize.pow(mag);
+                let sample_vals = random_numbers(size, 42);
+                let mut filter = BloomFilter::new_with_false_pos::<N>(0.01)
+                    .two_choice()
+                    .items(sample_vals.iter());
+                assert!(sample_vals.iter().all(|x| filter.contains(x)));
+                assert!(sample_vals.iter().all(|x| filter.insert(x)));
+            }
+        }
+        two_choice_inserts_always_contained_::<512>();
+        two_choice_inserts_always_contained_::<256>();
+        two_choice_inserts_always_contained_::<128>();
+        two_choice_inserts_always_contained_::<64>();
+    }
+
+    #[test]
+    fn two_choice_evens_block_load() {
+        fn variance(counts: &[u64]) -> f64 {
+            let mean = counts.iter().sum::<u64>() as f64 / counts.len() as f64;
+            counts
+                .iter()
+                .map(|&x| (x as f64 - mean) * (x as f64 - mean))
+                .sum::<f64>()
+                / counts.len() as f64
+        }
+        let sample_vals = random_numbers(10_000, 42);
+        let one = BloomFilter::with_false_pos(0.01)
+            .block_size_64()
+            .seed(&42)
+            .items(sample_vals.iter());
+        let two = BloomFilter::with_false_pos(0.01)
+            .block_size_64()
+            .seed(&42)
+            .two_choice()
+            .items(sample_vals.iter());
+        assert!(variance(&block_counts(&two)) < variance(&block_counts(&one)));
+    }
+
+    #[test]
+    fn enhanced_double_hashing() {
+        fn enhanced_double_hashing_<const N: usize>() {
+            let sample_vals = random_numbers(10_000, 42);
+            let control: HashSet<u64> = sample_vals.iter().copied().collect();
+            let anti_vals = random_numbers(100_000, 3);
+            let filter = |two_choice: bool, strategy: IndexStrategy| {
+                let mut builder = BloomFilter::new_with_false_pos::<N>(0.01)
+                    .seed(&42)
+                    .index_strategy(strategy);
+                if two_choice {
+                    builder = builder.two_choice();
+                }
+                builder.items(sample_vals.iter())
+            };
+            for two_choice in [false, true] {
+                let mut enhanced = filter(two_choice, IndexStrategy::EnhancedDoubleHashing);
+                assert_eq!(
+                    enhanced.index_strategy(),
+                    IndexStrategy::EnhancedDoubleHashing
+                );
+                assert!(sample_vals.iter().all(|x| enhanced.contains(x)));
+                assert!(sample_vals.iter().all(|x| enhanced.insert(x)));
+
+                let plain = filter(two_choice, IndexStrategy::DoubleHashing);
+                assert_ne!(enhanced, plain);
+                let fp = false_pos_rate_with_vals(&enhanced, &control, &anti_vals);
+                let plain_fp = false_pos_rate_with_vals(&plain, &control, &anti_vals);
+                assert!(fp < 0.05 && fp < plain_fp * 1.5, "{fp} {plain_fp}");
+
+                let bytes = enhanced.to_bytes_with_params();
+                assert_eq!(
+                    BloomFilter::<N>::from_bytes_with_params(&bytes),
+                    Some(enhanced)
+                );
+            }
+        }
+        enhanced_double_hashing_::<512>();
+        enhanced_double_hashing_::<256>();
+        enhanced_double_hashing_::<128>();
+        enhanced_double_hashing_::<64>();
+    }
+
     #[test]
     fn test_optimal_hashes_is_optimal() {
         fn test_optimal_hashes_is_optimal_<const BLOCK_SIZE_BITS: usize, H: Seeded>() {
@@ -632,15 +2438,7 @@ mod tests {
         control: &HashSet<X>,
         anti_vals: impl IntoIterator<Item = &'a X>,
     ) -> f64 {
-        let mut total = 0;
-        let mut false_positives = 0;
-        for x in anti_vals.into_iter() {
-            if !control.contains(x) {
-                total += 1;
-                false_positives += filter.contains(x) as usize;
-            }
-        }
-        (false_positives as f64) / (total as f64)
+        filter.measure_fp_rate(anti_vals.into_iter().filter(|x| !control.contains(x)))
     }
 
     #[test]
@@ -809,10 +2607,242 @@ mod tests {
         test_hash_integration_::<64, DefaultHasher>(pct);
     }
 
+    #[test]
+    fn raw_block_access() {
+        fn raw_block_access_<const N: usize>() {
+            let mut bloom = BloomFilter::new_builder::<N>(4 * N).seed(&1).hashes(7);
+            bloom.insert(&1);
+            let mut rebuilt = BloomFilter::new_builder::<N>(4 * N).seed(&1).hashes(7);
+            for i in (0..bloom.num_bits()).filter(|&i| bloom.test_bit(i)) {
+                assert!(!rebuilt.set_bit(i));
+            }
+            assert_eq!(rebuilt, bloom);
+            let ones: u32 = (0..bloom.num_blocks())
+                .flat_map(|i| bloom.get_block(i))
+                .map(|x| x.count_ones())
+                .sum();
+            assert_eq!(
+                ones as usize,
+                (0..bloom.num_bits()).filter(|&i| bloom.test_bit(i)).count()
+            );
+            let mut cleared = bloom.clone();
+            for i in 0..cleared.num_blocks() {
+                cleared.get_block_mut(i).fill(0);
+            }
+            assert!(!cleared.contains(&1));
+        }
+        raw_block_access_::<512>();
+        raw_block_access_::<256>();
+        raw_block_access_::<128>();
+        raw_block_access_::<64>();
+    }
+
     #[test]
     fn test_debug() {
         let filter = BloomFilter::with_num_bits(1).hashes(1);
         assert!(!format!("{:?}", filter).is_empty());
+
+        let filter = BloomFilter::with_num_bits(1 << 24).seed(&1).items(0..1000);
+        let debug = format!("{:?}", filter);
+        assert!(debug.len() < 1000, "{debug}");
+        assert!(debug.contains("num_bits: 16777216"));
+        assert!(debug.contains("... 262140 more"));
+    }
+
+    #[test]
+    fn test_into_vec() {
+        let bloom = BloomFilter::with_num_bits(4096).items(0..100);
+        let bits = bloom.as_slice().to_vec();
+        let ptr = bloom.as_slice().as_ptr();
+        let vec = bloom.into_vec();
+        assert_eq!(vec, bits);
+        assert_eq!(vec.as_ptr(), ptr);
+
+        let borrowed = BloomFilter::from_slice(&bits).hashes(3);
+        assert_eq!(borrowed.into_vec(), bits);
+    }
+
+    #[test]
+    fn union_and_intersect() {
+        fn union_and_intersect_<const N: usize>() {
+            let empty = BloomFilter::new_builder::<N>(1 << 12).seed(&7).hashes(40);
+            let (mut a, mut b) = (empty.clone(), empty.clone());
+            a.extend(0..200);
+            b.extend(100..300);
+            let mut all = empty.clone();
+            all.extend(0..300);
+
+            let mut union = a.clone();
+            union.union(&b);
+            assert_eq!(union, all);
+
+            let mut intersection = a.clone();
+            intersection.intersect(&b);
+            assert!((100..200).all(|x| intersection.contains(&x)));
+            assert!(intersection
+                .as_slice()
+                .iter()
+                .zip(a.as_slice().iter().zip(b.as_slice()))
+                .all(|(x, (y, z))| *x == y & z));
+
+            let mut scratch = empty.clone();
+            scratch.insert(&1000);
+            a.union_into(&b, &mut scratch);
+            assert_eq!(scratch, union);
+            a.intersect_into(&b, &mut scratch);
+            assert_eq!(scratch, intersection);
+
+            let mut buffer = vec![u64::MAX; a.as_slice().len()];
+            a.union_into_slice(&b, &mut buffer);
+            assert_eq!(buffer, union.as_slice());
+            b.intersect_into_slice(&a, &mut buffer);
+            assert_eq!(buffer, intersection.as_slice());
+            let mut boxed = empty.clone().into_storage::<Box<[u64]>>();
+            b.union_into(&a, &mut boxed);
+            assert_eq!(boxed.as_slice(), union.as_slice());
+        }
+        union_and_intersect_::<64>();
+        union_and_intersect_::<128>();
+        union_and_intersect_::<256>();
+        union_and_intersect_::<512>();
+    }
+
+    #[test]
+    fn rejects_mismatched_hash_splits() {
+        let mut filter = BloomFilter::with_num_bits(1 << 12)
+            .block_size_64()
+            .seed(&1)
+            .hashes(40);
+        let mut capped = BloomFilter::with_num_bits(1 << 12)
+            .block_size_64()
+            .seed(&1)
+            .max_rounds(16)
+            .hashes(40);
+        assert_eq!(filter.num_hashes(), capped.num_hashes());
+        assert_ne!(filter.num_rounds(), capped.num_rounds());
+        capped.extend(0..100);
+        assert_eq!(
+            filter.try_union(&capped),
+            Err(FastbloomError::MismatchedFilters)
+        );
+
+        let mut same = BloomFilter::with_num_bits(1 << 12)
+            .block_size_64()
+            .seed(&1)
+            .hashes_with_rounds(capped.num_hashes as u32, capped.num_rounds);
+        assert_eq!(same.try_union(&capped), Ok(()));
+        assert!((0..100).all(|x| same.contains(&x)));
+    }
+
+    #[test]
+    fn rejects_mismatched_filters() {
+        let filter = BloomFilter::with_num_bits(1024).seed(&1).hashes(4);
+        let others = [
+            BloomFilter::with_num_bits(2048).seed(&1).hashes(4),
+            BloomFilter::with_num_bits(1024).seed(&1).hashes(5),
+            BloomFilter::with_num_bits(1024)
+                .seed(&1)
+                .two_choice()
+                .hashes(4),
+            BloomFilter::with_num_bits(1024)
+                .seed(&1)
+                .index_strategy(IndexStrategy::EnhancedDoubleHashing)
+                .hashes(4),
+        ];
+        for mut other in others {
+            assert!(!filter.is_compatible(&other));
+            other.insert(&1);
+            let mut merged = filter.clone();
+            assert_eq!(
+                merged.try_union(&other),
+                Err(FastbloomError::MismatchedFilters)
+            );
+            assert_eq!(merged, filter);
+            let result = std::panic::catch_unwind(|| filter.clone().union(&other));
+            assert!(result.is_err());
+            let result = std::panic::catch_unwind(|| filter.clone().intersect(&other));
+            assert!(result.is_err());
+        }
+    }
+
+    #[test]
+    fn test_try_from_bytes() {
+        let orig = BloomFilter::with_num_bits(1024).seed(&1).items(0..10);
+        let bytes = orig.as_bytes();
+        let view = BuilderWithBits::try_from(&*bytes)
+            .unwrap()
+            .seed(&1)
+            .hashes(orig.num_hashes());
+        assert!((0..10).all(|x| view.contains(&x)));
+        assert_eq!(view.as_slice().as_ptr(), orig.as_slice().as_ptr());
+
+        let mut unaligned = vec![0u64; 17];
+        let unaligned = &mut words_as_bytes_mut(&mut unaligned)[1..129];
+        unaligned.copy_from_slice(&bytes);
+        assert_eq!(
+            BuilderWithBits::try_from(&*unaligned).unwrap_err(),
+            FastbloomError::UnalignedBytes
+        );
+        assert_eq!(
+            BuilderWithBits::try_from(&bytes[..12]).unwrap_err(),
+            FastbloomError::InvalidBytes { len: 12 }
+        );
+        assert_eq!(
+            BuilderWithBits::try_from(&[][..]).unwrap_err(),
+            FastbloomError::InvalidBytes { len: 0 }
+        );
+
+        // whole blocks are checked for the chosen block size
+        let mut words = [0u64; 9];
+        let words = words_as_bytes_mut(&mut words);
+        let builder = BuilderWithBits::try_from(&words[..8]).unwrap();
+        assert_eq!(
+            builder.clone().try_hashes(3).unwrap_err(),
+            FastbloomError::NotWholeBlocks {
+                num_u64s: 1,
+                block_size_bits: 512
+            }
+        );
+        assert!(builder.block_size_64().try_hashes(3).is_ok());
+        let builder = BuilderWithBits::try_from(&words[..72]).unwrap();
+        assert!(builder.clone().try_hashes(3).is_err());
+        assert!(builder.block_size_64().try_hashes(3).is_ok());
+        assert!(BuilderWithBits::try_from(&words[..64])
+            .unwrap()
+            .try_hashes(3)
+            .is_ok());
+    }
+
+    /// Returns the bytes of `words`.
+    fn words_as_bytes_mut(words: &mut [u64]) -> &mut [u8] {
+        let len = std::mem::size_of_val(words);
+        // SAFETY: `words` is `len` initialized bytes, and `u8` has no alignment requirement.
+        unsafe { std::slice::from_raw_parts_mut(words.as_mut_ptr().cast::<u8>(), len) }
+    }
+
+    #[test]
+    fn test_config_accessors() {
+        let bloom = BloomFilter::with_num_bits(1024)
+            .block_size_256()
+            .seed(&0x0123_4567_89ab_cdef_fedc_ba98_7654_3210)
+            .hashes(20);
+        assert_eq!(bloom.seed(), 0x0123_4567_89ab_cdef_fedc_ba98_7654_3210);
+        assert_eq!(bloom.block_size_bits(), 256);
+        assert_eq!(
+            BloomFilter::with_num_bits(1024).hashes(100).num_rounds(),
+            Some(11)
+        );
+        assert_eq!(
+            BloomFilter::with_num_bits(1024).hashes(1).num_rounds(),
+            None
+        );
+
+        let random = BloomFilter::with_num_bits(1024).items(0..10);
+        let copy = BloomFilter::from_vec(random.as_slice().to_vec())
+            .seed(&random.seed())
+            .hashes(random.num_hashes());
+        assert_eq!(random.hasher().keys(), copy.hasher().keys());
+        assert!((0..10).all(|x| copy.contains(&x)));
     }
 
     #[test]
@@ -821,6 +2851,142 @@ mod tests {
         assert_eq!(filter, filter.clone());
     }
 
+    #[test]
+    fn storage_backends_agree() {
+        let mut filter = BloomFilter::with_false_pos(0.01)
+            .seed(&42)
+            .expected_items(1000);
+        filter.extend(0..1000);
+        let boxed = filter.clone().into_storage::<Box<[u64]>>();
+        let shared = filter.clone().into_storage::<Arc<[u64]>>();
+        for x in 0..2000 {
+            assert_eq!(filter.contains(&x), boxed.contains(&x));
+            assert_eq!(filter.contains(&x), shared.contains(&x));
+        }
+        let mut boxed = boxed;
+        boxed.insert(&2000);
+        assert!(boxed.contains(&2000));
+        assert_eq!(
+            boxed.into_storage::<Vec<u64>>().as_slice().len(),
+            filter.as_slice().len()
+        );
+    }
+
+    #[test]
+    fn filter_ref_matches_owned() {
+        fn filter_ref_matches_owned_<const N: usize>() {
+            let filter = BloomFilter::new_with_false_pos::<N>(0.01)
+                .seed(&42)
+                .items(0..1000);
+            let view: BloomFilterRef<N> = BloomFilter::new_from_slice::<N>(filter.as_slice())
+                .seed(&42)
+                .hashes(filter.num_hashes());
+            assert_eq!(view.num_bits(), filter.num_bits());
+            for x in 0..2000 {
+                assert_eq!(filter.contains(&x), view.contains(&x));
+            }
+        }
+        filter_ref_matches_owned_::<512>();
+        filter_ref_matches_owned_::<256>();
+        filter_ref_matches_owned_::<128>();
+        filter_ref_matches_owned_::<64>();
+    }
+
+    #[test]
+    #[should_panic]
+    fn filter_ref_not_block_aligned() {
+        BloomFilter::from_slice(&[0; 7]).hashes(4);
+    }
+
+    #[test]
+    fn shared_clones_share_bits() {
+        let filter = BloomFilter::with_false_pos(0.01).seed(&42).items(0..1000);
+        let shared = filter.clone().into_shared();
+        let clone = shared.clone();
+        assert_eq!(shared.as_slice().as_ptr(), clone.as_slice().as_ptr());
+        assert_eq!(shared.as_slice(), filter.as_slice());
+        assert!((0..1000).all(|x| clone.contains(&x)));
+    }
+
+    #[test]
+    fn cow_copies_on_insert() {
+        let filter = BloomFilter::with_false_pos(0.01).seed(&42).items(0..1000);
+        let mut cow: CowBloomFilter = BloomFilter::from_slice(filter.as_slice())
+            .seed(&42)
+            .hashes(filter.num_hashes())
+            .into_storage();
+        assert!((0..1000).all(|x| cow.contains(&x)));
+        assert_eq!(cow.as_slice().as_ptr(), filter.as_slice().as_ptr());
+        cow.insert(&1000);
+        assert_ne!(cow.as_slice().as_ptr(), filter.as_slice().as_ptr());
+        assert!((0..=1000).all(|x| cow.contains(&x)));
+    }
+
+    #[test]
+    fn heap_bytes_has_no_slack() {
+        let mut vec = Vec::with_capacity(100);
+        vec.extend([0u64; 9]);
+        let filter = BloomFilter::from_vec(vec).hashes(4);
+        assert_eq!(filter.heap_bytes(), filter.num_bits() / 8);
+        let view = BloomFilter::from_slice(filter.as_slice()).hashes(4);
+        assert_eq!(view.heap_bytes(), 0);
+    }
+
+    #[test]
+    fn fold_keeps_items() {
+        fn fold_keeps_items_<const N: usize>() {
+            let filter = BloomFilter::new_builder::<N>(1 << 16)
+                .seed(&42)
+                .items(0..1000);
+            let false_pos = |f: &BloomFilter<N>| (1000..11_000).filter(|x| f.contains(x)).count();
+            assert_eq!(filter.fold(1), filter);
+            let mut prev = false_pos(&filter);
+            for factor in [2, 4, 8] {
+                let folded = filter.fold(factor);
+                assert_eq!(folded.num_blocks() * factor, filter.num_blocks());
+                assert!((0..1000).all(|x| folded.contains(&x)));
+                let fp = false_pos(&folded);
+                assert!(fp >= prev);
+                prev = fp;
+            }
+        }
+        fold_keeps_items_::<512>();
+        fold_keeps_items_::<256>();
+        fold_keeps_items_::<128>();
+        fold_keeps_items_::<64>();
+    }
+
+    #[test]
+    #[should_panic]
+    fn fold_uneven() {
+        BloomFilter::with_num_bits(3 * 512).hashes(4).fold(2);
+    }
+
+    #[test]
+    fn from_collections() {
+        let hash_set: HashSet<u64> = (0..1000).collect();
+        let btree_set: BTreeSet<u64> = (0..1000).collect();
+        for filter in [BloomFilter::from(&hash_set), BloomFilter::from(&btree_set)] {
+            assert!((0..1000u64).all(|x| filter.contains(&x)));
+            assert_eq!(
+                filter.num_bits(),
+                BloomFilter::with_false_pos(DEFAULT_FALSE_POS)
+                    .expected_items(1000)
+                    .num_bits()
+            );
+        }
+        let collected: BloomFilter = (0..1000u64).filter(|x| x % 2 == 0).collect();
+        assert!((0..1000u64).step_by(2).all(|x| collected.contains(&x)));
+        assert_eq!(
+            collected.num_bits(),
+            BloomFilter::with_false_pos(DEFAULT_FALSE_POS)
+                .expected_items(500)
+                .num_bits()
+        );
+        let filter = BloomFilter::from_collection(vec!["42", "🦀"], 0.001);
+        assert!(filter.contains("42") && filter.contains("🦀"));
+    }
+
     #[test]
     fn eq_constructors_num_bits() {
         assert_eq!(
@@ -897,4 +3063,60 @@ mod tests {
             }
         }
     }
+
+    #[cfg(feature = "borsh")]
+    #[test]
+    fn borsh_round_trip() {
+        let mut bloom = BloomFilter::with_num_bits(1024)
+            .prefix(Prefix::Len(3))
+            .expected_items(10);
+        bloom.extend(0..10);
+        bloom.insert_with_prefix(b"abcdef");
+        let bytes = borsh::to_vec(&bloom).unwrap();
+        let back: BloomFilter = borsh::from_slice(&bytes).unwrap();
+        assert_eq!(back, bloom);
+        assert!((0..10).all(|x| back.contains(&x)));
+        assert!(back.contains(b"abc".as_slice()));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_reads_0_7_filters() {
+        let mut bloom = BloomFilter::with_num_bits(1024).seed(&1).hashes(4);
+        bloom.extend(0..10);
+        // 0.7 had no fields after `hasher`
+        let json = serde_json::json!({
+            "bits": {"bits": bloom.as_slice()},
+            "target_hashes": bloom.target_hashes,
+            "num_rounds": bloom.num_rounds,
+            "num_hashes": bloom.num_hashes,
+            "hasher": bloom.hasher,
+        });
+        let back: BloomFilter = serde_json::from_value(json).unwrap();
+        assert_eq!(back, bloom);
+        assert!((0..10).all(|x| back.contains(&x)));
+
+        // binary formats can't leave out fields, so 0.7's fields are read as a tuple, as in the README
+        let old = (
+            &bloom.bits,
+            bloom.target_hashes,
+            bloom.num_rounds,
+            bloom.num_hashes,
+            &bloom.hasher,
+        );
+        let bytes = postcard::to_allocvec(&old).unwrap();
+        assert!(postcard::from_bytes::<BloomFilter>(&bytes).is_err());
+        let (bits, target_hashes, _, _, hasher): (
+            BlockedBitVec<512>,
+            u64,
+            Option<u64>,
+            u64,
+            DefaultHasher,
+        ) = postcard::from_bytes(&bytes).unwrap();
+        let back = BloomFilter::from_vec(bits.as_slice().to_vec())
+            .hasher(hasher)
+            .hashes(target_hashes as u32);
+        assert_eq!(back, bloom);
+        assert!((0..10).all(|x| back.contains(&x)));
+    }
 }
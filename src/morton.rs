@@ -0,0 +1,496 @@
+use crate::bit_vector::BlockedBitVec;
+use crate::{block_index, get_orginal_hashes, DefaultHasher};
+use std::hash::{BuildHasher, Hash};
+
+/// The number of logical buckets per block.
+const BUCKETS_PER_BLOCK: usize = 64;
+/// The max number of fingerprints per bucket, tracked by a 2 bit counter.
+const BUCKET_CAPACITY: u64 = 3;
+/// The number of 8 bit fingerprints that fit in a block's fingerprint storage array (FSA).
+const FSA_LEN: usize = 46;
+/// The bit offset of the fullness counter array (FCA), 2 bits per bucket, following the FSA.
+const FCA_OFFSET: usize = FSA_LEN * 8;
+/// The bit offset of the overflow tracking array (OTA), following the FCA.
+const OTA_OFFSET: usize = FCA_OFFSET + 2 * BUCKETS_PER_BLOCK;
+/// The number of bits in the OTA.
+const OTA_LEN: usize = 512 - OTA_OFFSET;
+/// The average number of fingerprints per block a new filter is sized for.
+const TARGET_BLOCK_LOAD: usize = 40;
+/// The max number of fingerprints displaced by one insert before the filter is considered full.
+const MAX_KICKS: usize = 500;
+
+/// A Morton filter, a compressed cuckoo filter that supports removing items.
+///
+/// Like a cuckoo filter, `MortonFilter` stores an 8 bit fingerprint of each item in one of two candidate buckets.
+/// Buckets are logical: each 512 bit block packs the fingerprints of its 64 buckets together in a fingerprint storage array,
+/// alongside a 2 bit fullness counter per bucket, so that empty slots take no memory.
+/// Inserts are biased towards the first candidate bucket, and a small overflow tracking array per block records
+/// which buckets had items placed in their second candidate, so most lookups only load a single block.
+///
+/// False positives from [`contains`](Self::contains) are possible (about 1-2% when full), but false negatives are not.
+/// Unlike [`BloomFilter`](crate::BloomFilter), items can be [`remove`](Self::remove)d, and inserts fail once the filter is full.
+///
+/// See <https://www.vldb.org/pvldb/vol11/p1041-breslow.pdf>.
+///
+/// # Examples
+/// ```
+/// use fastbloom::MortonFilter;
+///
+/// let mut filter = MortonFilter::new(1000);
+/// assert!(filter.insert("42"));
+/// assert!(filter.contains("42"));
+/// assert!(filter.remove("42"));
+/// assert!(!filter.contains("42"));
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MortonFilter<S = DefaultHasher> {
+    blocks: BlockedBitVec<512>,
+    /// A fingerprint and its bucket that could not be placed after `MAX_KICKS` displacements
+    victim: Option<(usize, u8)>,
+    /// The number of fingerprints stored, including `victim`
+    len: usize,
+    hasher: S,
+}
+
+impl MortonFilter {
+    /// Creates a new empty `MortonFilter` sized for `expected_items` items, using the default hasher with a random seed.
+    pub fn new(expected_items: usize) -> Self {
+        Self::with_hasher(expected_items, Default::default())
+    }
+
+    /// Sets the seed of the hasher.
+    ///
+    /// # Panics
+    /// Panics if the filter is not empty.
+    pub fn seed(self, seed: &u128) -> Self {
+        assert!(self.is_empty());
+        Self {
+            hasher: DefaultHasher::seeded(&seed.to_be_bytes()),
+            ..self
+        }
+    }
+}
+
+/// Reads `len` bits at bit `offset` of a block. The bits must not cross a `u64` boundary.
+#[inline]
+fn get_bits(block: &[u64], offset: usize, len: usize) -> u64 {
+    (block[offset >> 6] >> (offset & 63)) & ((1 << len) - 1)
+}
+
+/// Writes the lowest `len` bits of `value` at bit `offset` of a block. The bits must not cross a `u64` boundary.
+#[inline]
+fn set_bits(block: &mut [u64], offset: usize, len: usize, value: u64) {
+    let mask = ((1 << len) - 1) << (offset & 63);
+    block[offset >> 6] = (block[offset >> 6] & !mask) | ((value << (offset & 63)) & mask);
+}
+
+/// A decoded block: the fingerprints of each bucket in order, and the count per bucket.
+struct Fsa {
+    fingerprints: [u8; FSA_LEN],
+    counts: [u8; BUCKETS_PER_BLOCK],
+    len: usize,
+}
+
+impl Fsa {
+    fn read(block: &[u64]) -> Self {
+        let mut fingerprints = [0; FSA_LEN];
+        for (i, fp) in fingerprints.iter_mut().enumerate() {
+            *fp = get_bits(block, 8 * i, 8) as u8;
+        }
+        let mut counts = [0; BUCKETS_PER_BLOCK];
+        for (b, count) in counts.iter_mut().enumerate() {
+            *count = get_bits(block, FCA_OFFSET + 2 * b, 2) as u8;
+        }
+        let len = counts.iter().map(|&c| c as usize).sum();
+        Self {
+            fingerprints,
+            counts,
+            len,
+        }
+    }
+
+    fn write(&self, block: &mut [u64]) {
+        for (i, &fp) in self.fingerprints.iter().enumerate() {
+            set_bits(block, 8 * i, 8, fp as u64);
+        }
+        for (b, &count) in self.counts.iter().enumerate() {
+            set_bits(block, FCA_OFFSET + 2 * b, 2, count as u64);
+        }
+    }
+
+    /// Adds `fp` to the end of bucket `b`, shifting the fingerprints of the following buckets.
+    fn insert(&mut self, b: usize, fp: u8) {
+        let end = self.bucket(b).end;
+        self.fingerprints.copy_within(end..FSA_LEN - 1, end + 1);
+        self.fingerprints[end] = fp;
+        self.counts[b] += 1;
+        self.len += 1;
+    }
+
+    /// Removes the fingerprint at `index` of the FSA, which must be in bucket `b`.
+    fn remove(&mut self, b: usize, index: usize) -> u8 {
+        let removed = self.fingerprints[index];
+        self.fingerprints.copy_within(index + 1..FSA_LEN, index);
+        self.fingerprints[FSA_LEN - 1] = 0;
+        self.counts[b] -= 1;
+        self.len -= 1;
+        removed
+    }
+
+    /// The range of the FSA holding the fingerprints of bucket `b`.
+    #[inline]
+    fn bucket(&self, b: usize) -> std::ops::Range<usize> {
+        let start = self.counts[..b].iter().map(|&c| c as usize).sum();
+        start..start + self.counts[b] as usize
+    }
+}
+
+impl<S: BuildHasher> MortonFilter<S> {
+    /// Creates a new empty `MortonFilter` sized for `expected_items` items, using `hasher` to hash items.
+    pub fn with_hasher(expected_items: usize, hasher: S) -> Self {
+        let num_blocks = std::cmp::max(expected_items.div_ceil(TARGET_BLOCK_LOAD), 1);
+        Self {
+            blocks: vec![0; num_blocks * 8].into(),
+            victim: None,
+            len: 0,
+            hasher,
+        }
+    }
+
+    #[inline]
+    fn num_buckets(&self) -> usize {
+        self.blocks.num_blocks() * BUCKETS_PER_BLOCK
+    }
+
+    /// Returns the first candidate bucket and fingerprint of an item.
+    #[inline]
+    fn bucket_and_fingerprint(&self, val: &(impl Hash + ?Sized)) -> (usize, u8) {
+        let [h1, _] = get_orginal_hashes(&self.hasher, val);
+        (block_index(self.num_buckets(), h1), h1 as u8)
+    }
+
+    /// Returns the other candidate bucket of a fingerprint in `bucket`.
+    ///
+    /// The offset is odd, so the parity of the bucket flips, and the offset is applied in the opposite direction
+    /// from the other bucket: `alternate(alternate(b, fp), fp) == b`.
+    /// The offset skips at least one whole block, so with more than one block the two candidates are in different blocks.
+    #[inline]
+    fn alternate(&self, bucket: usize, fp: u8) -> usize {
+        let n = self.num_buckets();
+        let offset = (BUCKETS_PER_BLOCK * (1 + fp as usize) + 1) % n;
+        if bucket & 1 == 0 {
+            (bucket + offset) % n
+        } else {
+            (bucket + n - offset) % n
+        }
+    }
+
+    #[inline]
+    fn has_overflowed(&self, bucket: usize) -> bool {
+        let block = self.blocks.get_block(bucket / BUCKETS_PER_BLOCK);
+        get_bits(block, OTA_OFFSET + bucket % OTA_LEN, 1) == 1
+    }
+
+    #[inline]
+    fn set_overflowed(&mut self, bucket: usize) {
+        let block = self.blocks.get_block_mut(bucket / BUCKETS_PER_BLOCK);
+        set_bits(block, OTA_OFFSET + bucket % OTA_LEN, 1, 1);
+    }
+
+    fn bucket_contains(&self, bucket: usize, fp: u8) -> bool {
+        let fsa = Fsa::read(self.blocks.get_block(bucket / BUCKETS_PER_BLOCK));
+        fsa.fingerprints[fsa.bucket(bucket % BUCKETS_PER_BLOCK)].contains(&fp)
+    }
+
+    /// Adds `fp` to `bucket`, returning `false` if the bucket or its block is full.
+    fn bucket_insert(&mut self, bucket: usize, fp: u8) -> bool {
+        let block = self.blocks.get_block_mut(bucket / BUCKETS_PER_BLOCK);
+        let mut fsa = Fsa::read(block);
+        let b = bucket % BUCKETS_PER_BLOCK;
+        if fsa.counts[b] as u64 >= BUCKET_CAPACITY || fsa.len >= FSA_LEN {
+            return false;
+        }
+        fsa.insert(b, fp);
+        fsa.write(block);
+        true
+    }
+
+    /// Removes `fp` from `bucket`, returning `false` if the bucket does not contain it.
+    fn bucket_remove(&mut self, bucket: usize, fp: u8) -> bool {
+        let block = self.blocks.get_block_mut(bucket / BUCKETS_PER_BLOCK);
+        let mut fsa = Fsa::read(block);
+        let b = bucket % BUCKETS_PER_BLOCK;
+        let range = fsa.bucket(b);
+        match fsa.fingerprints[range.clone()]
+            .iter()
+            .position(|&x| x == fp)
+        {
+            Some(i) => {
+                fsa.remove(b, range.start + i);
+                fsa.write(block);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes a random fingerprint from the block of `bucket` to make room for one more in `bucket`,
+    /// returning the removed fingerprint and the bucket it was in.
+    ///
+    /// The fingerprint is from `bucket` if `bucket` is full, otherwise from any bucket in the block.
+    fn evict(&mut self, bucket: usize, rng: u64) -> (usize, u8) {
+        let block_index = bucket / BUCKETS_PER_BLOCK;
+        let block = self.blocks.get_block_mut(block_index);
+        let mut fsa = Fsa::read(block);
+        let b = bucket % BUCKETS_PER_BLOCK;
+        let (evicted_b, index) = if fsa.counts[b] as u64 >= BUCKET_CAPACITY {
+            (b, fsa.bucket(b).start + (rng % BUCKET_CAPACITY) as usize)
+        } else {
+            let index = (rng % fsa.len as u64) as usize;
+            let mut end = 0;
+            let evicted_b = (0..BUCKETS_PER_BLOCK)
+                .find(|&eb| {
+                    end += fsa.counts[eb] as usize;
+                    index < end
+                })
+                .unwrap();
+            (evicted_b, index)
+        };
+        let evicted = fsa.remove(evicted_b, index);
+        fsa.write(block);
+        (block_index * BUCKETS_PER_BLOCK + evicted_b, evicted)
+    }
+
+    /// Inserts an element into the filter. Inserting an item twice stores it twice, so it needs to be removed twice.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item was inserted, `false` if the filter is full.
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::MortonFilter;
+    ///
+    /// let mut filter = MortonFilter::new(1000);
+    /// assert!(filter.insert(&2));
+    /// assert!(filter.contains(&2));
+    /// ```
+    pub fn insert(&mut self, val: &(impl Hash + ?Sized)) -> bool {
+        if self.victim.is_some() {
+            return false;
+        }
+        let (bucket, fp) = self.bucket_and_fingerprint(val);
+        if self.bucket_insert(bucket, fp) {
+            self.len += 1;
+            return true;
+        }
+        let alternate = self.alternate(bucket, fp);
+        self.set_overflowed(bucket);
+        if self.bucket_insert(alternate, fp) {
+            self.len += 1;
+            return true;
+        }
+
+        // Both candidates are full: displace fingerprints, cuckoo style
+        let (mut bucket, mut fp) = (alternate, fp);
+        let mut rng = (bucket as u64 ^ ((fp as u64) << 32)) | 1;
+        for _ in 0..MAX_KICKS {
+            rng ^= rng << 13;
+            rng ^= rng >> 7;
+            rng ^= rng << 17;
+            let (evicted_bucket, evicted) = self.evict(bucket, rng);
+            let inserted = self.bucket_insert(bucket, fp);
+            debug_assert!(inserted);
+            self.set_overflowed(evicted_bucket);
+            bucket = self.alternate(evicted_bucket, evicted);
+            fp = evicted;
+            if self.bucket_insert(bucket, fp) {
+                self.len += 1;
+                return true;
+            }
+        }
+        self.victim = Some((bucket, fp));
+        self.len += 1;
+        true
+    }
+
+    /// Checks if an element is possibly in the filter.
+    ///
+    /// The second candidate bucket is only checked if an item overflowed from the first.
+    pub fn contains(&self, val: &(impl Hash + ?Sized)) -> bool {
+        let (bucket, fp) = self.bucket_and_fingerprint(val);
+        let alternate = self.alternate(bucket, fp);
+        self.bucket_contains(bucket, fp)
+            || (self.has_overflowed(bucket) && self.bucket_contains(alternate, fp))
+            || self
+                .victim
+                .is_some_and(|(b, v)| v == fp && (b == bucket || b == alternate))
+    }
+
+    /// Removes one copy of an element from the filter.
+    ///
+    /// Only items that were inserted should be removed: removing a false positive removes another item's fingerprint,
+    /// causing a false negative.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item was possibly in the filter and was removed, `false` otherwise.
+    pub fn remove(&mut self, val: &(impl Hash + ?Sized)) -> bool {
+        let (bucket, fp) = self.bucket_and_fingerprint(val);
+        let alternate = self.alternate(bucket, fp);
+        let removed = self.bucket_remove(bucket, fp)
+            || (self.has_overflowed(bucket) && self.bucket_remove(alternate, fp))
+            || match self.victim {
+                Some((b, v)) if v == fp && (b == bucket || b == alternate) => {
+                    self.victim = None;
+                    true
+                }
+                _ => false,
+            };
+        if removed {
+            self.len -= 1;
+            if let Some((b, v)) = self.victim.take() {
+                self.len -= 1;
+                self.insert_fingerprint(b, v);
+            }
+        }
+        removed
+    }
+
+    /// Reinserts a displaced fingerprint into `bucket` or its alternate.
+    fn insert_fingerprint(&mut self, bucket: usize, fp: u8) {
+        if self.bucket_insert(bucket, fp) {
+            self.len += 1;
+            return;
+        }
+        self.set_overflowed(bucket);
+        let alternate = self.alternate(bucket, fp);
+        if self.bucket_insert(alternate, fp) {
+            self.len += 1;
+            return;
+        }
+        self.victim = Some((bucket, fp));
+        self.len += 1;
+    }
+
+    /// Returns the number of items in the filter.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the filter contains no items.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the total number of in-memory blocks supporting the filter. Each block is 512 bits.
+    #[inline]
+    pub fn num_blocks(&self) -> usize {
+        self.blocks.num_blocks()
+    }
+
+    /// Returns the number of bytes this `MortonFilter` uses, including its blocks.
+    /// See [`BloomFilter::memory_usage`](crate::BloomFilter::memory_usage).
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>() + self.blocks.heap_bytes()
+    }
+
+    /// Clear the filter, removing all items.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.blocks.clear();
+        self.victim = None;
+        self.len = 0;
+    }
+}
+
+impl<T, S: BuildHasher> Extend<T> for MortonFilter<S>
+where
+    T: Hash,
+{
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.insert(&val);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alternate_is_involution() {
+        for num_items in [1, 100, 1000, 10_000] {
+            let filter = MortonFilter::new(num_items);
+            for bucket in 0..filter.num_buckets() {
+                for fp in 0..=u8::MAX {
+                    let alternate = filter.alternate(bucket, fp);
+                    assert_eq!(filter.alternate(alternate, fp), bucket);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn inserts_always_contained() {
+        let num_items = 10_000;
+        let mut filter = MortonFilter::new(num_items).seed(&42);
+        for x in 0..num_items {
+            assert!(filter.insert(&x));
+            assert!((0..=x).step_by(97).all(|y| filter.contains(&y)));
+        }
+        assert!((0..num_items).all(|x| filter.contains(&x)));
+        assert_eq!(filter.len(), num_items);
+    }
+
+    #[test]
+    fn false_pos_rate() {
+        let num_items = 10_000;
+        let mut filter = MortonFilter::new(num_items).seed(&42);
+        filter.extend(0..num_items);
+        let false_pos = (num_items..11 * num_items)
+            .filter(|x| filter.contains(x))
+            .count();
+        assert!((false_pos as f64) < 0.03 * (10 * num_items) as f64);
+    }
+
+    #[test]
+    fn remove_only_removes_one() {
+        let num_items = 10_000;
+        let mut filter = MortonFilter::new(num_items).seed(&42);
+        filter.extend(0..num_items);
+        for x in (0..num_items).step_by(2) {
+            assert!(filter.remove(&x));
+        }
+        assert!((1..num_items).step_by(2).all(|x| filter.contains(&x)));
+        let still_contained = (0..num_items)
+            .step_by(2)
+            .filter(|x| filter.contains(x))
+            .count();
+        assert!(still_contained < num_items / 50);
+        assert_eq!(filter.len(), num_items / 2);
+    }
+
+    #[test]
+    fn full_filter_rejects_inserts() {
+        let mut filter = MortonFilter::new(100).seed(&42);
+        let inserted = (0..10_000usize).take_while(|x| filter.insert(x)).count();
+        assert!(inserted >= 100);
+        assert!(inserted < 10_000);
+        assert!((0..inserted).all(|x| filter.contains(&x)));
+    }
+
+    #[test]
+    fn nothing_after_clear() {
+        let mut filter = MortonFilter::new(100).seed(&42);
+        filter.extend(0..100);
+        filter.clear();
+        assert!(!(0..100).any(|x| filter.contains(&x)));
+        assert!(filter.is_empty());
+    }
+}
@@ -0,0 +1,151 @@
+//! MurmurHash3 variants used by the Bloom filter formats of other libraries.
+
+const C1: u64 = 0x87c3_7b91_1142_53d5;
+const C2: u64 = 0x4cf5_ad43_2745_937f;
+
+#[inline]
+fn fmix64(mut k: u64) -> u64 {
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    k ^ (k >> 33)
+}
+
+#[inline]
+fn mix_k1(k1: u64) -> u64 {
+    k1.wrapping_mul(C1).rotate_left(31).wrapping_mul(C2)
+}
+
+#[inline]
+fn mix_k2(k2: u64) -> u64 {
+    k2.wrapping_mul(C2).rotate_left(33).wrapping_mul(C1)
+}
+
+/// Returns the two halves of the 128 bit MurmurHash3 x64 hash of `bytes`, as in Guava's `Hashing.murmur3_128(seed)`.
+pub(crate) fn murmur3_x64_128(bytes: &[u8], seed: u64) -> [u64; 2] {
+    x64_128(bytes, seed, u64::from)
+}
+
+/// Returns the two halves of Cassandra's `MurmurHash.hash3_x64_128`, which sign extends the trailing bytes of `bytes`,
+/// so differs from MurmurHash3 if any of them is 0x80 or more.
+pub(crate) fn cassandra_murmur3_x64_128(bytes: &[u8], seed: u64) -> [u64; 2] {
+    x64_128(bytes, seed, |byte| byte as i8 as u64)
+}
+
+#[inline]
+fn x64_128(bytes: &[u8], seed: u64, tail_byte: impl Fn(u8) -> u64) -> [u64; 2] {
+    let (mut h1, mut h2) = (seed, seed);
+    let mut chunks = bytes.chunks_exact(16);
+    for chunk in &mut chunks {
+        let k1 = u64::from_le_bytes(chunk[..8].try_into().unwrap());
+        let k2 = u64::from_le_bytes(chunk[8..].try_into().unwrap());
+        h1 ^= mix_k1(k1);
+        h1 = h1
+            .rotate_left(27)
+            .wrapping_add(h2)
+            .wrapping_mul(5)
+            .wrapping_add(0x52dc_e729);
+        h2 ^= mix_k2(k2);
+        h2 = h2
+            .rotate_left(31)
+            .wrapping_add(h1)
+            .wrapping_mul(5)
+            .wrapping_add(0x3849_5ab5);
+    }
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        let mut k = [0u64; 2];
+        for (i, &byte) in tail.iter().enumerate() {
+            k[i / 8] ^= tail_byte(byte) << (8 * (i % 8));
+        }
+        h1 ^= mix_k1(k[0]);
+        h2 ^= mix_k2(k[1]);
+    }
+    h1 ^= bytes.len() as u64;
+    h2 ^= bytes.len() as u64;
+    h1 = h1.wrapping_add(h2);
+    h2 = h2.wrapping_add(h1);
+    h1 = fmix64(h1);
+    h2 = fmix64(h2);
+    h1 = h1.wrapping_add(h2);
+    h2 = h2.wrapping_add(h1);
+    [h1, h2]
+}
+
+/// Mixes a 4 byte block of the 32 bit MurmurHash3 x86 hash.
+#[inline]
+pub(crate) fn mix_k1_32(k1: u32) -> u32 {
+    k1.wrapping_mul(0xcc9e_2d51)
+        .rotate_left(15)
+        .wrapping_mul(0x1b87_3593)
+}
+
+/// Combines a mixed 4 byte block into the state of the 32 bit MurmurHash3 x86 hash.
+#[inline]
+pub(crate) fn mix_h1_32(h1: u32, k1: u32) -> u32 {
+    (h1 ^ k1)
+        .rotate_left(13)
+        .wrapping_mul(5)
+        .wrapping_add(0xe654_6b64)
+}
+
+/// Finalizes the 32 bit MurmurHash3 x86 hash of `len` bytes.
+#[inline]
+pub(crate) fn fmix32(mut h1: u32, len: usize) -> u32 {
+    h1 ^= len as u32;
+    h1 ^= h1 >> 16;
+    h1 = h1.wrapping_mul(0x85eb_ca6b);
+    h1 ^= h1 >> 13;
+    h1 = h1.wrapping_mul(0xc2b2_ae35);
+    h1 ^ (h1 >> 16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn x64_128_known_values() {
+        // from Guava's Murmur3Hash128Test
+        for (seed, h1, h2, input) in [
+            (0, 0x6299_4269_3e10_f867, 0x92db_0b82_baeb_5347, "hell"),
+            (1, 0xa78d_dff5_adae_8d10, 0x1289_00ef_2090_0135, "hello"),
+            (2, 0x8a48_6b23_f422_e826, 0xf962_a2c5_8947_765f, "hello "),
+            (3, 0x2ea5_9f46_6f6b_ed8c, 0xc610_990a_cc42_8a17, "hello w"),
+            (4, 0x79f6_305a_386c_572c, 0x4630_5aed_3483_b94e, "hello wo"),
+            (5, 0xc221_9d21_3ec1_f1b5, 0xa1d8_e2e0_a527_85bd, "hello wor"),
+            (
+                0,
+                0xe34b_bc7b_bc07_1b6c,
+                0x7a43_3ca9_c49a_9347,
+                "The quick brown fox jumps over the lazy dog",
+            ),
+        ] {
+            assert_eq!(murmur3_x64_128(input.as_bytes(), seed), [h1, h2], "{input}");
+        }
+        assert_eq!(murmur3_x64_128(b"", 0), [0, 0]);
+    }
+
+    #[test]
+    fn cassandra_x64_128_known_values() {
+        // the Murmur3Partitioner tokens of the int partition keys 1, 2, and 3
+        for (key, token) in [
+            (1i32, -4069959284402364209),
+            (2, -3248873570005575792),
+            (3, 9010454139840013625),
+        ] {
+            let [h1, _] = cassandra_murmur3_x64_128(&key.to_be_bytes(), 0);
+            assert_eq!(h1 as i64, token, "{key}");
+        }
+        let input = b"\x80hello";
+        assert_eq!(
+            cassandra_murmur3_x64_128(&input[1..], 0),
+            murmur3_x64_128(&input[1..], 0)
+        );
+        assert_ne!(
+            cassandra_murmur3_x64_128(input, 0),
+            murmur3_x64_128(input, 0)
+        );
+    }
+}
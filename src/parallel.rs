@@ -0,0 +1,108 @@
+use crate::{BloomFilter, FastbloomError};
+use rayon::prelude::*;
+use std::hash::BuildHasher;
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher + Send> BloomFilter<BLOCK_SIZE_BITS, S> {
+    /// Merges `filters` into one filter containing all of their items, in parallel on the rayon thread pool.
+    ///
+    /// The filters are [`union`](Self::union)-ed pairwise in a tree reduction, so merging `n` filters takes
+    /// `log2(n)` rounds of vector ORs rather than `n - 1` in sequence, and reuses the bits of the filters
+    /// instead of allocating. Their sizes and parameters are checked once, before merging.
+    /// As for `union`, all filters must have the same hasher, e.g. be built from the same builder.
+    ///
+    /// # Returns
+    ///
+    /// The merged filter, or `None` if `filters` is empty.
+    ///
+    /// # Panics
+    /// Panics if the filters have different numbers of bits or hashes, use different index strategies,
+    /// or only some insert with two choices.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let empty = BloomFilter::with_num_bits(1 << 16).seed(&1).hashes(4);
+    /// let partitions = (0..100).map(|p| {
+    ///     let mut filter = empty.clone();
+    ///     filter.extend(p * 100..(p + 1) * 100);
+    ///     filter
+    /// });
+    /// let merged = BloomFilter::merge_all(partitions).unwrap();
+    /// assert!((0..10_000).all(|x| merged.contains(&x)));
+    /// ```
+    pub fn merge_all(filters: impl IntoIterator<Item = Self>) -> Option<Self> {
+        Self::try_merge_all(filters).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Like [`merge_all`](Self::merge_all), but returns an error instead of panicking.
+    ///
+    /// # Errors
+    /// Returns [`FastbloomError::MismatchedFilters`] if the filters have different numbers of bits or hashes,
+    /// use different index strategies, or only some insert with two choices.
+    pub fn try_merge_all(
+        filters: impl IntoIterator<Item = Self>,
+    ) -> Result<Option<Self>, FastbloomError> {
+        let filters: Vec<Self> = filters.into_iter().collect();
+        if let Some(first) = filters.first() {
+            if filters.iter().any(|filter| !filter.is_compatible(first)) {
+                return Err(FastbloomError::MismatchedFilters);
+            }
+        }
+        Ok(filters.into_par_iter().reduce_with(|mut a, b| {
+            a.bits.union(&b.bits);
+            a
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_all() {
+        fn merges_all_<const N: usize>() {
+            let empty = BloomFilter::new_builder::<N>(1 << 12).seed(&7).hashes(8);
+            let filters: Vec<_> = (0..37)
+                .map(|p| {
+                    let mut filter = empty.clone();
+                    filter.extend(p * 10..(p + 1) * 10);
+                    filter
+                })
+                .collect();
+            let mut expected = empty.clone();
+            for filter in &filters {
+                expected.union(filter);
+            }
+            let merged = BloomFilter::merge_all(filters).unwrap();
+            assert_eq!(merged, expected);
+            assert!((0..370).all(|x| merged.contains(&x)));
+        }
+        merges_all_::<64>();
+        merges_all_::<128>();
+        merges_all_::<256>();
+        merges_all_::<512>();
+
+        assert_eq!(BloomFilter::merge_all(Vec::<BloomFilter>::new()), None);
+        let a = BloomFilter::with_num_bits(1024).hashes(4);
+        let b = BloomFilter::with_num_bits(2048).hashes(4);
+        assert_eq!(
+            BloomFilter::try_merge_all([a.clone(), a, b]),
+            Err(FastbloomError::MismatchedFilters)
+        );
+        let c = BloomFilter::with_num_bits(1024).two_choice().hashes(4);
+        assert_eq!(
+            BloomFilter::try_merge_all([c, BloomFilter::with_num_bits(1024).hashes(4)]),
+            Err(FastbloomError::MismatchedFilters)
+        );
+        let d = BloomFilter::with_num_bits(1024)
+            .index_strategy(crate::IndexStrategy::EnhancedDoubleHashing)
+            .hashes(4);
+        assert_eq!(
+            BloomFilter::try_merge_all([BloomFilter::with_num_bits(1024).hashes(4), d]),
+            Err(FastbloomError::MismatchedFilters)
+        );
+    }
+}
@@ -0,0 +1,400 @@
+use crate::block_index;
+use xxhash_rust::xxh64::xxh64;
+
+/// Odd constants multiplied with an item's hash to select its bit in each `u32` of a block, as in the Parquet specification.
+const SALTS: [u32; 8] = [
+    0x47b6_137b,
+    0x4497_4d91,
+    0x8824_ad5b,
+    0xa2b7_289d,
+    0x7054_95c7,
+    0x2df1_424b,
+    0x9efc_4947,
+    0x5c6b_fb31,
+];
+
+/// The minimum and maximum bitset sizes in bytes allowed by the Parquet specification.
+const MIN_NUM_BYTES: usize = 32;
+const MAX_NUM_BYTES: usize = 128 * 1024 * 1024;
+
+/// A Bloom filter in the Apache Parquet split block Bloom filter (SBBF) format.
+///
+/// The bits are 256 bit blocks of eight `u32`s, and each item sets one bit in each `u32` of one block.
+/// Items are hashed with xxHash64 (seed 0) of their Parquet plain encoding: little-endian bytes for
+/// integers and floats, the raw bytes for strings and byte arrays. So a `ParquetBloomFilter` built here can be written
+/// into a Parquet file, and the Bloom filter of a Parquet column chunk can be queried here.
+///
+/// Requires the `parquet` feature.
+///
+/// # Examples
+/// ```
+/// use fastbloom::ParquetBloomFilter;
+///
+/// let mut filter = ParquetBloomFilter::new_with_false_pos(0.01, 1000);
+/// filter.insert("a string column value");
+/// filter.insert(&42i64.to_le_bytes());
+///
+/// // header and bitset, as stored at the column chunk's `bloom_filter_offset`
+/// let bytes = filter.to_bytes();
+/// let filter = ParquetBloomFilter::from_bytes(&bytes).unwrap();
+/// assert!(filter.contains("a string column value"));
+/// assert!(filter.contains(&42i64.to_le_bytes()));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParquetBloomFilter {
+    blocks: Vec<[u32; 8]>,
+}
+
+impl ParquetBloomFilter {
+    /// Creates an empty `ParquetBloomFilter` with a bitset of `num_bytes` bytes,
+    /// rounded up to a power of two between 32 bytes and 128 MiB as the Parquet specification requires.
+    pub fn new_with_num_bytes(num_bytes: usize) -> Self {
+        let num_bytes = num_bytes
+            .clamp(MIN_NUM_BYTES, MAX_NUM_BYTES)
+            .next_power_of_two();
+        Self {
+            blocks: vec![[0; 8]; num_bytes / 32],
+        }
+    }
+
+    /// Creates an empty `ParquetBloomFilter` sized for `expected_num_items` distinct values to meet
+    /// the false positive rate `fp`, the same way Parquet writers size it.
+    ///
+    /// # Panics
+    /// Panics if `fp` is not between 0 and 1.
+    pub fn new_with_false_pos(fp: f64, expected_num_items: usize) -> Self {
+        assert!(fp > 0.0 && fp < 1.0, "invalid false positive rate: {fp}");
+        let num_bits = -8.0 * expected_num_items as f64 / f64::ln(1.0 - fp.powf(1.0 / 8.0));
+        Self::new_with_num_bytes(num_bits as usize / 8)
+    }
+
+    /// Creates a `ParquetBloomFilter` from a bitset, i.e. the bytes following the header.
+    ///
+    /// Returns `None` if the bitset is empty or not a whole number of 32 byte blocks.
+    pub fn from_bitset(bitset: &[u8]) -> Option<Self> {
+        let chunks = bitset.chunks_exact(32);
+        if bitset.is_empty() || !chunks.remainder().is_empty() {
+            return None;
+        }
+        let blocks = chunks
+            .map(|chunk| {
+                let mut block = [0; 8];
+                for (x, word) in block.iter_mut().zip(chunk.chunks_exact(4)) {
+                    *x = u32::from_le_bytes(word.try_into().unwrap());
+                }
+                block
+            })
+            .collect();
+        Some(Self { blocks })
+    }
+
+    /// Returns the bitset of this `ParquetBloomFilter`, as stored in Parquet files after the header.
+    pub fn to_bitset(&self) -> Vec<u8> {
+        self.blocks
+            .iter()
+            .flatten()
+            .flat_map(|x| x.to_le_bytes())
+            .collect()
+    }
+
+    /// Creates a `ParquetBloomFilter` from its serialized form in a Parquet file:
+    /// the Thrift compact encoded `BloomFilterHeader` followed by the bitset.
+    /// Any bytes after the bitset are ignored.
+    ///
+    /// Returns `None` if the header is malformed, if it is not the split block algorithm
+    /// with xxHash and no compression, or if the bitset is truncated.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut header = HeaderReader { bytes, pos: 0 };
+        let num_bytes = header.read()?;
+        let bitset = bytes.get(header.pos..header.pos.checked_add(num_bytes)?)?;
+        Self::from_bitset(bitset)
+    }
+
+    /// Returns the serialized form of this `ParquetBloomFilter` in a Parquet file:
+    /// the Thrift compact encoded `BloomFilterHeader` followed by the bitset.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let num_bytes = (self.blocks.len() * 32) as i32;
+        // field 1: num_bytes, a zigzag varint i32
+        let mut bytes = vec![0x15];
+        let mut zigzag = ((num_bytes << 1) ^ (num_bytes >> 31)) as u32;
+        while zigzag >= 0x80 {
+            bytes.push(zigzag as u8 | 0x80);
+            zigzag >>= 7;
+        }
+        bytes.push(zigzag as u8);
+        // fields 2, 3, and 4: the algorithm, hash, and compression unions, each set to its empty struct field 1,
+        // i.e. BLOCK, XXHASH, and UNCOMPRESSED
+        for _ in 0..3 {
+            bytes.extend_from_slice(&[0x1c, 0x1c, 0x00, 0x00]);
+        }
+        bytes.push(0x00);
+        bytes.extend(self.to_bitset());
+        bytes
+    }
+
+    /// Returns the hash of a plain encoded value, from which its block and bits are derived.
+    #[inline]
+    pub fn hash(val: &(impl AsRef<[u8]> + ?Sized)) -> u64 {
+        xxh64(val.as_ref(), 0)
+    }
+
+    /// Returns the bit to set in each `u32` of the block of `hash`.
+    #[inline]
+    fn masks(hash: u64) -> [u32; 8] {
+        SALTS.map(|salt| 1 << ((hash as u32).wrapping_mul(salt) >> 27))
+    }
+
+    /// Inserts a plain encoded value into the Bloom filter.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item may have been previously in the Bloom filter (indicating a potential false positive),
+    /// `false` otherwise.
+    #[inline]
+    pub fn insert(&mut self, val: &(impl AsRef<[u8]> + ?Sized)) -> bool {
+        self.insert_hash(Self::hash(val))
+    }
+
+    /// Inserts the [`hash`](Self::hash) of a value into the Bloom filter.
+    #[inline]
+    pub fn insert_hash(&mut self, hash: u64) -> bool {
+        let index = block_index(self.blocks.len(), hash);
+        let block = &mut self.blocks[index];
+        let mut previously_contained = true;
+        for (x, mask) in block.iter_mut().zip(Self::masks(hash)) {
+            previously_contained &= *x & mask == mask;
+            *x |= mask;
+        }
+        previously_contained
+    }
+
+    /// Checks if a plain encoded value is possibly in the Bloom filter.
+    #[inline]
+    pub fn contains(&self, val: &(impl AsRef<[u8]> + ?Sized)) -> bool {
+        self.contains_hash(Self::hash(val))
+    }
+
+    /// Checks if the [`hash`](Self::hash) of a value is possibly in the Bloom filter.
+    #[inline]
+    pub fn contains_hash(&self, hash: u64) -> bool {
+        let block = &self.blocks[block_index(self.blocks.len(), hash)];
+        block
+            .iter()
+            .zip(Self::masks(hash))
+            .fold(true, |contained, (x, mask)| contained & (x & mask == mask))
+    }
+
+    /// Returns the number of hashes per item, i.e. the number of `u32`s per block.
+    #[inline]
+    pub fn num_hashes(&self) -> u32 {
+        8
+    }
+
+    /// Returns the total number of in-memory bits supporting the Bloom filter.
+    pub fn num_bits(&self) -> usize {
+        self.num_blocks() * 256
+    }
+
+    /// Returns the total number of in-memory blocks supporting the Bloom filter.
+    /// Each block is 256 bits.
+    pub fn num_blocks(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Clear all of the bits in the Bloom filter, removing all items.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.blocks.fill([0; 8]);
+    }
+}
+
+impl<T: AsRef<[u8]>> Extend<T> for ParquetBloomFilter {
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.insert(&val);
+        }
+    }
+}
+
+/// Reads the fields of a Thrift compact encoded `BloomFilterHeader`.
+struct HeaderReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl HeaderReader<'_> {
+    /// Reads the header, returning its `num_bytes`.
+    fn read(&mut self) -> Option<usize> {
+        let mut num_bytes = None;
+        let mut supported = [false; 3];
+        let mut field_id = 0;
+        while let Some((id, field_type)) = self.field_header(&mut field_id)? {
+            match (id, field_type) {
+                (1, 5) => num_bytes = usize::try_from(self.zigzag()?).ok(),
+                // algorithm, hash, and compression: only the first union field of each is defined
+                (2..=4, 12) => supported[id as usize - 2] = self.union_field_id()? == 1,
+                _ => self.skip(field_type)?,
+            }
+        }
+        match supported {
+            [true, true, true] => num_bytes,
+            _ => None,
+        }
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        let byte = *self.bytes.get(self.pos)?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    fn varint(&mut self) -> Option<u64> {
+        let mut result = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            result |= u64::from(byte & 0x7f) << shift;
+            if byte < 0x80 {
+                return Some(result);
+            }
+        }
+        None
+    }
+
+    fn zigzag(&mut self) -> Option<i64> {
+        let x = self.varint()?;
+        Some((x >> 1) as i64 ^ -((x & 1) as i64))
+    }
+
+    /// Reads the header of the next field of a struct, returning its id and type, or `None` at the end of the struct.
+    fn field_header(&mut self, field_id: &mut i64) -> Option<Option<(i64, u8)>> {
+        let byte = self.byte()?;
+        if byte == 0 {
+            return Some(None);
+        }
+        *field_id = match byte >> 4 {
+            0 => self.zigzag()?,
+            delta => *field_id + i64::from(delta),
+        };
+        Some(Some((*field_id, byte & 0x0f)))
+    }
+
+    /// Reads a union, returning the id of its set field.
+    fn union_field_id(&mut self) -> Option<i64> {
+        let mut field_id = 0;
+        let (id, field_type) = self.field_header(&mut field_id)??;
+        self.skip(field_type)?;
+        self.skip(12)?;
+        Some(id)
+    }
+
+    /// Skips a value of a type that may appear in the header.
+    fn skip(&mut self, field_type: u8) -> Option<()> {
+        match field_type {
+            // booleans are stored in the field type
+            1 | 2 => {}
+            3 => self.pos += 1,
+            4..=6 => {
+                self.varint()?;
+            }
+            7 => self.pos += 8,
+            8 => {
+                let len = usize::try_from(self.varint()?).ok()?;
+                self.pos = self.pos.checked_add(len)?;
+            }
+            12 => {
+                let mut field_id = 0;
+                while let Some((_, field_type)) = self.field_header(&mut field_id)? {
+                    self.skip(field_type)?;
+                }
+            }
+            _ => return None,
+        }
+        (self.pos <= self.bytes.len()).then_some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_is_xxhash64() {
+        assert_eq!(ParquetBloomFilter::hash(""), 0xef46_db37_51d8_e999);
+    }
+
+    #[test]
+    fn reads_parquet_mr_bitset() {
+        // written by parquet-mr for a column of the strings "a0" to "a9"
+        let bitset = [
+            200, 1, 80, 20, 64, 68, 8, 109, 6, 37, 4, 67, 144, 80, 96, 32, 8, 132, 43, 33, 0, 5,
+            99, 65, 2, 0, 224, 44, 64, 78, 96, 4,
+        ];
+        let filter = ParquetBloomFilter::from_bitset(&bitset).unwrap();
+        assert!((0..10).all(|i| filter.contains(&format!("a{i}"))));
+        let mut rebuilt = ParquetBloomFilter::new_with_num_bytes(32);
+        rebuilt.extend((0..10).map(|i| format!("a{i}")));
+        assert_eq!(rebuilt, filter);
+        assert_eq!(rebuilt.to_bitset(), bitset);
+    }
+
+    #[test]
+    fn header_round_trip() {
+        let mut filter = ParquetBloomFilter::new_with_num_bytes(32);
+        filter.insert("42");
+        let bytes = filter.to_bytes();
+        assert_eq!(
+            bytes[..15],
+            [21, 64, 28, 28, 0, 0, 28, 28, 0, 0, 28, 28, 0, 0, 0]
+        );
+        assert_eq!(ParquetBloomFilter::from_bytes(&bytes), Some(filter.clone()));
+
+        let filter = ParquetBloomFilter::new_with_num_bytes(1 << 20);
+        assert_eq!(
+            ParquetBloomFilter::from_bytes(&filter.to_bytes()),
+            Some(filter)
+        );
+    }
+
+    #[test]
+    fn rejects_malformed() {
+        let bytes = ParquetBloomFilter::new_with_num_bytes(64).to_bytes();
+        assert!(ParquetBloomFilter::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+        assert!(ParquetBloomFilter::from_bytes(&bytes[..10]).is_none());
+        // a hash other than XXHASH
+        let mut unsupported = bytes.clone();
+        unsupported[8] = 0x2c;
+        assert!(ParquetBloomFilter::from_bytes(&unsupported).is_none());
+        assert!(ParquetBloomFilter::from_bitset(&[0; 48]).is_none());
+        assert!(ParquetBloomFilter::from_bitset(&[]).is_none());
+    }
+
+    #[test]
+    fn sized_like_parquet_writers() {
+        for (fp, num_items, num_bytes) in [
+            (0.1, 10, 32),
+            (0.01, 1000, 2048),
+            (0.001, 100_000, 256 * 1024),
+            (0.01, usize::MAX, 128 * 1024 * 1024),
+        ] {
+            let filter = ParquetBloomFilter::new_with_false_pos(fp, num_items);
+            assert_eq!(filter.num_bits(), num_bytes * 8, "{fp} {num_items}");
+        }
+        assert_eq!(ParquetBloomFilter::new_with_num_bytes(33).num_blocks(), 2);
+    }
+
+    #[test]
+    fn target_fp_is_accurate() {
+        let num_items = 10_000u64;
+        let mut filter = ParquetBloomFilter::new_with_false_pos(0.01, num_items as usize);
+        filter.extend((0..num_items).map(u64::to_le_bytes));
+        assert!((0..num_items).all(|x| filter.contains(&x.to_le_bytes())));
+        let false_pos = (num_items..101 * num_items)
+            .filter(|x| filter.contains(&x.to_le_bytes()))
+            .count();
+        assert!(false_pos < num_items as usize);
+        filter.clear();
+        assert!(!(0..num_items).any(|x| filter.contains(&x.to_le_bytes())));
+    }
+}
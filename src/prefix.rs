@@ -0,0 +1,150 @@
+use crate::{BitStorage, BitStorageMut, BloomFilter};
+use std::hash::BuildHasher;
+
+/// How the prefix of a byte string key is extracted in prefix mode.
+///
+/// See [`BuilderWithBits::prefix`](crate::BuilderWithBits::prefix) and
+/// [`BuilderWithFalsePositiveRate::prefix`](crate::BuilderWithFalsePositiveRate::prefix).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub enum Prefix {
+    /// The first `n` bytes of the key. Keys shorter than `n` bytes have no prefix.
+    Len(usize),
+    /// The bytes of the key up to and including the first occurrence of the delimiter.
+    /// Keys without the delimiter have no prefix.
+    Delimiter(u8),
+}
+
+impl Prefix {
+    /// Returns the prefix of `key`, or `None` if `key` has no prefix.
+    #[inline]
+    pub fn extract<'a>(&self, key: &'a [u8]) -> Option<&'a [u8]> {
+        match *self {
+            Prefix::Len(n) => key.get(..n),
+            Prefix::Delimiter(d) => key.iter().position(|&b| b == d).map(|i| &key[..=i]),
+        }
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher, T: BitStorageMut>
+    BloomFilter<BLOCK_SIZE_BITS, S, T>
+{
+    /// Inserts a byte string key into the Bloom filter and, in prefix mode, also its prefix.
+    ///
+    /// The key is hashed as a `[u8]`, so it can be checked with `contains(key.as_bytes())` for `str` keys.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the key may have been previously in the Bloom filter (indicating a potential false positive),
+    /// `false` otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::{BloomFilter, Prefix};
+    ///
+    /// let mut bloom = BloomFilter::with_false_pos(0.001)
+    ///     .prefix(Prefix::Delimiter(b':'))
+    ///     .expected_items(100);
+    /// bloom.insert_with_prefix("user:42");
+    /// assert!(bloom.contains("user:42".as_bytes()));
+    /// assert!(bloom.contains_prefix("user:"));
+    /// ```
+    #[inline]
+    pub fn insert_with_prefix(&mut self, key: &(impl AsRef<[u8]> + ?Sized)) -> bool {
+        let key = key.as_ref();
+        if let Some(prefix) = self.prefix.and_then(|p| p.extract(key)) {
+            self.insert(prefix);
+        }
+        self.insert(key)
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher, T: BitStorage>
+    BloomFilter<BLOCK_SIZE_BITS, S, T>
+{
+    /// Checks if any key with the same prefix as `key` was possibly inserted with
+    /// [`insert_with_prefix`](Self::insert_with_prefix).
+    ///
+    /// `key` can be the prefix itself or any key starting with it, e.g. the start key of a range scan.
+    /// If `key` has no prefix, or the filter is not in prefix mode, nothing can be ruled out and `true` is returned.
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::{BloomFilter, Prefix};
+    ///
+    /// let mut bloom = BloomFilter::with_false_pos(0.001)
+    ///     .prefix(Prefix::Len(4))
+    ///     .expected_items(100);
+    /// bloom.insert_with_prefix("2024-01-01");
+    /// assert!(bloom.contains_prefix("2024"));
+    /// assert!(bloom.contains_prefix("2024-12-31"));
+    /// ```
+    #[inline]
+    pub fn contains_prefix(&self, key: &(impl AsRef<[u8]> + ?Sized)) -> bool {
+        match self.prefix.and_then(|p| p.extract(key.as_ref())) {
+            Some(prefix) => self.contains(prefix),
+            None => true,
+        }
+    }
+
+    /// Returns how key prefixes are extracted, or `None` if the filter is not in prefix mode.
+    #[inline]
+    pub fn prefix(&self) -> Option<Prefix> {
+        self.prefix
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract() {
+        assert_eq!(Prefix::Len(2).extract(b"abc"), Some(&b"ab"[..]));
+        assert_eq!(Prefix::Len(3).extract(b"abc"), Some(&b"abc"[..]));
+        assert_eq!(Prefix::Len(4).extract(b"abc"), None);
+        assert_eq!(Prefix::Delimiter(b'/').extract(b"a/b/c"), Some(&b"a/"[..]));
+        assert_eq!(Prefix::Delimiter(b'/').extract(b"abc"), None);
+    }
+
+    #[test]
+    fn prefixes_always_contained() {
+        let mut filter = BloomFilter::with_false_pos(0.001)
+            .seed(&42)
+            .prefix(Prefix::Delimiter(b':'))
+            .expected_items(2000);
+        for x in 0..1000 {
+            filter.insert_with_prefix(&format!("{}:{}", x, x * 7));
+        }
+        for x in 0..1000 {
+            let key = format!("{}:{}", x, x * 7);
+            assert!(filter.contains(key.as_bytes()));
+            assert!(filter.contains_prefix(&key));
+            assert!(filter.contains_prefix(&format!("{}:", x)));
+            assert!(filter.contains_prefix(&format!("{}:seek", x)));
+        }
+        let false_pos = (1000..11_000)
+            .filter(|x| filter.contains_prefix(&format!("{}:", x)))
+            .count();
+        assert!(false_pos < 100);
+    }
+
+    #[test]
+    fn no_prefix_not_ruled_out() {
+        let filter = BloomFilter::with_false_pos(0.001)
+            .prefix(Prefix::Len(8))
+            .expected_items(100);
+        assert!(filter.contains_prefix("short"));
+        assert!(!filter.contains_prefix("long enough"));
+        let filter = BloomFilter::with_false_pos(0.001).expected_items(100);
+        assert!(filter.contains_prefix("long enough"));
+    }
+}
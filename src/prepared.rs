@@ -0,0 +1,107 @@
+use crate::{derive_hashes, get_orginal_hashes, BitStorage, BitStorageMut, BloomFilter};
+use std::hash::{BuildHasher, Hash};
+
+/// An item's hashes, computed once to insert or check it in many `BloomFilter`s.
+///
+/// A `PreparedKey` from [`BloomFilter::prepare`] can be used with any filter with the same hasher (e.g. the same seed),
+/// of any size, block size, or number of hashes. It is checked by [`contains_prepared`](BloomFilter::contains_prepared)
+/// and inserted by [`insert_prepared`](BloomFilter::insert_prepared) exactly as the item is by
+/// [`contains`](BloomFilter::contains) and [`insert`](BloomFilter::insert), but without hashing it again.
+/// With a different hasher, results are meaningless.
+///
+/// # Examples
+/// ```
+/// use fastbloom::BloomFilter;
+///
+/// let shards: Vec<_> = (0..16)
+///     .map(|i| BloomFilter::with_false_pos(0.001).seed(&42).items(i * 100..(i + 1) * 100))
+///     .collect();
+/// let key = shards[0].prepare(&1234);
+/// let found: Vec<_> = (0..16).filter(|&i| shards[i].contains_prepared(&key)).collect();
+/// assert!(found.contains(&12));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PreparedKey {
+    h1: u64,
+    h2: u64,
+}
+
+impl PreparedKey {
+    /// Creates a `PreparedKey` from an item's 64 bit hash, as used by [`BloomFilter::insert_hash`].
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::{BloomFilter, PreparedKey};
+    ///
+    /// let mut bloom = BloomFilter::with_num_bits(1024).hashes(4);
+    /// bloom.insert_hash(7);
+    /// assert!(bloom.contains_prepared(&PreparedKey::from_hash(7)));
+    /// ```
+    #[inline]
+    pub fn from_hash(hash: u64) -> Self {
+        let [h1, h2] = derive_hashes(hash);
+        Self { h1, h2 }
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher, T: BitStorage>
+    BloomFilter<BLOCK_SIZE_BITS, S, T>
+{
+    /// Hashes an item with this filter's hasher, for use with any filter with the same hasher.
+    #[inline]
+    pub fn prepare(&self, val: &(impl Hash + ?Sized)) -> PreparedKey {
+        let [h1, h2] = get_orginal_hashes(&self.hasher, val);
+        PreparedKey { h1, h2 }
+    }
+
+    /// Checks if the item of `key` is possibly in the Bloom filter, as [`contains`](Self::contains) does.
+    #[inline]
+    pub fn contains_prepared(&self, key: &PreparedKey) -> bool {
+        self.contains_item(key.h1, key.h2)
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher, T: BitStorageMut>
+    BloomFilter<BLOCK_SIZE_BITS, S, T>
+{
+    /// Inserts the item of `key` into the Bloom filter, as [`insert`](Self::insert) does.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item may have been previously in the Bloom filter (indicating a potential false positive),
+    /// `false` otherwise.
+    #[inline]
+    pub fn insert_prepared(&mut self, key: &PreparedKey) -> bool {
+        let h1 = self.candidate(key.h1, key.h2);
+        self.insert_hashes(h1, key.h2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prepared_matches_item() {
+        let mut a = BloomFilter::with_num_bits(4096).seed(&1).hashes(7);
+        let mut b = BloomFilter::with_num_bits(8192)
+            .block_size_128()
+            .seed(&1)
+            .two_choice()
+            .hashes(3);
+        let mut expected_a = a.clone();
+        let mut expected_b = b.clone();
+        for x in 0..200 {
+            let key = a.prepare(&x);
+            assert_eq!(a.insert_prepared(&key), expected_a.insert(&x));
+            assert_eq!(b.insert_prepared(&key), expected_b.insert(&x));
+        }
+        assert_eq!(a, expected_a);
+        assert_eq!(b, expected_b);
+        for x in 0..400 {
+            let key = b.prepare(&x);
+            assert_eq!(a.contains_prepared(&key), a.contains(&x));
+            assert_eq!(b.contains_prepared(&key), b.contains(&x));
+        }
+    }
+}
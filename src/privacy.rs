@@ -0,0 +1,254 @@
+use crate::{BitStorage, BloomFilter};
+use rand::Rng;
+use std::hash::{BuildHasher, Hash};
+
+/// A local differential privacy encoder for `BloomFilter`s, by randomized response as in
+/// [RAPPOR](https://research.google/pubs/rappor-randomized-aggregatable-privacy-preserving-ordinal-response/).
+///
+/// [`privatize`](Self::privatize) reports a copy of a filter in which each bit is, with probability `flip`,
+/// replaced by a fair coin flip, and is otherwise kept. So a reported bit is set with probability `1 - flip / 2`
+/// if the true bit is set, and `flip / 2` if not, and no single report reveals whether an item was inserted.
+/// Many reports of filters with the same number of bits, hashes, and hasher are combined with a [`ReportAggregator`],
+/// which corrects for the noise to estimate how many of the filters contained each item.
+///
+/// Every report of the same filter reveals more about it, since the noise averages out across reports.
+/// To bound what is revealed, privatize a filter once and send that report every time, as RAPPOR's "permanent" response.
+///
+/// Requires the `rand` feature.
+///
+/// # Examples
+/// ```
+/// use fastbloom::{BloomFilter, RandomizedResponse};
+///
+/// let response = RandomizedResponse::new(0.5);
+/// let mut bloom = BloomFilter::with_num_bits(1024).seed(&1).hashes(2);
+/// bloom.insert("chrome");
+/// let report = response.privatize(&bloom, &mut rand::thread_rng());
+/// assert_eq!(report.num_bits(), bloom.num_bits());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RandomizedResponse {
+    flip: f64,
+}
+
+impl RandomizedResponse {
+    /// Creates a `RandomizedResponse` that replaces each bit with a fair coin flip with probability `flip`.
+    ///
+    /// # Panics
+    /// Panics if `flip` is not in `(0, 1)`. With `flip` of 1, reports would be only noise, from which nothing can be estimated.
+    pub fn new(flip: f64) -> Self {
+        assert!(flip > 0.0 && flip < 1.0, "flip must be in (0, 1)");
+        Self { flip }
+    }
+
+    /// Returns the probability that a bit is replaced by a fair coin flip.
+    #[inline]
+    pub fn flip(&self) -> f64 {
+        self.flip
+    }
+
+    /// Returns the privacy loss ε of one report, if each item sets at most `bits_per_item` bits.
+    ///
+    /// This is `2 * bits_per_item * ln((1 - flip / 2) / (flip / 2))`; smaller is more private.
+    /// An item sets at most [`num_hashes`](BloomFilter::num_hashes) bits in filters with 64 bit blocks
+    /// built with [`hashes`](crate::BuilderWithBits::hashes), and about as many otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::RandomizedResponse;
+    ///
+    /// let epsilon = RandomizedResponse::new(0.5).epsilon(2);
+    /// assert!((epsilon - 4.0 * 3f64.ln()).abs() < 1e-9);
+    /// ```
+    pub fn epsilon(&self, bits_per_item: u32) -> f64 {
+        let half = self.flip / 2.0;
+        2.0 * bits_per_item as f64 * ((1.0 - half) / half).ln()
+    }
+
+    /// Returns a randomized copy of the bits of `filter`, with the same hasher and number of hashes.
+    pub fn privatize<const BLOCK_SIZE_BITS: usize, S: BuildHasher + Clone, T: BitStorage>(
+        &self,
+        filter: &BloomFilter<BLOCK_SIZE_BITS, S, T>,
+        rng: &mut (impl Rng + ?Sized),
+    ) -> BloomFilter<BLOCK_SIZE_BITS, S> {
+        let mut report = filter.fold(1);
+        for word in report.as_mut_slice() {
+            for bit in 0..64 {
+                if rng.gen_bool(self.flip) {
+                    if rng.gen_bool(0.5) {
+                        *word |= 1 << bit;
+                    } else {
+                        *word &= !(1 << bit);
+                    }
+                }
+            }
+        }
+        report
+    }
+}
+
+/// Combines reports from [`RandomizedResponse::privatize`] to estimate how many of the reported filters
+/// contained each item.
+///
+/// # Examples
+/// ```
+/// use fastbloom::{BloomFilter, RandomizedResponse, ReportAggregator};
+///
+/// let response = RandomizedResponse::new(0.25);
+/// let empty = BloomFilter::with_num_bits(4096).seed(&1).hashes(2);
+/// let mut aggregator = ReportAggregator::new(empty.clone(), response);
+///
+/// let mut rng = rand::thread_rng();
+/// for user in 0..1000 {
+///     let browser = if user % 4 == 0 { "firefox" } else { "chrome" };
+///     let mut bloom = empty.clone();
+///     bloom.insert(browser);
+///     aggregator.add_report(&response.privatize(&bloom, &mut rng));
+/// }
+/// let chrome = aggregator.estimate("chrome");
+/// assert!((chrome - 750.0).abs() < 150.0);
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReportAggregator<const BLOCK_SIZE_BITS: usize = 512, S = crate::DefaultHasher> {
+    filter: BloomFilter<BLOCK_SIZE_BITS, S>,
+    response: RandomizedResponse,
+    /// The number of reports with each bit set
+    counts: Vec<u64>,
+    num_reports: u64,
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> ReportAggregator<BLOCK_SIZE_BITS, S> {
+    /// Creates a `ReportAggregator` for reports of filters like `filter`, privatized by `response`.
+    ///
+    /// `filter` is cleared before use.
+    ///
+    /// # Panics
+    /// Panics if `filter` uses two-choice insertion, since the bits of an item in a report would depend on the rest of
+    /// the reported filter.
+    pub fn new(mut filter: BloomFilter<BLOCK_SIZE_BITS, S>, response: RandomizedResponse) -> Self {
+        assert!(
+            !filter.two_choice,
+            "two-choice filters cannot be aggregated"
+        );
+        filter.clear();
+        Self {
+            counts: vec![0; filter.num_bits()],
+            filter,
+            response,
+            num_reports: 0,
+        }
+    }
+
+    /// Adds a report.
+    ///
+    /// # Panics
+    /// Panics if `report` has a different number of bits than the aggregated filters.
+    pub fn add_report<T: BitStorage>(&mut self, report: &BloomFilter<BLOCK_SIZE_BITS, S, T>) {
+        assert_eq!(report.num_bits(), self.filter.num_bits());
+        for (i, &word) in report.as_slice().iter().enumerate() {
+            let mut word = word;
+            while word != 0 {
+                self.counts[i * 64 + word.trailing_zeros() as usize] += 1;
+                word &= word - 1;
+            }
+        }
+        self.num_reports += 1;
+    }
+
+    /// Returns the number of reports added.
+    #[inline]
+    pub fn num_reports(&self) -> u64 {
+        self.num_reports
+    }
+
+    /// Returns the estimated number of reported filters with each bit set, corrected for the randomized response.
+    ///
+    /// Estimates are unbiased, so may be negative or more than [`num_reports`](Self::num_reports).
+    pub fn estimated_bit_counts(&self) -> Vec<f64> {
+        (0..self.counts.len())
+            .map(|i| self.bit_estimate(i))
+            .collect()
+    }
+
+    /// Returns the estimated number of reported filters that contained an item.
+    ///
+    /// This is the average of the corrected counts of the item's bits, so, like [`BloomFilter::contains`],
+    /// it also counts filters with other items that set the same bits, and with few reports it is noisy.
+    pub fn estimate(&self, val: &(impl Hash + ?Sized)) -> f64 {
+        let mut bits = Vec::new();
+        for (i, mut mask) in self.filter.masks(val) {
+            while mask != 0 {
+                bits.push(i * 64 + mask.trailing_zeros() as usize);
+                mask &= mask - 1;
+            }
+        }
+        bits.sort_unstable();
+        bits.dedup();
+        bits.iter().map(|&i| self.bit_estimate(i)).sum::<f64>() / bits.len() as f64
+    }
+
+    /// Returns the estimated number of reported filters with bit `i` set.
+    fn bit_estimate(&self, i: usize) -> f64 {
+        let half = self.response.flip / 2.0;
+        let noise = half * self.num_reports as f64;
+        (self.counts[i] as f64 - noise) / (1.0 - self.response.flip)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn flips_expected_fraction() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let empty = BloomFilter::with_num_bits(1 << 16).hashes(3);
+        let report = RandomizedResponse::new(0.2).privatize(&empty, &mut rng);
+        let set: u32 = report.as_slice().iter().map(|w| w.count_ones()).sum();
+        let frac = set as f64 / report.num_bits() as f64;
+        assert!((frac - 0.1).abs() < 0.01, "{frac}");
+
+        let full = BloomFilter::from_vec(vec![u64::MAX; 1024]).hashes(3);
+        let report = RandomizedResponse::new(0.2).privatize(&full, &mut rng);
+        let set: u32 = report.as_slice().iter().map(|w| w.count_ones()).sum();
+        let frac = set as f64 / report.num_bits() as f64;
+        assert!((frac - 0.9).abs() < 0.01, "{frac}");
+    }
+
+    #[test]
+    fn estimates_frequencies() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let response = RandomizedResponse::new(0.5);
+        let empty = BloomFilter::with_num_bits(1 << 12)
+            .block_size_64()
+            .seed(&3)
+            .hashes(4);
+        let mut aggregator = ReportAggregator::new(empty.clone(), response);
+        for user in 0..4000u64 {
+            let mut bloom = empty.clone();
+            bloom.insert(&(user % 10 == 0));
+            aggregator.add_report(&response.privatize(&bloom, &mut rng));
+        }
+        assert_eq!(aggregator.num_reports(), 4000);
+        assert!((aggregator.estimate(&true) - 400.0).abs() < 100.0);
+        assert!((aggregator.estimate(&false) - 3600.0).abs() < 100.0);
+        assert!(aggregator.estimate("never").abs() < 100.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_two_choice() {
+        let filter = BloomFilter::with_num_bits(1024).two_choice().hashes(3);
+        ReportAggregator::new(filter, RandomizedResponse::new(0.5));
+    }
+
+    #[test]
+    fn rejects_invalid_flip() {
+        for flip in [0.0, 1.0, 1.5, f64::NAN] {
+            assert!(std::panic::catch_unwind(|| RandomizedResponse::new(flip)).is_err());
+        }
+    }
+}
@@ -0,0 +1,283 @@
+//! Protobuf messages for Bloom filters, as defined in `proto/fastbloom.proto`.
+//!
+//! The messages are [`prost`] types, so they can be embedded in other prost messages, e.g. of a gRPC service.
+//!
+//! # Examples
+//! ```
+//! use fastbloom::{proto, BloomFilter};
+//! use prost::Message;
+//!
+//! let bloom = BloomFilter::with_false_pos(0.001).items(["42", "🦀"]);
+//! let bytes = proto::BloomFilter::from(&bloom).encode_to_vec();
+//!
+//! let message = proto::BloomFilter::decode(bytes.as_slice()).unwrap();
+//! let bloom = BloomFilter::<512>::try_from(message).unwrap();
+//! assert!(bloom.contains("42"));
+//! assert!(bloom.contains("🦀"));
+//! ```
+
+use crate::{sparse_hash, BitStorage, DefaultHasher, FastbloomError, IndexStrategy, Prefix};
+
+/// A Bloom filter with the default hasher, SipHash-1-3.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct BloomFilter {
+    /// The bits, as little-endian 64-bit words.
+    #[prost(bytes = "vec", tag = "1")]
+    pub bits: Vec<u8>,
+    /// The block size in bits: 64, 128, 256, or 512.
+    #[prost(uint32, tag = "2")]
+    pub block_size_bits: u32,
+    /// The number of hashes per item.
+    #[prost(uint32, tag = "3")]
+    pub num_hashes: u32,
+    /// The keys of the SipHash-1-3 hasher.
+    #[prost(fixed64, tag = "4")]
+    pub sip_key0: u64,
+    #[prost(fixed64, tag = "5")]
+    pub sip_key1: u64,
+    /// Whether each item is inserted into the less occupied of two candidate blocks.
+    #[prost(bool, tag = "6")]
+    pub two_choice: bool,
+    /// How the prefix of byte string keys is extracted in prefix mode, if enabled.
+    #[prost(oneof = "bloom_filter::Prefix", tags = "7, 8")]
+    pub prefix: Option<bloom_filter::Prefix>,
+    /// Whether bit indexes are derived with enhanced double hashing instead of double hashing.
+    #[prost(bool, tag = "9")]
+    pub enhanced_double_hashing: bool,
+    /// The number of single bit hashes per item. If unset, the hashes are split between single bit hashes
+    /// and a sparse hash as by `hashes(num_hashes)`, and `num_rounds` is ignored.
+    #[prost(uint32, optional, tag = "10")]
+    pub single_hashes: Option<u32>,
+    /// The number of bits per 64-bit word set by the sparse hash of each item, or 0 for no sparse hash.
+    #[prost(uint32, tag = "11")]
+    pub num_rounds: u32,
+}
+
+/// Nested types of [`BloomFilter`].
+pub mod bloom_filter {
+    /// How the prefix of byte string keys is extracted in prefix mode.
+    #[derive(Clone, Copy, PartialEq, Eq, Hash, prost::Oneof)]
+    pub enum Prefix {
+        /// The first `prefix_len` bytes of the key.
+        #[prost(uint64, tag = "7")]
+        PrefixLen(u64),
+        /// The bytes of the key up to and including the first occurrence of this byte.
+        #[prost(uint32, tag = "8")]
+        PrefixDelimiter(u32),
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, T: BitStorage>
+    From<&crate::BloomFilter<BLOCK_SIZE_BITS, DefaultHasher, T>> for BloomFilter
+{
+    fn from(filter: &crate::BloomFilter<BLOCK_SIZE_BITS, DefaultHasher, T>) -> Self {
+        let (sip_key0, sip_key1) = filter.hasher.keys();
+        Self {
+            bits: filter.as_bytes().into_owned(),
+            block_size_bits: BLOCK_SIZE_BITS as u32,
+            num_hashes: filter.num_hashes(),
+            sip_key0,
+            sip_key1,
+            two_choice: filter.two_choice,
+            prefix: filter.prefix.map(|prefix| match prefix {
+                Prefix::Len(n) => bloom_filter::Prefix::PrefixLen(n as u64),
+                Prefix::Delimiter(d) => bloom_filter::Prefix::PrefixDelimiter(d.into()),
+            }),
+            enhanced_double_hashing: filter.index_strategy == IndexStrategy::EnhancedDoubleHashing,
+            single_hashes: Some(filter.num_hashes as u32),
+            num_rounds: filter.num_rounds.unwrap_or(0) as u32,
+        }
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize> TryFrom<BloomFilter> for crate::BloomFilter<BLOCK_SIZE_BITS> {
+    type Error = FastbloomError;
+
+    /// Converts the message to a `BloomFilter`, checking that it has the same block size and valid parameters.
+    ///
+    /// # Errors
+    /// Returns [`FastbloomError::MismatchedBlockSize`] if the message has a different block size,
+    /// [`FastbloomError::ZeroBits`] or [`FastbloomError::NotWholeBlockBits`] if its bits are not a whole,
+    /// non-zero number of blocks, [`FastbloomError::InvalidHashes`] if it has no hashes,
+    /// and [`FastbloomError::InvalidParameters`] if its number of rounds or prefix is invalid.
+    fn try_from(message: BloomFilter) -> Result<Self, FastbloomError> {
+        if message.block_size_bits as usize != BLOCK_SIZE_BITS {
+            return Err(FastbloomError::MismatchedBlockSize {
+                block_size_bits: message.block_size_bits as usize,
+            });
+        }
+        if message.bits.is_empty() {
+            return Err(FastbloomError::ZeroBits);
+        }
+        if !message.bits.len().is_multiple_of(BLOCK_SIZE_BITS / 8) {
+            return Err(FastbloomError::NotWholeBlockBits {
+                num_bits: 8 * message.bits.len(),
+                block_size_bits: BLOCK_SIZE_BITS,
+            });
+        }
+        let bits = message
+            .bits
+            .chunks_exact(8)
+            .map(|word| u64::from_le_bytes(word.try_into().unwrap()))
+            .collect();
+        let mut builder = crate::BloomFilter::new_from_vec::<BLOCK_SIZE_BITS>(bits)
+            .hasher(DefaultHasher::with_keys(message.sip_key0, message.sip_key1));
+        if message.two_choice {
+            builder = builder.two_choice();
+        }
+        if message.enhanced_double_hashing {
+            builder = builder.index_strategy(IndexStrategy::EnhancedDoubleHashing);
+        }
+        if let Some(prefix) = message.prefix {
+            builder = builder.prefix(match prefix {
+                bloom_filter::Prefix::PrefixLen(n) => {
+                    Prefix::Len(usize::try_from(n).map_err(|_| FastbloomError::InvalidParameters)?)
+                }
+                bloom_filter::Prefix::PrefixDelimiter(d) => Prefix::Delimiter(
+                    u8::try_from(d).map_err(|_| FastbloomError::InvalidParameters)?,
+                ),
+            });
+        }
+        match message.single_hashes {
+            Some(single_hashes) => {
+                let num_rounds = match message.num_rounds as u64 {
+                    0 => None,
+                    r if sparse_hash::is_valid_rounds(r, BLOCK_SIZE_BITS) => Some(r),
+                    _ => return Err(FastbloomError::InvalidParameters),
+                };
+                if message.num_hashes == 0 || (single_hashes == 0 && num_rounds.is_none()) {
+                    return Err(FastbloomError::InvalidHashes(message.num_hashes));
+                }
+                Ok(builder.hashes_with_split(message.num_hashes, single_hashes, num_rounds))
+            }
+            None => builder.try_hashes(message.num_hashes),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+
+    #[test]
+    fn proto_round_trip() {
+        let mut bloom = crate::BloomFilter::with_num_bits(1024)
+            .block_size_128()
+            .seed(&7)
+            .two_choice()
+            .index_strategy(IndexStrategy::EnhancedDoubleHashing)
+            .prefix(Prefix::Delimiter(b'/'))
+            .expected_items(10);
+        bloom.extend(0..10);
+        bloom.insert_with_prefix(b"users/alice");
+        let message = BloomFilter::from(&bloom);
+        assert_eq!(message.bits, *bloom.as_bytes());
+        assert_eq!(message.block_size_bits, 128);
+        assert_eq!(
+            message.prefix,
+            Some(bloom_filter::Prefix::PrefixDelimiter(b'/'.into()))
+        );
+
+        let decoded = BloomFilter::decode(message.encode_to_vec().as_slice()).unwrap();
+        assert_eq!(decoded, message);
+        let back = crate::BloomFilter::<128>::try_from(decoded).unwrap();
+        assert_eq!(back, bloom);
+        assert!((0..10).all(|x| back.contains(&x)));
+        assert!(back.contains(b"users/".as_slice()));
+        assert!(crate::BloomFilter::<512>::try_from(message).is_err());
+    }
+
+    #[test]
+    fn proto_keeps_split_of_hashes() {
+        let mut bloom = crate::BloomFilter::with_num_bits(1 << 12)
+            .block_size_64()
+            .seed(&1)
+            .max_rounds(16)
+            .hashes(40);
+        bloom.extend(0..100);
+        let message = BloomFilter::from(&bloom);
+        assert_eq!(message.num_rounds, 16);
+        let back = crate::BloomFilter::<64>::try_from(message.clone()).unwrap();
+        assert_eq!(back, bloom);
+        assert!((0..100).all(|x| back.contains(&x)));
+
+        let unsplit = BloomFilter {
+            single_hashes: None,
+            ..message
+        };
+        let back = crate::BloomFilter::<64>::try_from(unsplit).unwrap();
+        assert_eq!(back.num_rounds(), Some(30));
+    }
+
+    #[test]
+    fn proto_wire_format() {
+        let message = BloomFilter {
+            bits: vec![1, 0, 0, 0, 0, 0, 0, 0],
+            block_size_bits: 64,
+            num_hashes: 3,
+            sip_key0: 1,
+            sip_key1: 2,
+            two_choice: false,
+            prefix: Some(bloom_filter::Prefix::PrefixLen(4)),
+            enhanced_double_hashing: false,
+            single_hashes: None,
+            num_rounds: 0,
+        };
+        let mut expected = vec![0x0a, 8, 1, 0, 0, 0, 0, 0, 0, 0]; // field 1, length-delimited
+        expected.extend([0x10, 64, 0x18, 3]); // fields 2 and 3, varint
+        expected.extend([0x21, 1, 0, 0, 0, 0, 0, 0, 0]); // field 4, 64-bit
+        expected.extend([0x29, 2, 0, 0, 0, 0, 0, 0, 0]); // field 5, 64-bit
+        expected.extend([0x38, 4]); // field 7, varint
+        assert_eq!(message.encode_to_vec(), expected);
+        assert!(crate::BloomFilter::<64>::try_from(message).is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid() {
+        let valid = BloomFilter::from(&crate::BloomFilter::with_num_bits(512).hashes(3));
+        assert!(crate::BloomFilter::<512>::try_from(valid.clone()).is_ok());
+        for message in [
+            BloomFilter {
+                bits: vec![0; 63],
+                ..valid.clone()
+            },
+            BloomFilter {
+                bits: vec![],
+                ..valid.clone()
+            },
+            BloomFilter {
+                prefix: Some(bloom_filter::Prefix::PrefixDelimiter(256)),
+                ..valid.clone()
+            },
+            BloomFilter {
+                num_rounds: 7,
+                ..valid.clone()
+            },
+            BloomFilter {
+                single_hashes: Some(0),
+                num_rounds: 0,
+                ..valid.clone()
+            },
+        ] {
+            assert!(crate::BloomFilter::<512>::try_from(message).is_err());
+        }
+
+        // a missing field 3 decodes as 0 hashes, which would report every item as present
+        let no_hashes = BloomFilter {
+            num_hashes: 0,
+            single_hashes: None,
+            ..valid.clone()
+        };
+        assert_eq!(
+            crate::BloomFilter::<512>::try_from(no_hashes).unwrap_err(),
+            FastbloomError::InvalidHashes(0)
+        );
+        assert_eq!(
+            crate::BloomFilter::<256>::try_from(valid).unwrap_err(),
+            FastbloomError::MismatchedBlockSize {
+                block_size_bits: 512
+            }
+        );
+    }
+}
@@ -0,0 +1,214 @@
+use crate::DynBloomFilter;
+use numpy::{PyArray1, PyReadonlyArray1};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyString};
+use std::borrow::Cow;
+
+/// A Bloom filter for Python, exported as `fastbloom.BloomFilter`, e.g. for querying filters built by Rust services.
+///
+/// Filters are loaded from and saved to the bytes of [`BloomFilter::to_bytes_with_params`](crate::BloomFilter::to_bytes_with_params),
+/// i.e. of `to_base64` before encoding, which include the block size, number of hashes, and hasher keys, and are pickled the same way.
+/// Items are hashed the same as in Rust: `str`s as `str`, `bytes` as `[u8]`, and `int`s as `u64`,
+/// so `"alice" in filter` finds an item inserted by a service with `insert("alice")`.
+///
+/// # Examples
+/// ```python
+/// import numpy as np
+/// from fastbloom import BloomFilter
+///
+/// bloom = BloomFilter.from_bytes(data)  # from `to_base64()`'s bytes on the service
+/// assert "alice" in bloom
+/// hits = bloom.contains_many(np.array([1, 2, 3], dtype=np.uint64))
+/// ```
+#[pyclass(name = "BloomFilter", module = "fastbloom")]
+#[derive(Debug, Clone)]
+pub struct PyBloomFilter(DynBloomFilter);
+
+/// An item of a Python filter, hashed as the equivalent Rust type.
+enum Item<'a> {
+    Str(Cow<'a, str>),
+    Bytes(&'a [u8]),
+    Int(u64),
+}
+
+impl<'a> Item<'a> {
+    fn extract(item: &'a Bound<'_, PyAny>) -> PyResult<Self> {
+        if let Ok(s) = item.cast::<PyString>() {
+            Ok(Self::Str(s.to_cow()?))
+        } else if let Ok(b) = item.cast::<PyBytes>() {
+            Ok(Self::Bytes(b.as_bytes()))
+        } else {
+            Ok(Self::Int(item.extract()?))
+        }
+    }
+}
+
+#[pymethods]
+impl PyBloomFilter {
+    /// Creates an empty filter with random hasher keys, sized for `expected_num_items` to meet the false positive rate `fp`.
+    #[new]
+    fn new(fp: f64, expected_num_items: usize) -> PyResult<Self> {
+        if !(fp > 0.0 && fp < 1.0) {
+            return Err(PyValueError::new_err("fp must be between 0 and 1"));
+        }
+        Ok(Self(DynBloomFilter::new_with_false_pos(
+            512,
+            fp,
+            expected_num_items,
+        )))
+    }
+
+    /// Creates a filter from the bytes of `BloomFilter::to_base64`, raising `ValueError` if they are not a valid filter.
+    #[staticmethod]
+    fn from_bytes(bytes: &[u8]) -> PyResult<Self> {
+        DynBloomFilter::from_bytes_with_params(bytes)
+            .map(Self)
+            .ok_or_else(|| PyValueError::new_err("invalid Bloom filter bytes"))
+    }
+
+    /// Returns the bytes of this filter, which `BloomFilter::from_base64` loads once base64 encoded.
+    fn to_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.0.to_bytes_with_params())
+    }
+
+    /// Inserts a `str`, `bytes`, or `int`, returning `True` if it may have been previously in the filter.
+    fn insert(&mut self, item: &Bound<'_, PyAny>) -> PyResult<bool> {
+        Ok(match Item::extract(item)? {
+            Item::Str(s) => self.0.insert(&*s),
+            Item::Bytes(b) => self.0.insert(b),
+            Item::Int(x) => self.0.insert(&x),
+        })
+    }
+
+    /// Checks if a `str`, `bytes`, or `int` is possibly in the filter.
+    fn contains(&self, item: &Bound<'_, PyAny>) -> PyResult<bool> {
+        Ok(match Item::extract(item)? {
+            Item::Str(s) => self.0.contains(&*s),
+            Item::Bytes(b) => self.0.contains(b),
+            Item::Int(x) => self.0.contains(&x),
+        })
+    }
+
+    fn __contains__(&self, item: &Bound<'_, PyAny>) -> PyResult<bool> {
+        self.contains(item)
+    }
+
+    /// Inserts every `u64` of a NumPy array.
+    fn insert_many(&mut self, items: PyReadonlyArray1<'_, u64>) {
+        for x in items.as_array() {
+            self.0.insert(x);
+        }
+    }
+
+    /// Checks every `u64` of a NumPy array, returning a `bool` array of whether each is possibly in the filter.
+    fn contains_many<'py>(
+        &self,
+        py: Python<'py>,
+        items: PyReadonlyArray1<'py, u64>,
+    ) -> Bound<'py, PyArray1<bool>> {
+        PyArray1::from_iter(py, items.as_array().iter().map(|x| self.0.contains(x)))
+    }
+
+    /// The number of hashes per item.
+    #[getter]
+    fn num_hashes(&self) -> u32 {
+        self.0.num_hashes()
+    }
+
+    /// The total number of bits in the filter.
+    #[getter]
+    fn num_bits(&self) -> usize {
+        self.0.num_bits()
+    }
+
+    /// Clears all of the bits in the filter, removing all items.
+    fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    fn __eq__(&self, other: &Self) -> bool {
+        self.0.to_bytes_with_params() == other.0.to_bytes_with_params()
+    }
+
+    fn __reduce__<'py>(
+        slf: &Bound<'py, Self>,
+    ) -> PyResult<(Bound<'py, PyAny>, (Bound<'py, PyBytes>,))> {
+        let from_bytes = slf.get_type().getattr("from_bytes")?;
+        Ok((from_bytes, (slf.borrow().to_bytes(slf.py()),)))
+    }
+}
+
+/// The `fastbloom` Python module.
+#[pymodule]
+fn fastbloom(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyBloomFilter>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BloomFilter;
+    use pyo3::types::PyDict;
+
+    fn run(code: &str, locals: impl FnOnce(Python<'_>, &Bound<'_, PyDict>)) {
+        Python::initialize();
+        Python::attach(|py| {
+            let module = PyModule::new(py, "fastbloom").unwrap();
+            fastbloom(&module).unwrap();
+            let sys = py.import("sys").unwrap();
+            sys.getattr("modules")
+                .unwrap()
+                .set_item("fastbloom", module)
+                .unwrap();
+            let dict = PyDict::new(py);
+            locals(py, &dict);
+            let code = std::ffi::CString::new(code).unwrap();
+            py.run(&code, None, Some(&dict)).unwrap();
+        });
+    }
+
+    #[test]
+    fn queries_service_filter() {
+        let mut service = BloomFilter::with_num_bits(1024)
+            .block_size_256()
+            .expected_items(10);
+        service.insert("alice");
+        service.insert(b"\x01\x02".as_slice());
+        service.insert(&7u64);
+        let bytes = service.to_bytes_with_params();
+        run(
+            r#"
+from fastbloom import BloomFilter
+bloom = BloomFilter.from_bytes(data)
+assert "alice" in bloom and bloom.contains(b"\x01\x02") and 7 in bloom
+assert "bob" not in bloom
+assert bloom.num_bits == 1024 and bloom.to_bytes() == data
+try:
+    BloomFilter.from_bytes(b"\x01")
+    raise AssertionError
+except ValueError:
+    pass
+"#,
+            |py, locals| locals.set_item("data", PyBytes::new(py, &bytes)).unwrap(),
+        );
+    }
+
+    #[test]
+    fn insert_and_pickle() {
+        run(
+            r#"
+import pickle
+from fastbloom import BloomFilter
+bloom = BloomFilter(0.01, 100)
+assert not bloom.insert("alice") and bloom.insert("alice")
+assert not bloom.insert(b"alice") and not bloom.insert(2**64 - 1)
+new = pickle.loads(pickle.dumps(bloom))
+assert new == bloom and "alice" in new and 2**64 - 1 in new
+bloom.clear()
+assert "alice" not in bloom
+"#,
+            |_, _| {},
+        );
+    }
+}
@@ -0,0 +1,207 @@
+use crate::BloomFilter;
+use std::hash::BuildHasher;
+use std::ops::{Bound, RangeBounds};
+
+/// A range filter of `u64` keys, answering whether any key in a range was possibly inserted.
+///
+/// Plain Bloom filters can only answer point queries. `RangeFilter` inserts each key's prefixes of all `levels`
+/// bit lengths into one [`BloomFilter`], i.e. the dyadic intervals `[prefix << level, (prefix + 1) << level)` containing it,
+/// as in [Rosetta](https://dl.acm.org/doi/10.1145/3318464.3389731).
+/// A range query checks the largest intervals covering the range and, only for those present,
+/// "doubts" them by checking their two halves, down to single keys. Intervals not containing any key are pruned
+/// early with high probability, so the false positive rate of a range query stays close to that of a point query.
+///
+/// Each key inserts `levels` items, so the filter must be sized for `levels` times the number of keys.
+/// Range queries are fast while the range spans few intervals of the largest level, i.e. `2^(levels - 1)` keys.
+///
+/// # Examples
+/// ```
+/// use fastbloom::{BloomFilter, RangeFilter};
+///
+/// let filter = BloomFilter::with_false_pos(0.01).expected_items(100 * 32);
+/// let mut ranges = RangeFilter::new(filter, 32);
+/// ranges.insert(1_000);
+/// ranges.insert(5_000);
+/// assert!(ranges.contains_range(900..1_100));
+/// assert!(ranges.contains_range(4_000..=5_000));
+/// assert!(!ranges.contains_range(1_001..4_999));
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RangeFilter<const BLOCK_SIZE_BITS: usize = 512, S = crate::DefaultHasher> {
+    filter: BloomFilter<BLOCK_SIZE_BITS, S>,
+    levels: u32,
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> RangeFilter<BLOCK_SIZE_BITS, S> {
+    /// Creates a new `RangeFilter` inserting the prefixes of `levels` bit lengths of each key into `filter`.
+    ///
+    /// `filter` is cleared before use.
+    ///
+    /// # Panics
+    /// Panics if `levels` is 0 or greater than 64.
+    pub fn new(mut filter: BloomFilter<BLOCK_SIZE_BITS, S>, levels: u32) -> Self {
+        assert!(levels > 0 && levels <= 64);
+        filter.clear();
+        Self { filter, levels }
+    }
+
+    /// Inserts a key.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the key may have been previously in the filter (indicating a potential false positive),
+    /// `false` otherwise.
+    #[inline]
+    pub fn insert(&mut self, key: u64) -> bool {
+        for level in 1..self.levels {
+            self.filter.insert(&(level, key >> level));
+        }
+        self.filter.insert(&(0u32, key))
+    }
+
+    /// Checks if a key is possibly in the filter.
+    #[inline]
+    pub fn contains(&self, key: u64) -> bool {
+        self.filter.contains(&(0u32, key))
+    }
+
+    /// Checks if any key in `range` is possibly in the filter.
+    pub fn contains_range(&self, range: impl RangeBounds<u64>) -> bool {
+        let lo = match range.start_bound() {
+            Bound::Included(&lo) => lo,
+            Bound::Excluded(&lo) => match lo.checked_add(1) {
+                Some(lo) => lo,
+                None => return false,
+            },
+            Bound::Unbounded => 0,
+        };
+        let hi = match range.end_bound() {
+            Bound::Included(&hi) => hi,
+            Bound::Excluded(&hi) => match hi.checked_sub(1) {
+                Some(hi) => hi,
+                None => return false,
+            },
+            Bound::Unbounded => u64::MAX,
+        };
+        if lo > hi {
+            return false;
+        }
+        let top = self.levels - 1;
+        ((lo >> top)..=(hi >> top)).any(|prefix| self.doubt(top, prefix, lo, hi))
+    }
+
+    /// Checks if the interval of `prefix` at `level` is possibly non-empty, and if so, if any of its halves
+    /// intersecting `[lo, hi]` are.
+    fn doubt(&self, level: u32, prefix: u64, lo: u64, hi: u64) -> bool {
+        if !self.filter.contains(&(level, prefix)) {
+            return false;
+        }
+        if level == 0 {
+            return true;
+        }
+        let level = level - 1;
+        (2 * prefix..=2 * prefix + 1).any(|child| {
+            let child_lo = child << level;
+            let child_hi = child_lo | ((1 << level) - 1);
+            child_lo <= hi && lo <= child_hi && self.doubt(level, child, lo, hi)
+        })
+    }
+
+    /// Returns the number of prefix bit lengths inserted per key.
+    #[inline]
+    pub fn levels(&self) -> u32 {
+        self.levels
+    }
+
+    /// Returns a reference to the underlying filter.
+    #[inline]
+    pub fn filter(&self) -> &BloomFilter<BLOCK_SIZE_BITS, S> {
+        &self.filter
+    }
+
+    /// Returns the number of bytes this `RangeFilter` uses, including its bits. See [`BloomFilter::memory_usage`].
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>() + self.filter.heap_bytes()
+    }
+
+    /// Clear the filter, removing all keys.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.filter.clear();
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> Extend<u64> for RangeFilter<BLOCK_SIZE_BITS, S> {
+    #[inline]
+    fn extend<I: IntoIterator<Item = u64>>(&mut self, iter: I) {
+        for key in iter {
+            self.insert(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn range_filter(num_keys: usize, levels: u32) -> RangeFilter {
+        let filter = BloomFilter::with_false_pos(0.01)
+            .seed(&42)
+            .expected_items(num_keys * levels as usize);
+        RangeFilter::new(filter, levels)
+    }
+
+    #[test]
+    fn ranges_with_keys_always_contained() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys: Vec<u64> = (0..1000).map(|_| rng.gen()).collect();
+        let mut filter = range_filter(keys.len(), 64);
+        filter.extend(keys.iter().copied());
+        for &key in keys.iter() {
+            assert!(filter.contains(key));
+            assert!(filter.contains_range(key..=key));
+            assert!(filter.contains_range(key.saturating_sub(rng.gen_range(0..1000))..));
+            assert!(filter.contains_range(..=key.saturating_add(rng.gen_range(0..1000))));
+            let lo = key.saturating_sub(rng.gen_range(0..1 << 40));
+            let hi = key.saturating_add(rng.gen_range(0..1 << 40));
+            assert!(filter.contains_range(lo..=hi));
+        }
+        assert!(filter.contains_range(..));
+    }
+
+    #[test]
+    fn empty_ranges_rarely_contained() {
+        // Keys are multiples of 2^20, so ranges strictly between two keys are empty
+        let num_keys = 1000;
+        let mut filter = range_filter(num_keys, 32);
+        filter.extend((0..num_keys as u64).map(|x| x << 20));
+        let mut rng = StdRng::seed_from_u64(7);
+        let num_queries = 10_000;
+        let false_pos = (0..num_queries)
+            .filter(|_| {
+                let gap = rng.gen_range(0..num_keys as u64 - 1) << 20;
+                let lo = gap + rng.gen_range(1..1 << 19);
+                let hi = lo + rng.gen_range(0..1 << 19);
+                filter.contains_range(lo..=hi)
+            })
+            .count();
+        assert!(
+            (false_pos as f64) < 0.01 * num_queries as f64,
+            "{false_pos}"
+        );
+    }
+
+    #[test]
+    fn empty_bounds() {
+        let mut filter = range_filter(10, 8);
+        filter.insert(5);
+        assert!(!filter.contains_range(5..5));
+        assert!(!filter.contains_range((Bound::Included(6), Bound::Included(5))));
+        assert!(!filter.contains_range(..0));
+        assert!(filter.contains_range(..6));
+        assert!(filter.contains_range((Bound::Excluded(4), Bound::Excluded(6))));
+        assert!(!filter.contains_range((Bound::Excluded(u64::MAX), Bound::Unbounded)));
+    }
+}
@@ -0,0 +1,470 @@
+use std::f64::consts::LN_2;
+
+/// RedisBloom rounds nothing, conserving memory, instead of sizing links to a power of 2.
+const OPT_NOROUND: u32 = 1;
+/// RedisBloom hashes items with 64 bit MurmurHash2. All filters created since RedisBloom 2.0 have it.
+const OPT_FORCE64: u32 = 4;
+/// RedisBloom never adds links to the filter.
+const OPT_NO_SCALING: u32 = 8;
+
+/// The largest chunk `BF.SCANDUMP` replies with.
+const MAX_CHUNK_SIZE: usize = 10 * 1024 * 1024;
+/// The length of the encoded header of a filter, before its links.
+const HEADER_LEN: usize = 20;
+/// The length of the encoded header of a link.
+const LINK_LEN: usize = 53;
+
+/// One fixed size Bloom filter of a [`RedisBloomFilter`], its "sub-filter".
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Link {
+    bits: Vec<u8>,
+    num_bits: u64,
+    num_items: u64,
+    fp: f64,
+    bits_per_item: f64,
+    num_hashes: u32,
+    capacity: u64,
+    /// The log 2 of the number of bits if rounded to a power of 2, 0 otherwise.
+    n2: u8,
+}
+
+impl Link {
+    /// Sizes a link like RedisBloom's `bloom_init` with `BLOOM_OPT_NOROUND`.
+    fn new(fp: f64, capacity: u64) -> Self {
+        let bits_per_item = -(fp.ln() / 0.480453013918201);
+        let num_bits = ((capacity as f64 * bits_per_item) as u64).max(1);
+        let num_bytes = num_bits.div_ceil(64) * 8;
+        Self {
+            bits: vec![0; num_bytes as usize],
+            num_bits: num_bytes * 8,
+            num_items: 0,
+            fp,
+            bits_per_item,
+            num_hashes: (LN_2 * bits_per_item).ceil() as u32,
+            capacity,
+            n2: 0,
+        }
+    }
+
+    #[inline]
+    fn bit_indexes(&self, [h1, h2]: [u64; 2]) -> impl Iterator<Item = usize> {
+        let modulus = match self.n2 {
+            0 => self.num_bits,
+            n2 => 1 << n2,
+        };
+        (0..u64::from(self.num_hashes))
+            .map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % modulus) as usize)
+    }
+
+    #[inline]
+    fn insert(&mut self, hash: [u64; 2]) -> bool {
+        let mut previously_contained = true;
+        for index in self.bit_indexes(hash).collect::<Vec<_>>() {
+            let bit = 1 << (index % 8);
+            previously_contained &= self.bits[index / 8] & bit != 0;
+            self.bits[index / 8] |= bit;
+        }
+        previously_contained
+    }
+
+    #[inline]
+    fn contains(&self, hash: [u64; 2]) -> bool {
+        self.bit_indexes(hash)
+            .all(|index| self.bits[index / 8] & (1 << (index % 8)) != 0)
+    }
+}
+
+/// A scalable Bloom filter in the format of RedisBloom's `BF.*` commands, which can be exported with `BF.SCANDUMP`
+/// and imported with `BF.LOADCHUNK`.
+///
+/// Like RedisBloom, the filter is a chain of Bloom filters. Once the last one reaches its capacity, a new one is added,
+/// with the capacity multiplied by the expansion and half the false positive rate.
+/// Items are the bytes given to `BF.ADD` and are hashed with 64 bit MurmurHash2 (`MurmurHash64A`).
+/// Filters from RedisBloom versions before 2.0, which hash with 32 bit MurmurHash2, are not supported.
+///
+/// # Examples
+/// ```
+/// use fastbloom::RedisBloomFilter;
+///
+/// // BF.RESERVE key 0.01 1000
+/// let mut filter = RedisBloomFilter::new_with_false_pos(0.01, 1000);
+/// filter.insert("alice");
+///
+/// // each pair is the iterator and data of a BF.SCANDUMP reply, to pass to BF.LOADCHUNK
+/// let chunks = filter.to_chunks();
+/// let filter = RedisBloomFilter::from_chunks(chunks).unwrap();
+/// assert!(filter.contains("alice"));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RedisBloomFilter {
+    links: Vec<Link>,
+    num_items: u64,
+    options: u32,
+    expansion: u32,
+}
+
+impl RedisBloomFilter {
+    /// Creates an empty `RedisBloomFilter` with a first link of `capacity` items at the false positive rate `fp`,
+    /// the same way as `BF.RESERVE key fp capacity`.
+    ///
+    /// # Panics
+    /// Panics if `fp` is not between 0 and 1, or `capacity` is 0.
+    pub fn new_with_false_pos(fp: f64, capacity: u64) -> Self {
+        assert!(fp > 0.0 && fp < 1.0, "invalid false positive rate: {fp}");
+        assert!(capacity > 0);
+        Self {
+            links: vec![Link::new(fp, capacity)],
+            num_items: 0,
+            options: OPT_NOROUND | OPT_FORCE64,
+            expansion: 2,
+        }
+    }
+
+    /// Sets how many times the capacity of each new link is the capacity of the last, like `EXPANSION` of `BF.RESERVE`.
+    /// The default is 2.
+    ///
+    /// # Panics
+    /// Panics if `expansion` is 0.
+    pub fn expansion(mut self, expansion: u32) -> Self {
+        assert!(expansion > 0);
+        self.expansion = expansion;
+        self
+    }
+
+    /// Never adds links to the filter, like `NONSCALING` of `BF.RESERVE`.
+    ///
+    /// Unlike RedisBloom, which rejects items once such a filter is full, items are still inserted,
+    /// exceeding the capacity and false positive rate.
+    pub fn non_scaling(mut self) -> Self {
+        self.options |= OPT_NO_SCALING;
+        self
+    }
+
+    /// Returns `MurmurHash64A` of `val` and of `val` seeded with that, the hash of an item in RedisBloom.
+    #[inline]
+    fn hash(val: &[u8]) -> [u64; 2] {
+        let h1 = murmur64a(val, 0xc6a4_a793_5bd1_e995);
+        [h1, murmur64a(val, h1)]
+    }
+
+    /// Inserts an item into the Bloom filter, like `BF.ADD`.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item may have been previously in the Bloom filter (indicating a potential false positive),
+    /// `false` otherwise.
+    pub fn insert(&mut self, val: &(impl AsRef<[u8]> + ?Sized)) -> bool {
+        let hash = Self::hash(val.as_ref());
+        if self.links.iter().rev().any(|link| link.contains(hash)) {
+            return true;
+        }
+        let last = self.links.last().unwrap();
+        if last.num_items >= last.capacity && self.options & OPT_NO_SCALING == 0 {
+            let link = Link::new(
+                last.fp * 0.5,
+                last.capacity.saturating_mul(u64::from(self.expansion)),
+            );
+            self.links.push(link);
+        }
+        let last = self.links.last_mut().unwrap();
+        last.insert(hash);
+        last.num_items += 1;
+        self.num_items += 1;
+        false
+    }
+
+    /// Checks if an item is possibly in the Bloom filter, like `BF.EXISTS`.
+    pub fn contains(&self, val: &(impl AsRef<[u8]> + ?Sized)) -> bool {
+        let hash = Self::hash(val.as_ref());
+        self.links.iter().any(|link| link.contains(hash))
+    }
+
+    /// Returns the number of items inserted, `Number of items inserted` of `BF.INFO`.
+    #[inline]
+    pub fn num_items(&self) -> u64 {
+        self.num_items
+    }
+
+    /// Returns the number of items the filter can hold before adding a link, `Capacity` of `BF.INFO`.
+    pub fn capacity(&self) -> u64 {
+        self.links.iter().map(|link| link.capacity).sum()
+    }
+
+    /// Returns the total number of in-memory bits supporting the Bloom filter.
+    pub fn num_bits(&self) -> u64 {
+        self.links.iter().map(|link| link.num_bits).sum()
+    }
+
+    /// Clear all of the bits in the Bloom filter, removing all items and all links but the first.
+    pub fn clear(&mut self) {
+        self.links.truncate(1);
+        self.links[0].bits.fill(0);
+        self.links[0].num_items = 0;
+        self.num_items = 0;
+    }
+
+    /// Returns the `BF.SCANDUMP` replies for this filter, each the iterator and data to pass to `BF.LOADCHUNK` in order.
+    /// The final reply, with iterator 0, is not included.
+    pub fn to_chunks(&self) -> Vec<(i64, Vec<u8>)> {
+        self.chunks(MAX_CHUNK_SIZE)
+    }
+
+    fn chunks(&self, max_chunk_size: usize) -> Vec<(i64, Vec<u8>)> {
+        let mut header = Vec::with_capacity(HEADER_LEN + LINK_LEN * self.links.len());
+        header.extend_from_slice(&self.num_items.to_le_bytes());
+        header.extend_from_slice(&(self.links.len() as u32).to_le_bytes());
+        header.extend_from_slice(&self.options.to_le_bytes());
+        header.extend_from_slice(&self.expansion.to_le_bytes());
+        for link in &self.links {
+            header.extend_from_slice(&(link.bits.len() as u64).to_le_bytes());
+            header.extend_from_slice(&link.num_bits.to_le_bytes());
+            header.extend_from_slice(&link.num_items.to_le_bytes());
+            header.extend_from_slice(&link.fp.to_le_bytes());
+            header.extend_from_slice(&link.bits_per_item.to_le_bytes());
+            header.extend_from_slice(&link.num_hashes.to_le_bytes());
+            header.extend_from_slice(&link.capacity.to_le_bytes());
+            header.push(link.n2);
+        }
+        let mut chunks = vec![(1, header)];
+        let mut iter = 1;
+        for link in &self.links {
+            for chunk in link.bits.chunks(max_chunk_size) {
+                iter += chunk.len() as i64;
+                chunks.push((iter, chunk.to_vec()));
+            }
+        }
+        chunks
+    }
+
+    /// Creates a `RedisBloomFilter` from all of the `BF.SCANDUMP` replies for a filter, each the iterator and data.
+    /// The final reply, with iterator 0, may be included.
+    ///
+    /// Returns `None` if the header is malformed, the filter does not hash with 64 bit MurmurHash2,
+    /// or the chunks do not cover the filter exactly.
+    pub fn from_chunks<D: AsRef<[u8]>>(chunks: impl IntoIterator<Item = (i64, D)>) -> Option<Self> {
+        let mut chunks = chunks.into_iter().filter(|(iter, _)| *iter != 0);
+        let (1, header) = chunks.next()? else {
+            return None;
+        };
+        let (mut filter, link_lens) = Self::from_header(header.as_ref())?;
+        let len = link_lens.iter().sum();
+        let chunks = chunks
+            .map(|(iter, data)| {
+                let offset = usize::try_from(iter)
+                    .ok()?
+                    .checked_sub(data.as_ref().len() + 1)?;
+                Some((offset, data))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let mut covered = 0usize;
+        for (offset, data) in &chunks {
+            covered = covered.checked_add(data.as_ref().len())?;
+            if offset.checked_add(data.as_ref().len())? > len {
+                return None;
+            }
+        }
+        if covered != len {
+            return None;
+        }
+        // The bits are only allocated once the chunks are known to cover them.
+        for (link, link_len) in filter.links.iter_mut().zip(link_lens) {
+            link.bits = vec![0; link_len];
+        }
+        for (mut offset, data) in &chunks {
+            let mut data = data.as_ref();
+            for link in &mut filter.links {
+                if offset >= link.bits.len() {
+                    offset -= link.bits.len();
+                    continue;
+                }
+                let n = data.len().min(link.bits.len() - offset);
+                link.bits[offset..offset + n].copy_from_slice(&data[..n]);
+                data = &data[n..];
+                offset = 0;
+                if data.is_empty() {
+                    break;
+                }
+            }
+        }
+        Some(filter)
+    }
+
+    /// Decodes the header of a filter into the filter without bits and the number of bytes of each link.
+    fn from_header(header: &[u8]) -> Option<(Self, Vec<usize>)> {
+        let (num_items, rest) = header.split_first_chunk::<8>()?;
+        let (num_links, rest) = rest.split_first_chunk::<4>()?;
+        let (options, rest) = rest.split_first_chunk::<4>()?;
+        let (expansion, rest) = rest.split_first_chunk::<4>()?;
+        let options = u32::from_le_bytes(*options);
+        let num_links = u32::from_le_bytes(*num_links) as usize;
+        if options & OPT_FORCE64 == 0
+            || num_links == 0
+            || Some(rest.len()) != num_links.checked_mul(LINK_LEN)
+        {
+            return None;
+        }
+        let mut link_lens = Vec::with_capacity(num_links);
+        let mut links = Vec::with_capacity(num_links);
+        for link in rest.chunks_exact(LINK_LEN) {
+            let u64_at = |i: usize| u64::from_le_bytes(link[i..i + 8].try_into().unwrap());
+            let num_bytes = u64_at(0);
+            let num_bits = u64_at(8);
+            let n2 = link[52];
+            let modulus = match n2 {
+                0 => num_bits,
+                n2 => 1u64.checked_shl(u32::from(n2))?,
+            };
+            if modulus == 0 || modulus.div_ceil(8) > num_bytes {
+                return None;
+            }
+            link_lens.push(usize::try_from(num_bytes).ok()?);
+            links.push(Link {
+                bits: Vec::new(),
+                num_bits,
+                num_items: u64_at(16),
+                fp: f64::from_le_bytes(link[24..32].try_into().unwrap()),
+                bits_per_item: f64::from_le_bytes(link[32..40].try_into().unwrap()),
+                num_hashes: u32::from_le_bytes(link[40..44].try_into().unwrap()),
+                capacity: u64_at(44),
+                n2,
+            });
+        }
+        let filter = Self {
+            links,
+            num_items: u64::from_le_bytes(*num_items),
+            options,
+            expansion: u32::from_le_bytes(*expansion),
+        };
+        Some((filter, link_lens))
+    }
+}
+
+impl<T: AsRef<[u8]>> Extend<T> for RedisBloomFilter {
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.insert(&val);
+        }
+    }
+}
+
+/// Austin Appleby's 64 bit MurmurHash2, `MurmurHash64A`.
+fn murmur64a(bytes: &[u8], seed: u64) -> u64 {
+    const M: u64 = 0xc6a4_a793_5bd1_e995;
+    let mut h = seed ^ (bytes.len() as u64).wrapping_mul(M);
+    let mut chunks = bytes.chunks_exact(8);
+    for chunk in &mut chunks {
+        let mut k = u64::from_le_bytes(chunk.try_into().unwrap()).wrapping_mul(M);
+        k ^= k >> 47;
+        h ^= k.wrapping_mul(M);
+        h = h.wrapping_mul(M);
+    }
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        for (i, &byte) in tail.iter().enumerate() {
+            h ^= u64::from(byte) << (8 * i);
+        }
+        h = h.wrapping_mul(M);
+    }
+    h ^= h >> 47;
+    h = h.wrapping_mul(M);
+    h ^ (h >> 47)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_known_values() {
+        // from the reference MurmurHash2.cpp of SMHasher
+        for (input, h1, h2) in [
+            ("", 0x1ab1_1ea5_a7b2_c56e, 0xbbdd_cb5a_b56d_d547),
+            ("a", 0x4292_cee2_27b9_150a, 0x7e9b_5270_31f5_0c11),
+            ("hello", 0x5ba5_b8a5_9803_e699, 0xa7d4_51d5_88a0_c2a4),
+            ("hello world!", 0x6fc0_90f3_e01a_7b8c, 0xac73_5213_3b63_861a),
+            (
+                "The quick brown fox jumps over the lazy dog",
+                0xc7a6_16a2_8f4a_74d6,
+                0xfab0_7743_84f7_4e2a,
+            ),
+        ] {
+            assert_eq!(
+                RedisBloomFilter::hash(input.as_bytes()),
+                [h1, h2],
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn sized_like_reserve() {
+        let filter = RedisBloomFilter::new_with_false_pos(0.01, 100);
+        let link = &filter.links[0];
+        assert_eq!(
+            (link.bits.len(), link.num_bits, link.num_hashes),
+            (120, 960, 7)
+        );
+        let (_, header) = &filter.to_chunks()[0];
+        assert_eq!(header.len(), HEADER_LEN + LINK_LEN);
+        assert_eq!(
+            header[..20],
+            [0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 5, 0, 0, 0, 2, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn scales() {
+        let mut filter = RedisBloomFilter::new_with_false_pos(0.01, 100);
+        for x in 0..1000 {
+            filter.insert(&format!("{x}"));
+        }
+        assert!((0..1000).all(|x| filter.contains(&format!("{x}"))));
+        assert_eq!(filter.links.len(), 4);
+        assert_eq!(filter.capacity(), 1500);
+        assert!(filter.num_items() > 990);
+        assert_eq!(
+            filter.num_items(),
+            filter.links.iter().map(|link| link.num_items).sum::<u64>()
+        );
+        let false_pos = (1000..11_000)
+            .filter(|x| filter.contains(&format!("{x}")))
+            .count();
+        assert!(false_pos < 200);
+
+        let mut filter = RedisBloomFilter::new_with_false_pos(0.01, 100).non_scaling();
+        filter.extend((0..1000).map(|x| format!("{x}")));
+        assert_eq!(filter.links.len(), 1);
+        filter.clear();
+        assert!(!(0..1000).any(|x| filter.contains(&format!("{x}"))));
+    }
+
+    #[test]
+    fn chunks_round_trip() {
+        let mut filter = RedisBloomFilter::new_with_false_pos(0.01, 100).expansion(4);
+        filter.extend((0..600).map(|x| format!("{x}")));
+        assert_eq!(filter.links.len(), 3);
+        let chunks = filter.chunks(64);
+        let num_bytes: usize = filter.links.iter().map(|link| link.bits.len()).sum();
+        assert_eq!(chunks.last().unwrap().0, 1 + num_bytes as i64);
+        assert!(chunks[1..].iter().all(|(_, chunk)| chunk.len() <= 64));
+        assert_eq!(
+            RedisBloomFilter::from_chunks(chunks.clone()),
+            Some(filter.clone())
+        );
+
+        // in any order, with the final reply
+        let mut reordered = chunks.clone();
+        reordered[1..].reverse();
+        reordered.push((0, Vec::new()));
+        assert_eq!(RedisBloomFilter::from_chunks(reordered), Some(filter));
+
+        assert!(RedisBloomFilter::from_chunks(chunks[..chunks.len() - 1].to_vec()).is_none());
+        assert!(RedisBloomFilter::from_chunks(chunks[1..].to_vec()).is_none());
+        let mut header = chunks[0].clone();
+        header.1[12] = OPT_NOROUND as u8;
+        assert!(RedisBloomFilter::from_chunks([header]).is_none());
+    }
+}
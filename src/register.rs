@@ -0,0 +1,207 @@
+use crate::bit_vector::BlockedBitVec;
+use crate::{block_index, get_orginal_hashes, DefaultHasher};
+use std::hash::{BuildHasher, Hash};
+
+/// Odd constants multiplied with an item's hash to select its bit in each `u64` of a block.
+/// The same as in the Parquet split block Bloom filter, extended to 64 bits.
+const SALTS: [u64; 8] = [
+    0x47b6_137b_4497_4d91,
+    0x8824_ad5b_a2b7_289d,
+    0x7054_95c7_2df1_424b,
+    0x9efc_4947_5c6b_fb31,
+    0xa2b7_289d_8824_ad5b,
+    0x2df1_424b_7054_95c7,
+    0x5c6b_fb31_9efc_4947,
+    0x4497_4d91_47b6_137b,
+];
+
+/// A register-blocked (also known as sectorized or split block) Bloom filter.
+///
+/// Like [`BloomFilter`](crate::BloomFilter), `RegisterBlockedBloomFilter` sets and checks bits in one `BLOCK_SIZE_BITS` block per item.
+/// Unlike it, each item sets exactly one bit in each `u64` of its block, as in Impala and Parquet Bloom filters,
+/// so the number of hashes per item is fixed at `BLOCK_SIZE_BITS / 64`. Every bit of every `u64` is derived from one multiplication,
+/// so inserts and membership checks are branchless and the same amount of work for every item.
+///
+/// The trade-off is accuracy: with a fixed number of hashes, a `RegisterBlockedBloomFilter` needs a few more bits per item
+/// than a [`BloomFilter`](crate::BloomFilter) for the same false positive rate, many more for small block sizes.
+/// Since items are not spread evenly across blocks, the actual false positive rate is also somewhat above the target.
+///
+/// Constructed via [`BuilderWithBits::register_blocked`](crate::BuilderWithBits::register_blocked)
+/// or [`BuilderWithFalsePositiveRate::register_blocked`](crate::BuilderWithFalsePositiveRate::register_blocked).
+///
+/// # Examples
+/// ```
+/// use fastbloom::BloomFilter;
+///
+/// let mut filter = BloomFilter::with_false_pos(0.01).block_size_256().register_blocked(1000);
+/// filter.insert("42");
+/// assert!(filter.contains("42"));
+/// assert_eq!(filter.num_hashes(), 4);
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RegisterBlockedBloomFilter<const BLOCK_SIZE_BITS: usize = 512, S = DefaultHasher> {
+    pub(crate) bits: BlockedBitVec<BLOCK_SIZE_BITS>,
+    pub(crate) hasher: S,
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> RegisterBlockedBloomFilter<BLOCK_SIZE_BITS, S> {
+    /// Returns the block index of an item and the hash its bits are derived from.
+    #[inline]
+    fn hashes(&self, val: &(impl Hash + ?Sized)) -> (usize, u64) {
+        let [h1, _] = get_orginal_hashes(&self.hasher, val);
+        // The upper 32 bits select the block, so derive the bits from the lower 32
+        (block_index(self.num_blocks(), h1), h1 & 0xffff_ffff)
+    }
+
+    /// Returns the bit to set in the `i`th `u64` of the item's block.
+    #[inline]
+    fn mask(hash: u64, i: usize) -> u64 {
+        1 << (hash.wrapping_mul(SALTS[i]) >> 58)
+    }
+
+    /// Inserts an element into the Bloom filter.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item may have been previously in the Bloom filter (indicating a potential false positive),
+    /// `false` otherwise.
+    #[inline]
+    pub fn insert(&mut self, val: &(impl Hash + ?Sized)) -> bool {
+        let (index, hash) = self.hashes(val);
+        let block = self.bits.get_block_mut(index);
+        let mut previously_contained = true;
+        for (i, x) in block.iter_mut().enumerate() {
+            let mask = Self::mask(hash, i);
+            previously_contained &= *x & mask == mask;
+            *x |= mask;
+        }
+        previously_contained
+    }
+
+    /// Checks if an element is possibly in the Bloom filter.
+    #[inline]
+    pub fn contains(&self, val: &(impl Hash + ?Sized)) -> bool {
+        let (index, hash) = self.hashes(val);
+        let block = self.bits.get_block(index);
+        block.iter().enumerate().fold(true, |contained, (i, x)| {
+            let mask = Self::mask(hash, i);
+            contained & (x & mask == mask)
+        })
+    }
+
+    /// Returns the number of hashes per item, i.e. the number of `u64`s per block.
+    #[inline]
+    pub fn num_hashes(&self) -> u32 {
+        (BLOCK_SIZE_BITS / 64) as u32
+    }
+
+    /// Returns the total number of in-memory bits supporting the Bloom filter.
+    pub fn num_bits(&self) -> usize {
+        self.num_blocks() * BLOCK_SIZE_BITS
+    }
+
+    /// Returns the total number of in-memory blocks supporting the Bloom filter.
+    /// Each block is `BLOCK_SIZE_BITS` bits.
+    pub fn num_blocks(&self) -> usize {
+        self.bits.num_blocks()
+    }
+
+    /// Returns a `u64` slice of this `RegisterBlockedBloomFilter`’s contents.
+    #[inline]
+    pub fn as_slice(&self) -> &[u64] {
+        self.bits.as_slice()
+    }
+
+    /// Returns the number of bytes this `RegisterBlockedBloomFilter` uses, including its bits.
+    /// See [`BloomFilter::memory_usage`](crate::BloomFilter::memory_usage).
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>() + self.bits.heap_bytes()
+    }
+
+    /// Clear all of the bits in the Bloom filter, removing all items.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.bits.clear();
+    }
+}
+
+impl<T, const BLOCK_SIZE_BITS: usize, S: BuildHasher> Extend<T>
+    for RegisterBlockedBloomFilter<BLOCK_SIZE_BITS, S>
+where
+    T: Hash,
+{
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.insert(&val);
+        }
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> PartialEq
+    for RegisterBlockedBloomFilter<BLOCK_SIZE_BITS, S>
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.bits == other.bits
+    }
+}
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> Eq
+    for RegisterBlockedBloomFilter<BLOCK_SIZE_BITS, S>
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BloomFilter;
+
+    #[test]
+    fn inserts_always_contained() {
+        fn inserts_always_contained_<const N: usize>() {
+            let mut filter = BloomFilter::new_with_false_pos::<N>(0.01)
+                .seed(&42)
+                .register_blocked(1000);
+            for x in 0..1000 {
+                assert!(!filter.contains(&(x + 1000)) || filter.insert(&(x + 1000)));
+                filter.insert(&x);
+                assert!(filter.contains(&x));
+            }
+            assert!((0..1000).all(|x| filter.contains(&x)));
+            assert_eq!(filter.num_hashes() as usize, N / 64);
+        }
+        inserts_always_contained_::<64>();
+        inserts_always_contained_::<128>();
+        inserts_always_contained_::<256>();
+        inserts_always_contained_::<512>();
+    }
+
+    #[test]
+    fn target_fp_is_accurate() {
+        fn target_fp_is_accurate_<const N: usize>() {
+            for fp in [0.1, 0.01, 0.001] {
+                let num_items = 10_000usize;
+                let mut filter = BloomFilter::new_with_false_pos::<N>(fp)
+                    .seed(&42)
+                    .register_blocked(num_items);
+                filter.extend(0..num_items);
+                let false_pos = (num_items..101 * num_items)
+                    .filter(|x| filter.contains(x))
+                    .count();
+                let actual_fp = false_pos as f64 / (100 * num_items) as f64;
+                assert!(actual_fp < 2.0 * fp, "{N} {fp} {actual_fp}");
+            }
+        }
+        target_fp_is_accurate_::<64>();
+        target_fp_is_accurate_::<128>();
+        target_fp_is_accurate_::<256>();
+        target_fp_is_accurate_::<512>();
+    }
+
+    #[test]
+    fn nothing_after_clear() {
+        let mut filter = BloomFilter::with_num_bits(1024).register_blocked();
+        filter.extend(0..100);
+        filter.clear();
+        assert!(!(0..100).any(|x| filter.contains(&x)));
+    }
+}
@@ -0,0 +1,510 @@
+use crate::bit_vector::BlockedBitVec;
+
+/// The number of bytes of metadata RocksDB appends to the bits of a filter block.
+const METADATA_LEN: usize = 5;
+
+/// A Bloom filter in the format of RocksDB's full filter blocks, the "FastLocalBloom" implementation
+/// used by `BlockBasedTableOptions::format_version` 5 and later.
+///
+/// The bits are 512 bit cache lines, and each key sets all its bits in one cache line. Keys are hashed with
+/// the 64 bit XXH3 preview hash RocksDB uses (`GetSliceHash64`): the lower 32 bits select the cache line,
+/// and the upper 32 bits the bits in it. So filter blocks read from SST files can be queried here,
+/// and filter blocks built here are accepted by RocksDB.
+///
+/// A `RocksDbBloomFilter` always has at least one cache line. Legacy Bloom (`format_version` < 5) and Ribbon filter blocks are not supported.
+///
+/// # Examples
+/// ```
+/// use fastbloom::RocksDbBloomFilter;
+///
+/// let mut filter = RocksDbBloomFilter::new_with_bits_per_key(10.0, 1000);
+/// filter.insert("key1");
+///
+/// // the contents of a full filter block, including the metadata trailer
+/// let block = filter.to_bytes();
+/// let filter = RocksDbBloomFilter::from_bytes(&block).unwrap();
+/// assert!(filter.contains("key1"));
+/// assert_eq!(filter.num_hashes(), 6);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RocksDbBloomFilter {
+    bits: BlockedBitVec<512>,
+    num_hashes: u32,
+}
+
+impl RocksDbBloomFilter {
+    /// Creates an empty `RocksDbBloomFilter` sized for `expected_num_items` keys with `bits_per_key` bits each,
+    /// the same way RocksDB's `NewBloomFilterPolicy(bits_per_key)` sizes full filters.
+    ///
+    /// `bits_per_key` is clamped between 1 and 100, and the number of hashes is chosen from it as in RocksDB.
+    pub fn new_with_bits_per_key(bits_per_key: f64, expected_num_items: usize) -> Self {
+        let millibits_per_key = (bits_per_key.clamp(1.0, 100.0) * 1000.0 + 0.500001) as usize;
+        let num_bytes = expected_num_items
+            .saturating_mul(millibits_per_key)
+            .div_ceil(8000);
+        let num_lines = num_bytes.div_ceil(64).max(1);
+        Self::new(num_lines, Self::optimal_hashes(millibits_per_key))
+    }
+
+    fn new(num_lines: usize, num_hashes: u32) -> Self {
+        Self {
+            bits: vec![0; num_lines * 8].into(),
+            num_hashes,
+        }
+    }
+
+    /// Returns the number of hashes RocksDB chooses for `millibits_per_key` thousandths of a bit per key.
+    fn optimal_hashes(millibits_per_key: usize) -> u32 {
+        const THRESHOLDS: [usize; 12] = [
+            2080, 3580, 5100, 6640, 8300, 10070, 11720, 14001, 16050, 18300, 22001, 25501,
+        ];
+        match THRESHOLDS.iter().position(|&t| millibits_per_key <= t) {
+            Some(i) => i as u32 + 1,
+            None if millibits_per_key > 50000 => 24,
+            None => ((millibits_per_key - 1) / 2000 - 1) as u32,
+        }
+    }
+
+    /// Creates a `RocksDbBloomFilter` from the contents of a full filter block, including its 5 byte metadata trailer.
+    ///
+    /// Returns `None` if `block` is not a FastLocalBloom filter with 64 byte cache lines, e.g. an empty,
+    /// legacy Bloom, or Ribbon filter block.
+    pub fn from_bytes(block: &[u8]) -> Option<Self> {
+        let len = block.len().checked_sub(METADATA_LEN)?;
+        let (bits, metadata) = block.split_at(len);
+        // -1: a newer Bloom implementation, 0: FastLocalBloom,
+        // then the number of hashes with 0 in the upper 3 bits for 64 byte cache lines, then 2 reserved bytes
+        let [0xff, 0x00, num_hashes @ 1..=30, 0x00, 0x00] = metadata else {
+            return None;
+        };
+        let lines = bits.chunks_exact(64);
+        if bits.is_empty() || !lines.remainder().is_empty() {
+            return None;
+        }
+        let words = bits
+            .chunks_exact(8)
+            .map(|word| u64::from_le_bytes(word.try_into().unwrap()))
+            .collect::<Vec<_>>();
+        Some(Self {
+            bits: words.into(),
+            num_hashes: u32::from(*num_hashes),
+        })
+    }
+
+    /// Returns the contents of a full filter block for this `RocksDbBloomFilter`, including its 5 byte metadata trailer.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut block: Vec<u8> = self
+            .bits
+            .as_slice()
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect();
+        block.extend_from_slice(&[0xff, 0x00, self.num_hashes as u8, 0x00, 0x00]);
+        block
+    }
+
+    /// Returns RocksDB's 64 bit hash of a key, from which its cache line and bits are derived.
+    #[inline]
+    pub fn hash(key: &(impl AsRef<[u8]> + ?Sized)) -> u64 {
+        xxph3::hash64(key.as_ref())
+    }
+
+    /// Returns the index of the cache line of `hash`.
+    #[inline]
+    fn line_index(&self, hash: u64) -> usize {
+        (((hash & 0xffff_ffff) * self.num_blocks() as u64) >> 32) as usize
+    }
+
+    /// Returns the indexes of the bits of `hash` in its cache line.
+    #[inline]
+    fn bit_indexes(&self, hash: u64) -> impl Iterator<Item = usize> {
+        let h = (hash >> 32) as u32;
+        (0..self.num_hashes).scan(h, |h, _| {
+            let bit_index = (*h >> 23) as usize;
+            *h = h.wrapping_mul(0x9e37_79b9);
+            Some(bit_index)
+        })
+    }
+
+    /// Inserts a key into the Bloom filter.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the key may have been previously in the Bloom filter (indicating a potential false positive),
+    /// `false` otherwise.
+    #[inline]
+    pub fn insert(&mut self, key: &(impl AsRef<[u8]> + ?Sized)) -> bool {
+        self.insert_hash(Self::hash(key))
+    }
+
+    /// Inserts the [`hash`](Self::hash) of a key into the Bloom filter.
+    #[inline]
+    pub fn insert_hash(&mut self, hash: u64) -> bool {
+        let index = self.line_index(hash);
+        let bit_indexes = self.bit_indexes(hash);
+        let line = self.bits.get_block_mut(index);
+        let mut previously_contained = true;
+        for bit_index in bit_indexes {
+            previously_contained &= BlockedBitVec::<512>::set_for_block(line, bit_index);
+        }
+        previously_contained
+    }
+
+    /// Checks if a key is possibly in the Bloom filter.
+    #[inline]
+    pub fn contains(&self, key: &(impl AsRef<[u8]> + ?Sized)) -> bool {
+        self.contains_hash(Self::hash(key))
+    }
+
+    /// Checks if the [`hash`](Self::hash) of a key is possibly in the Bloom filter.
+    #[inline]
+    pub fn contains_hash(&self, hash: u64) -> bool {
+        let line = self.bits.get_block(self.line_index(hash));
+        self.bit_indexes(hash)
+            .all(|bit_index| BlockedBitVec::<512>::check_for_block(line, bit_index))
+    }
+
+    /// Returns the number of hashes per key.
+    #[inline]
+    pub fn num_hashes(&self) -> u32 {
+        self.num_hashes
+    }
+
+    /// Returns the total number of in-memory bits supporting the Bloom filter.
+    pub fn num_bits(&self) -> usize {
+        self.num_blocks() * 512
+    }
+
+    /// Returns the total number of in-memory blocks, i.e. cache lines, supporting the Bloom filter.
+    /// Each block is 512 bits.
+    pub fn num_blocks(&self) -> usize {
+        self.bits.num_blocks()
+    }
+
+    /// Clear all of the bits in the Bloom filter, removing all keys.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.bits.clear();
+    }
+}
+
+impl<T: AsRef<[u8]>> Extend<T> for RocksDbBloomFilter {
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for key in iter {
+            self.insert(&key);
+        }
+    }
+}
+
+/// The 64 bit hash of the XXH3 preview release (xxHash 0.7.2) with seed 0, as modified by RocksDB.
+/// It differs from the final XXH3, so it cannot be taken from an xxHash crate.
+mod xxph3 {
+    const PRIME32_1: u64 = 0x9e37_79b1;
+    const PRIME32_2: u64 = 0x85eb_ca77;
+    const PRIME32_3: u64 = 0xc2b2_ae3d;
+    const PRIME64_1: u64 = 0x9e37_79b1_85eb_ca87;
+    const PRIME64_2: u64 = 0xc2b2_ae3d_27d4_eb4f;
+    const PRIME64_3: u64 = 0x1656_67b1_9e37_79f9;
+    const PRIME64_4: u64 = 0x85eb_ca77_c2b2_ae63;
+    const PRIME64_5: u64 = 0x27d4_eb2f_1656_67c5;
+
+    const SECRET: [u8; 192] = [
+        0xb8, 0xfe, 0x6c, 0x39, 0x23, 0xa4, 0x4b, 0xbe, 0x7c, 0x01, 0x81, 0x2c, 0xf7, 0x21, 0xad,
+        0x1c, 0xde, 0xd4, 0x6d, 0xe9, 0x83, 0x90, 0x97, 0xdb, 0x72, 0x40, 0xa4, 0xa4, 0xb7, 0xb3,
+        0x67, 0x1f, 0xcb, 0x79, 0xe6, 0x4e, 0xcc, 0xc0, 0xe5, 0x78, 0x82, 0x5a, 0xd0, 0x7d, 0xcc,
+        0xff, 0x72, 0x21, 0xb8, 0x08, 0x46, 0x74, 0xf7, 0x43, 0x24, 0x8e, 0xe0, 0x35, 0x90, 0xe6,
+        0x81, 0x3a, 0x26, 0x4c, 0x3c, 0x28, 0x52, 0xbb, 0x91, 0xc3, 0x00, 0xcb, 0x88, 0xd0, 0x65,
+        0x8b, 0x1b, 0x53, 0x2e, 0xa3, 0x71, 0x64, 0x48, 0x97, 0xa2, 0x0d, 0xf9, 0x4e, 0x38, 0x19,
+        0xef, 0x46, 0xa9, 0xde, 0xac, 0xd8, 0xa8, 0xfa, 0x76, 0x3f, 0xe3, 0x9c, 0x34, 0x3f, 0xf9,
+        0xdc, 0xbb, 0xc7, 0xc7, 0x0b, 0x4f, 0x1d, 0x8a, 0x51, 0xe0, 0x4b, 0xcd, 0xb4, 0x59, 0x31,
+        0xc8, 0x9f, 0x7e, 0xc9, 0xd9, 0x78, 0x73, 0x64, 0xea, 0xc5, 0xac, 0x83, 0x34, 0xd3, 0xeb,
+        0xc3, 0xc5, 0x81, 0xa0, 0xff, 0xfa, 0x13, 0x63, 0xeb, 0x17, 0x0d, 0xdd, 0x51, 0xb7, 0xf0,
+        0xda, 0x49, 0xd3, 0x16, 0x55, 0x26, 0x29, 0xd4, 0x68, 0x9e, 0x2b, 0x16, 0xbe, 0x58, 0x7d,
+        0x47, 0xa1, 0xfc, 0x8f, 0xf8, 0xb8, 0xd1, 0x7a, 0xd0, 0x31, 0xce, 0x45, 0xcb, 0x3a, 0x8f,
+        0x95, 0x16, 0x04, 0x28, 0xaf, 0xd7, 0xfb, 0xca, 0xbb, 0x4b, 0x40, 0x7e,
+    ];
+
+    /// The minimum secret size of XXH3, used to place the last bytes of mid-size inputs.
+    const SECRET_SIZE_MIN: usize = 136;
+    const STRIPE_LEN: usize = 64;
+    const SECRET_CONSUME_RATE: usize = 8;
+
+    #[inline]
+    fn read32(bytes: &[u8], i: usize) -> u64 {
+        u64::from(u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap()))
+    }
+
+    #[inline]
+    fn read64(bytes: &[u8], i: usize) -> u64 {
+        u64::from_le_bytes(bytes[i..i + 8].try_into().unwrap())
+    }
+
+    #[inline]
+    fn mul128_fold64(lhs: u64, rhs: u64) -> u64 {
+        let product = u128::from(lhs) * u128::from(rhs);
+        product as u64 ^ (product >> 64) as u64
+    }
+
+    #[inline]
+    fn avalanche(mut h: u64) -> u64 {
+        h ^= h >> 37;
+        h = h.wrapping_mul(PRIME64_3);
+        h ^ (h >> 32)
+    }
+
+    #[inline]
+    fn mix16(input: &[u8], i: usize, secret: usize) -> u64 {
+        mul128_fold64(
+            read64(input, i) ^ read64(&SECRET, secret),
+            read64(input, i + 8) ^ read64(&SECRET, secret + 8),
+        )
+    }
+
+    pub(super) fn hash64(input: &[u8]) -> u64 {
+        let len = input.len();
+        let len64 = len as u64;
+        match len {
+            // RocksDB's modification: hash the seed instead of returning 0
+            0 => mul128_fold64(read64(&SECRET, 0), PRIME64_2),
+            1..=3 => {
+                let combined = u64::from(input[0])
+                    | u64::from(input[len >> 1]) << 8
+                    | u64::from(input[len - 1]) << 16
+                    | len64 << 24;
+                avalanche((combined ^ read32(&SECRET, 0)).wrapping_mul(PRIME64_1))
+            }
+            4..=8 => {
+                let input64 = read32(input, 0) | read32(input, len - 4) << 32;
+                let keyed = input64 ^ read64(&SECRET, 0);
+                let mix = len64.wrapping_add((keyed ^ (keyed >> 51)).wrapping_mul(PRIME32_1));
+                avalanche((mix ^ (mix >> 47)).wrapping_mul(PRIME64_2))
+            }
+            9..=16 => {
+                let lo = read64(input, 0) ^ read64(&SECRET, 0);
+                let hi = read64(input, len - 8) ^ read64(&SECRET, 8);
+                avalanche(
+                    len64
+                        .wrapping_add(lo.wrapping_add(hi))
+                        .wrapping_add(mul128_fold64(lo, hi)),
+                )
+            }
+            17..=128 => {
+                let mut acc = len64.wrapping_mul(PRIME64_1);
+                let rounds = (len - 1) / 32;
+                for i in (0..=rounds).rev() {
+                    acc = acc
+                        .wrapping_add(mix16(input, 16 * i, 32 * i))
+                        .wrapping_add(mix16(input, len - 16 * (i + 1), 32 * i + 16));
+                }
+                avalanche(acc)
+            }
+            129..=240 => {
+                let mut acc = len64.wrapping_mul(PRIME64_1);
+                for i in 0..8 {
+                    acc = acc.wrapping_add(mix16(input, 16 * i, 16 * i));
+                }
+                acc = avalanche(acc);
+                for i in 8..len / 16 {
+                    acc = acc.wrapping_add(mix16(input, 16 * i, 16 * (i - 8) + 3));
+                }
+                acc = acc.wrapping_add(mix16(input, len - 16, SECRET_SIZE_MIN - 17));
+                avalanche(acc)
+            }
+            _ => hash_long(input),
+        }
+    }
+
+    #[inline]
+    fn accumulate_512(acc: &mut [u64; 8], stripe: &[u8], secret: usize) {
+        for (i, x) in acc.iter_mut().enumerate() {
+            let data = read64(stripe, 8 * i);
+            let key = data ^ read64(&SECRET, secret + 8 * i);
+            *x = x
+                .wrapping_add(data)
+                .wrapping_add((key & 0xffff_ffff) * (key >> 32));
+        }
+    }
+
+    #[inline]
+    fn scramble(acc: &mut [u64; 8]) {
+        let secret = SECRET.len() - STRIPE_LEN;
+        for (i, x) in acc.iter_mut().enumerate() {
+            *x = ((*x ^ (*x >> 47)) ^ read64(&SECRET, secret + 8 * i)).wrapping_mul(PRIME32_1);
+        }
+    }
+
+    fn hash_long(input: &[u8]) -> u64 {
+        let len = input.len();
+        let mut acc = [
+            PRIME32_3, PRIME64_1, PRIME64_2, PRIME64_3, PRIME64_4, PRIME32_2, PRIME64_5, PRIME32_1,
+        ];
+        let stripes_per_block = (SECRET.len() - STRIPE_LEN) / SECRET_CONSUME_RATE;
+        let block_len = STRIPE_LEN * stripes_per_block;
+        let num_blocks = len / block_len;
+        for block in input.chunks_exact(block_len) {
+            for (n, stripe) in block.chunks_exact(STRIPE_LEN).enumerate() {
+                accumulate_512(&mut acc, stripe, n * SECRET_CONSUME_RATE);
+            }
+            scramble(&mut acc);
+        }
+        let stripes = input[num_blocks * block_len..].chunks_exact(STRIPE_LEN);
+        let last_stripe_partial = !stripes.remainder().is_empty();
+        for (n, stripe) in stripes.enumerate() {
+            accumulate_512(&mut acc, stripe, n * SECRET_CONSUME_RATE);
+        }
+        if last_stripe_partial {
+            accumulate_512(
+                &mut acc,
+                &input[len - STRIPE_LEN..],
+                SECRET.len() - STRIPE_LEN - 7,
+            );
+        }
+        let mut result = (len as u64).wrapping_mul(PRIME64_1);
+        for (i, pair) in acc.chunks_exact(2).enumerate() {
+            result = result.wrapping_add(mul128_fold64(
+                pair[0] ^ read64(&SECRET, 11 + 16 * i),
+                pair[1] ^ read64(&SECRET, 11 + 16 * i + 8),
+            ));
+        }
+        avalanche(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_small_value_schema() {
+        // from RocksDB's HashTest.Hash64SmallValueSchema
+        for (key, hash) in [
+            (&b""[..], 5999572062939766020),
+            (b"\x08", 583283813901344696),
+            (b"\x9a", 16322991629225003903),
+            (b"\x4d\x76", 6859542833406258115),
+            (b"\x30\x46\x0b", 11404699285340020889),
+            (b"\x67\x53\x81\x1c", 9010661983527562386),
+            (b"\xd0\x7a\x6e\xea\x56", 6384167718754869899),
+            (b"\x5c\x5e\xe1\xa0\x73\x81", 17358142495308219330),
+            (b"\x5c\xbe\x97\x75\x54\x9a\x52", 5716522398211028826),
+            (b"\x31\x1b\x98\x75\x96\x22\xd3\x9a", 9844314944338447628),
+            (
+                b"\x1a\xb5\xd0\xfe\xab\xc3\x61\xb2\x99",
+                13253403748084181481,
+            ),
+            (
+                b"\xbd\x2c\x63\x38\xbf\xe9\x78\xb7\xbf\x15",
+                10551812464348219044,
+            ),
+        ] {
+            assert_eq!(RocksDbBloomFilter::hash(key), hash, "{key:?}");
+        }
+    }
+
+    /// The hashes of all prefixes of `repeat` repeated, shorter than `limit`, encoded as in RocksDB's `Hash64TestDescriptor`.
+    fn hash_descriptor(repeat: &str, limit: usize) -> String {
+        let encode = b"abcdefghijklmnopqrstuvwxyz123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+        let input = repeat.repeat(limit / repeat.len() + 1);
+        (0..limit)
+            .map(|i| encode[(RocksDbBloomFilter::hash(&input[..i]) % 61) as usize] as char)
+            .collect()
+    }
+
+    #[test]
+    fn hash_large_value_schema() {
+        // from RocksDB's HashTest.Hash64LargeValueSchema
+        assert_eq!(
+            hash_descriptor("foo", 430),
+            "cRhyWsY67B6klRA1udmOuiYuX7IthyGBKqbeosz2hzVglWCmQx8nEdnpkvPfYX56Up2OWOTV\
+             lTzfAoYwvtqKzjD8E9xttR2unelbXbIV67NUe6bOO23BxaSFRcA3njGu5cUWfgwOqNoTsszp\
+             uPvKRP6qaUR5VdoBkJUCFIefd7edlNK5mv6JYWaGdwxehg65hTkTmjZoPKxTZo4PLyzbL9U4\
+             xt12ITSfeP2MfBHuLI2z2pDlBb44UQKVMx27LEoAHsdLp3WfWfgH3sdRBRCHm33UxCM4QmE2\
+             xJ7gqSvNwTeH7v9GlC8zWbGroyD3UVNeShMLx29O7tH1biemLULwAHyIw8zdtLMDpEJ8m2ic\
+             l6Lb4fDuuFNAs1GCVUthjK8CV8SWI8Rsz5THSwn5CGhpqUwSZcFknjwWIl5rNCvDxXJqYr"
+        );
+        assert_eq!(
+            hash_descriptor("RocksDB", 430),
+            "c1EeRkukbkb28wLTahwD2sfUhZzaBEnF8SVrxnPVB6A7b8CaAl3UKsDZISF92GSq2wDCukOq\
+             Jgrsp7A3KZhDiLW8dFXp8UPqPxMCRlMdZeVeJ2dJxrmA6cyt99zkQFj7ELbut6jAeVqARFnw\
+             fnWVXOsaLrq7bDCbMcns2DKvTaaqTCLMYxI7nhtLpFN1jR755FRQFcOzrrDbh7QhypjdvlYw\
+             cdAMSZgp9JMHxbM23wPSuH6BOFgxejz35PScZfhDPvTOxIy1jc3MZsWrMC3P324zNolO7JdW\
+             CX2I5UDKjjaEJfxbgVgJIXxtQGlmj2xkO5sPpjULQV4X2HlY7FQleJ4QRaJIB4buhCA4vUTF\
+             eMFlxCIYUpTCsal2qsmnGOWa8WCcefrohMjDj1fjzSvSaQwlpyR1GZHF2uPOoQagiCpHpm"
+        );
+    }
+
+    /// The first `count` false positives among the keys from 1,000,000, as in RocksDB's `FullBloomTest::FirstFPs`.
+    fn first_fps(filter: &RocksDbBloomFilter, count: usize) -> Vec<u32> {
+        (0..1_000_000u32)
+            .filter(|i| filter.contains(&(i + 1_000_000).to_le_bytes()))
+            .take(count)
+            .collect()
+    }
+
+    #[test]
+    fn filter_schema() {
+        // from RocksDB's FullBloomTest.Schema
+        for (bits_per_key, num_keys, num_hashes, fps) in [
+            (5.0, 87, 3, [31, 38, 40, 43, 61, 83, 86, 112, 125, 131]),
+            (2.0, 2087, 1, [11, 13, 17, 25, 29, 30, 35, 37, 45, 53]),
+            (3.0, 2087, 2, [4, 15, 17, 24, 27, 28, 29, 53, 63, 70]),
+            (5.0, 2087, 3, [15, 24, 29, 39, 53, 87, 89, 100, 103, 104]),
+            (
+                8.0,
+                2087,
+                5,
+                [16, 60, 66, 126, 220, 238, 244, 256, 265, 287],
+            ),
+        ] {
+            let mut filter = RocksDbBloomFilter::new_with_bits_per_key(bits_per_key, num_keys);
+            filter.extend((0..num_keys as u32).map(u32::to_le_bytes));
+            assert_eq!(filter.num_hashes(), num_hashes);
+            assert_eq!(first_fps(&filter, 10), fps, "{bits_per_key} {num_keys}");
+            assert!((0..num_keys as u32).all(|i| filter.contains(&i.to_le_bytes())));
+        }
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let mut filter = RocksDbBloomFilter::new_with_bits_per_key(10.0, 1000);
+        filter.extend((0..1000).map(|i| format!("key{i}")));
+        let block = filter.to_bytes();
+        assert_eq!(block.len(), filter.num_bits() / 8 + 5);
+        assert_eq!(block[block.len() - 5..], [0xff, 0x00, 6, 0x00, 0x00]);
+        assert_eq!(RocksDbBloomFilter::from_bytes(&block), Some(filter));
+    }
+
+    #[test]
+    fn rejects_unsupported() {
+        let block = RocksDbBloomFilter::new_with_bits_per_key(10.0, 100).to_bytes();
+        let len = block.len() - 5;
+        assert!(RocksDbBloomFilter::from_bytes(&block[..5]).is_none());
+        assert!(RocksDbBloomFilter::from_bytes(&block[1..]).is_none());
+        // legacy Bloom, Ribbon, 128 byte cache lines, and reserved number of hashes
+        for (i, byte) in [(len, 6), (len, 0xfe), (len + 2, 0x26), (len + 2, 31)] {
+            let mut unsupported = block.clone();
+            unsupported[i] = byte;
+            assert!(RocksDbBloomFilter::from_bytes(&unsupported).is_none());
+        }
+    }
+
+    #[test]
+    fn optimal_hashes() {
+        for (millibits_per_key, num_hashes) in [
+            (1000, 1),
+            (2081, 2),
+            (10000, 6),
+            (25501, 12),
+            (25502, 11),
+            (40000, 18),
+            (50001, 24),
+        ] {
+            assert_eq!(
+                RocksDbBloomFilter::optimal_hashes(millibits_per_key),
+                num_hashes
+            );
+        }
+    }
+}
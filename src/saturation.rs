@@ -0,0 +1,257 @@
+use crate::{BloomFilter, DefaultHasher, FastbloomError};
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+
+/// A [`BloomFilter`] that detects when the fraction of its bits that are set reaches a threshold.
+///
+/// The false positive rate of a filter grows with the fraction of its bits that are set, its fill ratio,
+/// as about `fill_ratio^k` for `k` hashes: a filter sized for its items is about half full, and a filter with more
+/// items than it was sized for fills up, and its false positive rate climbs, without any error.
+/// `SaturationMonitor` counts the bits set by each insert, so that the fill ratio is known exactly at any time,
+/// and reports reaching the threshold in up to three ways: [`is_saturated`](Self::is_saturated), an error
+/// from [`try_insert`](Self::try_insert), and a callback registered with [`on_saturated`](Self::on_saturated).
+///
+/// # Examples
+/// ```
+/// use fastbloom::{BloomFilter, FastbloomError, SaturationMonitor};
+/// use std::sync::atomic::{AtomicBool, Ordering};
+/// use std::sync::Arc;
+///
+/// let alerted = Arc::new(AtomicBool::new(false));
+/// let flag = alerted.clone();
+/// let mut bloom = SaturationMonitor::new(BloomFilter::with_false_pos(0.01).expected_items(100), 0.6)
+///     .on_saturated(move |_fill_ratio| flag.store(true, Ordering::Relaxed));
+///
+/// bloom.extend(0..100);
+/// assert!(!bloom.is_saturated());
+/// bloom.extend(100..1000);
+/// assert!(bloom.is_saturated());
+/// assert!(alerted.load(Ordering::Relaxed));
+/// assert!(matches!(bloom.try_insert(&1000), Err(FastbloomError::Saturated { .. })));
+/// ```
+pub struct SaturationMonitor<const BLOCK_SIZE_BITS: usize = 512, S = DefaultHasher> {
+    filter: BloomFilter<BLOCK_SIZE_BITS, S>,
+    /// The number of bits set in `filter`
+    ones: u64,
+    threshold: f64,
+    on_saturated: Option<Box<dyn FnMut(f64) + Send>>,
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> SaturationMonitor<BLOCK_SIZE_BITS, S> {
+    /// Creates a `SaturationMonitor` of `filter`, including the items already in it, which is saturated when
+    /// at least `threshold` of its bits are set.
+    ///
+    /// # Panics
+    /// Panics if `threshold` is not in `(0, 1]`.
+    pub fn new(filter: BloomFilter<BLOCK_SIZE_BITS, S>, threshold: f64) -> Self {
+        assert!(
+            threshold > 0.0 && threshold <= 1.0,
+            "threshold must be in (0, 1]"
+        );
+        let ones = filter
+            .as_slice()
+            .iter()
+            .map(|x| x.count_ones() as u64)
+            .sum();
+        Self {
+            filter,
+            ones,
+            threshold,
+            on_saturated: None,
+        }
+    }
+
+    /// Registers `callback` to be called with the fill ratio by the insert that saturates the filter.
+    ///
+    /// The callback is called once, when the fill ratio reaches the threshold, and again only if the filter is
+    /// [`clear`](Self::clear)ed and fills up again. It is not called for a filter that is already saturated.
+    pub fn on_saturated(mut self, callback: impl FnMut(f64) + Send + 'static) -> Self {
+        self.on_saturated = Some(Box::new(callback));
+        self
+    }
+
+    /// Inserts an element into the filter, calling the [`on_saturated`](Self::on_saturated) callback
+    /// if the filter becomes saturated.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item may have been previously in the filter (indicating a potential false positive),
+    /// `false` otherwise.
+    pub fn insert(&mut self, val: &(impl Hash + ?Sized)) -> bool {
+        let was_saturated = self.is_saturated();
+        let masks = self.filter.masks(val);
+        let bits = self.filter.as_mut_slice();
+        let mut previously_contained = true;
+        for (i, mask) in masks {
+            let new = mask & !bits[i];
+            previously_contained &= new == 0;
+            self.ones += new.count_ones() as u64;
+            bits[i] |= mask;
+        }
+        if !was_saturated && self.is_saturated() {
+            let fill_ratio = self.fill_ratio();
+            if let Some(callback) = &mut self.on_saturated {
+                callback(fill_ratio);
+            }
+        }
+        previously_contained
+    }
+
+    /// Inserts an element into the filter, unless it is saturated.
+    ///
+    /// # Errors
+    /// Returns [`FastbloomError::Saturated`], leaving the filter unchanged, if the filter is already saturated.
+    pub fn try_insert(&mut self, val: &(impl Hash + ?Sized)) -> Result<bool, FastbloomError> {
+        if self.is_saturated() {
+            return Err(FastbloomError::Saturated {
+                fill_ratio: self.fill_ratio(),
+            });
+        }
+        Ok(self.insert(val))
+    }
+
+    /// Checks if an element is possibly in the filter.
+    #[inline]
+    pub fn contains(&self, val: &(impl Hash + ?Sized)) -> bool {
+        self.filter.contains(val)
+    }
+
+    /// Returns the fraction of the filter's bits that are set.
+    #[inline]
+    pub fn fill_ratio(&self) -> f64 {
+        self.ones as f64 / self.filter.num_bits() as f64
+    }
+
+    /// Returns the fill ratio at which the filter is saturated.
+    #[inline]
+    pub fn threshold(&self) -> f64 {
+        self.threshold
+    }
+
+    /// Returns `true` if at least [`threshold`](Self::threshold) of the filter's bits are set.
+    #[inline]
+    pub fn is_saturated(&self) -> bool {
+        self.fill_ratio() >= self.threshold
+    }
+
+    /// Returns the underlying filter.
+    #[inline]
+    pub fn as_filter(&self) -> &BloomFilter<BLOCK_SIZE_BITS, S> {
+        &self.filter
+    }
+
+    /// Returns the underlying filter.
+    #[inline]
+    pub fn into_inner(self) -> BloomFilter<BLOCK_SIZE_BITS, S> {
+        self.filter
+    }
+
+    /// Returns the number of bytes this `SaturationMonitor` uses, including the bits of the filter and the callback.
+    /// See [`BloomFilter::memory_usage`].
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.filter.heap_bytes()
+            + self
+                .on_saturated
+                .as_ref()
+                .map_or(0, |f| std::mem::size_of_val(&**f))
+    }
+
+    /// Clear all of the bits in the filter, removing all items, so that it is no longer saturated.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.filter.clear();
+        self.ones = 0;
+    }
+}
+
+impl<T, const BLOCK_SIZE_BITS: usize, S: BuildHasher> Extend<T>
+    for SaturationMonitor<BLOCK_SIZE_BITS, S>
+where
+    T: Hash,
+{
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.insert(&val);
+        }
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: fmt::Debug> fmt::Debug
+    for SaturationMonitor<BLOCK_SIZE_BITS, S>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SaturationMonitor")
+            .field("filter", &self.filter)
+            .field("ones", &self.ones)
+            .field("threshold", &self.threshold)
+            .field("on_saturated", &self.on_saturated.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn counts_ones_exactly() {
+        let mut bloom = BloomFilter::with_num_bits(1 << 12).seed(&1).hashes(20);
+        bloom.extend(0..10);
+        let mut monitor = SaturationMonitor::new(bloom, 0.9);
+        for x in 10..200 {
+            let contained = monitor.as_filter().contains(&x);
+            assert_eq!(monitor.insert(&x), contained);
+            let ones: u32 = monitor
+                .as_filter()
+                .as_slice()
+                .iter()
+                .map(|x| x.count_ones())
+                .sum();
+            assert_eq!(monitor.fill_ratio(), ones as f64 / 4096.0);
+        }
+        assert!((0..200).all(|x| monitor.contains(&x)));
+    }
+
+    #[test]
+    fn callback_once_per_saturation() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let log = calls.clone();
+        let mut monitor = SaturationMonitor::new(
+            BloomFilter::with_num_bits(1024).block_size_64().hashes(4),
+            0.25,
+        )
+        .on_saturated(move |fill_ratio| log.lock().unwrap().push(fill_ratio));
+
+        let mut x = 0;
+        while monitor.try_insert(&x).is_ok() {
+            x += 1;
+        }
+        assert!(monitor.fill_ratio() >= 0.25 && monitor.fill_ratio() < 0.3);
+        assert_eq!(*calls.lock().unwrap(), [monitor.fill_ratio()]);
+        let before = monitor.as_filter().clone();
+        let err = monitor.try_insert(&x).unwrap_err();
+        assert_eq!(
+            err,
+            FastbloomError::Saturated {
+                fill_ratio: monitor.fill_ratio()
+            }
+        );
+        assert_eq!(monitor.as_filter(), &before);
+
+        monitor.extend(x..x + 100);
+        assert_eq!(calls.lock().unwrap().len(), 1);
+        monitor.clear();
+        assert!(!monitor.is_saturated());
+        monitor.extend(0..x);
+        assert_eq!(calls.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_threshold() {
+        SaturationMonitor::new(BloomFilter::with_num_bits(1024).hashes(4), 0.0);
+    }
+}
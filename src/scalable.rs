@@ -0,0 +1,417 @@
+use std::hash::{Hash, Hasher};
+use std::num::NonZeroU64;
+use xxhash_rust::xxh3::Xxh3;
+
+const DEFAULT_GROWTH_FACTOR: usize = 2;
+/// About 0.85, but exactly representable.
+const DEFAULT_TIGHTENING_RATIO: f64 = 0.8515625;
+
+#[cfg(feature = "serde")]
+fn default_growth_factor() -> usize {
+    DEFAULT_GROWTH_FACTOR
+}
+
+#[cfg(feature = "serde")]
+fn default_tightening_ratio() -> f64 {
+    DEFAULT_TIGHTENING_RATIO
+}
+
+/// Serializes the bits of a stage as bytes, and deserializes them from bytes or a sequence of `u8`s, like `serde_bytes`.
+#[cfg(feature = "serde")]
+mod bytes {
+    use serde::de::{Deserializer, Error, SeqAccess, Visitor};
+    use serde::Serializer;
+    use std::fmt;
+
+    pub(super) fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(bytes)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Box<[u8]>, D::Error> {
+        struct BytesVisitor;
+
+        impl<'de> Visitor<'de> for BytesVisitor {
+            type Value = Box<[u8]>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("bytes")
+            }
+
+            fn visit_bytes<E: Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+                Ok(v.into())
+            }
+
+            fn visit_byte_buf<E: Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+                Ok(v.into())
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+                while let Some(byte) = seq.next_element()? {
+                    bytes.push(byte);
+                }
+                Ok(bytes.into())
+            }
+        }
+
+        deserializer.deserialize_byte_buf(BytesVisitor)
+    }
+}
+
+/// xxh3 of the bytes an item writes, with integers always little-endian and `usize`s and `isize`s always 64 bits,
+/// so hashes are the same on every platform.
+struct StableHasher(Xxh3);
+
+impl Hasher for StableHasher {
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.0.digest()
+    }
+
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    #[inline]
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    #[inline]
+    fn write_i16(&mut self, i: i16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    #[inline]
+    fn write_i32(&mut self, i: i32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    #[inline]
+    fn write_i64(&mut self, i: i64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    #[inline]
+    fn write_i128(&mut self, i: i128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    #[inline]
+    fn write_isize(&mut self, i: isize) {
+        self.write_i64(i as i64);
+    }
+}
+
+/// Returns the two hashes of an item for double hashing, the second never 0.
+#[inline]
+fn hashes(val: &(impl Hash + ?Sized)) -> (u64, u64) {
+    let mut hasher = StableHasher(Xxh3::new());
+    val.hash(&mut hasher);
+    let h1 = hasher.finish();
+    0u8.hash(&mut hasher);
+    (h1, hasher.finish().max(1))
+}
+
+/// One fixed size Bloom filter of a [`ScalableBloomFilter`], with its bits partitioned into a slice per hash.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Stage {
+    #[cfg_attr(feature = "serde", serde(rename = "b", with = "bytes"))]
+    bits: Box<[u8]>,
+    #[cfg_attr(feature = "serde", serde(rename = "k"))]
+    num_slices: NonZeroU64,
+}
+
+impl Stage {
+    fn new(capacity: usize, fp: f64) -> Self {
+        let num_slices = (1.0 / fp).log2().ceil() as u64;
+        let slice_len = (capacity as f64 / std::f64::consts::LN_2).ceil() as u64;
+        let num_bytes = (num_slices * slice_len).div_ceil(8) as usize;
+        Self {
+            bits: vec![0; num_bytes].into_boxed_slice(),
+            num_slices: NonZeroU64::new(num_slices).unwrap(),
+        }
+    }
+
+    /// Returns the byte and mask of the bits of an item with hashes `h1` and `h2`, one in each slice.
+    #[inline]
+    fn bit_indexes(&self, mut h1: u64, mut h2: u64) -> impl Iterator<Item = (usize, u8)> {
+        let slice_len = self.bits.len() as u64 * 8 / self.num_slices;
+        (0..self.num_slices.get()).map(move |i| {
+            let index = h1 % slice_len + i * slice_len;
+            h1 = h1.wrapping_add(h2);
+            h2 = h2.wrapping_add(i);
+            ((index / 8) as usize, 1 << (index % 8))
+        })
+    }
+
+    #[inline]
+    fn insert(&mut self, h1: u64, h2: u64) {
+        for (byte, mask) in self.bit_indexes(h1, h2) {
+            self.bits[byte] |= mask;
+        }
+    }
+
+    #[inline]
+    fn contains(&self, h1: u64, h2: u64) -> bool {
+        self.bit_indexes(h1, h2)
+            .all(|(byte, mask)| self.bits[byte] & mask != 0)
+    }
+}
+
+/// A scalable Bloom filter, which adds Bloom filters as items are inserted to keep its false positive rate,
+/// with the layout, hashing, and, with the `serde` feature, the serialized form of the `growable-bloom-filter` crate's `GrowableBloom`.
+///
+/// So filters persisted by `growable-bloom-filter` (2.x) can be deserialized and used as is.
+/// Each added Bloom filter has the capacity of the last times the growth factor,
+/// and the false positive rate of the last times the tightening ratio.
+/// Items are hashed with their [`Hash`] implementation, so must be of the same type as the items of the `GrowableBloom`.
+///
+/// # Examples
+/// ```
+/// use fastbloom::ScalableBloomFilter;
+///
+/// let mut filter = ScalableBloomFilter::new_with_false_pos(0.01, 100);
+/// filter.extend(0..1000);
+/// assert!((0..1000).all(|x| filter.contains(&x)));
+/// assert!(filter.capacity() >= 1000);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScalableBloomFilter {
+    #[cfg_attr(feature = "serde", serde(rename = "b"))]
+    stages: Vec<Stage>,
+    #[cfg_attr(feature = "serde", serde(rename = "e"))]
+    fp: f64,
+    #[cfg_attr(feature = "serde", serde(rename = "t"))]
+    expected_num_items: usize,
+    #[cfg_attr(feature = "serde", serde(rename = "i"))]
+    num_items: usize,
+    #[cfg_attr(feature = "serde", serde(rename = "c"))]
+    capacity: usize,
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "g", default = "default_growth_factor")
+    )]
+    growth_factor: usize,
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "r", default = "default_tightening_ratio")
+    )]
+    tightening_ratio: f64,
+}
+
+impl ScalableBloomFilter {
+    /// Creates an empty `ScalableBloomFilter` whose first Bloom filter holds `expected_num_items` at the false positive rate `fp`,
+    /// the same as `GrowableBloom::new(fp, expected_num_items)`.
+    ///
+    /// No memory is allocated until the first item is inserted.
+    ///
+    /// # Panics
+    /// Panics if `fp` is not between 0 and 1, or `expected_num_items` is 0.
+    pub fn new_with_false_pos(fp: f64, expected_num_items: usize) -> Self {
+        assert!(fp > 0.0 && fp < 1.0, "invalid false positive rate: {fp}");
+        assert!(expected_num_items > 0);
+        Self {
+            stages: Vec::new(),
+            fp,
+            expected_num_items,
+            num_items: 0,
+            capacity: 0,
+            growth_factor: DEFAULT_GROWTH_FACTOR,
+            tightening_ratio: DEFAULT_TIGHTENING_RATIO,
+        }
+    }
+
+    /// Sets how many times the capacity of each added Bloom filter is the capacity of the last. The default is 2.
+    ///
+    /// # Panics
+    /// Panics if `growth_factor` is less than 2.
+    pub fn growth_factor(mut self, growth_factor: usize) -> Self {
+        assert!(growth_factor > 1);
+        self.growth_factor = growth_factor;
+        self
+    }
+
+    /// Sets how many times the false positive rate of each added Bloom filter is the rate of the last. The default is about 0.85.
+    ///
+    /// # Panics
+    /// Panics if `tightening_ratio` is not between 0 and 1.
+    pub fn tightening_ratio(mut self, tightening_ratio: f64) -> Self {
+        assert!(tightening_ratio > 0.0 && tightening_ratio < 1.0);
+        self.tightening_ratio = tightening_ratio;
+        self
+    }
+
+    /// Inserts an element into the Bloom filter, adding a Bloom filter if the last is full.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item may have been previously in the Bloom filter (indicating a potential false positive),
+    /// `false` otherwise. This is the opposite of `GrowableBloom::insert`.
+    pub fn insert(&mut self, val: &(impl Hash + ?Sized)) -> bool {
+        let (h1, h2) = hashes(val);
+        if self.stages.iter().any(|stage| stage.contains(h1, h2)) {
+            return true;
+        }
+        if self.num_items >= self.capacity {
+            self.grow();
+        }
+        self.num_items += 1;
+        self.stages.last_mut().unwrap().insert(h1, h2);
+        false
+    }
+
+    /// Checks if an element is possibly in the Bloom filter.
+    pub fn contains(&self, val: &(impl Hash + ?Sized)) -> bool {
+        let (h1, h2) = hashes(val);
+        self.stages.iter().any(|stage| stage.contains(h1, h2))
+    }
+
+    fn grow(&mut self) {
+        let num_stages = self.stages.len() as u32;
+        let fp = self.fp * self.tightening_ratio.powi(num_stages as i32);
+        let capacity = self.expected_num_items * self.growth_factor.pow(num_stages);
+        self.stages.push(Stage::new(capacity, fp));
+        self.capacity += capacity;
+    }
+
+    /// Returns the number of items inserted, not counting those that may have been previously in the Bloom filter.
+    #[inline]
+    pub fn num_items(&self) -> usize {
+        self.num_items
+    }
+
+    /// Returns the number of items the Bloom filter can hold before adding a Bloom filter.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of bytes this `ScalableBloomFilter` uses, including the bits of all of its Bloom filters.
+    /// See [`BloomFilter::memory_usage`](crate::BloomFilter::memory_usage).
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.stages.capacity() * std::mem::size_of::<Stage>()
+            + self
+                .stages
+                .iter()
+                .map(|stage| stage.bits.len())
+                .sum::<usize>()
+    }
+
+    /// Clear all of the Bloom filters, removing all items and freeing their memory.
+    pub fn clear(&mut self) {
+        self.stages.clear();
+        self.num_items = 0;
+        self.capacity = 0;
+    }
+}
+
+impl<T: Hash> Extend<T> for ScalableBloomFilter {
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.insert(&val);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_bits_as_growable_bloom() {
+        let mut filter = ScalableBloomFilter::new_with_false_pos(0.1, 4);
+        filter.extend(0..10u64);
+        filter.insert("hello");
+        filter.insert(&[1u8, 2, 3][..]);
+        let bits = filter
+            .stages
+            .iter()
+            .map(|stage| (&*stage.bits, stage.num_slices.get()))
+            .collect::<Vec<_>>();
+        // from growable-bloom-filter 2.1.1
+        assert_eq!(
+            bits,
+            [
+                (&[102, 132, 171][..], 4),
+                (&[73, 125, 86, 141, 38, 203][..], 4)
+            ]
+        );
+        assert_eq!((filter.num_items(), filter.capacity()), (12, 12));
+    }
+
+    #[test]
+    fn known_false_positives() {
+        let mut filter = ScalableBloomFilter::new_with_false_pos(0.01, 100);
+        filter.extend(0..1000u32);
+        assert!((0..1000u32).all(|x| filter.contains(&x)));
+        assert_eq!((filter.num_items(), filter.capacity()), (979, 1500));
+        // from growable-bloom-filter 2.1.1
+        let false_pos = (1000..3000u32)
+            .filter(|x| filter.contains(x))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            false_pos,
+            [
+                1012, 1025, 1096, 1170, 1197, 1212, 1246, 1249, 1313, 1340, 1349, 1357, 1363, 1408,
+                1477, 1487, 1538, 1551, 1601, 1614, 1653, 1761, 1763, 1776, 1842, 1852, 1931, 1945,
+                1995, 2021, 2183, 2241, 2389, 2398, 2430, 2456, 2462, 2478, 2599, 2620, 2664, 2715,
+                2724, 2735, 2764, 2866
+            ]
+        );
+        let false_pos = (1000..101_000u32).filter(|x| filter.contains(x)).count();
+        assert_eq!(false_pos, 2268);
+        filter.clear();
+        assert_eq!((filter.num_items(), filter.capacity()), (0, 0));
+        assert!(!(0..1000u32).any(|x| filter.contains(&x)));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserializes_growable_bloom() {
+        // serialized by growable-bloom-filter 2.1.1
+        let json = r#"{"b":[{"b":[185,66,39,241,224,190,152,173,128,251],"k":5},{"b":[0,0,8,128,0,0,0,0,0,2,0,0,0,0,16,0,0,0,8],"k":5}],"e":0.05,"t":10,"i":11,"c":30,"g":2,"r":0.8515625}"#;
+        let mut filter: ScalableBloomFilter = serde_json::from_str(json).unwrap();
+        let names = [
+            "alice", "bob", "carol", "dave", "erin", "frank", "grace", "heidi", "ivan", "judy",
+            "mallory", "niaj",
+        ];
+        assert!(names.iter().all(|name| filter.contains(name)));
+        assert_eq!(serde_json::to_string(&filter).unwrap(), json);
+        assert!(!filter.insert("oscar"));
+        assert_eq!(filter.num_items(), 12);
+
+        // growable-bloom-filter 2.0 has no growth factor or tightening ratio
+        let json = r#"{"b":[{"b":[102,132,171],"k":4}],"e":0.1,"t":4,"i":4,"c":4}"#;
+        let filter: ScalableBloomFilter = serde_json::from_str(json).unwrap();
+        assert_eq!(filter.growth_factor, DEFAULT_GROWTH_FACTOR);
+        assert_eq!(filter.tightening_ratio, DEFAULT_TIGHTENING_RATIO);
+    }
+}
@@ -0,0 +1,178 @@
+use crate::hasher::random_seed;
+use std::hash::{BuildHasher, Hasher};
+
+/// Defines a seeded `BuildHasher` and its `Hasher`, which hashes each write with `$hash` seeded by the hash so far.
+///
+/// Integers are written as little-endian bytes, and `usize`s and `isize`s as 64 bits, so hashes are the same on every platform.
+macro_rules! seeded_hasher {
+    ($(#[$meta:meta])* $build:ident, $hasher:ident, $hash:path) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        pub struct $build {
+            seed: u64,
+        }
+
+        impl $build {
+            #[doc = concat!("Creates a `", stringify!($build), "` with `seed`.")]
+            pub fn with_seed(seed: u64) -> Self {
+                Self { seed }
+            }
+        }
+
+        impl Default for $build {
+            /// Uses a random seed.
+            fn default() -> Self {
+                let seed = random_seed();
+                Self::with_seed(u64::from_le_bytes(seed[..8].try_into().unwrap()))
+            }
+        }
+
+        impl BuildHasher for $build {
+            type Hasher = $hasher;
+            #[inline]
+            fn build_hasher(&self) -> Self::Hasher {
+                $hasher(self.seed)
+            }
+        }
+
+        #[doc = concat!("The [`Hasher`] built by [`", stringify!($build), "`].")]
+        #[derive(Clone, Copy, Debug)]
+        pub struct $hasher(u64);
+
+        impl Hasher for $hasher {
+            #[inline]
+            fn finish(&self) -> u64 {
+                self.0
+            }
+            #[inline]
+            fn write(&mut self, bytes: &[u8]) {
+                self.0 = $hash(bytes, self.0);
+            }
+            #[inline]
+            fn write_u16(&mut self, i: u16) {
+                self.write(&i.to_le_bytes());
+            }
+            #[inline]
+            fn write_u32(&mut self, i: u32) {
+                self.write(&i.to_le_bytes());
+            }
+            #[inline]
+            fn write_u64(&mut self, i: u64) {
+                self.write(&i.to_le_bytes());
+            }
+            #[inline]
+            fn write_u128(&mut self, i: u128) {
+                self.write(&i.to_le_bytes());
+            }
+            #[inline]
+            fn write_usize(&mut self, i: usize) {
+                self.write_u64(i as u64);
+            }
+            #[inline]
+            fn write_i16(&mut self, i: i16) {
+                self.write(&i.to_le_bytes());
+            }
+            #[inline]
+            fn write_i32(&mut self, i: i32) {
+                self.write(&i.to_le_bytes());
+            }
+            #[inline]
+            fn write_i64(&mut self, i: i64) {
+                self.write(&i.to_le_bytes());
+            }
+            #[inline]
+            fn write_i128(&mut self, i: i128) {
+                self.write(&i.to_le_bytes());
+            }
+            #[inline]
+            fn write_isize(&mut self, i: isize) {
+                self.write_i64(i as i64);
+            }
+        }
+    };
+}
+
+#[cfg(feature = "xxh3")]
+seeded_hasher!(
+    /// A seeded xxh3 hasher for `BloomFilter`.
+    ///
+    /// Each write of an item is hashed with 64 bit xxh3, seeded by the hash of its previous writes, so an item that
+    /// writes its bytes once, like a `u64`, hashes to `xxh3_64_with_seed` of its little-endian bytes.
+    /// xxh3 mixes every input bit into the whole 64 bit hash, including the upper 32 bits that select the block and
+    /// derive the filter's second hash. It is not keyed: for untrusted input, prefer [`DefaultHasher`](crate::DefaultHasher).
+    ///
+    /// Requires the `xxh3` feature.
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::{BloomFilter, Xxh3};
+    ///
+    /// let bloom = BloomFilter::with_num_bits(1024).hasher(Xxh3::with_seed(7)).items(["42"]);
+    /// assert!(bloom.contains("42"));
+    /// ```
+    Xxh3,
+    Xxh3Hasher,
+    xxhash_rust::xxh3::xxh3_64_with_seed
+);
+
+#[cfg(feature = "wyhash")]
+seeded_hasher!(
+    /// A seeded wyhash hasher for `BloomFilter`.
+    ///
+    /// Each write of an item is hashed with wyhash, seeded by the hash of its previous writes, so an item that
+    /// writes its bytes once, like a `u64`, hashes to `wyhash` of its little-endian bytes.
+    /// wyhash mixes every input bit into the whole 64 bit hash, including the upper 32 bits that select the block and
+    /// derive the filter's second hash. It is not keyed: for untrusted input, prefer [`DefaultHasher`](crate::DefaultHasher).
+    ///
+    /// Requires the `wyhash` feature.
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::{BloomFilter, WyHash};
+    ///
+    /// let bloom = BloomFilter::with_num_bits(1024).hasher(WyHash::with_seed(7)).items(["42"]);
+    /// assert!(bloom.contains("42"));
+    /// ```
+    WyHash,
+    WyHasher,
+    wyhash::wyhash
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BloomFilter;
+
+    fn check_fp<S: BuildHasher>(hasher: S) {
+        let mut bloom = BloomFilter::with_false_pos(0.01)
+            .hasher(hasher)
+            .expected_items(10_000);
+        bloom.extend(0..10_000u64);
+        assert!((0..10_000u64).all(|x| bloom.contains(&x)));
+        let fp = (10_000..110_000u64).filter(|x| bloom.contains(x)).count();
+        assert!(fp < 2_000, "{fp}");
+    }
+
+    #[cfg(feature = "xxh3")]
+    #[test]
+    fn xxh3() {
+        let hasher = Xxh3::with_seed(7);
+        let expected = xxhash_rust::xxh3::xxh3_64_with_seed(&42u64.to_le_bytes(), 7);
+        assert_eq!(hasher.hash_one(42u64), expected);
+        assert_eq!(hasher.hash_one(42usize), expected);
+        assert_ne!(Xxh3::default().hash_one(42u64), expected);
+        check_fp(hasher);
+    }
+
+    #[cfg(feature = "wyhash")]
+    #[test]
+    fn wyhash() {
+        let hasher = WyHash::with_seed(7);
+        let expected = wyhash::wyhash(&42u64.to_le_bytes(), 7);
+        assert_eq!(hasher.hash_one(42u64), expected);
+        assert_eq!(hasher.hash_one(42usize), expected);
+        assert_ne!(WyHash::default().hash_one(42u64), expected);
+        check_fp(hasher);
+    }
+}
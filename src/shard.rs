@@ -0,0 +1,314 @@
+use crate::bit_vector::BlockedBitVec;
+use crate::{
+    get_orginal_hashes, AtomicCounters, BloomFilter, DefaultHasher, IndexStrategy, Prefix,
+};
+use std::hash::{BuildHasher, Hash};
+use std::ops::Range;
+
+/// A contiguous range of the blocks of a [`BloomFilter`], to be placed on its own machine.
+///
+/// Constructed via [`BloomFilter::split_into`], and reassembled into the filter with [`BloomFilter::assemble`].
+/// Each shard keeps the hashing parameters of the whole filter, so it routes items as the filter would:
+/// an item's bits may be in the blocks of more than one shard, given by [`shards_for`](Self::shards_for).
+/// An item is in the filter if it is in every shard it is routed to.
+///
+/// # Examples
+/// ```
+/// use fastbloom::BloomFilter;
+///
+/// let bloom = BloomFilter::with_num_bits(4096).seed(&1).hashes(4);
+/// let mut shards = bloom.split_into(4);
+///
+/// for shard in shards[0].shards_for("42") {
+///     shards[shard].insert("42");
+/// }
+/// assert!(shards[0]
+///     .shards_for("42")
+///     .into_iter()
+///     .all(|shard| shards[shard].contains("42")));
+///
+/// let bloom = BloomFilter::assemble(shards);
+/// assert!(bloom.contains("42"));
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BloomShard<const BLOCK_SIZE_BITS: usize = 512, S = DefaultHasher> {
+    index: usize,
+    num_shards: usize,
+    /// The blocks of the whole filter in this shard.
+    blocks: Range<usize>,
+    /// The bits of `blocks`.
+    bits: Vec<u64>,
+    /// The number of blocks of the whole filter.
+    num_blocks: usize,
+    target_hashes: u64,
+    num_rounds: Option<u64>,
+    num_hashes: u64,
+    index_strategy: IndexStrategy,
+    prefix: Option<Prefix>,
+    hasher: S,
+}
+
+/// Returns the first block of shard `index` of `num_shards` of `num_blocks` blocks.
+fn first_block(index: usize, num_shards: usize, num_blocks: usize) -> usize {
+    (index as u128 * num_blocks as u128 / num_shards as u128) as usize
+}
+
+/// Returns the shard, of `num_shards` of `num_blocks` blocks, containing `block`.
+fn shard_of_block(block: usize, num_shards: usize, num_blocks: usize) -> usize {
+    (((block as u128 + 1) * num_shards as u128 - 1) / num_blocks as u128) as usize
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher + Clone> BloomFilter<BLOCK_SIZE_BITS, S> {
+    /// Splits the filter into `num_shards` [`BloomShard`]s of contiguous, and as even as possible, ranges of its blocks.
+    ///
+    /// # Panics
+    /// Panics if `num_shards` is 0 or greater than the number of blocks, or if the filter is in two-choice mode,
+    /// whose items are inserted into one of two blocks depending on both blocks' bits.
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_num_bits(4096).items([1, 2, 3]);
+    /// let shards = bloom.split_into(3);
+    /// assert_eq!(shards[1].blocks(), 2..5);
+    /// assert_eq!(BloomFilter::assemble(shards), bloom);
+    /// ```
+    pub fn split_into(&self, num_shards: usize) -> Vec<BloomShard<BLOCK_SIZE_BITS, S>> {
+        let num_blocks = self.num_blocks();
+        assert!(num_shards > 0 && num_shards <= num_blocks);
+        assert!(!self.two_choice, "two-choice filters cannot be split");
+        let block_len = BLOCK_SIZE_BITS / 64;
+        (0..num_shards)
+            .map(|index| {
+                let blocks = first_block(index, num_shards, num_blocks)
+                    ..first_block(index + 1, num_shards, num_blocks);
+                BloomShard {
+                    index,
+                    num_shards,
+                    bits: self.as_slice()[blocks.start * block_len..blocks.end * block_len]
+                        .to_vec(),
+                    blocks,
+                    num_blocks,
+                    target_hashes: self.target_hashes,
+                    num_rounds: self.num_rounds,
+                    num_hashes: self.num_hashes,
+                    index_strategy: self.index_strategy,
+                    prefix: self.prefix,
+                    hasher: self.hasher.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// Reassembles a filter from all the shards [`split_into`](Self::split_into) returned, in any order.
+    ///
+    /// # Panics
+    /// Panics if the shards are not all the shards of one split filter.
+    pub fn assemble(shards: impl IntoIterator<Item = BloomShard<BLOCK_SIZE_BITS, S>>) -> Self {
+        let mut shards: Vec<_> = shards.into_iter().collect();
+        shards.sort_by_key(|shard| shard.index);
+        let first = shards.first().expect("no shards to assemble");
+        assert_eq!(shards.len(), first.num_shards);
+        let mut bits = Vec::with_capacity(first.num_blocks * BLOCK_SIZE_BITS / 64);
+        for (index, shard) in shards.iter().enumerate() {
+            assert_eq!(shard.index, index);
+            assert_eq!(shard.num_shards, first.num_shards);
+            assert_eq!(shard.num_blocks, first.num_blocks);
+            assert_eq!(shard.num_hashes, first.num_hashes);
+            assert_eq!(shard.num_rounds, first.num_rounds);
+            assert_eq!(shard.index_strategy, first.index_strategy);
+            bits.extend_from_slice(&shard.bits);
+        }
+        let first = shards.swap_remove(0);
+        BloomFilter {
+            bits: BlockedBitVec::from_storage(bits),
+            target_hashes: first.target_hashes,
+            num_rounds: first.num_rounds,
+            num_hashes: first.num_hashes,
+            two_choice: false,
+            index_strategy: first.index_strategy,
+            prefix: first.prefix,
+            hasher: first.hasher,
+            counters: AtomicCounters::new(),
+        }
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> BloomShard<BLOCK_SIZE_BITS, S> {
+    /// Returns the bits an item sets, by index into `bits` of the whole filter.
+    fn masks(&self, val: &(impl Hash + ?Sized)) -> Vec<(usize, u64)> {
+        let [h1, h2] = get_orginal_hashes(&self.hasher, val);
+        BloomFilter::<BLOCK_SIZE_BITS, S>::hash_masks(
+            self.num_blocks,
+            false,
+            self.index_strategy,
+            self.num_hashes,
+            self.num_rounds,
+            h1,
+            h2,
+        )
+    }
+
+    /// Returns the indexes of the shards with bits of the item, in ascending order.
+    ///
+    /// Items are inserted into, and checked in, each of these shards. Routing only needs the hashing parameters,
+    /// so any shard of a split filter, or a copy of it with its bits cleared, routes for all of them.
+    pub fn shards_for(&self, val: &(impl Hash + ?Sized)) -> Vec<usize> {
+        let block_len = BLOCK_SIZE_BITS / 64;
+        let mut shards: Vec<usize> = self
+            .masks(val)
+            .into_iter()
+            .map(|(i, _)| shard_of_block(i / block_len, self.num_shards, self.num_blocks))
+            .collect();
+        shards.sort_unstable();
+        shards.dedup();
+        shards
+    }
+
+    /// Sets the bits of the item in this shard.
+    ///
+    /// # Returns
+    ///
+    /// `true` if this shard's bits of the item were all previously set, `false` otherwise.
+    pub fn insert(&mut self, val: &(impl Hash + ?Sized)) -> bool {
+        let offset = self.blocks.start * BLOCK_SIZE_BITS / 64;
+        let mut previously_contained = true;
+        for (i, mask) in self.masks(val) {
+            if let Some(word) = i.checked_sub(offset).and_then(|i| self.bits.get_mut(i)) {
+                previously_contained &= *word & mask == mask;
+                *word |= mask;
+            }
+        }
+        previously_contained
+    }
+
+    /// Checks if this shard's bits of the item are all set.
+    ///
+    /// The item is possibly in the filter if this is `true` for every shard in [`shards_for`](Self::shards_for),
+    /// and is `true` for shards without bits of the item.
+    pub fn contains(&self, val: &(impl Hash + ?Sized)) -> bool {
+        let offset = self.blocks.start * BLOCK_SIZE_BITS / 64;
+        self.masks(val).into_iter().all(|(i, mask)| {
+            i.checked_sub(offset)
+                .and_then(|i| self.bits.get(i))
+                .is_none_or(|word| word & mask == mask)
+        })
+    }
+
+    /// Returns the index of this shard.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the number of shards the filter was split into.
+    pub fn num_shards(&self) -> usize {
+        self.num_shards
+    }
+
+    /// Returns the range of the blocks of the whole filter in this shard.
+    pub fn blocks(&self) -> Range<usize> {
+        self.blocks.clone()
+    }
+
+    /// Returns a `u64` slice of this shard's blocks.
+    pub fn as_slice(&self) -> &[u64] {
+        &self.bits
+    }
+
+    /// Returns the number of bytes this `BloomShard` uses, including the bits of its blocks.
+    /// See [`BloomFilter::memory_usage`].
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>() + self.bits.capacity() * std::mem::size_of::<u64>()
+    }
+
+    /// Clear all of the bits in this shard.
+    pub fn clear(&mut self) {
+        self.bits.fill(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_and_assemble() {
+        fn split_and_assemble_<const N: usize>() {
+            let bloom = BloomFilter::new_with_false_pos::<N>(0.01)
+                .seed(&42)
+                .items(0..1000);
+            for num_shards in [1, 2, 3, 7] {
+                let shards = bloom.split_into(num_shards);
+                assert_eq!(shards.len(), num_shards);
+                assert_eq!(shards[0].blocks().start, 0);
+                assert_eq!(shards[num_shards - 1].blocks().end, bloom.num_blocks());
+                assert!(shards
+                    .windows(2)
+                    .all(|w| w[0].blocks().end == w[1].blocks().start));
+                for x in 0..2000 {
+                    let expected = bloom.contains(&x);
+                    let routed = shards[0].shards_for(&x);
+                    assert!(!routed.is_empty());
+                    assert!(routed.iter().all(|&i| i < num_shards));
+                    assert_eq!(routed.iter().all(|&i| shards[i].contains(&x)), expected);
+                }
+                let mut shards = shards;
+                shards.reverse();
+                assert_eq!(BloomFilter::assemble(shards), bloom);
+            }
+        }
+        split_and_assemble_::<64>();
+        split_and_assemble_::<128>();
+        split_and_assemble_::<256>();
+        split_and_assemble_::<512>();
+    }
+
+    #[test]
+    fn insert_into_routed_shards() {
+        let mut bloom = BloomFilter::with_num_bits(1 << 14).seed(&1).hashes(10);
+        let mut shards = bloom.split_into(5);
+        for x in 0..500 {
+            for i in shards[0].shards_for(&x) {
+                shards[i].insert(&x);
+            }
+            bloom.insert(&x);
+        }
+        assert_eq!(BloomFilter::assemble(shards), bloom);
+    }
+
+    #[test]
+    fn shard_of_block_is_inverse() {
+        for num_blocks in [1, 5, 64, 1000] {
+            for num_shards in 1..=num_blocks.min(9) {
+                for index in 0..num_shards {
+                    let start = first_block(index, num_shards, num_blocks);
+                    let end = first_block(index + 1, num_shards, num_blocks);
+                    assert!(start < end);
+                    assert!(
+                        (start..end).all(|b| shard_of_block(b, num_shards, num_blocks) == index)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn assemble_missing_shard() {
+        let bloom = BloomFilter::with_num_bits(4096).items(0..10);
+        let mut shards = bloom.split_into(3);
+        shards.pop();
+        BloomFilter::assemble(shards);
+    }
+
+    #[test]
+    #[should_panic]
+    fn split_two_choice() {
+        BloomFilter::with_num_bits(4096)
+            .two_choice()
+            .hashes(4)
+            .split_into(2);
+    }
+}
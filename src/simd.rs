@@ -0,0 +1,603 @@
+use wide::u64x4;
+
+/// The number of `u64`s in a `u64x4`.
+const LANES: usize = 4;
+
+/// Loads the first 4 `u64`s of `data`.
+#[inline(always)]
+pub(crate) fn load(data: &[u64]) -> u64x4 {
+    u64x4::new([data[0], data[1], data[2], data[3]])
+}
+
+/// Stores `x` into the first 4 `u64`s of `data`.
+#[inline(always)]
+pub(crate) fn store(data: &mut [u64], x: u64x4) {
+    data[..LANES].copy_from_slice(x.as_array_ref());
+}
+
+/// The kernels for single blocks that are chosen at build time: NEON kernels on aarch64, SIMD128 kernels on wasm32
+/// built with `-C target-feature=+simd128`, and otherwise portable ones, which x86-64 falls back to
+/// on CPUs with neither AVX-512 nor AVX2.
+#[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+use aarch64 as block;
+#[cfg(not(any(
+    all(target_arch = "aarch64", target_feature = "neon"),
+    all(target_arch = "wasm32", target_feature = "simd128")
+)))]
+use portable as block;
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+use wasm32 as block;
+
+/// Kernels with `wide` vectors, which are SSE2, AVX2, or WASM SIMD128 instructions depending on the target,
+/// and otherwise scalar.
+#[cfg_attr(
+    any(
+        all(target_arch = "aarch64", target_feature = "neon"),
+        all(target_arch = "wasm32", target_feature = "simd128")
+    ),
+    allow(dead_code)
+)]
+mod portable {
+    use super::{load, store, LANES};
+    use wide::u64x4;
+
+    /// Sets the bits of `other` in `bits`, 256 bits at a time.
+    ///
+    /// So a 512 bit block is two vector ORs, and a 256 bit block is one, rather than a loop over their `u64`s.
+    #[inline]
+    pub(crate) fn set(bits: &mut [u64], other: &[u64]) {
+        let mut chunks = bits.chunks_exact_mut(LANES);
+        let mut other_chunks = other.chunks_exact(LANES);
+        for (x, y) in (&mut chunks).zip(&mut other_chunks) {
+            store(x, load(x) | load(y));
+        }
+        for (x, y) in chunks
+            .into_remainder()
+            .iter_mut()
+            .zip(other_chunks.remainder())
+        {
+            *x |= y;
+        }
+    }
+
+    /// Clears the bits of `bits` that are not set in `other`, 256 bits at a time.
+    #[inline]
+    pub(crate) fn intersect(bits: &mut [u64], other: &[u64]) {
+        let mut chunks = bits.chunks_exact_mut(LANES);
+        let mut other_chunks = other.chunks_exact(LANES);
+        for (x, y) in (&mut chunks).zip(&mut other_chunks) {
+            store(x, load(x) & load(y));
+        }
+        for (x, y) in chunks
+            .into_remainder()
+            .iter_mut()
+            .zip(other_chunks.remainder())
+        {
+            *x &= y;
+        }
+    }
+
+    /// Returns `true` if all bits of `mask` are set in `bits`.
+    ///
+    /// The missing bits of every 256 bits are accumulated and checked once at the end,
+    /// so a whole 512 bit block is checked with a single branch.
+    #[inline]
+    pub(crate) fn contains(bits: &[u64], mask: &[u64]) -> bool {
+        let chunks = bits.chunks_exact(LANES);
+        let mask_chunks = mask.chunks_exact(LANES);
+        let remainder = chunks
+            .remainder()
+            .iter()
+            .zip(mask_chunks.remainder())
+            .fold(0, |missing, (x, y)| missing | (y & !x));
+        let missing = chunks
+            .zip(mask_chunks)
+            .fold(u64x4::splat(remainder), |missing, (x, y)| {
+                missing | (load(y) & !load(x))
+            });
+        missing == u64x4::splat(0)
+    }
+}
+
+/// NEON kernels for aarch64, where NEON is always available, e.g. Graviton and Apple Silicon.
+///
+/// `wide` also uses NEON on aarch64, but compares vectors a lane at a time. These accumulate the
+/// missing bits of a block and check them with a single horizontal max, and OR or AND 128 bits at a time.
+#[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+mod aarch64 {
+    use std::arch::aarch64::*;
+
+    /// The number of `u64`s in a `uint64x2_t`.
+    const LANES: usize = 2;
+
+    /// Sets the bits of `other` in `bits`, 128 bits at a time.
+    #[inline]
+    pub(crate) fn set(bits: &mut [u64], other: &[u64]) {
+        let mut chunks = bits.chunks_exact_mut(LANES);
+        let mut other_chunks = other.chunks_exact(LANES);
+        for (x, y) in (&mut chunks).zip(&mut other_chunks) {
+            // SAFETY: `x` and `y` are 2 `u64`s, and NEON is enabled.
+            unsafe {
+                let data = vorrq_u64(vld1q_u64(x.as_ptr()), vld1q_u64(y.as_ptr()));
+                vst1q_u64(x.as_mut_ptr(), data);
+            }
+        }
+        for (x, y) in chunks
+            .into_remainder()
+            .iter_mut()
+            .zip(other_chunks.remainder())
+        {
+            *x |= y;
+        }
+    }
+
+    /// Clears the bits of `bits` that are not set in `other`, 128 bits at a time.
+    #[inline]
+    pub(crate) fn intersect(bits: &mut [u64], other: &[u64]) {
+        let mut chunks = bits.chunks_exact_mut(LANES);
+        let mut other_chunks = other.chunks_exact(LANES);
+        for (x, y) in (&mut chunks).zip(&mut other_chunks) {
+            // SAFETY: `x` and `y` are 2 `u64`s, and NEON is enabled.
+            unsafe {
+                let data = vandq_u64(vld1q_u64(x.as_ptr()), vld1q_u64(y.as_ptr()));
+                vst1q_u64(x.as_mut_ptr(), data);
+            }
+        }
+        for (x, y) in chunks
+            .into_remainder()
+            .iter_mut()
+            .zip(other_chunks.remainder())
+        {
+            *x &= y;
+        }
+    }
+
+    /// Returns `true` if all bits of `mask` are set in `bits`.
+    #[inline]
+    pub(crate) fn contains(bits: &[u64], mask: &[u64]) -> bool {
+        let chunks = bits.chunks_exact(LANES);
+        let mask_chunks = mask.chunks_exact(LANES);
+        let remainder = chunks
+            .remainder()
+            .iter()
+            .zip(mask_chunks.remainder())
+            .fold(0, |missing, (x, y)| missing | (y & !x));
+        // SAFETY: every chunk is 2 `u64`s, and NEON is enabled.
+        unsafe {
+            let missing =
+                chunks
+                    .zip(mask_chunks)
+                    .fold(vdupq_n_u64(remainder), |missing, (x, y)| {
+                        vorrq_u64(
+                            missing,
+                            vbicq_u64(vld1q_u64(y.as_ptr()), vld1q_u64(x.as_ptr())),
+                        )
+                    });
+            vmaxvq_u32(vreinterpretq_u32_u64(missing)) == 0
+        }
+    }
+}
+
+/// SIMD128 kernels for wasm32, e.g. edge workers, for builds with `-C target-feature=+simd128`.
+///
+/// WebAssembly has no runtime feature detection, so SIMD128 must be enabled at build time;
+/// every current browser and runtime supports it. As for NEON, the missing bits of a block are checked
+/// with a single `v128.any_true`.
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+mod wasm32 {
+    use std::arch::wasm32::*;
+
+    /// The number of `u64`s in a `v128`.
+    const LANES: usize = 2;
+
+    /// Sets the bits of `other` in `bits`, 128 bits at a time.
+    #[inline]
+    pub(crate) fn set(bits: &mut [u64], other: &[u64]) {
+        let mut chunks = bits.chunks_exact_mut(LANES);
+        let mut other_chunks = other.chunks_exact(LANES);
+        for (x, y) in (&mut chunks).zip(&mut other_chunks) {
+            // SAFETY: `x` and `y` are 2 `u64`s, and unaligned loads and stores are allowed.
+            unsafe {
+                let data = v128_or(v128_load(x.as_ptr().cast()), v128_load(y.as_ptr().cast()));
+                v128_store(x.as_mut_ptr().cast(), data);
+            }
+        }
+        for (x, y) in chunks
+            .into_remainder()
+            .iter_mut()
+            .zip(other_chunks.remainder())
+        {
+            *x |= y;
+        }
+    }
+
+    /// Clears the bits of `bits` that are not set in `other`, 128 bits at a time.
+    #[inline]
+    pub(crate) fn intersect(bits: &mut [u64], other: &[u64]) {
+        let mut chunks = bits.chunks_exact_mut(LANES);
+        let mut other_chunks = other.chunks_exact(LANES);
+        for (x, y) in (&mut chunks).zip(&mut other_chunks) {
+            // SAFETY: `x` and `y` are 2 `u64`s, and unaligned loads and stores are allowed.
+            unsafe {
+                let data = v128_and(v128_load(x.as_ptr().cast()), v128_load(y.as_ptr().cast()));
+                v128_store(x.as_mut_ptr().cast(), data);
+            }
+        }
+        for (x, y) in chunks
+            .into_remainder()
+            .iter_mut()
+            .zip(other_chunks.remainder())
+        {
+            *x &= y;
+        }
+    }
+
+    /// Returns `true` if all bits of `mask` are set in `bits`.
+    #[inline]
+    pub(crate) fn contains(bits: &[u64], mask: &[u64]) -> bool {
+        let chunks = bits.chunks_exact(LANES);
+        let mask_chunks = mask.chunks_exact(LANES);
+        let remainder = chunks
+            .remainder()
+            .iter()
+            .zip(mask_chunks.remainder())
+            .fold(0, |missing, (x, y)| missing | (y & !x));
+        // SAFETY: every chunk is 2 `u64`s, and unaligned loads are allowed.
+        unsafe {
+            let missing =
+                chunks
+                    .zip(mask_chunks)
+                    .fold(u64x2_splat(remainder), |missing, (x, y)| {
+                        let (x, y) = (v128_load(x.as_ptr().cast()), v128_load(y.as_ptr().cast()));
+                        v128_or(missing, v128_andnot(y, x))
+                    });
+            !v128_any_true(missing)
+        }
+    }
+}
+
+/// Sets the bits of `other` in the block `bits`, e.g. an item's sparse hash when inserting it.
+///
+/// On x86-64, the widest kernel the CPU supports is selected at runtime, AVX-512 or AVX2,
+/// so builds for a baseline CPU still set a 512 bit block with one or two vector ORs.
+#[inline]
+pub(crate) fn set(bits: &mut [u64], other: &[u64]) {
+    #[cfg(target_arch = "x86_64")]
+    match x86_64::Kernel::detect() {
+        // SAFETY: the CPU supports the kernel's target feature.
+        x86_64::Kernel::Avx512 => return unsafe { x86_64::set_avx512(bits, other) },
+        x86_64::Kernel::Avx2 => return unsafe { x86_64::set_avx2(bits, other) },
+        x86_64::Kernel::Portable => {}
+    }
+    block::set(bits, other)
+}
+
+/// Returns `true` if all bits of `mask` are set in the block `bits`, with a kernel selected as in [`set`].
+#[inline]
+pub(crate) fn contains(bits: &[u64], mask: &[u64]) -> bool {
+    #[cfg(target_arch = "x86_64")]
+    match x86_64::Kernel::detect() {
+        // SAFETY: the CPU supports the kernel's target feature.
+        x86_64::Kernel::Avx512 => return unsafe { x86_64::contains_avx512(bits, mask) },
+        x86_64::Kernel::Avx2 => return unsafe { x86_64::contains_avx2(bits, mask) },
+        x86_64::Kernel::Portable => {}
+    }
+    block::contains(bits, mask)
+}
+
+/// Sets the bits of `other` in `bits`, which may be a whole bit vector, with a kernel selected as in [`set`].
+#[inline]
+pub(crate) fn union(bits: &mut [u64], other: &[u64]) {
+    #[cfg(target_arch = "x86_64")]
+    match x86_64::Kernel::detect() {
+        // SAFETY: the CPU supports the kernel's target feature.
+        x86_64::Kernel::Avx512 => return unsafe { x86_64::union_avx512(bits, other) },
+        x86_64::Kernel::Avx2 => return unsafe { x86_64::union_avx2(bits, other) },
+        x86_64::Kernel::Portable => {}
+    }
+    block::set(bits, other)
+}
+
+/// Clears the bits of `bits` that are not set in `other`, with a kernel selected as in [`union`].
+#[inline]
+pub(crate) fn intersect(bits: &mut [u64], other: &[u64]) {
+    #[cfg(target_arch = "x86_64")]
+    match x86_64::Kernel::detect() {
+        // SAFETY: the CPU supports the kernel's target feature.
+        x86_64::Kernel::Avx512 => return unsafe { x86_64::intersect_avx512(bits, other) },
+        x86_64::Kernel::Avx2 => return unsafe { x86_64::intersect_avx2(bits, other) },
+        x86_64::Kernel::Portable => {}
+    }
+    block::intersect(bits, other)
+}
+
+/// Kernels compiled for x86-64 vector extensions, for CPUs detected to support them at runtime.
+///
+/// The union and intersection kernels are plain loops over `u64`s, which the compiler vectorizes and unrolls
+/// to the width of their target feature. Blocks are too short for the unrolled loops, so the block kernels
+/// use the intrinsics of a 512 or 256 bit vector directly, and check all of a block's missing bits at once,
+/// as the portable kernels do.
+#[cfg(target_arch = "x86_64")]
+mod x86_64 {
+    use std::arch::x86_64::*;
+
+    /// The widest kernels supported by the CPU.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub(super) enum Kernel {
+        Avx512,
+        Avx2,
+        Portable,
+    }
+
+    impl Kernel {
+        /// Returns the widest kernels supported by the CPU. The detection is cached by `std`.
+        #[inline]
+        pub(super) fn detect() -> Self {
+            if is_x86_feature_detected!("avx512f") {
+                Self::Avx512
+            } else if is_x86_feature_detected!("avx2") {
+                Self::Avx2
+            } else {
+                Self::Portable
+            }
+        }
+    }
+
+    macro_rules! kernel {
+        ($name:ident, $feature:literal, $op:tt) => {
+            /// # Safety
+            #[doc = concat!("The CPU must support `", $feature, "`.")]
+            #[target_feature(enable = $feature)]
+            pub(super) unsafe fn $name(bits: &mut [u64], other: &[u64]) {
+                for (x, y) in bits.iter_mut().zip(other) {
+                    *x $op *y;
+                }
+            }
+        };
+    }
+
+    kernel!(union_avx512, "avx512f", |=);
+    kernel!(union_avx2, "avx2", |=);
+    kernel!(intersect_avx512, "avx512f", &=);
+    kernel!(intersect_avx2, "avx2", &=);
+
+    /// Sets the bits of `other` in `bits`, 512 bits at a time.
+    ///
+    /// # Safety
+    /// The CPU must support `avx512f`.
+    #[target_feature(enable = "avx512f")]
+    pub(super) unsafe fn set_avx512(bits: &mut [u64], other: &[u64]) {
+        let mut chunks = bits.chunks_exact_mut(8);
+        let mut other_chunks = other.chunks_exact(8);
+        for (x, y) in (&mut chunks).zip(&mut other_chunks) {
+            // SAFETY: `x` and `y` are 8 `u64`s, and the loads and stores are unaligned.
+            unsafe {
+                let data = _mm512_or_si512(
+                    _mm512_loadu_si512(x.as_ptr().cast()),
+                    _mm512_loadu_si512(y.as_ptr().cast()),
+                );
+                _mm512_storeu_si512(x.as_mut_ptr().cast(), data);
+            }
+        }
+        for (x, y) in chunks
+            .into_remainder()
+            .iter_mut()
+            .zip(other_chunks.remainder())
+        {
+            *x |= y;
+        }
+    }
+
+    /// Sets the bits of `other` in `bits`, 256 bits at a time.
+    ///
+    /// # Safety
+    /// The CPU must support `avx2`.
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn set_avx2(bits: &mut [u64], other: &[u64]) {
+        let mut chunks = bits.chunks_exact_mut(4);
+        let mut other_chunks = other.chunks_exact(4);
+        for (x, y) in (&mut chunks).zip(&mut other_chunks) {
+            // SAFETY: `x` and `y` are 4 `u64`s, and the loads and stores are unaligned.
+            unsafe {
+                let data = _mm256_or_si256(
+                    _mm256_loadu_si256(x.as_ptr().cast()),
+                    _mm256_loadu_si256(y.as_ptr().cast()),
+                );
+                _mm256_storeu_si256(x.as_mut_ptr().cast(), data);
+            }
+        }
+        for (x, y) in chunks
+            .into_remainder()
+            .iter_mut()
+            .zip(other_chunks.remainder())
+        {
+            *x |= y;
+        }
+    }
+
+    /// Returns `true` if all bits of `mask` are set in `bits`, checking 512 bits at a time.
+    ///
+    /// # Safety
+    /// The CPU must support `avx512f`.
+    #[target_feature(enable = "avx512f")]
+    pub(super) unsafe fn contains_avx512(bits: &[u64], mask: &[u64]) -> bool {
+        let chunks = bits.chunks_exact(8);
+        let mask_chunks = mask.chunks_exact(8);
+        let remainder = chunks
+            .remainder()
+            .iter()
+            .zip(mask_chunks.remainder())
+            .fold(0, |missing, (x, y)| missing | (y & !x));
+        let missing =
+            chunks
+                .zip(mask_chunks)
+                .fold(_mm512_set1_epi64(remainder as i64), |missing, (x, y)| {
+                    // SAFETY: `x` and `y` are 8 `u64`s, and the loads are unaligned.
+                    let (x, y) = unsafe {
+                        (
+                            _mm512_loadu_si512(x.as_ptr().cast()),
+                            _mm512_loadu_si512(y.as_ptr().cast()),
+                        )
+                    };
+                    _mm512_or_si512(missing, _mm512_andnot_si512(x, y))
+                });
+        _mm512_test_epi64_mask(missing, missing) == 0
+    }
+
+    /// Returns `true` if all bits of `mask` are set in `bits`, checking 256 bits at a time.
+    ///
+    /// # Safety
+    /// The CPU must support `avx2`.
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn contains_avx2(bits: &[u64], mask: &[u64]) -> bool {
+        let chunks = bits.chunks_exact(4);
+        let mask_chunks = mask.chunks_exact(4);
+        let remainder = chunks
+            .remainder()
+            .iter()
+            .zip(mask_chunks.remainder())
+            .fold(0, |missing, (x, y)| missing | (y & !x));
+        let missing = chunks.zip(mask_chunks).fold(
+            _mm256_set1_epi64x(remainder as i64),
+            |missing, (x, y)| {
+                // SAFETY: `x` and `y` are 4 `u64`s, and the loads are unaligned.
+                let (x, y) = unsafe {
+                    (
+                        _mm256_loadu_si256(x.as_ptr().cast()),
+                        _mm256_loadu_si256(y.as_ptr().cast()),
+                    )
+                };
+                _mm256_or_si256(missing, _mm256_andnot_si256(x, y))
+            },
+        );
+        _mm256_testz_si256(missing, missing) == 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_as_scalar() {
+        let a: Vec<u64> = (0..11u64)
+            .map(|i| i.wrapping_mul(0x9e3779b97f4a7c15))
+            .collect();
+        let b: Vec<u64> = (0..11u64).map(|i| (i << 40) | i).collect();
+
+        let mut or = a.clone();
+        set(&mut or, &b);
+        assert!(or
+            .iter()
+            .zip(a.iter().zip(&b))
+            .all(|(o, (x, y))| *o == x | y));
+        let mut and = a.clone();
+        block::intersect(&mut and, &b);
+        assert!(and
+            .iter()
+            .zip(a.iter().zip(&b))
+            .all(|(o, (x, y))| *o == x & y));
+
+        assert!(contains(&or, &a) && contains(&or, &b));
+        assert!(contains(&a, &and) && contains(&b, &and));
+        for i in 0..a.len() {
+            let mut missing = vec![0; a.len()];
+            missing[i] = !a[i] & (1 << 63 | 1);
+            assert_eq!(contains(&a, &missing), missing[i] == 0);
+        }
+    }
+
+    #[test]
+    fn dispatched_same_as_portable() {
+        let a: Vec<u64> = (0..1001u64)
+            .map(|i| i.wrapping_mul(0x9e3779b97f4a7c15))
+            .collect();
+        let b: Vec<u64> = (0..1001u64).map(|i| (i << 40) | i).collect();
+        let (mut or, mut expected_or) = (a.clone(), a.clone());
+        union(&mut or, &b);
+        set(&mut expected_or, &b);
+        assert_eq!(or, expected_or);
+        let (mut and, mut expected_and) = (a.clone(), a.clone());
+        intersect(&mut and, &b);
+        block::intersect(&mut expected_and, &b);
+        assert_eq!(and, expected_and);
+
+        for len in [1, 2, 4, 8] {
+            let (mut set_bits, mut expected_set) = (a[..len].to_vec(), a[..len].to_vec());
+            set(&mut set_bits, &b[..len]);
+            block::set(&mut expected_set, &b[..len]);
+            assert_eq!(set_bits, expected_set);
+            for (bits, mask) in [(&a, &b), (&or, &a), (&or, &b), (&a, &and)] {
+                assert_eq!(
+                    contains(&bits[..len], &mask[..len]),
+                    block::contains(&bits[..len], &mask[..len])
+                );
+            }
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn x86_64_kernels() {
+        let a: Vec<u64> = (0..1001u64)
+            .map(|i| i.wrapping_mul(0x9e3779b97f4a7c15))
+            .collect();
+        let b: Vec<u64> = (0..1001u64).map(|i| (i << 40) | i).collect();
+        let or: Vec<u64> = a.iter().zip(&b).map(|(x, y)| x | y).collect();
+        let and: Vec<u64> = a.iter().zip(&b).map(|(x, y)| x & y).collect();
+        type Kernel = unsafe fn(&mut [u64], &[u64]);
+        let kernels: [(&str, Kernel, &[u64]); 4] = [
+            ("avx2", x86_64::union_avx2, &or),
+            ("avx2", x86_64::intersect_avx2, &and),
+            ("avx512f", x86_64::union_avx512, &or),
+            ("avx512f", x86_64::intersect_avx512, &and),
+        ];
+        for (feature, kernel, expected) in kernels {
+            let supported = match feature {
+                "avx2" => is_x86_feature_detected!("avx2"),
+                _ => is_x86_feature_detected!("avx512f"),
+            };
+            if supported {
+                let mut bits = a.clone();
+                // SAFETY: the CPU supports `feature`.
+                unsafe { kernel(&mut bits, &b) };
+                assert_eq!(bits, expected);
+            }
+        }
+
+        type SetKernel = unsafe fn(&mut [u64], &[u64]);
+        type ContainsKernel = unsafe fn(&[u64], &[u64]) -> bool;
+        let block_kernels: [(bool, SetKernel, ContainsKernel); 2] = [
+            (
+                is_x86_feature_detected!("avx2"),
+                x86_64::set_avx2,
+                x86_64::contains_avx2,
+            ),
+            (
+                is_x86_feature_detected!("avx512f"),
+                x86_64::set_avx512,
+                x86_64::contains_avx512,
+            ),
+        ];
+        for (supported, set_kernel, contains_kernel) in block_kernels {
+            if !supported {
+                continue;
+            }
+            for len in [1, 2, 4, 8, 11] {
+                let mut bits = a[..len].to_vec();
+                // SAFETY: the CPU supports the kernels' target feature.
+                unsafe {
+                    set_kernel(&mut bits, &b[..len]);
+                    assert_eq!(bits, or[..len]);
+                    assert!(contains_kernel(&bits, &a[..len]));
+                    assert!(contains_kernel(&bits, &b[..len]));
+                    for i in 0..len {
+                        let mut missing = vec![0; len];
+                        missing[i] = !a[i] & (1 << 63 | 1);
+                        assert_eq!(contains_kernel(&a[..len], &missing), missing[i] == 0);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use crate::hasher::random_seed;
+use siphasher::sip::SipHasher13;
+use std::hash::{Hash, Hasher};
+
+/// The largest prime below 2^64, which the `bloomfilter` crate reduces hashes after the first two by.
+const LARGEST_U64_PRIME: u64 = 0xffff_ffff_ffff_ffc5;
+
+/// A Bloom filter with the layout and hashing of the `bloomfilter` crate's `Bloom` (1.x),
+/// so filters persisted with it can be loaded and queried, and written back.
+///
+/// A `Bloom` is persisted as its bitmap (`Bloom::bitmap`), number of bits (`Bloom::number_of_bits`),
+/// number of hashes (`Bloom::number_of_hash_functions`), and the keys of its two SipHash-1-3 hashers (`Bloom::sip_keys`),
+/// which are loaded with [`from_existing`](Self::from_existing), the same as `Bloom::from_existing`.
+/// Items are hashed with their [`Hash`] implementation, so must be of the same type as the items of the `Bloom`.
+///
+/// # Examples
+/// ```
+/// use fastbloom::SipBloomFilter;
+///
+/// // persisted from bloomfilter::Bloom<str>
+/// # let mut bloom = SipBloomFilter::new_with_false_pos(0.01, 100);
+/// # bloom.insert("alice");
+/// # let (bitmap, num_bits, num_hashes, sip_keys) =
+/// #     (bloom.bitmap().to_vec(), bloom.num_bits(), bloom.num_hashes(), bloom.sip_keys());
+/// let filter = SipBloomFilter::from_existing(&bitmap, num_bits, num_hashes, sip_keys).unwrap();
+/// assert!(filter.contains("alice"));
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SipBloomFilter {
+    bitmap: Vec<u8>,
+    num_bits: u64,
+    num_hashes: u32,
+    sips: [SipHasher13; 2],
+}
+
+impl SipBloomFilter {
+    /// Creates an empty `SipBloomFilter` with random keys sized for `expected_num_items` to meet the false positive rate `fp`,
+    /// the same way as `Bloom::new_for_fp_rate(items_count, fp_p)`.
+    ///
+    /// # Panics
+    /// Panics if `fp` is not between 0 and 1.
+    pub fn new_with_false_pos(fp: f64, expected_num_items: usize) -> Self {
+        assert!(fp > 0.0 && fp < 1.0, "invalid false positive rate: {fp}");
+        let n = expected_num_items.max(1) as f64;
+        let ln2 = std::f64::consts::LN_2;
+        let num_bytes = ((n * fp.ln() / (-8.0 * ln2 * ln2)).ceil() as usize).max(1);
+        let num_bits = num_bytes as u64 * 8;
+        let num_hashes = ((num_bits as f64 / n * ln2).ceil() as u32).max(1);
+        Self {
+            bitmap: vec![0; num_bytes],
+            num_bits,
+            num_hashes,
+            sips: [
+                SipHasher13::new_with_key(&random_seed()),
+                SipHasher13::new_with_key(&random_seed()),
+            ],
+        }
+    }
+
+    /// Creates a `SipBloomFilter` from the persisted parts of a `Bloom`, the same as `Bloom::from_existing`.
+    ///
+    /// Returns `None` if there are no bits, or `bitmap` has fewer than `num_bits` bits.
+    pub fn from_existing(
+        bitmap: &[u8],
+        num_bits: u64,
+        num_hashes: u32,
+        sip_keys: [(u64, u64); 2],
+    ) -> Option<Self> {
+        if num_bits == 0 || (bitmap.len() as u64).checked_mul(8)? < num_bits {
+            return None;
+        }
+        Some(Self {
+            bitmap: bitmap.to_vec(),
+            num_bits,
+            num_hashes,
+            sips: sip_keys.map(|(k0, k1)| SipHasher13::new_with_keys(k0, k1)),
+        })
+    }
+
+    /// Returns the bitmap of the Bloom filter, as `Bloom::bitmap`: the first bit is the high-order bit of the first byte.
+    #[inline]
+    pub fn bitmap(&self) -> &[u8] {
+        &self.bitmap
+    }
+
+    /// Returns the keys of the two hashers, as `Bloom::sip_keys`.
+    pub fn sip_keys(&self) -> [(u64, u64); 2] {
+        [self.sips[0].keys(), self.sips[1].keys()]
+    }
+
+    /// Returns the indexes of the bits of an item, as `Bloom::set` derives them from its two hashes.
+    #[inline]
+    fn bit_indexes(&self, val: &(impl Hash + ?Sized)) -> impl Iterator<Item = u64> {
+        let [h1, h2] = self.sips.map(|mut sip| {
+            val.hash(&mut sip);
+            sip.finish()
+        });
+        let num_bits = self.num_bits;
+        (0..u64::from(self.num_hashes)).map(move |i| {
+            let hash = match i {
+                0 => h1,
+                1 => h2,
+                _ => h1.wrapping_add(i.wrapping_mul(h2)) % LARGEST_U64_PRIME,
+            };
+            hash % num_bits
+        })
+    }
+
+    /// Inserts an element into the Bloom filter, like `Bloom::check_and_set`.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item may have been previously in the Bloom filter (indicating a potential false positive),
+    /// `false` otherwise.
+    #[inline]
+    pub fn insert(&mut self, val: &(impl Hash + ?Sized)) -> bool {
+        let mut previously_contained = true;
+        for index in self.bit_indexes(val).collect::<Vec<_>>() {
+            let bit = 0x80 >> (index % 8);
+            previously_contained &= self.bitmap[(index / 8) as usize] & bit != 0;
+            self.bitmap[(index / 8) as usize] |= bit;
+        }
+        previously_contained
+    }
+
+    /// Checks if an element is possibly in the Bloom filter, like `Bloom::check`.
+    #[inline]
+    pub fn contains(&self, val: &(impl Hash + ?Sized)) -> bool {
+        self.bit_indexes(val)
+            .all(|index| self.bitmap[(index / 8) as usize] & (0x80 >> (index % 8)) != 0)
+    }
+
+    /// Returns the number of hashes per item.
+    #[inline]
+    pub fn num_hashes(&self) -> u32 {
+        self.num_hashes
+    }
+
+    /// Returns the total number of in-memory bits supporting the Bloom filter.
+    #[inline]
+    pub fn num_bits(&self) -> u64 {
+        self.num_bits
+    }
+
+    /// Returns the number of bytes this `SipBloomFilter` uses, including its bits.
+    /// See [`BloomFilter::memory_usage`](crate::BloomFilter::memory_usage).
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>() + self.bitmap.capacity()
+    }
+
+    /// Clear all of the bits in the Bloom filter, removing all items.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.bitmap.fill(0);
+    }
+}
+
+impl PartialEq for SipBloomFilter {
+    fn eq(&self, other: &Self) -> bool {
+        self.bitmap == other.bitmap
+            && self.num_bits == other.num_bits
+            && self.num_hashes == other.num_hashes
+            && self.sip_keys() == other.sip_keys()
+    }
+}
+impl Eq for SipBloomFilter {}
+
+impl<T: Hash> Extend<T> for SipBloomFilter {
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.insert(&val);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIP_KEYS: [(u64, u64); 2] = [(1, 2), (3, 4)];
+
+    #[test]
+    fn sized_like_bloom() {
+        let filter = SipBloomFilter::new_with_false_pos(0.01, 100);
+        assert_eq!(filter.bitmap().len(), 120);
+        assert_eq!(filter.num_bits(), 960);
+        assert_eq!(filter.num_hashes(), 7);
+        assert_ne!(filter.sip_keys()[0], filter.sip_keys()[1]);
+    }
+
+    #[test]
+    fn first_bit_is_high_order() {
+        let mut filter = SipBloomFilter::from_existing(&[0; 16], 125, 1, SIP_KEYS).unwrap();
+        assert!(!filter.insert("alice"));
+        let mut sip = SipHasher13::new_with_keys(1, 2);
+        "alice".hash(&mut sip);
+        let index = sip.finish() % 125;
+        let mut bitmap = [0; 16];
+        bitmap[index as usize / 8] = 0x80 >> (index % 8);
+        assert_eq!(filter.bitmap(), bitmap);
+        assert!(filter.insert("alice"));
+    }
+
+    #[test]
+    fn inserts_always_contained() {
+        let mut filter = SipBloomFilter::new_with_false_pos(0.01, 1000);
+        filter.extend(0..1000u64);
+        assert!((0..1000u64).all(|x| filter.contains(&x)));
+        let false_pos = (1000..11_000u64).filter(|x| filter.contains(x)).count();
+        assert!(false_pos < 200);
+        filter.clear();
+        assert!(!(0..1000u64).any(|x| filter.contains(&x)));
+    }
+
+    #[test]
+    fn from_existing_round_trip() {
+        let mut filter = SipBloomFilter::from_existing(&[0; 120], 960, 7, SIP_KEYS).unwrap();
+        filter.extend(["a", "bb", "ccc"]);
+        let loaded = SipBloomFilter::from_existing(
+            filter.bitmap(),
+            filter.num_bits(),
+            filter.num_hashes(),
+            filter.sip_keys(),
+        );
+        assert_eq!(loaded.as_ref(), Some(&filter));
+        assert!(["a", "bb", "ccc"]
+            .iter()
+            .all(|x| loaded.as_ref().unwrap().contains(x)));
+        assert!(SipBloomFilter::from_existing(&[0; 120], 961, 7, SIP_KEYS).is_none());
+        assert!(SipBloomFilter::from_existing(&[], 0, 7, SIP_KEYS).is_none());
+    }
+}
@@ -0,0 +1,263 @@
+use crate::murmur3::{fmix32, mix_h1_32, mix_k1_32};
+
+/// The only version of the `BloomFilterImpl` serialized form.
+const VERSION: i32 = 1;
+
+/// A Bloom filter in the serialized form of Apache Spark's `BloomFilterImpl`, as built by `DataFrame.stat.bloomFilter`,
+/// written by `BloomFilter#writeTo`, and read by `BloomFilter.readFrom`.
+///
+/// Like Spark, strings and binary values are inserted with [`insert`](Self::insert) as their UTF-8 bytes or the bytes themselves,
+/// and `Long`, `Integer`, `Short`, and `Byte` values with [`insert_long`](Self::insert_long) as `i64`s.
+/// Items are hashed with Spark's `Murmur3_x86_32`, which differs from MurmurHash3 for byte strings whose length is not a multiple of 4.
+///
+/// # Examples
+/// ```
+/// use fastbloom::SparkBloomFilter;
+///
+/// let mut filter = SparkBloomFilter::new_with_false_pos(0.03, 1000);
+/// filter.insert("alice"); // a StringType column
+/// filter.insert_long(42); // a LongType or IntegerType column
+///
+/// // written by BloomFilter#writeTo
+/// let bytes = filter.to_bytes();
+/// let filter = SparkBloomFilter::from_bytes(&bytes).unwrap();
+/// assert!(filter.contains("alice"));
+/// assert!(filter.contains_long(42));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SparkBloomFilter {
+    bits: Vec<u64>,
+    num_hashes: u32,
+}
+
+impl SparkBloomFilter {
+    /// Creates an empty `SparkBloomFilter` sized for `expected_num_items` to meet the false positive rate `fp`,
+    /// the same way as Spark's `BloomFilter.create(expectedNumItems, fpp)`.
+    ///
+    /// # Panics
+    /// Panics if `fp` is not between 0 and 1.
+    pub fn new_with_false_pos(fp: f64, expected_num_items: usize) -> Self {
+        assert!(fp > 0.0 && fp < 1.0, "invalid false positive rate: {fp}");
+        let n = expected_num_items.max(1) as f64;
+        let ln2 = f64::ln(2.0);
+        let num_bits = ((-n * fp.ln() / (ln2 * ln2)) as usize).max(1);
+        let num_hashes = ((num_bits as f64 / n * ln2).round() as u32).max(1);
+        Self {
+            bits: vec![0; num_bits.div_ceil(64)],
+            num_hashes,
+        }
+    }
+
+    /// Creates a `SparkBloomFilter` from its serialized form, as written by `BloomFilter#writeTo`.
+    /// Any bytes after the filter are ignored.
+    ///
+    /// Returns `None` if the bytes are truncated, the version is unsupported, or there are no bits.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let (version, rest) = bytes.split_first_chunk::<4>()?;
+        if i32::from_be_bytes(*version) != VERSION {
+            return None;
+        }
+        let (num_hashes, rest) = rest.split_first_chunk::<4>()?;
+        let num_hashes = u32::try_from(i32::from_be_bytes(*num_hashes)).ok()?;
+        let (len, rest) = rest.split_first_chunk::<4>()?;
+        let len = usize::try_from(i32::from_be_bytes(*len)).ok()?;
+        if len == 0 {
+            return None;
+        }
+        let bits = rest
+            .get(..len.checked_mul(8)?)?
+            .chunks_exact(8)
+            .map(|word| u64::from_be_bytes(word.try_into().unwrap()))
+            .collect();
+        Some(Self { bits, num_hashes })
+    }
+
+    /// Returns the serialized form of this `SparkBloomFilter`, as read by `BloomFilter.readFrom`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(12 + 8 * self.bits.len());
+        bytes.extend_from_slice(&VERSION.to_be_bytes());
+        bytes.extend_from_slice(&(self.num_hashes as i32).to_be_bytes());
+        bytes.extend_from_slice(&(self.bits.len() as i32).to_be_bytes());
+        for word in &self.bits {
+            bytes.extend_from_slice(&word.to_be_bytes());
+        }
+        bytes
+    }
+
+    /// Spark's `Murmur3_x86_32.hashUnsafeBytes`: MurmurHash3, except that each trailing byte is mixed
+    /// as a whole sign-extended block.
+    fn hash_bytes(bytes: &[u8], seed: u32) -> u32 {
+        let mut blocks = bytes.chunks_exact(4);
+        let mut h1 = seed;
+        for block in &mut blocks {
+            h1 = mix_h1_32(h1, mix_k1_32(u32::from_le_bytes(block.try_into().unwrap())));
+        }
+        for &byte in blocks.remainder() {
+            h1 = mix_h1_32(h1, mix_k1_32(byte as i8 as u32));
+        }
+        fmix32(h1, bytes.len())
+    }
+
+    /// Spark's `Murmur3_x86_32.hashLong`, MurmurHash3 of the 8 little-endian bytes of `val`.
+    fn hash_long(val: i64, seed: u32) -> u32 {
+        let h1 = mix_h1_32(seed, mix_k1_32(val as u32));
+        let h1 = mix_h1_32(h1, mix_k1_32((val >> 32) as u32));
+        fmix32(h1, 8)
+    }
+
+    /// Returns the indexes of the bits of an item with hashes `h1` and `h2`.
+    #[inline]
+    fn bit_indexes(&self, h1: u32, h2: u32) -> impl Iterator<Item = usize> {
+        let num_bits = self.num_bits() as u64;
+        (1..=self.num_hashes).map(move |i| {
+            let combined = (h1 as i32).wrapping_add((i as i32).wrapping_mul(h2 as i32));
+            let combined = if combined < 0 { !combined } else { combined };
+            (combined as u64 % num_bits) as usize
+        })
+    }
+
+    #[inline]
+    fn insert_hashes(&mut self, h1: u32, h2: u32) -> bool {
+        let mut previously_contained = true;
+        for index in self.bit_indexes(h1, h2).collect::<Vec<_>>() {
+            let bit = 1 << (index % 64);
+            previously_contained &= self.bits[index / 64] & bit != 0;
+            self.bits[index / 64] |= bit;
+        }
+        previously_contained
+    }
+
+    #[inline]
+    fn contains_hashes(&self, h1: u32, h2: u32) -> bool {
+        self.bit_indexes(h1, h2)
+            .all(|index| self.bits[index / 64] & (1 << (index % 64)) != 0)
+    }
+
+    /// Inserts a binary value, or the UTF-8 bytes of a string, into the Bloom filter, like Spark's `putBinary` and `putString`.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item may have been previously in the Bloom filter (indicating a potential false positive),
+    /// `false` otherwise.
+    #[inline]
+    pub fn insert(&mut self, val: &(impl AsRef<[u8]> + ?Sized)) -> bool {
+        let val = val.as_ref();
+        let h1 = Self::hash_bytes(val, 0);
+        self.insert_hashes(h1, Self::hash_bytes(val, h1))
+    }
+
+    /// Inserts an integral value into the Bloom filter, like Spark's `putLong`.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item may have been previously in the Bloom filter (indicating a potential false positive),
+    /// `false` otherwise.
+    #[inline]
+    pub fn insert_long(&mut self, val: i64) -> bool {
+        let h1 = Self::hash_long(val, 0);
+        self.insert_hashes(h1, Self::hash_long(val, h1))
+    }
+
+    /// Checks if a binary value, or the UTF-8 bytes of a string, is possibly in the Bloom filter.
+    #[inline]
+    pub fn contains(&self, val: &(impl AsRef<[u8]> + ?Sized)) -> bool {
+        let val = val.as_ref();
+        let h1 = Self::hash_bytes(val, 0);
+        self.contains_hashes(h1, Self::hash_bytes(val, h1))
+    }
+
+    /// Checks if an integral value is possibly in the Bloom filter.
+    #[inline]
+    pub fn contains_long(&self, val: i64) -> bool {
+        let h1 = Self::hash_long(val, 0);
+        self.contains_hashes(h1, Self::hash_long(val, h1))
+    }
+
+    /// Returns the number of hashes per item.
+    #[inline]
+    pub fn num_hashes(&self) -> u32 {
+        self.num_hashes
+    }
+
+    /// Returns the total number of in-memory bits supporting the Bloom filter.
+    pub fn num_bits(&self) -> usize {
+        self.bits.len() * 64
+    }
+
+    /// Returns a `u64` slice of this `SparkBloomFilter`’s contents.
+    #[inline]
+    pub fn as_slice(&self) -> &[u64] {
+        &self.bits
+    }
+
+    /// Clear all of the bits in the Bloom filter, removing all items.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.bits.fill(0);
+    }
+}
+
+impl<T: AsRef<[u8]>> Extend<T> for SparkBloomFilter {
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.insert(&val);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_known_values() {
+        // from Spark's Murmur3_x86_32Suite
+        for (val, hash) in [
+            (0, 1669671676),
+            (-42, -846261623),
+            (42, 1871679806),
+            (i64::MIN, 1366273829),
+            (i64::MAX, -2106506049),
+        ] {
+            assert_eq!(SparkBloomFilter::hash_long(val, 0) as i32, hash, "{val}");
+        }
+        // MurmurHash3 for lengths that are a multiple of 4
+        assert_eq!(SparkBloomFilter::hash_bytes(b"", 0), 0);
+        assert_eq!(SparkBloomFilter::hash_bytes(b"", 1), 0x514e_28b7);
+        assert_eq!(SparkBloomFilter::hash_bytes(b"test", 0), 0xba6b_d213);
+        // each trailing byte is mixed as a sign-extended block
+        let mut h1 = 0;
+        for byte in [b'a', 0xff] {
+            h1 = mix_h1_32(h1, mix_k1_32(byte as i8 as u32));
+        }
+        assert_eq!(SparkBloomFilter::hash_bytes(b"a\xff", 0), fmix32(h1, 2));
+    }
+
+    #[test]
+    fn inserts_always_contained() {
+        let mut filter = SparkBloomFilter::new_with_false_pos(0.03, 2000);
+        for x in 0..1000 {
+            filter.insert(&format!("{x}"));
+            filter.insert_long(x);
+        }
+        assert!((0..1000).all(|x| filter.contains(&format!("{x}")) && filter.contains_long(x)));
+        let false_pos = (1000..11_000).filter(|&x| filter.contains_long(x)).count();
+        assert!(false_pos < 600);
+        filter.clear();
+        assert!(!(0..1000).any(|x| filter.contains_long(x)));
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let mut filter = SparkBloomFilter::new_with_false_pos(0.03, 100);
+        filter.extend(["a", "bb", "ccc"]);
+        let bytes = filter.to_bytes();
+        assert_eq!(bytes[..12], [0, 0, 0, 1, 0, 0, 0, 5, 0, 0, 0, 12]);
+        assert_eq!(SparkBloomFilter::from_bytes(&bytes), Some(filter));
+        assert!(SparkBloomFilter::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+        assert!(SparkBloomFilter::from_bytes(&[0, 0, 0, 2, 0, 0, 0, 5, 0, 0, 0, 0]).is_none());
+        assert!(SparkBloomFilter::from_bytes(&[0, 0, 0, 1, 0, 0, 0, 5, 0, 0, 0, 0]).is_none());
+    }
+}
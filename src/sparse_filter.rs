@@ -0,0 +1,430 @@
+use crate::bit_vector::BlockedBitVec;
+use crate::{
+    get_orginal_hashes, sparse_hash, AtomicCounters, BloomFilter, DefaultHasher, IndexStrategy,
+    Prefix,
+};
+use roaring::RoaringTreemap;
+use std::hash::{BuildHasher, Hash};
+
+/// The default fraction of bits set at which a [`SparseBloomFilter`] converts to dense bits.
+///
+/// A Roaring bitmap of sparse bits takes about 2 bytes per set bit, and dense bits take 1 byte per 8 bits,
+/// so the sparse bits are smaller while fewer than about 1 in 16 bits are set.
+pub const DEFAULT_DENSITY_THRESHOLD: f64 = 1.0 / 16.0;
+
+/// A [`BloomFilter`] that stores the indexes of its set bits in a Roaring bitmap while few are set,
+/// and converts to dense bits once the fraction of bits set passes a threshold.
+///
+/// A nearly empty filter takes memory in proportion to the bits set, not the bits, e.g. for many per-user filters
+/// sized for their largest users. The bits and the items they contain are the same as those of the dense filter
+/// with the same parameters, which [`into_filter`](Self::into_filter) returns at any time. While sparse, inserts and
+/// checks look up each bit of an item in the bitmap, which is slower than a dense filter's block of `u64`s.
+///
+/// Two-choice filters are kept dense, since their inserts compare the fill of blocks.
+///
+/// # Examples
+/// ```
+/// use fastbloom::{BloomFilter, SparseBloomFilter};
+///
+/// let mut bloom = SparseBloomFilter::new_seeded(1 << 20, 7, &1);
+/// bloom.extend(0..100);
+/// assert!(bloom.is_sparse());
+/// assert!((0..100).all(|x| bloom.contains(&x)));
+///
+/// let mut dense = BloomFilter::new_seeded(1 << 20, 7, &1);
+/// dense.extend(0..100);
+/// assert_eq!(bloom.into_filter(), dense);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SparseBloomFilter<const BLOCK_SIZE_BITS: usize = 512, S = DefaultHasher> {
+    repr: Repr<BLOCK_SIZE_BITS, S>,
+    density_threshold: f64,
+}
+
+#[derive(Debug, Clone)]
+enum Repr<const BLOCK_SIZE_BITS: usize, S> {
+    Sparse {
+        /// The indexes of the set bits, as in [`BloomFilter::test_bit`]
+        bits: RoaringTreemap,
+        params: Params<S>,
+    },
+    Dense(BloomFilter<BLOCK_SIZE_BITS, S>),
+}
+
+/// The parameters of a [`BloomFilter`], other than its bits.
+#[derive(Debug, Clone)]
+struct Params<S> {
+    num_blocks: usize,
+    target_hashes: u64,
+    num_rounds: Option<u64>,
+    num_hashes: u64,
+    index_strategy: IndexStrategy,
+    prefix: Option<Prefix>,
+    hasher: S,
+}
+
+impl SparseBloomFilter {
+    /// Creates an empty, sparse `SparseBloomFilter` with at least `num_bits` bits and `num_hashes` hashes per item,
+    /// with the same parameters as [`BloomFilter::new`].
+    ///
+    /// # Panics
+    /// Panics if the number of bits, `num_bits`, is 0.
+    pub fn new(num_bits: usize, num_hashes: u32) -> Self {
+        Self::with_hasher(num_bits, num_hashes, DefaultHasher::default())
+    }
+
+    /// Creates an empty, sparse `SparseBloomFilter` with at least `num_bits` bits, `num_hashes` hashes per item,
+    /// and `seed`, with the same parameters as [`BloomFilter::new_seeded`].
+    ///
+    /// # Panics
+    /// Panics if the number of bits, `num_bits`, is 0.
+    pub fn new_seeded(num_bits: usize, num_hashes: u32, seed: &u128) -> Self {
+        Self::with_hasher(
+            num_bits,
+            num_hashes,
+            DefaultHasher::seeded(&seed.to_be_bytes()),
+        )
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher + Clone> SparseBloomFilter<BLOCK_SIZE_BITS, S> {
+    /// Creates an empty, sparse `SparseBloomFilter` with at least `num_bits` bits, `num_hashes` hashes per item,
+    /// and `hasher`.
+    ///
+    /// # Panics
+    /// Panics if the number of bits, `num_bits`, is 0.
+    pub fn with_hasher(num_bits: usize, num_hashes: u32, hasher: S) -> Self {
+        assert!(num_bits > 0);
+        let (single_hashes, num_rounds) =
+            sparse_hash::optimize_hashing(num_hashes as f64, BLOCK_SIZE_BITS, 32);
+        Self {
+            repr: Repr::Sparse {
+                bits: RoaringTreemap::new(),
+                params: Params {
+                    num_blocks: num_bits.div_ceil(BLOCK_SIZE_BITS),
+                    target_hashes: num_hashes as u64,
+                    num_rounds,
+                    num_hashes: single_hashes,
+                    index_strategy: IndexStrategy::DoubleHashing,
+                    prefix: None,
+                    hasher,
+                },
+            },
+            density_threshold: DEFAULT_DENSITY_THRESHOLD,
+        }
+    }
+
+    /// Sets the fraction of bits set, from 0 to 1, past which the filter converts to dense bits,
+    /// instead of [`DEFAULT_DENSITY_THRESHOLD`]. The filter converts immediately if it is already past it.
+    ///
+    /// # Panics
+    /// Panics if `threshold` is not in `[0, 1]`.
+    pub fn density_threshold(mut self, threshold: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&threshold),
+            "threshold must be in [0, 1]"
+        );
+        self.density_threshold = threshold;
+        self.densify_if_past_threshold();
+        self
+    }
+
+    /// Inserts an element into the filter, converting it to dense bits if the fraction of bits set passes the threshold.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item may have been previously in the filter (indicating a potential false positive),
+    /// `false` otherwise.
+    pub fn insert(&mut self, val: &(impl Hash + ?Sized)) -> bool {
+        let previously_contained = match &mut self.repr {
+            Repr::Dense(filter) => return filter.insert(val),
+            Repr::Sparse { bits, params } => {
+                let mut previously_contained = true;
+                for bit in params.bit_indexes::<BLOCK_SIZE_BITS>(val) {
+                    previously_contained &= !bits.insert(bit);
+                }
+                previously_contained
+            }
+        };
+        self.densify_if_past_threshold();
+        previously_contained
+    }
+
+    /// Checks if an element is possibly in the filter.
+    pub fn contains(&self, val: &(impl Hash + ?Sized)) -> bool {
+        match &self.repr {
+            Repr::Dense(filter) => filter.contains(val),
+            Repr::Sparse { bits, params } => params
+                .bit_indexes::<BLOCK_SIZE_BITS>(val)
+                .all(|bit| bits.contains(bit)),
+        }
+    }
+
+    /// Returns `true` if the set bits are stored in a Roaring bitmap, `false` if the filter has converted to dense bits.
+    #[inline]
+    pub fn is_sparse(&self) -> bool {
+        matches!(self.repr, Repr::Sparse { .. })
+    }
+
+    /// Returns the number of bits set.
+    pub fn count_ones(&self) -> u64 {
+        match &self.repr {
+            Repr::Dense(filter) => filter.block_popcounts().map(u64::from).sum(),
+            Repr::Sparse { bits, .. } => bits.len(),
+        }
+    }
+
+    /// Returns the total number of in-memory bits supporting the filter, as [`BloomFilter::num_bits`].
+    pub fn num_bits(&self) -> usize {
+        match &self.repr {
+            Repr::Dense(filter) => filter.num_bits(),
+            Repr::Sparse { params, .. } => params.num_blocks * BLOCK_SIZE_BITS,
+        }
+    }
+
+    /// Returns the number of hashes per item.
+    pub fn num_hashes(&self) -> u32 {
+        match &self.repr {
+            Repr::Dense(filter) => filter.num_hashes(),
+            Repr::Sparse { params, .. } => params.target_hashes as u32,
+        }
+    }
+
+    /// Returns the number of bytes this `SparseBloomFilter` uses, including its Roaring bitmap or dense bits.
+    /// See [`BloomFilter::memory_usage`].
+    ///
+    /// The Roaring bitmap is estimated as its serialized size, which is close to its size in memory.
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + match &self.repr {
+                Repr::Dense(filter) => filter.heap_bytes(),
+                Repr::Sparse { bits, .. } => bits.serialized_size(),
+            }
+    }
+
+    /// Returns the filter as a dense [`BloomFilter`], with the same bits.
+    pub fn into_filter(self) -> BloomFilter<BLOCK_SIZE_BITS, S> {
+        match self.repr {
+            Repr::Dense(filter) => filter,
+            Repr::Sparse { bits, params } => params.into_filter(&bits),
+        }
+    }
+
+    /// Clear all of the bits in the filter, removing all items, and converts it back to sparse bits.
+    pub fn clear(&mut self) {
+        match &mut self.repr {
+            Repr::Dense(filter) if !filter.two_choice => {
+                self.repr = Repr::Sparse {
+                    bits: RoaringTreemap::new(),
+                    params: Params::of(filter),
+                };
+            }
+            Repr::Dense(filter) => filter.clear(),
+            Repr::Sparse { bits, .. } => bits.clear(),
+        }
+    }
+
+    fn densify_if_past_threshold(&mut self) {
+        if let Repr::Sparse { bits, params } = &self.repr {
+            let num_bits = params.num_blocks * BLOCK_SIZE_BITS;
+            if bits.len() as f64 > self.density_threshold * num_bits as f64 {
+                self.repr = Repr::Dense(params.clone().into_filter(bits));
+            }
+        }
+    }
+}
+
+impl<S: BuildHasher> Params<S> {
+    fn of<const BLOCK_SIZE_BITS: usize>(filter: &BloomFilter<BLOCK_SIZE_BITS, S>) -> Self
+    where
+        S: Clone,
+    {
+        Self {
+            num_blocks: filter.num_blocks(),
+            target_hashes: filter.target_hashes,
+            num_rounds: filter.num_rounds,
+            num_hashes: filter.num_hashes,
+            index_strategy: filter.index_strategy,
+            prefix: filter.prefix,
+            hasher: filter.hasher.clone(),
+        }
+    }
+
+    /// Returns the indexes of the bits `val` sets, in the same order as [`BloomFilter::insert`].
+    fn bit_indexes<const BLOCK_SIZE_BITS: usize>(
+        &self,
+        val: &(impl Hash + ?Sized),
+    ) -> impl Iterator<Item = u64> {
+        let [h1, h2] = get_orginal_hashes(&self.hasher, val);
+        BloomFilter::<BLOCK_SIZE_BITS, S>::hash_masks(
+            self.num_blocks,
+            false,
+            self.index_strategy,
+            self.num_hashes,
+            self.num_rounds,
+            h1,
+            h2,
+        )
+        .into_iter()
+        .flat_map(|(index, mut mask)| {
+            std::iter::from_fn(move || {
+                (mask != 0).then(|| {
+                    let bit = mask.trailing_zeros() as u64;
+                    mask &= mask - 1;
+                    index as u64 * 64 + bit
+                })
+            })
+        })
+    }
+
+    fn into_filter<const BLOCK_SIZE_BITS: usize>(
+        self,
+        bits: &RoaringTreemap,
+    ) -> BloomFilter<BLOCK_SIZE_BITS, S> {
+        let mut words = vec![0u64; self.num_blocks * BLOCK_SIZE_BITS / 64];
+        for bit in bits {
+            words[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+        BloomFilter {
+            bits: BlockedBitVec::from_storage(words),
+            target_hashes: self.target_hashes,
+            num_rounds: self.num_rounds,
+            num_hashes: self.num_hashes,
+            two_choice: false,
+            index_strategy: self.index_strategy,
+            prefix: self.prefix,
+            hasher: self.hasher,
+            counters: AtomicCounters::new(),
+        }
+    }
+}
+
+impl<T, const BLOCK_SIZE_BITS: usize, S: BuildHasher + Clone> Extend<T>
+    for SparseBloomFilter<BLOCK_SIZE_BITS, S>
+where
+    T: Hash,
+{
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.insert(&val);
+        }
+    }
+}
+
+/// Converts a dense filter to sparse bits, if it is not two-choice and not past [`DEFAULT_DENSITY_THRESHOLD`].
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher + Clone> From<BloomFilter<BLOCK_SIZE_BITS, S>>
+    for SparseBloomFilter<BLOCK_SIZE_BITS, S>
+{
+    fn from(filter: BloomFilter<BLOCK_SIZE_BITS, S>) -> Self {
+        let ones: u64 = filter.block_popcounts().map(u64::from).sum();
+        let sparse = !filter.two_choice
+            && ones as f64 <= DEFAULT_DENSITY_THRESHOLD * filter.num_bits() as f64;
+        let repr = if sparse {
+            let bits = filter
+                .as_slice()
+                .iter()
+                .enumerate()
+                .flat_map(|(index, &word)| {
+                    (0..64)
+                        .filter(move |bit| word & (1 << bit) != 0)
+                        .map(move |bit| index as u64 * 64 + bit)
+                })
+                .collect();
+            Repr::Sparse {
+                bits,
+                params: Params::of(&filter),
+            }
+        } else {
+            Repr::Dense(filter)
+        };
+        Self {
+            repr,
+            density_threshold: DEFAULT_DENSITY_THRESHOLD,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_bits_as_dense() {
+        fn same_bits_as_dense_<const N: usize>() {
+            for num_hashes in [1, 7, 20] {
+                let mut sparse = SparseBloomFilter::<N>::with_hasher(
+                    1 << 16,
+                    num_hashes,
+                    DefaultHasher::seeded(&[1; 16]),
+                );
+                let mut dense = BloomFilter::new_builder::<N>(1 << 16)
+                    .hasher(DefaultHasher::seeded(&[1; 16]))
+                    .hashes(num_hashes);
+                for x in 0..100 {
+                    assert_eq!(sparse.insert(&x), dense.insert(&x));
+                }
+                assert!(sparse.is_sparse());
+                assert_eq!(sparse.count_ones(), dense.stats().bits_set);
+                assert!((0..1000).all(|x| sparse.contains(&x) == dense.contains(&x)));
+                assert_eq!(sparse.into_filter(), dense);
+            }
+        }
+        same_bits_as_dense_::<512>();
+        same_bits_as_dense_::<256>();
+        same_bits_as_dense_::<128>();
+        same_bits_as_dense_::<64>();
+    }
+
+    #[test]
+    fn converts_past_threshold() {
+        let mut bloom = SparseBloomFilter::new_seeded(1 << 14, 7, &1).density_threshold(0.1);
+        let mut x = 0;
+        while bloom.is_sparse() {
+            bloom.insert(&x);
+            x += 1;
+        }
+        assert!(bloom.count_ones() as f64 > 0.1 * (1 << 14) as f64);
+        assert!(bloom.count_ones() as f64 <= 0.1 * (1 << 14) as f64 + 7.0);
+        bloom.extend(x..x + 100);
+        assert!((0..x + 100).all(|x| bloom.contains(&x)));
+        let mut dense = BloomFilter::new_seeded(1 << 14, 7, &1);
+        dense.extend(0..x + 100);
+        assert_eq!(bloom.clone().into_filter(), dense);
+
+        bloom.clear();
+        assert!(bloom.is_sparse() && bloom.count_ones() == 0);
+        assert!(!SparseBloomFilter::new(1024, 4)
+            .density_threshold(0.0)
+            .insert(&1));
+    }
+
+    #[test]
+    fn memory_usage() {
+        let mut bloom = SparseBloomFilter::new_seeded(1 << 20, 7, &1);
+        bloom.extend(0..10);
+        let dense = bloom.clone().into_filter();
+        assert!(bloom.memory_usage() < dense.memory_usage() / 100);
+        bloom.extend(10..100_000);
+        assert!(!bloom.is_sparse());
+        assert_eq!(
+            bloom.memory_usage(),
+            dense.heap_bytes() + std::mem::size_of_val(&bloom)
+        );
+    }
+
+    #[test]
+    fn from_dense() {
+        let mut dense = BloomFilter::new_seeded(1 << 14, 5, &1);
+        dense.extend(0..10);
+        let sparse = SparseBloomFilter::from(dense.clone());
+        assert!(sparse.is_sparse());
+        assert!((0..10).all(|x| sparse.contains(&x)));
+        assert_eq!(sparse.into_filter(), dense);
+
+        dense.extend(10..10_000);
+        assert!(!SparseBloomFilter::from(dense.clone()).is_sparse());
+        let two_choice = BloomFilter::with_num_bits(1 << 14).two_choice().hashes(5);
+        assert!(!SparseBloomFilter::from(two_choice).is_sparse());
+    }
+}
@@ -1,14 +1,54 @@
+use crate::simd;
 use std::ops::{AddAssign, BitAndAssign, BitOrAssign};
 use wide::{u64x2, u64x4};
 
+/// `hashes_for_bits(bits)` for `bits` in `0..=32`, i.e. `ln(1 - bits / 64) / ln(63 / 64)`,
+/// tabulated so that hashing can be optimized in const contexts, where `ln` is not available.
+const HASHES_FOR_BITS: [f64; 33] = [
+    0.0,                // 0
+    1.0,                // 1
+    2.0160006773285466, // 2
+    3.048522413067539,  // 3
+    4.098111394613445,  // 4
+    5.165341350753255,  // 5
+    6.250815435058318,  // 6
+    7.355168273075311,  // 7
+    8.479068190711756,  // 8
+    9.62321964340818,   // 9
+    10.788365868206048, // 10
+    11.97529178371387,  // 11
+    13.184827166308464, // 12
+    14.417850134760775, // 13
+    15.675290979938644, // 14
+    16.95813638142351,  // 15
+    18.2674340589178,   // 16
+    19.604297913377412, // 17
+    20.969913721075525, // 18
+    22.365545453531247, // 19
+    23.792542307712537, // 20
+    25.252346544510658, // 21
+    26.746502249629554, // 22
+    28.276665150293592, // 23
+    29.844613644243005, // 24
+    31.452261225226266, // 25
+    33.101670522704865, // 26
+    34.79506921414388,  // 27
+    36.5348681178356,   // 28
+    38.323681834954755, // 29
+    40.164352384390334, // 30
+    42.05997636663034,  // 31
+    44.01393630854736,  // 32
+];
+
+/// Returns the number of single bit hashes that set as many bits of a `u64`, on average,
+/// as a sparse hash targeting `target_bits_per_u64_per_item` bits, which must be at most 32.
 #[inline]
-pub(crate) fn hashes_for_bits(target_bits_per_u64_per_item: u64) -> f64 {
-    f64::ln(-(((target_bits_per_u64_per_item as f64) / 64.0f64) - 1.0f64))
-        / f64::ln(63.0f64 / 64.0f64)
+pub(crate) const fn hashes_for_bits(target_bits_per_u64_per_item: u64) -> f64 {
+    HASHES_FOR_BITS[target_bits_per_u64_per_item as usize]
 }
 
 #[inline]
-pub(crate) fn work(bits: u64) -> u64 {
+pub(crate) const fn work(bits: u64) -> u64 {
     match bits {
         8 => 3,
         9 => 6,
@@ -84,13 +124,11 @@ impl SparseHash for u64x4 {
     }
     #[inline]
     fn matches(data: &[u64], x: Self) -> bool {
-        let t = unsafe { std::mem::transmute::<&[u64], &[Self]>(data) };
-        (t[0] & x) == x
+        (simd::load(data) & x) == x
     }
     #[inline]
     fn set(data: &mut [u64], x: Self) {
-        let t = unsafe { std::mem::transmute::<&mut [u64], &mut [Self]>(data) };
-        t[0] |= x;
+        simd::store(data, simd::load(data) | x);
     }
 }
 
@@ -105,16 +143,26 @@ impl SparseHash for u64x2 {
     }
     #[inline]
     fn matches(data: &[u64], x: Self) -> bool {
-        let t = unsafe { std::mem::transmute::<&[u64], &[Self]>(data) };
-        (t[0] & x) == x
+        (Self::new([data[0], data[1]]) & x) == x
     }
     #[inline]
     fn set(data: &mut [u64], x: Self) {
-        let t = unsafe { std::mem::transmute::<&mut [u64], &mut [Self]>(data) };
-        t[0] |= x;
+        data[0] |= x.as_array_ref()[0];
+        data[1] |= x.as_array_ref()[1];
     }
 }
 
+/// Returns the two sparse hashes of a 512 bit block, for its first and last 4 `u64`s,
+/// so the block is checked or set in one pass with [`simd::contains`] and [`simd::set`].
+#[inline]
+pub(crate) fn sparse_hash_512(hashes_1: &mut u64x4, hashes_2: u64x4, num_rounds: u64) -> [u64; 8] {
+    let mut data = [0; 8];
+    for half in data.chunks_exact_mut(4) {
+        simd::store(half, u64x4::sparse_hash(hashes_1, hashes_2, num_rounds));
+    }
+    data
+}
+
 pub(crate) trait SparseHash: Sized + AddAssign + Copy + BitAndAssign + BitOrAssign {
     fn h1(h1: &mut u64, h2: u64) -> Self;
     fn h2(h2: u64) -> Self;
@@ -291,7 +339,7 @@ pub(crate) trait SparseHash: Sized + AddAssign + Copy + BitAndAssign + BitOrAssi
     }
 }
 
-fn min_target_bits(block_size: usize) -> u64 {
+pub(crate) const fn min_target_bits(block_size: usize) -> u64 {
     // We will not accept rounds too low the variance is too high, and bits may be 0, which is bad for false positives.
     // TODO: a more precise formula for this
     match block_size {
@@ -300,7 +348,18 @@ fn min_target_bits(block_size: usize) -> u64 {
     }
 }
 
-pub(crate) fn optimize_hashing(total_num_hashes: f64, block_size: usize) -> (u64, Option<u64>) {
+/// Returns `true` if a sparse hash of `num_rounds` bits per `u64` is allowed for `block_size` bit blocks.
+pub(crate) const fn is_valid_rounds(num_rounds: u64, block_size: usize) -> bool {
+    min_target_bits(block_size) <= num_rounds && num_rounds <= 32
+}
+
+/// Returns the split of `total_num_hashes` into single bit hashes and a sparse hash, if any, with the least work,
+/// among sparse hashes of at most `max_rounds` bits per `u64`.
+pub(crate) const fn optimize_hashing(
+    total_num_hashes: f64,
+    block_size: usize,
+    max_rounds: u64,
+) -> (u64, Option<u64>) {
     let num_u64s_per_block = (block_size as u64 / 64) as f64;
     let mut num_hashes = if block_size == 512 {
         total_num_hashes.round() as u64
@@ -308,20 +367,26 @@ pub(crate) fn optimize_hashing(total_num_hashes: f64, block_size: usize) -> (u64
         total_num_hashes.floor() as u64
     };
     let mut num_rounds = None;
-    let min_target_bits = min_target_bits(block_size);
-    for target_bits_per_u64_per_item in min_target_bits..=32 {
+    // `for` loops are not allowed in const fns
+    let mut target_bits_per_u64_per_item = min_target_bits(block_size);
+    while target_bits_per_u64_per_item <= max_rounds {
         let hashes_covered = hashes_for_bits(target_bits_per_u64_per_item);
         let remaining = (total_num_hashes - (hashes_covered * num_u64s_per_block)).round();
-        if remaining < 0.0 {
-            continue; // sparse hash has too many bits
-        }
-        let hashing_work = remaining as u64;
-        let work_for_target_bits = work(target_bits_per_u64_per_item);
-        let cur_work = num_hashes + num_rounds.unwrap_or(0);
-        if (hashing_work + work_for_target_bits) < cur_work {
-            num_rounds = Some(target_bits_per_u64_per_item);
-            num_hashes = hashing_work;
+        // A negative `remaining` means the sparse hash has too many bits
+        if remaining >= 0.0 {
+            let hashing_work = remaining as u64;
+            let work_for_target_bits = work(target_bits_per_u64_per_item);
+            let cur_work = num_hashes
+                + match num_rounds {
+                    Some(rounds) => rounds,
+                    None => 0,
+                };
+            if (hashing_work + work_for_target_bits) < cur_work {
+                num_rounds = Some(target_bits_per_u64_per_item);
+                num_hashes = hashing_work;
+            }
         }
+        target_bits_per_u64_per_item += 1;
     }
     (num_hashes, num_rounds)
 }
@@ -352,11 +417,19 @@ mod test {
         }
     }
 
+    #[test]
+    fn hashes_for_bits_table() {
+        for bits in 0..=32 {
+            let expected = f64::ln(1.0 - bits as f64 / 64.0) / f64::ln(63.0 / 64.0);
+            assert!((hashes_for_bits(bits) - expected).abs() < 1e-12, "{bits}");
+        }
+    }
+
     #[test]
     fn hash_creation() {
         for block_size in [64, 128, 256, 512] {
             for num_hashes in 1..5000 {
-                let (hashes, num_rounds) = optimize_hashing(num_hashes as f64, block_size);
+                let (hashes, num_rounds) = optimize_hashing(num_hashes as f64, block_size, 32);
                 assert!(num_rounds.unwrap_or(0) <= 32);
                 match num_rounds {
                     None => assert_eq!(num_hashes, hashes, "Not equal when num rounds is None"),
@@ -0,0 +1,235 @@
+use crate::BloomFilter;
+use std::hash::{BuildHasher, Hash};
+
+/// A spectral Bloom filter, a [`BloomFilter`] that estimates how many times each item was inserted.
+///
+/// Every bit of the underlying filter is paired with a small saturating counter. Inserting an item increments the
+/// counters of all its bits, and the estimated multiplicity of an item is the minimum of its counters.
+/// Like false positives from [`BloomFilter::contains`], estimates may be too high, but are never too low
+/// (unless a counter saturates at [`u8::MAX`]).
+///
+/// Each item increments one counter per bit it sets, so filters with few hashes per item
+/// (see [`BuilderWithBits::hashes`](crate::BuilderWithBits::hashes)) are faster and more accurate.
+///
+/// See <https://theory.stanford.edu/~matias/papers/sbf-sigmod-03.pdf>.
+///
+/// # Examples
+/// ```
+/// use fastbloom::{BloomFilter, SpectralBloomFilter};
+///
+/// let filter = BloomFilter::with_num_bits(8192).hashes(4);
+/// let mut spectral = SpectralBloomFilter::new(filter);
+/// spectral.insert("42");
+/// spectral.insert("42");
+/// spectral.insert("🦀");
+/// assert_eq!(spectral.count("42"), 2);
+/// assert_eq!(spectral.count("🦀"), 1);
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpectralBloomFilter<const BLOCK_SIZE_BITS: usize = 512, S = crate::DefaultHasher> {
+    filter: BloomFilter<BLOCK_SIZE_BITS, S>,
+    /// One counter per bit in `filter`
+    counters: Vec<u8>,
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> SpectralBloomFilter<BLOCK_SIZE_BITS, S> {
+    /// Creates a new `SpectralBloomFilter` with one counter per bit of `filter`.
+    ///
+    /// `filter` is cleared before use.
+    pub fn new(mut filter: BloomFilter<BLOCK_SIZE_BITS, S>) -> Self {
+        filter.clear();
+        Self {
+            counters: vec![0; filter.as_slice().len() * 64],
+            filter,
+        }
+    }
+
+    /// Returns the indexes of the counters for an item, without duplicates.
+    fn counter_indexes(&self, val: &(impl Hash + ?Sized)) -> Vec<usize> {
+        let mut masks = self.filter.masks(val);
+        masks.sort_unstable_by_key(|&(i, _)| i);
+        masks.dedup_by(|(i, mask), (prev_i, prev_mask)| {
+            let same = i == prev_i;
+            if same {
+                *prev_mask |= *mask;
+            }
+            same
+        });
+        let mut indexes = Vec::new();
+        for (i, mut mask) in masks {
+            while mask != 0 {
+                indexes.push(i * 64 + mask.trailing_zeros() as usize);
+                mask &= mask - 1;
+            }
+        }
+        indexes
+    }
+
+    /// Inserts an element into the filter, incrementing its estimated multiplicity.
+    ///
+    /// # Returns
+    ///
+    /// The estimated multiplicity of the item before it was inserted.
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::{BloomFilter, SpectralBloomFilter};
+    ///
+    /// let mut spectral = SpectralBloomFilter::new(BloomFilter::with_num_bits(1024).hashes(4));
+    /// assert_eq!(spectral.insert(&1), 0);
+    /// assert_eq!(spectral.insert(&1), 1);
+    /// ```
+    pub fn insert(&mut self, val: &(impl Hash + ?Sized)) -> u8 {
+        let mut previous = u8::MAX;
+        for index in self.counter_indexes(val) {
+            previous = std::cmp::min(previous, self.counters[index]);
+            self.counters[index] = self.counters[index].saturating_add(1);
+        }
+        self.filter.insert(val);
+        previous
+    }
+
+    /// Removes one occurrence of an element from the filter, decrementing its estimated multiplicity.
+    ///
+    /// Only items that were inserted should be removed: removing a false positive decrements
+    /// counters of other items, which may then be underestimated.
+    /// Saturated counters are never decremented.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item was possibly in the filter and was removed, `false` otherwise.
+    pub fn remove(&mut self, val: &(impl Hash + ?Sized)) -> bool {
+        if !self.contains(val) {
+            return false;
+        }
+        for index in self.counter_indexes(val) {
+            let counter = &mut self.counters[index];
+            if *counter < u8::MAX {
+                *counter -= 1;
+            }
+            if *counter == 0 {
+                self.filter.as_mut_slice()[index / 64] &= !(1 << (index % 64));
+            }
+        }
+        true
+    }
+
+    /// Returns the estimated number of times an element was inserted.
+    /// The estimate is never lower than the true count, but may be higher.
+    pub fn count(&self, val: &(impl Hash + ?Sized)) -> u8 {
+        if !self.contains(val) {
+            return 0;
+        }
+        self.counter_indexes(val)
+            .into_iter()
+            .map(|index| self.counters[index])
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Checks if an element is possibly in the filter.
+    #[inline]
+    pub fn contains(&self, val: &(impl Hash + ?Sized)) -> bool {
+        self.filter.contains(val)
+    }
+
+    /// Returns a reference to the underlying Bloom filter.
+    #[inline]
+    pub fn filter(&self) -> &BloomFilter<BLOCK_SIZE_BITS, S> {
+        &self.filter
+    }
+
+    /// Returns the number of bytes this `SpectralBloomFilter` uses, including the bits of the filter and the counters.
+    /// See [`BloomFilter::memory_usage`].
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>() + self.filter.heap_bytes() + self.counters.capacity()
+    }
+
+    /// Clear the filter and all counters, removing all items.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.filter.clear();
+        for x in self.counters.iter_mut() {
+            *x = 0;
+        }
+    }
+}
+
+impl<T, const BLOCK_SIZE_BITS: usize, S: BuildHasher> Extend<T>
+    for SpectralBloomFilter<BLOCK_SIZE_BITS, S>
+where
+    T: Hash,
+{
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.insert(&val);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spectral<const N: usize>(num_items: usize) -> SpectralBloomFilter<N> {
+        let filter = BloomFilter::new_builder::<N>(16 * num_items)
+            .seed(&42)
+            .hashes(4);
+        SpectralBloomFilter::new(filter)
+    }
+
+    #[test]
+    fn count_is_never_lower() {
+        fn count_is_never_lower_<const N: usize>() {
+            let num_items = 1000;
+            let mut filter = spectral::<N>(num_items);
+            for x in 0..num_items {
+                for _ in 0..(x % 7) {
+                    filter.insert(&x);
+                }
+            }
+            assert!((0..num_items).all(|x| filter.count(&x) as usize >= x % 7));
+            let exact = (0..num_items)
+                .filter(|x| filter.count(x) as usize == x % 7)
+                .count();
+            assert!(exact > num_items * 9 / 10);
+        }
+        count_is_never_lower_::<64>();
+        count_is_never_lower_::<128>();
+        count_is_never_lower_::<256>();
+        count_is_never_lower_::<512>();
+    }
+
+    #[test]
+    fn remove_decrements() {
+        let mut filter = spectral::<512>(100);
+        filter.extend([1, 1, 1, 2]);
+        assert!(filter.remove(&1));
+        assert_eq!(filter.count(&1), 2);
+        assert!(filter.remove(&2));
+        assert!(!filter.contains(&2));
+        assert!(!filter.remove(&2));
+    }
+
+    #[test]
+    fn counters_saturate() {
+        let mut filter = spectral::<512>(100);
+        for _ in 0..300 {
+            filter.insert(&1);
+        }
+        assert_eq!(filter.count(&1), u8::MAX);
+        assert!(filter.remove(&1));
+        assert_eq!(filter.count(&1), u8::MAX);
+    }
+
+    #[test]
+    fn nothing_after_clear() {
+        let mut filter = spectral::<512>(100);
+        filter.extend(0..100);
+        filter.clear();
+        assert!(!(0..100).any(|x| filter.contains(&x)));
+        assert!((0..100).all(|x| filter.count(&x) == 0));
+    }
+}
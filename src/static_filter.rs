@@ -0,0 +1,209 @@
+use crate::bit_vector::BlockedBitVec;
+use crate::text::Params;
+use crate::{sparse_hash, AtomicCounters, BloomFilter, ConstDefaultHasher, IndexStrategy, Prefix};
+
+/// A read-only [`BloomFilter`] over `&'static` bits, constructible in `const` and `static` items,
+/// e.g. to bake a dictionary filter into a binary with no startup cost.
+///
+/// Constructed via [`StaticBloomFilter::from_static`], or from a filter file with [`include_bloom!`](crate::include_bloom).
+/// Membership checks are the same as for a [`BloomFilter`] with the same bits, block size, number of hashes,
+/// and [`DefaultHasher`](crate::DefaultHasher) keys, but items cannot be inserted.
+/// With the `counters` feature, prefer `static` items: each use of a `const` item is a new filter, with new counters.
+///
+/// # Examples
+/// ```
+/// use fastbloom::{BloomFilter, ConstDefaultHasher, StaticBloomFilter};
+///
+/// static BITS: [u64; 8] = [0; 8];
+/// static EMPTY: StaticBloomFilter = StaticBloomFilter::from_static(&BITS, 7, ConstDefaultHasher::with_seed(&1));
+///
+/// assert!(!EMPTY.contains("42"));
+/// assert_eq!(EMPTY.num_hashes(), 7);
+/// ```
+pub type StaticBloomFilter<const BLOCK_SIZE_BITS: usize = 512> =
+    BloomFilter<BLOCK_SIZE_BITS, ConstDefaultHasher, &'static [u64]>;
+
+impl<const BLOCK_SIZE_BITS: usize> StaticBloomFilter<BLOCK_SIZE_BITS> {
+    /// Creates a `StaticBloomFilter` reading its bits from `bits`, with `num_hashes` hashes per item, in const contexts.
+    ///
+    /// The hashes are split between single bits and sparse hashes as by [`BuilderWithBits::hashes`](crate::BuilderWithBits::hashes),
+    /// so a filter built with the same number of hashes and hasher keys at runtime contains the same items.
+    ///
+    /// # Panics
+    /// Panics, at compile time in const contexts, if `bits` is empty or not a multiple of the block size.
+    pub const fn from_static(
+        bits: &'static [u64],
+        num_hashes: u32,
+        hasher: ConstDefaultHasher,
+    ) -> Self {
+        Self::from_static_parts(
+            bits,
+            num_hashes,
+            sparse_hash::optimize_hashing(num_hashes as f64, BLOCK_SIZE_BITS, 32),
+            hasher,
+            false,
+            IndexStrategy::DoubleHashing,
+            None,
+        )
+    }
+
+    /// Creates a `StaticBloomFilter` with `num_hashes` hashes per item, split into `single_hashes`
+    /// single bit hashes and a sparse hash of `num_rounds` bits per `u64`, if any.
+    const fn from_static_parts(
+        bits: &'static [u64],
+        num_hashes: u32,
+        (single_hashes, num_rounds): (u64, Option<u64>),
+        hasher: ConstDefaultHasher,
+        two_choice: bool,
+        index_strategy: IndexStrategy,
+        prefix: Option<Prefix>,
+    ) -> Self {
+        BloomFilter {
+            bits: BlockedBitVec::from_static(bits),
+            target_hashes: num_hashes as u64,
+            num_rounds,
+            num_hashes: single_hashes,
+            two_choice,
+            index_strategy,
+            prefix,
+            hasher,
+            counters: AtomicCounters::new(),
+        }
+    }
+
+    /// Creates a `StaticBloomFilter` from a filter file included by [`include_bloom!`](crate::include_bloom).
+    ///
+    /// # Safety
+    /// The bits after the parameters at the start of `bytes` must be 8 byte aligned.
+    #[doc(hidden)]
+    pub const unsafe fn __from_included(bytes: &'static [u8]) -> Self {
+        if cfg!(target_endian = "big") {
+            panic!("include_bloom! requires a little-endian target");
+        }
+        let params = included_params(bytes);
+        assert!(
+            params.block_size == BLOCK_SIZE_BITS,
+            "the included filter has a different block size than the StaticBloomFilter"
+        );
+        let (_, bits) = bytes.split_at(params.len);
+        // SAFETY: the bits are aligned, as guaranteed by the caller, and as little-endian `u64`s, are the `u64`s of the filter.
+        let bits =
+            unsafe { std::slice::from_raw_parts(bits.as_ptr().cast::<u64>(), bits.len() / 8) };
+        let (k0, k1) = params.keys;
+        Self::from_static_parts(
+            bits,
+            params.num_hashes,
+            (params.single_hashes as u64, params.num_rounds),
+            ConstDefaultHasher::with_keys(k0, k1),
+            params.two_choice,
+            params.index_strategy,
+            params.prefix,
+        )
+    }
+}
+
+/// Parses the parameters of a filter file included by [`include_bloom!`](crate::include_bloom).
+const fn included_params(bytes: &[u8]) -> Params {
+    match Params::parse(bytes) {
+        Some(params) if (bytes.len() - params.len).is_multiple_of(8) => params,
+        _ => panic!("the included file is not a filter written by to_bytes_with_params"),
+    }
+}
+
+/// Returns the number of bytes to pad a filter file included by [`include_bloom!`](crate::include_bloom) with
+/// so that its bits are 8 byte aligned.
+#[doc(hidden)]
+pub const fn __include_bloom_padding(bytes: &[u8]) -> usize {
+    (8 - included_params(bytes).len % 8) % 8
+}
+
+/// Embeds a filter file into the binary as a [`StaticBloomFilter`], with no startup cost.
+///
+/// The file, located as for [`include_bytes!`], must be in the layout of
+/// [`BloomFilter::to_bytes_with_params`] or [`DynBloomFilter::to_bytes_with_params`](crate::DynBloomFilter::to_bytes_with_params),
+/// e.g. written from a word list by `BuilderWithFalsePositiveRate::embed_lines` in a build script, with the `build` feature,
+/// or converted by `fastbloom-cli`. Its bits are embedded in place, 8 byte aligned,
+/// so they are neither copied nor parsed at runtime. Only little-endian targets are supported.
+///
+/// Compilation fails if the file is not a filter, or its block size differs from the `StaticBloomFilter`'s.
+///
+/// # Examples
+/// ```ignore
+/// use fastbloom::{include_bloom, StaticBloomFilter};
+///
+/// static WORDS: StaticBloomFilter = include_bloom!("words.bf");
+///
+/// assert!(WORDS.contains("hello"));
+/// ```
+#[macro_export]
+macro_rules! include_bloom {
+    ($path:expr) => {{
+        const BYTES: &[u8] = include_bytes!($path);
+        const PADDING: usize = $crate::__include_bloom_padding(BYTES);
+        #[repr(C, align(8))]
+        struct Aligned([u8; PADDING], [u8; BYTES.len()]);
+        static ALIGNED: Aligned = Aligned([0; PADDING], *include_bytes!($path));
+        // SAFETY: the padding aligns the bits after the parameters to 8 bytes.
+        unsafe { $crate::StaticBloomFilter::__from_included(&ALIGNED.1) }
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BloomFilter, ConstDefaultHasher, Prefix, StaticBloomFilter};
+
+    static FRUIT: StaticBloomFilter<256> = include_bloom!("../testdata/fruit.bf");
+
+    /// Returns the filter in `testdata/fruit.bf`.
+    fn fruit() -> BloomFilter<256> {
+        let mut bloom = BloomFilter::with_num_bits(1024)
+            .block_size_256()
+            .seed(&42)
+            .prefix(Prefix::Delimiter(b':'))
+            .hashes(5);
+        bloom.extend(["apple", "banana", "cherry"]);
+        bloom.insert_with_prefix("citrus:lemon");
+        bloom
+    }
+
+    #[test]
+    fn same_as_bloom_filter() {
+        let expected = fruit();
+        assert_eq!(FRUIT.as_slice(), expected.as_slice());
+        assert_eq!(FRUIT.as_slice().as_ptr() as usize % 8, 0);
+        assert_eq!(FRUIT.num_hashes(), 5);
+        assert_eq!(FRUIT.num_rounds(), expected.num_rounds());
+        assert!(["apple", "banana", "cherry"]
+            .iter()
+            .all(|x| FRUIT.contains(x)));
+        assert!((0..1000).all(|x| FRUIT.contains(&x) == expected.contains(&x)));
+        assert_eq!(FRUIT.prefix(), Some(Prefix::Delimiter(b':')));
+        assert!(FRUIT.contains_prefix("citrus:orange"));
+    }
+
+    #[test]
+    #[cfg_attr(
+        feature = "counters",
+        allow(
+            clippy::declare_interior_mutable_const,
+            clippy::borrow_interior_mutable_const
+        )
+    )]
+    fn from_static_in_const() {
+        const BITS: &[u64] = &[0; 16];
+        const EMPTY: StaticBloomFilter =
+            StaticBloomFilter::from_static(BITS, 20, ConstDefaultHasher::with_seed(&1));
+        let runtime = BloomFilter::from_vec(BITS.to_vec()).seed(&1).hashes(20);
+        assert_eq!(EMPTY.num_rounds(), runtime.num_rounds());
+        assert_eq!(EMPTY.num_hashes(), runtime.num_hashes());
+        assert!(!EMPTY.contains(&1));
+
+        let mut bloom = BloomFilter::with_num_bits(1024).seed(&1).hashes(20);
+        bloom.extend(0..10);
+        let bits: &'static [u64] = bloom.as_slice().to_vec().leak();
+        let view: StaticBloomFilter =
+            StaticBloomFilter::from_static(bits, 20, ConstDefaultHasher::with_seed(&1));
+        assert!((0..10).all(|x| view.contains(&x)));
+        assert!((10..1000).all(|x| view.contains(&x) == bloom.contains(&x)));
+    }
+}
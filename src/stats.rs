@@ -0,0 +1,103 @@
+use crate::{BitStorage, BloomFilter};
+use std::hash::BuildHasher;
+
+/// A snapshot of the size, parameters, and fill of a [`BloomFilter`], e.g. for a health endpoint or a dashboard.
+///
+/// Constructed via [`BloomFilter::stats`]. With the `serde` feature, it serializes as a flat map of its fields.
+/// `estimated_items` is infinite for a filter with every bit set, which some formats, like JSON, encode as `null`.
+///
+/// # Examples
+/// ```
+/// use fastbloom::BloomFilter;
+///
+/// let bloom = BloomFilter::with_false_pos(0.01).items(0..1000);
+/// let stats = bloom.stats();
+/// assert_eq!(stats.num_bits, bloom.num_bits());
+/// assert_eq!(stats.block_size_bits, 512);
+/// assert!(stats.fill_ratio > 0.4 && stats.fill_ratio < 0.6);
+/// assert!((stats.estimated_items - 1000.0).abs() < 50.0);
+/// assert!(stats.estimated_fp_rate < 0.02);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FilterStats {
+    /// The number of bits, as [`BloomFilter::num_bits`].
+    pub num_bits: usize,
+    /// The number of blocks, as [`BloomFilter::num_blocks`].
+    pub num_blocks: usize,
+    /// The number of bits per block, as [`BloomFilter::block_size_bits`].
+    pub block_size_bits: usize,
+    /// The number of hashes per item, as [`BloomFilter::num_hashes`].
+    pub num_hashes: u32,
+    /// The number of bits set.
+    pub bits_set: u64,
+    /// The fraction of bits set, `bits_set / num_bits`.
+    pub fill_ratio: f64,
+    /// The estimated number of distinct items, as [`BloomFilter::estimate_len`].
+    pub estimated_items: f64,
+    /// The estimated false positive rate, as [`BloomFilter::estimate_fp_rate`].
+    pub estimated_fp_rate: f64,
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher, T: BitStorage>
+    BloomFilter<BLOCK_SIZE_BITS, S, T>
+{
+    /// Returns a snapshot of the size, parameters, and fill of this filter. See [`FilterStats`].
+    pub fn stats(&self) -> FilterStats {
+        let bits_set = self.as_slice().iter().map(|x| x.count_ones() as u64).sum();
+        FilterStats {
+            num_bits: self.num_bits(),
+            num_blocks: self.num_blocks(),
+            block_size_bits: BLOCK_SIZE_BITS,
+            num_hashes: self.num_hashes(),
+            bits_set,
+            fill_ratio: bits_set as f64 / self.num_bits() as f64,
+            estimated_items: self.estimate_len_with_ones(bits_set),
+            estimated_fp_rate: self.estimate_fp_rate(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats() {
+        let mut bloom = BloomFilter::with_num_bits(4096)
+            .block_size_256()
+            .seed(&1)
+            .hashes(5);
+        let empty = bloom.stats();
+        assert_eq!(
+            empty,
+            FilterStats {
+                num_bits: 4096,
+                num_blocks: 16,
+                block_size_bits: 256,
+                num_hashes: 5,
+                bits_set: 0,
+                fill_ratio: 0.0,
+                estimated_items: 0.0,
+                estimated_fp_rate: 0.0,
+            }
+        );
+
+        bloom.extend(0..200);
+        let stats = bloom.stats();
+        assert_eq!(stats.bits_set as f64 / 4096.0, stats.fill_ratio);
+        assert_eq!(stats.estimated_items, bloom.estimate_len());
+        assert_eq!(stats.estimated_fp_rate, bloom.estimate_fp_rate());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serializes_flat() {
+        let bloom = BloomFilter::with_num_bits(1024).hashes(4);
+        let json = serde_json::to_value(bloom.stats()).unwrap();
+        assert_eq!(json["num_bits"], 1024);
+        assert_eq!(json["fill_ratio"], 0.0);
+        let stats: FilterStats = serde_json::from_value(json).unwrap();
+        assert_eq!(stats, bloom.stats());
+    }
+}
@@ -0,0 +1,146 @@
+//! [`proptest`] strategies that generate filters, for property testing code that uses them.
+//!
+//! The filters vary in number of blocks, seed, number of hashes, two-choice mode, index strategy, and contents,
+//! and are generated with the items inserted into them, so that properties such as "no false negatives"
+//! can be checked. Block sizes are chosen by the const parameter, or at random by [`dyn_filter_with_items`].
+//!
+//! # Examples
+//! ```
+//! use fastbloom::strategy::compatible_filters;
+//! use proptest::prelude::*;
+//!
+//! proptest! {
+//!     fn no_false_negatives_after_union(filters in compatible_filters::<256>(2)) {
+//!         let (mut a, a_items) = filters[0].clone();
+//!         let (b, b_items) = &filters[1];
+//!         a.union(b);
+//!         prop_assert!(a_items.iter().chain(b_items).all(|x| a.contains(x)));
+//!     }
+//! }
+//! no_false_negatives_after_union();
+//! ```
+
+use crate::{BloomFilter, BuilderWithBits, DynBloomFilter, IndexStrategy};
+use proptest::collection::{vec, SizeRange};
+use proptest::prelude::*;
+
+/// The most blocks of a generated filter.
+const MAX_BLOCKS: usize = 64;
+
+/// The most items inserted into a generated filter.
+const MAX_ITEMS: usize = 256;
+
+/// The most hashes per item of a generated filter.
+const MAX_HASHES: u32 = 16;
+
+/// Returns a strategy for empty [`BuilderWithBits`] of 1 to 64 blocks, with any seed, two-choice mode,
+/// and index strategy.
+pub fn builder<const BLOCK_SIZE_BITS: usize>(
+) -> impl Strategy<Value = BuilderWithBits<BLOCK_SIZE_BITS>> {
+    (
+        1..=MAX_BLOCKS,
+        any::<u128>(),
+        any::<bool>(),
+        prop_oneof![
+            Just(IndexStrategy::DoubleHashing),
+            Just(IndexStrategy::EnhancedDoubleHashing),
+        ],
+    )
+        .prop_map(|(num_blocks, seed, two_choice, index_strategy)| {
+            let builder = BloomFilter::new_builder::<BLOCK_SIZE_BITS>(num_blocks * BLOCK_SIZE_BITS)
+                .seed(&seed)
+                .index_strategy(index_strategy);
+            match two_choice {
+                true => builder.two_choice(),
+                false => builder,
+            }
+        })
+}
+
+/// Returns a strategy for filters built from [`builder`] with 1 to 16 hashes, and up to 256 items inserted.
+pub fn filter<const BLOCK_SIZE_BITS: usize>() -> impl Strategy<Value = BloomFilter<BLOCK_SIZE_BITS>>
+{
+    filter_with_items::<BLOCK_SIZE_BITS>().prop_map(|(filter, _)| filter)
+}
+
+/// Like [`filter`], but also returns the items inserted into the filter.
+pub fn filter_with_items<const BLOCK_SIZE_BITS: usize>(
+) -> impl Strategy<Value = (BloomFilter<BLOCK_SIZE_BITS>, Vec<u64>)> {
+    (builder::<BLOCK_SIZE_BITS>(), 1..=MAX_HASHES, items()).prop_map(
+        |(builder, num_hashes, items)| {
+            let mut filter = builder.hashes(num_hashes);
+            filter.extend(&items);
+            (filter, items)
+        },
+    )
+}
+
+/// Returns a strategy for `count` filters with the same number of bits, hashes, and hasher, and their items,
+/// so that they can be combined with [`BloomFilter::union`] and [`BloomFilter::intersect`].
+pub fn compatible_filters<const BLOCK_SIZE_BITS: usize>(
+    count: impl Into<SizeRange>,
+) -> impl Strategy<Value = Vec<(BloomFilter<BLOCK_SIZE_BITS>, Vec<u64>)>> {
+    let count = count.into();
+    (builder::<BLOCK_SIZE_BITS>(), 1..=MAX_HASHES).prop_flat_map(move |(builder, num_hashes)| {
+        vec(items(), count.clone()).prop_map(move |all_items| {
+            all_items
+                .into_iter()
+                .map(|items| {
+                    let mut filter = builder.build(num_hashes);
+                    filter.extend(&items);
+                    (filter, items)
+                })
+                .collect()
+        })
+    })
+}
+
+/// Like [`filter_with_items`], with a block size of 64, 128, 256, or 512 bits.
+pub fn dyn_filter_with_items() -> impl Strategy<Value = (DynBloomFilter, Vec<u64>)> {
+    fn into_dyn<const BLOCK_SIZE_BITS: usize>(
+        (filter, items): (BloomFilter<BLOCK_SIZE_BITS>, Vec<u64>),
+    ) -> (DynBloomFilter, Vec<u64>)
+    where
+        DynBloomFilter: From<BloomFilter<BLOCK_SIZE_BITS>>,
+    {
+        (filter.into(), items)
+    }
+    prop_oneof![
+        filter_with_items::<64>().prop_map(into_dyn),
+        filter_with_items::<128>().prop_map(into_dyn),
+        filter_with_items::<256>().prop_map(into_dyn),
+        filter_with_items::<512>().prop_map(into_dyn),
+    ]
+}
+
+fn items() -> impl Strategy<Value = Vec<u64>> {
+    vec(any::<u64>(), 0..=MAX_ITEMS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn generated_filters_contain_their_items((filter, items) in filter_with_items::<64>()) {
+            prop_assert!(filter.num_blocks() >= 1 && filter.num_blocks() <= MAX_BLOCKS);
+            prop_assert!(filter.num_hashes() >= 1 && filter.num_hashes() <= MAX_HASHES);
+            prop_assert!(items.iter().all(|x| filter.contains(x)));
+        }
+
+        #[test]
+        fn dyn_filters_contain_their_items((filter, items) in dyn_filter_with_items()) {
+            prop_assert!(items.iter().all(|x| filter.contains(x)));
+        }
+
+        #[test]
+        fn no_false_negatives_after_union(filters in compatible_filters::<128>(1..4)) {
+            let mut union = filters[0].0.clone();
+            for (filter, _) in &filters[1..] {
+                union.union(filter);
+            }
+            prop_assert!(filters.iter().flat_map(|(_, items)| items).all(|x| union.contains(x)));
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use crate::{BitStorage, BloomFilter, BuilderWithBits};
+use std::hash::BuildHasher;
+use std::io::{self, Read, Write};
+#[cfg(feature = "tokio")]
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// The number of `u64`s converted to bytes at a time when streaming.
+const CHUNK_WORDS: usize = 1024;
+
+/// Writes `chunk` into the start of `buf` as little-endian bytes, returning the written bytes.
+fn encode_chunk<'a>(chunk: &[u64], buf: &'a mut [u8]) -> &'a [u8] {
+    for (word, bytes) in chunk.iter().zip(buf.chunks_exact_mut(8)) {
+        bytes.copy_from_slice(&word.to_le_bytes());
+    }
+    &buf[..8 * chunk.len()]
+}
+
+/// Collects the bits of a filter read in chunks.
+struct BitsReader {
+    bits: Vec<u64>,
+    len: usize,
+}
+
+impl BitsReader {
+    /// Allocates the bits of a filter with the number of `u64`s in the little-endian `header`.
+    fn new(header: [u8; 8]) -> io::Result<Self> {
+        let len = usize::try_from(u64::from_le_bytes(header))
+            .ok()
+            .filter(|&len| len > 0)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid number of bits"))?;
+        let mut bits = Vec::new();
+        bits.try_reserve_exact(len)
+            .map_err(|e| io::Error::new(io::ErrorKind::OutOfMemory, e))?;
+        Ok(Self { bits, len })
+    }
+
+    /// Returns the number of bytes of the next chunk, or 0 if all bits have been read.
+    fn next_chunk_len(&self) -> usize {
+        8 * (self.len - self.bits.len()).min(CHUNK_WORDS)
+    }
+
+    /// Appends the little-endian `u64`s of `bytes`.
+    fn push_chunk(&mut self, bytes: &[u8]) {
+        self.bits.extend(
+            bytes
+                .chunks_exact(8)
+                .map(|word| u64::from_le_bytes(word.try_into().unwrap())),
+        );
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher, T: BitStorage>
+    BloomFilter<BLOCK_SIZE_BITS, S, T>
+{
+    /// Writes the bits of this `BloomFilter` to `writer`: the number of `u64`s as a little-endian `u64`,
+    /// followed by the bytes of [`as_bytes`](Self::as_bytes).
+    ///
+    /// The bits are converted and written in small chunks, so no copy of the whole filter is made.
+    /// `writer` is not flushed; wrapping it in a [`BufWriter`](std::io::BufWriter) is unnecessary.
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_num_bits(1024).seed(&42).items([1, 2]);
+    /// let mut file = Vec::new(); // e.g. a `std::fs::File`
+    /// bloom.write_to(&mut file).unwrap();
+    /// assert_eq!(file.len(), 8 + bloom.num_bits() / 8);
+    /// ```
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        let bits = self.as_slice();
+        writer.write_all(&(bits.len() as u64).to_le_bytes())?;
+        let mut buf = [0u8; 8 * CHUNK_WORDS];
+        for chunk in bits.chunks(CHUNK_WORDS) {
+            writer.write_all(encode_chunk(chunk, &mut buf))?;
+        }
+        Ok(())
+    }
+}
+
+impl BloomFilter {
+    /// Creates a new instance of [`BuilderWithBits`] to construct a `BloomFilter` from bits read from `reader`,
+    /// as written by [`write_to`](BloomFilter::write_to).
+    ///
+    /// The bits are read in small chunks directly into the new filter's bit vector.
+    /// Only the bytes of the filter are read, so more data may follow it in `reader`.
+    /// As with [`from_vec`](Self::from_vec), the bits will be padded with `0u64`s to fit the block size.
+    ///
+    /// # Errors
+    /// Returns an error if reading fails, `reader` ends before the filter does, the filter has no bits,
+    /// or the bits cannot be allocated.
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let orig = BloomFilter::with_false_pos(0.001).seed(&42).items([1, 2]);
+    /// let mut file = Vec::new();
+    /// orig.write_to(&mut file).unwrap();
+    ///
+    /// let new = BloomFilter::read_from(&mut file.as_slice())
+    ///     .unwrap()
+    ///     .seed(&42)
+    ///     .hashes(orig.num_hashes());
+    /// assert!(new.contains(&1));
+    /// assert!(new.contains(&2));
+    /// ```
+    pub fn read_from(reader: &mut impl Read) -> io::Result<BuilderWithBits<512>> {
+        let mut len = [0u8; 8];
+        reader.read_exact(&mut len)?;
+        let mut bits = BitsReader::new(len)?;
+        let mut buf = [0u8; 8 * CHUNK_WORDS];
+        while bits.next_chunk_len() > 0 {
+            let bytes = &mut buf[..bits.next_chunk_len()];
+            reader.read_exact(bytes)?;
+            bits.push_chunk(bytes);
+        }
+        Ok(BloomFilter::from_vec(bits.bits))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher, T: BitStorage>
+    BloomFilter<BLOCK_SIZE_BITS, S, T>
+{
+    /// Writes the bits of this `BloomFilter` to the async `writer`, in the same format and chunks as
+    /// [`write_to`](Self::write_to).
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let bloom = BloomFilter::with_num_bits(1024).seed(&42).items([1, 2]);
+    /// let mut socket = Vec::new(); // e.g. a `tokio::net::TcpStream`
+    /// bloom.write_to_async(&mut socket).await.unwrap();
+    /// assert_eq!(socket.len(), 8 + bloom.num_bits() / 8);
+    /// # });
+    /// ```
+    pub async fn write_to_async(&self, writer: &mut (impl AsyncWrite + Unpin)) -> io::Result<()> {
+        let bits = self.as_slice();
+        writer.write_all(&(bits.len() as u64).to_le_bytes()).await?;
+        let mut buf = vec![0u8; 8 * CHUNK_WORDS];
+        for chunk in bits.chunks(CHUNK_WORDS) {
+            writer.write_all(encode_chunk(chunk, &mut buf)).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl BloomFilter {
+    /// Creates a new instance of [`BuilderWithBits`] to construct a `BloomFilter` from bits read from the async `reader`,
+    /// as written by [`write_to`](BloomFilter::write_to) or [`write_to_async`](BloomFilter::write_to_async).
+    ///
+    /// Reads the same way as [`read_from`](Self::read_from).
+    ///
+    /// # Errors
+    /// Returns an error if reading fails, `reader` ends before the filter does, the filter has no bits,
+    /// or the bits cannot be allocated.
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let orig = BloomFilter::with_false_pos(0.001).seed(&42).items([1, 2]);
+    /// let mut socket = Vec::new();
+    /// orig.write_to_async(&mut socket).await.unwrap();
+    ///
+    /// let new = BloomFilter::read_from_async(&mut socket.as_slice())
+    ///     .await
+    ///     .unwrap()
+    ///     .seed(&42)
+    ///     .hashes(orig.num_hashes());
+    /// assert!(new.contains(&1));
+    /// assert!(new.contains(&2));
+    /// # });
+    /// ```
+    pub async fn read_from_async(
+        reader: &mut (impl AsyncRead + Unpin),
+    ) -> io::Result<BuilderWithBits<512>> {
+        let mut len = [0u8; 8];
+        reader.read_exact(&mut len).await?;
+        let mut bits = BitsReader::new(len)?;
+        let mut buf = vec![0u8; 8 * CHUNK_WORDS];
+        while bits.next_chunk_len() > 0 {
+            let bytes = &mut buf[..bits.next_chunk_len()];
+            reader.read_exact(bytes).await?;
+            bits.push_chunk(bytes);
+        }
+        Ok(BloomFilter::from_vec(bits.bits))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stream_round_trip() {
+        for num_bits in [64, 1024, 64 * CHUNK_WORDS, 64 * CHUNK_WORDS * 3 + 512] {
+            let orig = BloomFilter::with_num_bits(num_bits).seed(&7).items(0..1000);
+            let mut bytes = Vec::new();
+            orig.write_to(&mut bytes).unwrap();
+            assert_eq!(bytes[..8], (orig.as_slice().len() as u64).to_le_bytes());
+            assert_eq!(bytes[8..], *orig.as_bytes());
+
+            bytes.extend_from_slice(b"trailing");
+            let mut reader = bytes.as_slice();
+            let new = BloomFilter::read_from(&mut reader)
+                .unwrap()
+                .seed(&7)
+                .hashes(orig.num_hashes());
+            assert_eq!(new, orig);
+            assert_eq!(reader, b"trailing");
+        }
+    }
+
+    #[test]
+    fn read_rejects_malformed() {
+        let mut bytes = Vec::new();
+        BloomFilter::with_num_bits(1024)
+            .hashes(3)
+            .write_to(&mut bytes)
+            .unwrap();
+        let err = BloomFilter::read_from(&mut &bytes[..bytes.len() - 1]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        let err = BloomFilter::read_from(&mut &[0u8; 8][..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = BloomFilter::read_from(&mut &u64::MAX.to_le_bytes()[..]).unwrap_err();
+        assert_ne!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn async_stream_round_trip() {
+        let orig = BloomFilter::with_num_bits(64 * CHUNK_WORDS * 3 + 512)
+            .seed(&7)
+            .items(0..1000);
+        let mut bytes = Vec::new();
+        orig.write_to_async(&mut bytes).await.unwrap();
+        let mut sync_bytes = Vec::new();
+        orig.write_to(&mut sync_bytes).unwrap();
+        assert_eq!(bytes, sync_bytes);
+
+        bytes.extend_from_slice(b"trailing");
+        let mut reader = bytes.as_slice();
+        let new = BloomFilter::read_from_async(&mut reader)
+            .await
+            .unwrap()
+            .seed(&7)
+            .hashes(orig.num_hashes());
+        assert_eq!(new, orig);
+        assert_eq!(reader, b"trailing");
+
+        let err = BloomFilter::read_from_async(&mut &bytes[..100])
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
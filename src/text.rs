@@ -0,0 +1,381 @@
+use crate::{
+    sparse_hash, BitStorage, BloomFilter, DefaultHasher, DynBloomFilter, IndexStrategy, Prefix,
+};
+#[cfg(feature = "base64")]
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+#[cfg(feature = "base64")]
+use base64::Engine;
+
+/// The version of the layout of [`BloomFilter::to_bytes_with_params`].
+const VERSION: u8 = 2;
+const TWO_CHOICE: u8 = 1;
+const PREFIX_LEN: u8 = 2;
+const PREFIX_DELIMITER: u8 = 4;
+const ENHANCED_DOUBLE_HASHING: u8 = 8;
+
+impl<const BLOCK_SIZE_BITS: usize, T: BitStorage> BloomFilter<BLOCK_SIZE_BITS, DefaultHasher, T> {
+    /// Returns the bytes of this `BloomFilter` together with its parameters:
+    /// the layout version, block size, number of hashes, the split of the hashes into single bit hashes and
+    /// sparse hash rounds, hasher keys, flags, and prefix, followed by [`as_bytes`](Self::as_bytes).
+    ///
+    /// This is the layout of [`to_hex`](Self::to_hex) and `to_base64` before encoding, and of the files embedded by
+    /// [`include_bloom!`](crate::include_bloom).
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::{BloomFilter, DynBloomFilter};
+    ///
+    /// let bloom = BloomFilter::with_num_bits(1024).items(["hello"]);
+    /// let bytes = bloom.to_bytes_with_params();
+    ///
+    /// let filter = DynBloomFilter::from_bytes_with_params(&bytes).unwrap();
+    /// assert!(filter.contains("hello"));
+    /// ```
+    pub fn to_bytes_with_params(&self) -> Vec<u8> {
+        let bits = self.as_bytes();
+        let mut bytes = Vec::with_capacity(48 + bits.len());
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&(BLOCK_SIZE_BITS as u16).to_le_bytes());
+        bytes.extend_from_slice(&self.num_hashes().to_le_bytes());
+        bytes.extend_from_slice(&(self.num_hashes as u32).to_le_bytes());
+        // Sparse hashes have at least 8 rounds, so 0 is no sparse hash
+        bytes.push(self.num_rounds.unwrap_or(0) as u8);
+        let (k0, k1) = self.hasher.keys();
+        bytes.extend_from_slice(&k0.to_le_bytes());
+        bytes.extend_from_slice(&k1.to_le_bytes());
+        let mut flags = if self.two_choice { TWO_CHOICE } else { 0 };
+        if self.index_strategy == IndexStrategy::EnhancedDoubleHashing {
+            flags |= ENHANCED_DOUBLE_HASHING;
+        }
+        match self.prefix {
+            Some(Prefix::Len(n)) => {
+                bytes.push(flags | PREFIX_LEN);
+                bytes.extend_from_slice(&(n as u64).to_le_bytes());
+            }
+            Some(Prefix::Delimiter(d)) => {
+                flags |= PREFIX_DELIMITER;
+                bytes.extend_from_slice(&[flags, d]);
+            }
+            None => bytes.push(flags),
+        }
+        bytes.extend_from_slice(&bits);
+        bytes
+    }
+
+    /// Returns this `BloomFilter` and the parameters needed to reconstruct it as URL-safe base64 without padding,
+    /// e.g. to embed a small filter in an environment variable, HTTP header, or URL.
+    ///
+    /// Only filters with the default hasher can be encoded, since its keys are included.
+    /// This requires the `base64` feature.
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_num_bits(256).block_size_256().items(["alice", "bob"]);
+    /// let token = bloom.to_base64();
+    ///
+    /// let bloom: BloomFilter<256> = BloomFilter::from_base64(&token).unwrap();
+    /// assert!(bloom.contains("alice"));
+    /// assert!(bloom.contains("bob"));
+    /// ```
+    #[cfg(feature = "base64")]
+    pub fn to_base64(&self) -> String {
+        URL_SAFE_NO_PAD.encode(self.to_bytes_with_params())
+    }
+
+    /// Returns this `BloomFilter` and the parameters needed to reconstruct it as lowercase hex,
+    /// in the layout of [`to_bytes_with_params`](Self::to_bytes_with_params).
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::BloomFilter;
+    ///
+    /// let bloom = BloomFilter::with_num_bits(64).block_size_64().items(["alice"]);
+    /// let hex = bloom.to_hex();
+    ///
+    /// let bloom: BloomFilter<64> = BloomFilter::from_hex(&hex).unwrap();
+    /// assert!(bloom.contains("alice"));
+    /// ```
+    pub fn to_hex(&self) -> String {
+        use std::fmt::Write;
+
+        let bytes = self.to_bytes_with_params();
+        let mut hex = String::with_capacity(2 * bytes.len());
+        for byte in bytes {
+            write!(hex, "{byte:02x}").unwrap();
+        }
+        hex
+    }
+}
+
+/// The parameters at the start of the layout of [`BloomFilter::to_bytes_with_params`], which are followed by the bits.
+pub(crate) struct Params {
+    pub(crate) block_size: usize,
+    pub(crate) num_hashes: u32,
+    pub(crate) single_hashes: u32,
+    pub(crate) num_rounds: Option<u64>,
+    pub(crate) keys: (u64, u64),
+    pub(crate) two_choice: bool,
+    pub(crate) index_strategy: IndexStrategy,
+    pub(crate) prefix: Option<Prefix>,
+    /// The number of bytes of the parameters, i.e. the offset of the bits.
+    pub(crate) len: usize,
+}
+
+impl Params {
+    /// Parses the parameters at the start of `bytes`, in const contexts too, e.g. for [`include_bloom!`](crate::include_bloom).
+    pub(crate) const fn parse(bytes: &[u8]) -> Option<Self> {
+        let [VERSION, b0, b1, h0, h1, h2, h3, s0, s1, s2, s3, r, rest @ ..] = bytes else {
+            return None;
+        };
+        let block_size = u16::from_le_bytes([*b0, *b1]) as usize;
+        let num_rounds = match *r {
+            0 => None,
+            r if sparse_hash::is_valid_rounds(r as u64, block_size) => Some(r as u64),
+            _ => return None,
+        };
+        let Some((k0, rest)) = rest.split_first_chunk::<8>() else {
+            return None;
+        };
+        let Some((k1, rest)) = rest.split_first_chunk::<8>() else {
+            return None;
+        };
+        let Some((&flags, rest)) = rest.split_first() else {
+            return None;
+        };
+        let prefix = match flags & !(TWO_CHOICE | ENHANCED_DOUBLE_HASHING) {
+            0 => None,
+            PREFIX_LEN => match rest.first_chunk::<8>() {
+                Some(n) if u64::from_le_bytes(*n) <= usize::MAX as u64 => {
+                    Some(Prefix::Len(u64::from_le_bytes(*n) as usize))
+                }
+                _ => return None,
+            },
+            PREFIX_DELIMITER => match rest.first() {
+                Some(&d) => Some(Prefix::Delimiter(d)),
+                None => return None,
+            },
+            _ => return None,
+        };
+        let prefix_len = match prefix {
+            Some(Prefix::Len(_)) => 8,
+            Some(Prefix::Delimiter(_)) => 1,
+            None => 0,
+        };
+        Some(Self {
+            block_size,
+            num_hashes: u32::from_le_bytes([*h0, *h1, *h2, *h3]),
+            single_hashes: u32::from_le_bytes([*s0, *s1, *s2, *s3]),
+            num_rounds,
+            keys: (u64::from_le_bytes(*k0), u64::from_le_bytes(*k1)),
+            two_choice: flags & TWO_CHOICE != 0,
+            index_strategy: if flags & ENHANCED_DOUBLE_HASHING != 0 {
+                IndexStrategy::EnhancedDoubleHashing
+            } else {
+                IndexStrategy::DoubleHashing
+            },
+            prefix,
+            len: bytes.len() - rest.len() + prefix_len,
+        })
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize> BloomFilter<BLOCK_SIZE_BITS> {
+    /// Creates a `BloomFilter` from the layout of [`to_bytes_with_params`](BloomFilter::to_bytes_with_params).
+    pub(crate) fn from_bytes_with_params(bytes: &[u8]) -> Option<Self> {
+        let params = Params::parse(bytes)?;
+        if params.block_size != BLOCK_SIZE_BITS {
+            return None;
+        }
+        let rest = &bytes[params.len..];
+        if rest.is_empty()
+            || !rest
+                .chunks_exact(BLOCK_SIZE_BITS / 8)
+                .remainder()
+                .is_empty()
+        {
+            return None;
+        }
+        let bits = rest
+            .chunks_exact(8)
+            .map(|word| u64::from_le_bytes(word.try_into().unwrap()))
+            .collect();
+        let (k0, k1) = params.keys;
+        let mut builder = BloomFilter::new_from_vec::<BLOCK_SIZE_BITS>(bits)
+            .hasher(DefaultHasher::with_keys(k0, k1))
+            .index_strategy(params.index_strategy);
+        if params.two_choice {
+            builder = builder.two_choice();
+        }
+        if let Some(prefix) = params.prefix {
+            builder = builder.prefix(prefix);
+        }
+        Some(builder.hashes_with_split(params.num_hashes, params.single_hashes, params.num_rounds))
+    }
+
+    /// Creates a `BloomFilter` from URL-safe base64, as returned by [`to_base64`](BloomFilter::to_base64).
+    ///
+    /// Returns `None` if `s` is not valid base64 of a filter, or the filter has a different block size.
+    /// This requires the `base64` feature.
+    #[cfg(feature = "base64")]
+    pub fn from_base64(s: &str) -> Option<Self> {
+        Self::from_bytes_with_params(&URL_SAFE_NO_PAD.decode(s).ok()?)
+    }
+
+    /// Creates a `BloomFilter` from hex, as returned by [`to_hex`](BloomFilter::to_hex). Upper and lowercase are accepted.
+    ///
+    /// Returns `None` if `s` is not valid hex of a filter, or the filter has a different block size.
+    pub fn from_hex(s: &str) -> Option<Self> {
+        let pairs = s.as_bytes().chunks_exact(2);
+        if !pairs.remainder().is_empty() || !s.bytes().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let digit = |c: u8| (c as char).to_digit(16).unwrap() as u8;
+        let bytes: Vec<u8> = pairs
+            .map(|pair| digit(pair[0]) << 4 | digit(pair[1]))
+            .collect();
+        Self::from_bytes_with_params(&bytes)
+    }
+}
+
+impl DynBloomFilter {
+    /// Returns the bytes of the wrapped `BloomFilter` with its parameters, as [`BloomFilter::to_bytes_with_params`] does,
+    /// e.g. to save a filter of any block size to a file.
+    ///
+    /// # Examples
+    /// ```
+    /// use fastbloom::DynBloomFilter;
+    ///
+    /// let filter = DynBloomFilter::new_with_false_pos(128, 0.01, 100);
+    /// let bytes = filter.to_bytes_with_params();
+    ///
+    /// let filter = DynBloomFilter::from_bytes_with_params(&bytes).unwrap();
+    /// assert_eq!(filter.block_size_bits(), 128);
+    /// ```
+    pub fn to_bytes_with_params(&self) -> Vec<u8> {
+        match self {
+            DynBloomFilter::Block64(filter) => filter.to_bytes_with_params(),
+            DynBloomFilter::Block128(filter) => filter.to_bytes_with_params(),
+            DynBloomFilter::Block256(filter) => filter.to_bytes_with_params(),
+            DynBloomFilter::Block512(filter) => filter.to_bytes_with_params(),
+        }
+    }
+
+    /// Creates a `DynBloomFilter` with the block size in `bytes`, as returned by [`to_bytes_with_params`](Self::to_bytes_with_params)
+    /// or [`BloomFilter::to_bytes_with_params`].
+    ///
+    /// Returns `None` if `bytes` are not a filter.
+    pub fn from_bytes_with_params(bytes: &[u8]) -> Option<Self> {
+        let block_size = bytes.get(1..3)?;
+        Some(match u16::from_le_bytes(block_size.try_into().unwrap()) {
+            64 => BloomFilter::<64>::from_bytes_with_params(bytes)?.into(),
+            128 => BloomFilter::<128>::from_bytes_with_params(bytes)?.into(),
+            256 => BloomFilter::<256>::from_bytes_with_params(bytes)?.into(),
+            512 => BloomFilter::<512>::from_bytes_with_params(bytes)?.into(),
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_round_trip() {
+        let mut bloom = BloomFilter::with_num_bits(1024)
+            .block_size_128()
+            .seed(&7)
+            .two_choice()
+            .prefix(Prefix::Len(3))
+            .expected_items(10);
+        bloom.extend(0..10);
+        bloom.insert_with_prefix(b"abcdef");
+
+        let hex = bloom.to_hex();
+        assert_eq!(hex.len(), 2 * (1 + 2 + 4 + 4 + 1 + 16 + 1 + 8 + 128));
+        let back: BloomFilter<128> = BloomFilter::from_hex(&hex).unwrap();
+        assert_eq!(back, bloom);
+        assert!((0..10).all(|x| back.contains(&x)));
+        assert!(back.contains(b"abc".as_slice()));
+        assert_eq!(
+            BloomFilter::<128>::from_hex(&hex.to_uppercase()),
+            Some(back)
+        );
+
+        #[cfg(feature = "base64")]
+        {
+            let token = bloom.to_base64();
+            assert!(token
+                .bytes()
+                .all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_'));
+            assert_eq!(BloomFilter::<128>::from_base64(&token), Some(bloom));
+        }
+    }
+
+    #[test]
+    fn text_keeps_split_of_hashes() {
+        let builder = BloomFilter::with_num_bits(1 << 12).block_size_64().seed(&1);
+        for mut bloom in [
+            builder.clone().max_rounds(16).hashes(40),
+            builder.clone().no_sparse_hash().hashes(40),
+            builder.hashes_with_rounds(3, Some(20)),
+        ] {
+            bloom.extend(0..100);
+            let bytes = bloom.to_bytes_with_params();
+            let DynBloomFilter::Block64(back) =
+                DynBloomFilter::from_bytes_with_params(&bytes).unwrap()
+            else {
+                panic!("different block size");
+            };
+            assert_eq!(back, bloom);
+            assert_eq!(back.num_hashes(), bloom.num_hashes());
+            assert!((0..100).all(|x| back.contains(&x)));
+        }
+    }
+
+    #[test]
+    fn text_layout() {
+        let bloom = BloomFilter::from_vec(vec![0x0102_0304_0506_0708])
+            .block_size_64()
+            .hasher(DefaultHasher::with_keys(1, 2))
+            .prefix(Prefix::Delimiter(b'/'))
+            .hashes(3);
+        assert_eq!(
+            bloom.to_hex(),
+            [
+                "02",               // version
+                "4000",             // block size
+                "03000000",         // hashes
+                "03000000",         // single bit hashes
+                "00",               // no sparse hash
+                "0100000000000000", // first key
+                "0200000000000000", // second key
+                "04",               // flags: prefix delimiter
+                "2f",               // delimiter
+                "0807060504030201", // bits
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    fn text_rejects_invalid() {
+        let bloom = BloomFilter::with_num_bits(512).hashes(3);
+        let hex = bloom.to_hex();
+        assert!(BloomFilter::<512>::from_hex(&hex).is_some());
+        assert!(BloomFilter::<256>::from_hex(&hex).is_none());
+        assert!(BloomFilter::<512>::from_hex(&hex[..hex.len() - 1]).is_none());
+        assert!(BloomFilter::<512>::from_hex(&hex[..hex.len() - 2]).is_none());
+        assert!(BloomFilter::<512>::from_hex(&format!("+{}", &hex[1..])).is_none());
+        assert!(BloomFilter::<512>::from_hex(&format!("01{}", &hex[2..])).is_none());
+        // 7 rounds, fewer than a sparse hash has
+        assert!(BloomFilter::<512>::from_hex(&format!("{}07{}", &hex[..22], &hex[24..])).is_none());
+        #[cfg(feature = "base64")]
+        {
+            assert!(BloomFilter::<512>::from_base64("not base64!").is_none());
+            assert!(BloomFilter::<512>::from_base64(&bloom.to_base64()[..40]).is_none());
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use crate::{BloomFilter, DefaultHasher};
+use std::borrow::Borrow;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::marker::PhantomData;
+
+/// A [`BloomFilter`] of items of one type `T`, so that it cannot be queried with items of another type.
+///
+/// [`BloomFilter::contains`] accepts any `Hash` type, and an item hashes differently as a different type,
+/// e.g. a `UserId(u64)` and its `u64`, or `1u32` and `1i64`: querying with the wrong type always returns `false`
+/// for inserted items, without any error. `BloomSet` only accepts `T`, or types `T` borrows as, like the standard
+/// sets: a `BloomSet<String>` can be queried with `&str`, since a `String` and its `str` hash the same.
+///
+/// # Examples
+/// ```
+/// use fastbloom::{BloomFilter, BloomSet};
+///
+/// let mut names: BloomSet<String> = BloomSet::new(BloomFilter::with_false_pos(0.001).expected_items(100));
+/// names.insert("alice");
+/// names.insert(&String::from("bob"));
+/// assert!(names.contains("alice"));
+/// assert!(names.contains(&String::from("bob")));
+/// ```
+///
+/// ```compile_fail
+/// use fastbloom::{BloomFilter, BloomSet};
+///
+/// #[derive(Hash)]
+/// struct UserId(u64);
+///
+/// let mut users: BloomSet<UserId> = BloomSet::new(BloomFilter::with_false_pos(0.001).expected_items(100));
+/// users.insert(&UserId(1));
+/// users.contains(&1u64); // does not compile
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BloomSet<T: ?Sized, const BLOCK_SIZE_BITS: usize = 512, S = DefaultHasher> {
+    filter: BloomFilter<BLOCK_SIZE_BITS, S>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    _item: PhantomData<fn(&T)>,
+}
+
+impl<T: Hash + ?Sized, const BLOCK_SIZE_BITS: usize, S: BuildHasher>
+    BloomSet<T, BLOCK_SIZE_BITS, S>
+{
+    /// Creates a `BloomSet` of the items of `filter`, which must all be `T`s.
+    pub fn new(filter: BloomFilter<BLOCK_SIZE_BITS, S>) -> Self {
+        Self {
+            filter,
+            _item: PhantomData,
+        }
+    }
+
+    /// Inserts an element into the Bloom filter.
+    ///
+    /// `val` may be any borrowed form of `T`, whose `Hash` must match that of `T`, as for [`Borrow`].
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item may have been previously in the Bloom filter (indicating a potential false positive),
+    /// `false` otherwise.
+    #[inline]
+    pub fn insert<Q>(&mut self, val: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + ?Sized,
+    {
+        self.filter.insert(val)
+    }
+
+    /// Checks if an element is possibly in the Bloom filter.
+    ///
+    /// `val` may be any borrowed form of `T`, whose `Hash` must match that of `T`, as for [`Borrow`].
+    #[inline]
+    pub fn contains<Q>(&self, val: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + ?Sized,
+    {
+        self.filter.contains(val)
+    }
+
+    /// Adds all items of `other` to this set. See [`BloomFilter::union`].
+    ///
+    /// # Panics
+    /// Panics if the filters have different numbers of bits or hashes.
+    pub fn union(&mut self, other: &Self) {
+        self.filter.union(&other.filter);
+    }
+
+    /// Returns the underlying untyped filter.
+    #[inline]
+    pub fn as_filter(&self) -> &BloomFilter<BLOCK_SIZE_BITS, S> {
+        &self.filter
+    }
+
+    /// Returns the underlying untyped filter.
+    #[inline]
+    pub fn into_inner(self) -> BloomFilter<BLOCK_SIZE_BITS, S> {
+        self.filter
+    }
+
+    /// Returns the number of bytes this `BloomSet` uses, including its bits. See [`BloomFilter::memory_usage`].
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>() + self.filter.heap_bytes()
+    }
+
+    /// Clear all of the bits in the Bloom filter, removing all items.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.filter.clear();
+    }
+}
+
+impl<T: Hash + ?Sized, const BLOCK_SIZE_BITS: usize, S: BuildHasher>
+    From<BloomFilter<BLOCK_SIZE_BITS, S>> for BloomSet<T, BLOCK_SIZE_BITS, S>
+{
+    fn from(filter: BloomFilter<BLOCK_SIZE_BITS, S>) -> Self {
+        Self::new(filter)
+    }
+}
+
+impl<T: Hash, const BLOCK_SIZE_BITS: usize, S: BuildHasher> Extend<T>
+    for BloomSet<T, BLOCK_SIZE_BITS, S>
+{
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.filter.extend(iter);
+    }
+}
+
+impl<'a, T: Hash + ?Sized, const BLOCK_SIZE_BITS: usize, S: BuildHasher> Extend<&'a T>
+    for BloomSet<T, BLOCK_SIZE_BITS, S>
+{
+    #[inline]
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        for val in iter {
+            self.filter.insert(val);
+        }
+    }
+}
+
+impl<T: ?Sized, const BLOCK_SIZE_BITS: usize, S: Clone> Clone for BloomSet<T, BLOCK_SIZE_BITS, S> {
+    fn clone(&self) -> Self {
+        Self {
+            filter: self.filter.clone(),
+            _item: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized, const BLOCK_SIZE_BITS: usize, S: fmt::Debug> fmt::Debug
+    for BloomSet<T, BLOCK_SIZE_BITS, S>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("BloomSet").field(&self.filter).finish()
+    }
+}
+
+impl<T: ?Sized, const BLOCK_SIZE_BITS: usize, S: BuildHasher> PartialEq
+    for BloomSet<T, BLOCK_SIZE_BITS, S>
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.filter == other.filter
+    }
+}
+impl<T: ?Sized, const BLOCK_SIZE_BITS: usize, S: BuildHasher> Eq
+    for BloomSet<T, BLOCK_SIZE_BITS, S>
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Hash)]
+    struct UserId(u64);
+
+    #[test]
+    fn borrowed_lookups() {
+        let mut set: BloomSet<String> = BloomSet::new(
+            BloomFilter::with_false_pos(0.001)
+                .seed(&1)
+                .expected_items(100),
+        );
+        set.extend((0..100).map(|x| x.to_string()));
+        set.insert("abc");
+        assert!((0..100).all(|x| set.contains(x.to_string().as_str())));
+        assert!(set.contains(&String::from("abc")));
+
+        let untyped = set.clone().into_inner();
+        assert!(untyped.contains("abc"));
+        assert_eq!(BloomSet::<String>::from(untyped), set);
+    }
+
+    #[test]
+    fn unsized_items() {
+        let mut set: BloomSet<str> = BloomFilter::with_num_bits(1024).hashes(4).into();
+        set.extend(["a", "b"]);
+        assert!(set.contains("a") && set.contains("b"));
+        set.clear();
+        assert!(!set.contains("a"));
+    }
+
+    #[test]
+    fn newtype_items() {
+        let mut users: BloomSet<UserId> =
+            BloomSet::new(BloomFilter::with_num_bits(4096).seed(&1).hashes(4));
+        users.extend([UserId(1), UserId(2)]);
+        let mut other = BloomSet::new(BloomFilter::with_num_bits(4096).seed(&1).hashes(4));
+        other.insert(&UserId(3));
+        users.union(&other);
+        assert!((1..=3).all(|id| users.contains(&UserId(id))));
+    }
+}
@@ -0,0 +1,251 @@
+use crate::{BloomFilter, DefaultHasher};
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A [`BloomFilter`] that also records its items in an exact [`HashSet`], to check the filter during development.
+///
+/// Since it knows exactly which items were inserted, it can check that the filter contains all of them
+/// with [`debug_assert_contains_consistency`](Self::debug_assert_contains_consistency), and tell the false positives
+/// among the positive results of [`contains`](Self::contains), so that [`observed_fp_rate`](Self::observed_fp_rate)
+/// is the real false positive rate of the application's queries. Recording the items takes far more memory
+/// than the filter, so `VerifyingBloomFilter` is meant for tests and debug builds, with the plain filter,
+/// from [`into_inner`](Self::into_inner), used in production.
+///
+/// # Examples
+/// ```
+/// use fastbloom::{BloomFilter, VerifyingBloomFilter};
+///
+/// let mut bloom = VerifyingBloomFilter::new(BloomFilter::with_false_pos(0.01).expected_items(1000));
+/// bloom.extend(0..1000);
+/// bloom.debug_assert_contains_consistency();
+///
+/// for query in 500..100_500 {
+///     bloom.contains(&query);
+/// }
+/// assert!(bloom.observed_fp_rate() < 0.02);
+/// ```
+pub struct VerifyingBloomFilter<T, const BLOCK_SIZE_BITS: usize = 512, S = DefaultHasher> {
+    filter: BloomFilter<BLOCK_SIZE_BITS, S>,
+    items: HashSet<T>,
+    /// The number of queries for items that were not inserted
+    negative_queries: AtomicU64,
+    /// The number of queries for items that were not inserted, that the filter contains
+    false_positives: AtomicU64,
+}
+
+impl<T: Hash + Eq, const BLOCK_SIZE_BITS: usize, S: BuildHasher>
+    VerifyingBloomFilter<T, BLOCK_SIZE_BITS, S>
+{
+    /// Creates a `VerifyingBloomFilter` of `filter`, which must be empty, since the items already in it are unknown.
+    ///
+    /// # Panics
+    /// Panics if `filter` has any bits set.
+    pub fn new(filter: BloomFilter<BLOCK_SIZE_BITS, S>) -> Self {
+        assert!(
+            filter.as_slice().iter().all(|&x| x == 0),
+            "filter must be empty"
+        );
+        Self {
+            filter,
+            items: HashSet::new(),
+            negative_queries: AtomicU64::new(0),
+            false_positives: AtomicU64::new(0),
+        }
+    }
+
+    /// Inserts an element into the filter, and records it.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item may have been previously in the filter (indicating a potential false positive),
+    /// `false` otherwise.
+    pub fn insert(&mut self, val: T) -> bool {
+        let previously_contained = self.filter.insert(&val);
+        self.items.insert(val);
+        previously_contained
+    }
+
+    /// Checks if an element is possibly in the filter, counting a false positive if it is not a recorded item.
+    ///
+    /// `val` may be any borrowed form of `T`, whose `Hash` and `Eq` must match those of `T`, as for [`HashSet`].
+    ///
+    /// In debug builds, panics if the filter does not contain a recorded item.
+    pub fn contains<Q>(&self, val: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let contains = self.filter.contains(val);
+        if self.items.contains(val) {
+            debug_assert!(contains, "false negative for an inserted item");
+        } else {
+            self.negative_queries.fetch_add(1, Ordering::Relaxed);
+            if contains {
+                self.false_positives.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        contains
+    }
+
+    /// Checks that the filter contains every recorded item, in debug builds.
+    ///
+    /// # Panics
+    /// In debug builds, panics if the filter does not contain a recorded item, which is a bug:
+    /// e.g. the filter was modified other than by inserting, or items with inconsistent `Hash` implementations.
+    pub fn debug_assert_contains_consistency(&self) {
+        if cfg!(debug_assertions) {
+            let missing = self
+                .items
+                .iter()
+                .filter(|x| !self.filter.contains(*x))
+                .count();
+            assert!(
+                missing == 0,
+                "the filter does not contain {missing} of {} inserted items",
+                self.items.len()
+            );
+        }
+    }
+
+    /// Returns the fraction of the queries to [`contains`](Self::contains) for items that were not inserted
+    /// that returned `true`, or 0 if there were no such queries.
+    ///
+    /// Unlike [`BloomFilter::measure_fp_rate`], the queries may include inserted items, which are not counted.
+    pub fn observed_fp_rate(&self) -> f64 {
+        let negative_queries = self.negative_queries.load(Ordering::Relaxed);
+        if negative_queries == 0 {
+            0.0
+        } else {
+            self.false_positives.load(Ordering::Relaxed) as f64 / negative_queries as f64
+        }
+    }
+
+    /// Returns the number of distinct items inserted.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns `true` if no items have been inserted.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns the recorded items.
+    #[inline]
+    pub fn items(&self) -> &HashSet<T> {
+        &self.items
+    }
+
+    /// Returns the underlying filter.
+    #[inline]
+    pub fn as_filter(&self) -> &BloomFilter<BLOCK_SIZE_BITS, S> {
+        &self.filter
+    }
+
+    /// Returns the underlying filter, discarding the recorded items.
+    #[inline]
+    pub fn into_inner(self) -> BloomFilter<BLOCK_SIZE_BITS, S> {
+        self.filter
+    }
+
+    /// Returns the number of bytes this `VerifyingBloomFilter` uses, including the bits of the filter and the recorded
+    /// items. See [`BloomFilter::memory_usage`].
+    ///
+    /// The `HashSet` is estimated as one `T` and one control byte per item of its capacity,
+    /// not counting memory the items themselves allocate.
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.filter.heap_bytes()
+            + self.items.capacity() * (std::mem::size_of::<T>() + 1)
+    }
+
+    /// Clear all of the bits in the filter and the recorded items, removing all items, and resets the
+    /// [`observed_fp_rate`](Self::observed_fp_rate).
+    pub fn clear(&mut self) {
+        self.filter.clear();
+        self.items.clear();
+        *self.negative_queries.get_mut() = 0;
+        *self.false_positives.get_mut() = 0;
+    }
+}
+
+impl<T: Hash + Eq, const BLOCK_SIZE_BITS: usize, S: BuildHasher> Extend<T>
+    for VerifyingBloomFilter<T, BLOCK_SIZE_BITS, S>
+{
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.insert(val);
+        }
+    }
+}
+
+impl<T, const BLOCK_SIZE_BITS: usize, S: fmt::Debug> fmt::Debug
+    for VerifyingBloomFilter<T, BLOCK_SIZE_BITS, S>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VerifyingBloomFilter")
+            .field("filter", &self.filter)
+            .field("items", &self.items.len())
+            .field("negative_queries", &self.negative_queries)
+            .field("false_positives", &self.false_positives)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn observes_fp_rate() {
+        let mut bloom = VerifyingBloomFilter::new(
+            BloomFilter::with_false_pos(0.01)
+                .seed(&1)
+                .expected_items(10_000),
+        );
+        bloom.extend((0..10_000).map(|x| x.to_string()));
+        bloom.insert("0".to_string());
+        assert_eq!(bloom.len(), 10_000);
+        bloom.debug_assert_contains_consistency();
+        assert_eq!(bloom.observed_fp_rate(), 0.0);
+
+        assert!((0..10_000).all(|x| bloom.contains(x.to_string().as_str())));
+        assert_eq!(bloom.observed_fp_rate(), 0.0);
+        for x in 10_000..110_000 {
+            bloom.contains(x.to_string().as_str());
+        }
+        let expected = bloom
+            .as_filter()
+            .measure_fp_rate((10_000..110_000).map(|x| x.to_string()));
+        assert_eq!(bloom.observed_fp_rate(), expected);
+        assert!(expected > 0.0 && expected < 0.02);
+
+        bloom.clear();
+        assert!(bloom.is_empty() && bloom.as_filter().as_slice().iter().all(|&x| x == 0));
+        assert_eq!(bloom.observed_fp_rate(), 0.0);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic]
+    fn detects_false_negatives() {
+        let mut bloom = VerifyingBloomFilter::new(BloomFilter::with_num_bits(1024).hashes(4));
+        bloom.extend(0..10);
+        bloom.filter.clear();
+        bloom.debug_assert_contains_consistency();
+    }
+
+    #[test]
+    #[should_panic]
+    fn non_empty_filter() {
+        let mut filter = BloomFilter::with_num_bits(1024).hashes(4);
+        filter.insert(&1);
+        VerifyingBloomFilter::<i32>::new(filter);
+    }
+}
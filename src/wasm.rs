@@ -0,0 +1,142 @@
+use crate::DynBloomFilter;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// A Bloom filter for JavaScript, exported as `BloomFilter`, e.g. for edge workers querying filters built by a backend.
+///
+/// Filters are shipped as the bytes, or base64, of [`BloomFilter::to_base64`](crate::BloomFilter::to_base64),
+/// which include the block size, number of hashes, and hasher keys.
+/// Strings are hashed the same as Rust `str`s and byte arrays the same as `[u8]`s, so `contains("alice")`
+/// finds an item inserted by the backend with `insert("alice")`.
+///
+/// # Examples
+/// ```js
+/// import { BloomFilter } from "fastbloom";
+///
+/// const filter = BloomFilter.fromBase64(token); // from `to_base64()` on the backend
+/// filter.contains("alice");
+/// filter.containsBytes(new Uint8Array([1, 2, 3]));
+/// ```
+#[wasm_bindgen(js_name = BloomFilter)]
+#[derive(Debug, Clone)]
+pub struct WasmBloomFilter(DynBloomFilter);
+
+#[wasm_bindgen(js_class = BloomFilter)]
+impl WasmBloomFilter {
+    /// Creates an empty filter with random hasher keys, sized for `expected_num_items` to meet the false positive rate `fp`.
+    ///
+    /// # Panics
+    /// Panics if `fp` is 0.
+    #[wasm_bindgen(constructor)]
+    pub fn new(fp: f64, expected_num_items: usize) -> Self {
+        Self(DynBloomFilter::new_with_false_pos(
+            512,
+            fp,
+            expected_num_items,
+        ))
+    }
+
+    /// Creates a filter from the bytes of `BloomFilter::to_base64`, e.g. a `Uint8Array`,
+    /// or returns `undefined` if they are not a valid filter.
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(bytes: &[u8]) -> Option<WasmBloomFilter> {
+        DynBloomFilter::from_bytes_with_params(bytes).map(Self)
+    }
+
+    /// Returns the bytes of this filter as a `Uint8Array`, which `BloomFilter::from_base64` loads once base64 encoded.
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes_with_params()
+    }
+
+    /// Creates a filter from the base64 of `BloomFilter::to_base64`, or returns `undefined` if it is not a valid filter.
+    #[wasm_bindgen(js_name = fromBase64)]
+    pub fn from_base64(s: &str) -> Option<WasmBloomFilter> {
+        Self::from_bytes(&URL_SAFE_NO_PAD.decode(s).ok()?)
+    }
+
+    /// Returns this filter as URL-safe base64, the same as `BloomFilter::to_base64`.
+    #[wasm_bindgen(js_name = toBase64)]
+    pub fn to_base64(&self) -> String {
+        URL_SAFE_NO_PAD.encode(self.to_bytes())
+    }
+
+    /// Inserts a string, returning `true` if it may have been previously in the filter.
+    pub fn insert(&mut self, value: &str) -> bool {
+        self.0.insert(value)
+    }
+
+    /// Checks if a string is possibly in the filter.
+    pub fn contains(&self, value: &str) -> bool {
+        self.0.contains(value)
+    }
+
+    /// Inserts a byte array, returning `true` if it may have been previously in the filter.
+    #[wasm_bindgen(js_name = insertBytes)]
+    pub fn insert_bytes(&mut self, value: &[u8]) -> bool {
+        self.0.insert(value)
+    }
+
+    /// Checks if a byte array is possibly in the filter.
+    #[wasm_bindgen(js_name = containsBytes)]
+    pub fn contains_bytes(&self, value: &[u8]) -> bool {
+        self.0.contains(value)
+    }
+
+    /// The number of hashes per item.
+    #[wasm_bindgen(getter, js_name = numHashes)]
+    pub fn num_hashes(&self) -> u32 {
+        self.0.num_hashes()
+    }
+
+    /// The total number of bits in the filter.
+    #[wasm_bindgen(getter, js_name = numBits)]
+    pub fn num_bits(&self) -> usize {
+        self.0.num_bits()
+    }
+
+    /// Clears all of the bits in the filter, removing all items.
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BloomFilter;
+
+    #[test]
+    fn queries_backend_filter() {
+        let mut backend = BloomFilter::with_num_bits(1024)
+            .block_size_128()
+            .expected_items(10);
+        backend.insert("alice");
+        backend.insert(b"\x01\x02".as_slice());
+
+        let filter = WasmBloomFilter::from_base64(&backend.to_base64()).unwrap();
+        assert!(filter.contains("alice"));
+        assert!(filter.contains_bytes(&[1, 2]));
+        assert!(!filter.contains("bob"));
+        assert_eq!(filter.num_bits(), 1024);
+        assert_eq!(filter.num_hashes(), backend.num_hashes());
+
+        let back: BloomFilter<128> = BloomFilter::from_base64(&filter.to_base64()).unwrap();
+        assert_eq!(back, backend);
+        assert!(WasmBloomFilter::from_bytes(&filter.to_bytes()).is_some());
+        assert!(WasmBloomFilter::from_bytes(&[1, 0x20, 0]).is_none());
+        assert!(WasmBloomFilter::from_base64("!").is_none());
+    }
+
+    #[test]
+    fn inserts_contained() {
+        let mut filter = WasmBloomFilter::new(0.01, 100);
+        assert!(!filter.insert("alice"));
+        assert!(filter.insert("alice"));
+        assert!(!filter.insert_bytes(b"alice"));
+        assert!(filter.contains("alice") && filter.contains_bytes(b"alice"));
+        filter.clear();
+        assert!(!filter.contains("alice"));
+    }
+}
@@ -0,0 +1,287 @@
+use crate::BloomFilter;
+#[cfg(feature = "serde")]
+use crate::FastbloomError;
+use std::hash::{BuildHasher, Hash};
+
+/// A Bloom filter over a sliding window of "ticks", made of a fixed ring of [`BloomFilter`] slices.
+///
+/// Each slice holds the items inserted during one tick, e.g. one minute. Ticks are supplied by the user and
+/// are any monotonically increasing `u64`. [`contains`](Self::contains) checks all slices in the window,
+/// i.e. the current tick and the `window - 1` ticks before it.
+///
+/// Advancing to a new tick evicts the oldest slice by clearing it and reusing it for the new tick;
+/// no slices are allocated or shifted.
+///
+/// # Examples
+/// ```
+/// use fastbloom::{BloomFilter, WindowedBloomFilter};
+///
+/// let filter = BloomFilter::with_false_pos(0.001).expected_items(1000);
+/// let mut windowed = WindowedBloomFilter::new(filter, 3);
+/// windowed.insert(0, "42");
+/// windowed.insert(1, "🦀");
+/// assert!(windowed.contains("42"));
+///
+/// windowed.advance(3);
+/// assert!(!windowed.contains("42"));
+/// assert!(windowed.contains("🦀"));
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(try_from = "WindowedBloomFilterFields<BLOCK_SIZE_BITS, S>")
+)]
+pub struct WindowedBloomFilter<const BLOCK_SIZE_BITS: usize = 512, S = crate::DefaultHasher> {
+    slices: Vec<BloomFilter<BLOCK_SIZE_BITS, S>>,
+    /// The tick the items in each slice were inserted in. `None` if the slice is empty.
+    ticks: Vec<Option<u64>>,
+    /// The most recent tick
+    current: u64,
+}
+
+/// The fields of a [`WindowedBloomFilter`], deserialized before they are checked.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(rename = "WindowedBloomFilter")]
+struct WindowedBloomFilterFields<const BLOCK_SIZE_BITS: usize, S> {
+    slices: Vec<BloomFilter<BLOCK_SIZE_BITS, S>>,
+    ticks: Vec<Option<u64>>,
+    current: u64,
+}
+
+#[cfg(feature = "serde")]
+impl<const BLOCK_SIZE_BITS: usize, S> TryFrom<WindowedBloomFilterFields<BLOCK_SIZE_BITS, S>>
+    for WindowedBloomFilter<BLOCK_SIZE_BITS, S>
+{
+    type Error = FastbloomError;
+
+    /// Checks that the window is not empty and has a tick for each slice, as [`WindowedBloomFilter::new`] ensures.
+    fn try_from(
+        fields: WindowedBloomFilterFields<BLOCK_SIZE_BITS, S>,
+    ) -> Result<Self, FastbloomError> {
+        if fields.slices.is_empty() || fields.ticks.len() != fields.slices.len() {
+            return Err(FastbloomError::InvalidParameters);
+        }
+        Ok(Self {
+            slices: fields.slices,
+            ticks: fields.ticks,
+            current: fields.current,
+        })
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher + Clone> WindowedBloomFilter<BLOCK_SIZE_BITS, S> {
+    /// Creates a new `WindowedBloomFilter` covering `window` ticks, using `filter` as the template for each slice.
+    /// The window starts at tick 0.
+    ///
+    /// `filter` is cleared before use.
+    ///
+    /// # Panics
+    /// Panics if `window` is 0.
+    pub fn new(mut filter: BloomFilter<BLOCK_SIZE_BITS, S>, window: usize) -> Self {
+        assert!(window > 0);
+        filter.clear();
+        Self {
+            slices: vec![filter; window],
+            ticks: vec![None; window],
+            current: 0,
+        }
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> WindowedBloomFilter<BLOCK_SIZE_BITS, S> {
+    #[inline]
+    fn slice_index(&self, tick: u64) -> usize {
+        (tick % self.slices.len() as u64) as usize
+    }
+
+    /// Returns true if items inserted at `tick` are in the window ending at `current`.
+    #[inline]
+    fn in_window(&self, tick: u64, current: u64) -> bool {
+        tick <= current && current - tick < self.slices.len() as u64
+    }
+
+    /// Moves the window forward to end at `tick`, evicting slices that fall out of the window.
+    /// Does nothing if `tick` is not after the current tick.
+    pub fn advance(&mut self, tick: u64) {
+        if tick <= self.current {
+            return;
+        }
+        let num_evicted = std::cmp::min(tick - self.current, self.slices.len() as u64);
+        for t in (tick - num_evicted + 1)..=tick {
+            let index = self.slice_index(t);
+            if self.ticks[index].is_some() {
+                self.slices[index].clear();
+                self.ticks[index] = None;
+            }
+        }
+        self.current = tick;
+    }
+
+    /// Inserts an element into the slice for `tick`, first advancing the window if `tick` is after the current tick.
+    ///
+    /// Items for ticks that are already out of the window are not inserted.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item may have been previously in the window (indicating a potential false positive),
+    /// `false` otherwise.
+    pub fn insert(&mut self, tick: u64, val: &(impl Hash + ?Sized)) -> bool {
+        self.advance(tick);
+        if !self.in_window(tick, self.current) {
+            return false;
+        }
+        let previously_contained = self.contains(val);
+        let index = self.slice_index(tick);
+        self.ticks[index] = Some(tick);
+        self.slices[index].insert(val);
+        previously_contained
+    }
+
+    /// Checks if an element is possibly in the window ending at the current tick.
+    #[inline]
+    pub fn contains(&self, val: &(impl Hash + ?Sized)) -> bool {
+        self.contains_at(self.current, val)
+    }
+
+    /// Checks if an element is possibly in the window ending at `tick`, without moving the window.
+    ///
+    /// Slices already evicted from the window are not considered, even if they would be in the window ending at `tick`.
+    pub fn contains_at(&self, tick: u64, val: &(impl Hash + ?Sized)) -> bool {
+        self.slices
+            .iter()
+            .zip(self.ticks.iter())
+            .any(|(slice, slice_tick)| match slice_tick {
+                Some(t) => self.in_window(*t, tick) && slice.contains(val),
+                None => false,
+            })
+    }
+
+    /// Returns the most recent tick, i.e. the end of the window.
+    #[inline]
+    pub fn current_tick(&self) -> u64 {
+        self.current
+    }
+
+    /// Returns the number of ticks covered by the window.
+    #[inline]
+    pub fn window(&self) -> usize {
+        self.slices.len()
+    }
+
+    /// Returns the slice for `tick`, or `None` if `tick` is not in the window.
+    pub fn slice(&self, tick: u64) -> Option<&BloomFilter<BLOCK_SIZE_BITS, S>> {
+        let index = self.slice_index(tick);
+        match self.ticks[index] {
+            Some(t) if t == tick && self.in_window(t, self.current) => Some(&self.slices[index]),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of bytes this `WindowedBloomFilter` uses, including the bits of all slices and their ticks.
+    /// See [`BloomFilter::memory_usage`].
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.slices.capacity() * std::mem::size_of::<BloomFilter<BLOCK_SIZE_BITS, S>>()
+            + self
+                .slices
+                .iter()
+                .map(|slice| slice.heap_bytes())
+                .sum::<usize>()
+            + self.ticks.capacity() * std::mem::size_of::<Option<u64>>()
+    }
+
+    /// Clear all slices, removing all items. The current tick is unchanged.
+    #[inline]
+    pub fn clear(&mut self) {
+        for slice in self.slices.iter_mut() {
+            slice.clear();
+        }
+        self.ticks.fill(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn windowed(window: usize) -> WindowedBloomFilter {
+        let filter = BloomFilter::with_false_pos(0.0001)
+            .seed(&42)
+            .expected_items(1000);
+        WindowedBloomFilter::new(filter, window)
+    }
+
+    #[test]
+    fn contained_within_window() {
+        let mut filter = windowed(5);
+        for tick in 0..100u64 {
+            filter.insert(tick, &tick);
+            for prev in tick.saturating_sub(4)..=tick {
+                assert!(filter.contains(&prev));
+            }
+            for prev in 0..tick.saturating_sub(4) {
+                assert!(!filter.contains(&prev));
+            }
+        }
+    }
+
+    #[test]
+    fn advance_past_window() {
+        let mut filter = windowed(5);
+        for tick in 0..5 {
+            filter.insert(tick, &tick);
+        }
+        filter.advance(1000);
+        assert!(!(0..5).any(|x| filter.contains(&x)));
+        assert!(filter.ticks.iter().all(|t| t.is_none()));
+    }
+
+    #[test]
+    fn stale_inserts_ignored() {
+        let mut filter = windowed(2);
+        filter.advance(10);
+        assert!(!filter.insert(5, &1));
+        assert!(!filter.contains(&1));
+        filter.insert(9, &2);
+        assert!(filter.contains(&2));
+        assert!(filter.slice(9).is_some());
+        assert!(filter.slice(8).is_none());
+    }
+
+    #[test]
+    fn nothing_after_clear() {
+        let mut filter = windowed(3);
+        for tick in 0..3 {
+            filter.insert(tick, &tick);
+        }
+        filter.clear();
+        assert!(!(0..3).any(|x| filter.contains(&x)));
+    }
+
+    #[test]
+    fn memory_usage() {
+        let filter = windowed(5);
+        let bits: usize = filter.slices.iter().map(|slice| slice.heap_bytes()).sum();
+        assert_eq!(bits, 5 * filter.slices[0].heap_bytes());
+        assert!(filter.memory_usage() > bits);
+        assert!(filter.memory_usage() < bits + 5 * std::mem::size_of::<BloomFilter>() + 1024);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_checks_window() {
+        let mut filter = windowed(3);
+        filter.insert(4, &4);
+        let json = serde_json::to_value(&filter).unwrap();
+        let back: WindowedBloomFilter = serde_json::from_value(json.clone()).unwrap();
+        assert!(back.contains(&4));
+
+        let empty = serde_json::json!({"slices": [], "ticks": [], "current": 0});
+        assert!(serde_json::from_value::<WindowedBloomFilter>(empty).is_err());
+        let mut missing_tick = json;
+        missing_tick["ticks"].as_array_mut().unwrap().pop();
+        assert!(serde_json::from_value::<WindowedBloomFilter>(missing_tick).is_err());
+    }
+}
@@ -0,0 +1,232 @@
+use crate::{block_index, get_orginal_hashes, validate_block_size, DefaultHasher};
+use std::hash::{BuildHasher, Hash};
+
+/// Added to the step between an item's bits after each bit, so that the bits do not form an arithmetic progression
+/// whose bits coincide or cluster when the step's upper bits are small ("enhanced double hashing").
+const STEP_INCREMENT: u32 = 0x9e37_79b9;
+
+/// A Bloom filter whose bits are stored, and set and checked, in `u32` words, for targets without native 64-bit operations.
+///
+/// Like [`BloomFilter`](crate::BloomFilter), each item sets `num_hashes` bits in its `BLOCK_SIZE_BITS` block,
+/// and the block sizes are the same. Unlike it, the block is a `[u32]`, and each bit is derived from the item's hash
+/// with 32-bit additions and shifts, so on microcontrollers and 32-bit wasm, where 64-bit operations are emulated
+/// or split in two, inserts and membership checks need none apart from hashing the item and selecting its block,
+/// a 32 by 32 bit multiply. There are no sparse hashes, so `U32BloomFilter` is best suited to few hashes per item.
+///
+/// Constructed via [`BuilderWithBits::u32_words`](crate::BuilderWithBits::u32_words)
+/// or [`BuilderWithFalsePositiveRate::u32_words`](crate::BuilderWithFalsePositiveRate::u32_words).
+///
+/// # Examples
+/// ```
+/// use fastbloom::BloomFilter;
+///
+/// let mut filter = BloomFilter::with_false_pos(0.01).block_size_128().u32_words(1000);
+/// filter.insert("42");
+/// assert!(filter.contains("42"));
+/// assert_eq!(filter.as_slice().len(), filter.num_blocks() * 4);
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct U32BloomFilter<const BLOCK_SIZE_BITS: usize = 512, S = DefaultHasher> {
+    /// The bits, a multiple of `BLOCK_SIZE_BITS / 32` words long.
+    pub(crate) bits: Vec<u32>,
+    pub(crate) num_hashes: u32,
+    pub(crate) hasher: S,
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> U32BloomFilter<BLOCK_SIZE_BITS, S> {
+    /// Block size in `u32`s.
+    const BLOCK_LEN: usize = validate_block_size(BLOCK_SIZE_BITS) / 32;
+    /// The shift selecting a bit of a block from the upper bits of a 32-bit hash.
+    const BIT_INDEX_SHIFT: u32 = 32 - BLOCK_SIZE_BITS.trailing_zeros();
+
+    /// Pads `bits` with `0u32`s to whole blocks.
+    pub(crate) fn new(mut bits: Vec<u32>, num_hashes: u32, hasher: S) -> Self {
+        bits.resize(bits.len().next_multiple_of(Self::BLOCK_LEN), 0);
+        Self {
+            bits,
+            num_hashes,
+            hasher,
+        }
+    }
+
+    /// Returns the block index of an item, and the two 32-bit hashes its bits are derived from.
+    ///
+    /// The upper 32 bits of the item's hash select the block, as in [`BloomFilter`](crate::BloomFilter),
+    /// so the bits are derived from the lower 32, stepping by an odd multiple of the upper 32.
+    #[inline]
+    fn hashes(&self, val: &(impl Hash + ?Sized)) -> (usize, u32, u32) {
+        let [hash, _] = get_orginal_hashes(&self.hasher, val);
+        let upper = (hash >> 32) as u32;
+        let step = upper.wrapping_mul(0x9e37_79b9) | 1;
+        (block_index(self.num_blocks(), hash), hash as u32, step)
+    }
+
+    /// Returns the index of the word, and the mask of the bit, for the next hash of an item.
+    #[inline]
+    fn next_bit(h: &mut u32, step: &mut u32) -> (usize, u32) {
+        let bit = (*h >> Self::BIT_INDEX_SHIFT) as usize;
+        *h = h.wrapping_add(*step);
+        *step = step.wrapping_add(STEP_INCREMENT);
+        (bit / 32, 1 << (bit % 32))
+    }
+
+    #[inline]
+    fn block(&self, index: usize) -> &[u32] {
+        &self.bits[index * Self::BLOCK_LEN..(index + 1) * Self::BLOCK_LEN]
+    }
+
+    /// Inserts an element into the Bloom filter.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the item may have been previously in the Bloom filter (indicating a potential false positive),
+    /// `false` otherwise.
+    #[inline]
+    pub fn insert(&mut self, val: &(impl Hash + ?Sized)) -> bool {
+        let (index, mut h, mut step) = self.hashes(val);
+        let block = &mut self.bits[index * Self::BLOCK_LEN..(index + 1) * Self::BLOCK_LEN];
+        let mut previously_contained = true;
+        for _ in 0..self.num_hashes {
+            let (word, mask) = Self::next_bit(&mut h, &mut step);
+            previously_contained &= block[word] & mask == mask;
+            block[word] |= mask;
+        }
+        previously_contained
+    }
+
+    /// Checks if an element is possibly in the Bloom filter.
+    #[inline]
+    pub fn contains(&self, val: &(impl Hash + ?Sized)) -> bool {
+        let (index, mut h, mut step) = self.hashes(val);
+        let block = self.block(index);
+        (0..self.num_hashes).all(|_| {
+            let (word, mask) = Self::next_bit(&mut h, &mut step);
+            block[word] & mask == mask
+        })
+    }
+
+    /// Returns the number of hashes per item.
+    #[inline]
+    pub fn num_hashes(&self) -> u32 {
+        self.num_hashes
+    }
+
+    /// Returns the total number of in-memory bits supporting the Bloom filter.
+    pub fn num_bits(&self) -> usize {
+        self.bits.len() * 32
+    }
+
+    /// Returns the total number of in-memory blocks supporting the Bloom filter.
+    /// Each block is `BLOCK_SIZE_BITS` bits.
+    pub fn num_blocks(&self) -> usize {
+        self.bits.len() / Self::BLOCK_LEN
+    }
+
+    /// Returns a `u32` slice of this `U32BloomFilter`’s contents.
+    #[inline]
+    pub fn as_slice(&self) -> &[u32] {
+        &self.bits
+    }
+
+    /// Returns the number of bytes this `U32BloomFilter` uses, including its bits.
+    /// See [`BloomFilter::memory_usage`](crate::BloomFilter::memory_usage).
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>() + self.bits.capacity() * std::mem::size_of::<u32>()
+    }
+
+    /// Clear all of the bits in the Bloom filter, removing all items.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.bits.fill(0);
+    }
+}
+
+impl<T, const BLOCK_SIZE_BITS: usize, S: BuildHasher> Extend<T>
+    for U32BloomFilter<BLOCK_SIZE_BITS, S>
+where
+    T: Hash,
+{
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.insert(&val);
+        }
+    }
+}
+
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> PartialEq
+    for U32BloomFilter<BLOCK_SIZE_BITS, S>
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.bits == other.bits && self.num_hashes == other.num_hashes
+    }
+}
+impl<const BLOCK_SIZE_BITS: usize, S: BuildHasher> Eq for U32BloomFilter<BLOCK_SIZE_BITS, S> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::BloomFilter;
+
+    #[test]
+    fn inserts_always_contained() {
+        fn inserts_always_contained_<const N: usize>() {
+            let mut filter = BloomFilter::new_with_false_pos::<N>(0.01)
+                .seed(&42)
+                .u32_words(1000);
+            for x in 0..1000 {
+                assert!(!filter.contains(&(x + 1000)) || filter.insert(&(x + 1000)));
+                filter.insert(&x);
+                assert!(filter.contains(&x));
+            }
+            assert!((0..1000).all(|x| filter.contains(&x)));
+            assert_eq!(filter.as_slice().len() * 32, filter.num_blocks() * N);
+        }
+        inserts_always_contained_::<64>();
+        inserts_always_contained_::<128>();
+        inserts_always_contained_::<256>();
+        inserts_always_contained_::<512>();
+    }
+
+    #[test]
+    fn target_fp_is_accurate() {
+        fn target_fp_is_accurate_<const N: usize>(thresh: f64) {
+            for fp in [0.1, 0.01, 0.001] {
+                let num_items = 10_000usize;
+                let mut filter = BloomFilter::new_with_false_pos::<N>(fp)
+                    .seed(&42)
+                    .u32_words(num_items);
+                filter.extend(0..num_items);
+                let false_pos = (num_items..101 * num_items)
+                    .filter(|x| filter.contains(x))
+                    .count();
+                let actual_fp = false_pos as f64 / (100 * num_items) as f64;
+                // Small blocks are more often crowded, as in `BloomFilter`
+                assert!(actual_fp < thresh * fp, "{N} {fp} {actual_fp}");
+            }
+        }
+        target_fp_is_accurate_::<64>(10.0);
+        target_fp_is_accurate_::<128>(5.0);
+        target_fp_is_accurate_::<256>(3.0);
+        target_fp_is_accurate_::<512>(2.0);
+    }
+
+    #[test]
+    fn from_vec_keeps_bits() {
+        let filter = BloomFilter::from_vec(vec![0x8000_0000_0000_0001; 3]).u32_words(4);
+        assert_eq!(filter.num_blocks(), 1);
+        assert_eq!(
+            filter.as_slice()[..6],
+            [1, 0x8000_0000, 1, 0x8000_0000, 1, 0x8000_0000]
+        );
+        assert_eq!(filter.as_slice()[6..], [0; 10]);
+    }
+
+    #[test]
+    fn nothing_after_clear() {
+        let mut filter = BloomFilter::with_num_bits(1024).u32_words(4);
+        filter.extend(0..100);
+        filter.clear();
+        assert!(!(0..100).any(|x| filter.contains(&x)));
+    }
+}